@@ -0,0 +1,328 @@
+//! VMS BACKUP block layout shared by the reader, writer, hex viewer and
+//! damage checks.
+//!
+//! Every block starts with a 256-byte block header (BBH) followed by
+//! records, each introduced by a 16-byte record header (BRH):
+//!
+//! | BBH offset | field                                                 |
+//! |------------|-------------------------------------------------------|
+//! | 0          | `w_size` header size (256)                            |
+//! | 2          | `w_opsys` 0x0400 (VAX/VMS)                            |
+//! | 4          | `w_subsys` 1                                          |
+//! | 6          | `w_applic` 1, or 2 in an XOR block                    |
+//! | 8          | `l_number` block number, from 1                       |
+//! | 32         | `w_struclev` 0x0101                                   |
+//! | 34         | `w_volnum` volume number                              |
+//! | 36         | `l_crc` CRC-32 of the block                           |
+//! | 40         | `l_blocksize`                                         |
+//! | 44         | `l_flags`                                             |
+//! | 48         | `t_ssname` counted saveset name                       |
+//! | 254        | `w_checksum` CRC-16 of the header                     |
+//!
+//! | BRH offset | field                                                 |
+//! |------------|-------------------------------------------------------|
+//! | 0          | `w_rsize` record data size                            |
+//! | 2          | `w_rtype` 1 summary, 3 file, 4 VBN                    |
+//! | 4          | `l_flags`                                             |
+//! | 8          | `l_address` starting VBN for VBN records              |
+//!
+//! Summary and file records hold a structure level word and then BACKUP
+//! save attributes (BSA): `w_size`, `w_type` and data.
+//!
+//! `l_crc` is a CRC-32 of the whole block taken with the CRC and checksum
+//! fields zero; VMS writes it as zero under BACKUP/NOCRC. `w_checksum` is a
+//! CRC-16 (polynomial 0x8005, reflected, initial value 0, as the VAX `CRC`
+//! instruction computes it) of the 256 header bytes with the same two
+//! fields zero, so neither covers the other.
+use std::ops::Range;
+
+use crate::core::fields::{Field, FieldKind};
+
+pub const BBH_SIZE: usize = 256;
+pub const BRH_SIZE: usize = 16;
+pub const VBN_SIZE: usize = 512;
+
+pub const BBH_OPSYS_VAX: u16 = 0x0400;
+pub const STRUCLEV: u16 = 0x0101;
+/// `w_applic` of XOR blocks; data blocks carry 1.
+pub const BBH_APPLIC_XOR: u16 = 2;
+
+pub const BRH_SUMMARY: u16 = 1;
+pub const BRH_FILE: u16 = 3;
+pub const BRH_VBN: u16 = 4;
+
+pub const BSA_SSNAME: u16 = 1;
+pub const BSA_COMMAND: u16 = 2;
+pub const BSA_USERNAME: u16 = 4;
+pub const BSA_USERUIC: u16 = 5;
+pub const BSA_DATE: u16 = 6;
+pub const BSA_OPSYS: u16 = 7;
+pub const BSA_SYSVER: u16 = 8;
+pub const BSA_BACKVER: u16 = 12;
+pub const BSA_BLOCKSIZE: u16 = 13;
+pub const BSA_XORSIZE: u16 = 14;
+pub const BSA_BUFFERS: u16 = 15;
+pub const BSA_NVOLS: u16 = 17;
+pub const BSA_BACKFILES: u16 = 19;
+
+pub const BSA_FILENAME: u16 = 0x2A;
+pub const BSA_STRUCLEV: u16 = 0x2B;
+pub const BSA_FID: u16 = 0x2C;
+pub const BSA_FILESIZE: u16 = 0x2E;
+pub const BSA_UIC: u16 = 0x2F;
+pub const BSA_FPRO: u16 = 0x30;
+pub const BSA_RECATTR: u16 = 0x34;
+pub const BSA_REVISION: u16 = 0x35;
+pub const BSA_CREDATE: u16 = 0x36;
+pub const BSA_REVDATE: u16 = 0x37;
+pub const BSA_EXPDATE: u16 = 0x38;
+
+/// True when a record starts with a VMS BACKUP block header (BBH).
+pub fn has_bbh(raw: &[u8]) -> bool {
+    raw.len() >= BBH_SIZE
+        && u16::from_le_bytes([raw[0], raw[1]]) as usize == BBH_SIZE
+        && u16::from_le_bytes([raw[32], raw[33]]) == STRUCLEV
+}
+
+/// True when a record is the XOR block that closes a redundancy group.
+/// Its other header fields are the XOR of the group's blocks, so the
+/// structure level may not survive and `has_bbh` can miss it.
+pub fn is_xor_block(raw: &[u8]) -> bool {
+    raw.len() >= BBH_SIZE
+        && u16::from_le_bytes([raw[0], raw[1]]) as usize == BBH_SIZE
+        && u16::from_le_bytes([raw[6], raw[7]]) == BBH_APPLIC_XOR
+}
+
+/// `w_checksum` of a block header: CRC-16 of the header with `l_crc` and
+/// `w_checksum` zero.
+pub fn bbh_checksum(block: &[u8]) -> u16 {
+    let mut header = [0u8; BBH_SIZE];
+    let len = block.len().min(BBH_SIZE);
+    header[..len].copy_from_slice(&block[..len]);
+    header[36..40].fill(0);
+    header[254..256].fill(0);
+    header.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= u16::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+/// True when a block's header checksum matches `bbh_checksum`.
+pub fn verify_bbh_checksum(block: &[u8]) -> bool {
+    block.len() >= BBH_SIZE && block[254..256] == bbh_checksum(block).to_le_bytes()
+}
+
+/// True when a block's CRC verifies or was not recorded (BACKUP/NOCRC
+/// leaves it zero). The header checksum is checked by `verify_bbh_checksum`.
+pub fn verify_block_crc(block: &[u8]) -> bool {
+    if block.len() < BBH_SIZE {
+        return false;
+    }
+    let stored_crc = u32::from_le_bytes(block[36..40].try_into().unwrap_or_default());
+    let mut copy = block.to_vec();
+    copy[36..40].fill(0);
+    copy[254..256].fill(0);
+    stored_crc == 0 || stored_crc == crc32fast::hash(&copy)
+}
+
+/// One record of a BACKUP block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupRecord {
+    /// Offset of the record header in the block.
+    pub offset: usize,
+    pub rtype: u16,
+    pub flags: u32,
+    /// Starting VBN of the data in a VBN record.
+    pub address: u32,
+    /// Record data in the block, cut off at the end of a short block.
+    pub data: Range<usize>,
+}
+
+/// Records of a BACKUP block in order, up to the first null record. A
+/// record running past the end of `raw` (a block cut short on tape) is the
+/// last one returned.
+pub fn block_records(raw: &[u8]) -> Vec<BackupRecord> {
+    let mut records = Vec::new();
+    let mut pos = BBH_SIZE;
+    while pos + BRH_SIZE <= raw.len() {
+        let size = u16::from_le_bytes([raw[pos], raw[pos + 1]]) as usize;
+        let rtype = u16::from_le_bytes([raw[pos + 2], raw[pos + 3]]);
+        if rtype == 0 {
+            break;
+        }
+        let body = pos + BRH_SIZE;
+        records.push(BackupRecord {
+            offset: pos,
+            rtype,
+            flags: u32::from_le_bytes([raw[pos + 4], raw[pos + 5], raw[pos + 6], raw[pos + 7]]),
+            address: u32::from_le_bytes([raw[pos + 8], raw[pos + 9], raw[pos + 10], raw[pos + 11]]),
+            data: body..(body + size).min(raw.len()),
+        });
+        pos = body + size;
+    }
+    records
+}
+
+/// BSA attributes of a summary or file record as (type, data range), after
+/// the leading structure level word. Type 0 pads the rest of the record.
+pub fn record_attributes(raw: &[u8], data: &Range<usize>) -> Vec<(u16, Range<usize>)> {
+    let mut attributes = Vec::new();
+    let mut at = data.start + 2;
+    while at + 4 <= data.end {
+        let len = u16::from_le_bytes([raw[at], raw[at + 1]]) as usize;
+        let kind = u16::from_le_bytes([raw[at + 2], raw[at + 3]]);
+        if kind == 0 || at + 4 + len > data.end {
+            break;
+        }
+        attributes.push((kind, at + 4..at + 4 + len));
+        at += 4 + len;
+    }
+    attributes
+}
+
+/// BBH, BRH and BSA attribute fields of a BACKUP block, for the hex viewer;
+/// empty unless `raw` starts with a block header. XOR blocks show only
+/// their header.
+pub fn block_fields(raw: &[u8]) -> Vec<Field> {
+    if !has_bbh(raw) && !is_xor_block(raw) {
+        return Vec::new();
+    }
+    let bbh = [
+        (0, 2, "w_size"),
+        (2, 4, "w_opsys"),
+        (4, 6, "w_subsys"),
+        (6, 8, "w_applic"),
+        (8, 12, "l_number"),
+        (32, 34, "w_struclev"),
+        (34, 36, "w_volnum"),
+        (36, 40, "l_crc"),
+        (40, 44, "l_blocksize"),
+        (44, 48, "l_flags"),
+        (48, 80, "t_ssname"),
+        (254, 256, "w_checksum"),
+    ];
+    let mut fields: Vec<Field> = bbh
+        .iter()
+        .map(|&(start, end, name)| {
+            Field::new(start..end, format!("BBH {name}"), FieldKind::BlockHeader)
+        })
+        .collect();
+    if is_xor_block(raw) {
+        return fields;
+    }
+    for record in block_records(raw) {
+        let kind = match record.rtype {
+            BRH_SUMMARY => "summary",
+            BRH_FILE => "file",
+            BRH_VBN => "VBN",
+            _ => "record",
+        };
+        for (start, end, name) in [
+            (0, 2, "w_rsize"),
+            (2, 4, "w_rtype"),
+            (4, 8, "l_flags"),
+            (8, 12, "l_address"),
+        ] {
+            fields.push(Field::new(
+                record.offset + start..record.offset + end,
+                format!("BRH {name} ({kind})"),
+                FieldKind::RecordHeader,
+            ));
+        }
+        if matches!(record.rtype, BRH_SUMMARY | BRH_FILE) && record.data.len() >= 2 {
+            let body = record.data.start;
+            fields.push(Field::new(
+                body..body + 2,
+                "BSA structure level",
+                FieldKind::Attribute,
+            ));
+            for (kind, range) in record_attributes(raw, &record.data) {
+                let name = match bsa_name(kind) {
+                    Some(name) => format!("BSA {name}"),
+                    None => format!("BSA type {kind:#04X}"),
+                };
+                fields.push(Field::new(
+                    range.start - 4..range.end,
+                    name,
+                    FieldKind::Attribute,
+                ));
+            }
+        }
+    }
+    fields
+}
+
+fn bsa_name(kind: u16) -> Option<&'static str> {
+    Some(match kind {
+        BSA_SSNAME => "SSNAME",
+        BSA_COMMAND => "COMMAND",
+        BSA_USERNAME => "USERNAME",
+        BSA_USERUIC => "USERUIC",
+        BSA_DATE => "DATE",
+        BSA_OPSYS => "OPSYS",
+        BSA_SYSVER => "SYSVER",
+        BSA_BACKVER => "BACKVER",
+        BSA_BLOCKSIZE => "BLOCKSIZE",
+        BSA_XORSIZE => "XORSIZE",
+        BSA_BUFFERS => "BUFFERS",
+        BSA_NVOLS => "NVOLS",
+        BSA_BACKFILES => "BACKFILES",
+        BSA_FILENAME => "FILENAME",
+        BSA_STRUCLEV => "STRUCLEV",
+        BSA_FID => "FID",
+        BSA_FILESIZE => "FILESIZE",
+        BSA_UIC => "UIC",
+        BSA_FPRO => "FPRO",
+        BSA_RECATTR => "RECATTR",
+        BSA_REVISION => "REVISION",
+        BSA_CREDATE => "CREDATE",
+        BSA_REVDATE => "REVDATE",
+        BSA_EXPDATE => "EXPDATE",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{block_fields, BBH_SIZE};
+    use crate::backup::vms::FAT_STMLF;
+    use crate::backup::writer::{build_saveset, SavesetFile, SavesetOptions};
+
+    #[test]
+    fn names_block_record_and_attribute_fields() {
+        let opts = SavesetOptions {
+            block_size: 2048,
+            ..SavesetOptions::default()
+        };
+        let file = SavesetFile {
+            vms_name: "[000000]A.TXT;1".into(),
+            data: b"a\n".to_vec(),
+            record_format: FAT_STMLF,
+            record_attributes: 2,
+            max_record: 1,
+            modified: opts.date,
+        };
+        let blocks = build_saveset(&[file], &opts).unwrap();
+        let fields = block_fields(&blocks[0]);
+        let name_at = |offset: usize| {
+            fields
+                .iter()
+                .find(|f| f.range.contains(&offset))
+                .map(|f| f.name.as_str())
+        };
+        assert_eq!(name_at(50), Some("BBH t_ssname"));
+        assert_eq!(name_at(BBH_SIZE + 2), Some("BRH w_rtype (summary)"));
+        assert!(fields.iter().any(|f| f.name == "BSA SSNAME"));
+        assert!(fields
+            .windows(2)
+            .all(|w| w[0].range.end <= w[1].range.start));
+        assert!(block_fields(&[0u8; 2048]).is_empty());
+    }
+}
@@ -1,2 +1,3 @@
-pub mod extract;
+pub mod bbh;
 pub mod vms;
+pub mod writer;
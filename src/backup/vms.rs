@@ -1,318 +1,78 @@
-//! VMS BACKUP parsing for Phase-1 savesets: block headers, FH2/XH2 metadata, directory entries, and helpers.
-use std::convert::TryInto;
-
-use crate::{TapeError, TapeResult};
-
-/// Parsed view of a VMS BACKUP Phase-1 block header and payload.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct BackupBlock {
-    pub block_size: u16,
-    pub format_version: u8,
-    pub phase: u8,
-    pub sequence_number: u32,
-    pub checksum: u16,
-    pub payload: Vec<u8>,
-}
+//! VMS file attributes shared by the BACKUP reader and writer: RMS record
+//! formats and attributes, and protection masks. Blocks and records are
+//! decoded by `backup::bbh` and `core::vms`.
+
+/// RMS record formats as stored in the record attribute block.
+pub const FAT_UDF: u8 = 0;
+pub const FAT_FIX: u8 = 1;
+pub const FAT_VAR: u8 = 2;
+pub const FAT_VFC: u8 = 3;
+pub const FAT_STM: u8 = 4;
+pub const FAT_STMLF: u8 = 5;
+pub const FAT_STMCR: u8 = 6;
+/// Record attribute: FORTRAN carriage control in the first byte.
+pub const FAT_FTN: u8 = 0x01;
+/// Record attribute: implied carriage control.
+pub const FAT_CR: u8 = 0x02;
+/// Record attribute: print file carriage control in the VFC header.
+pub const FAT_PRN: u8 = 0x04;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RecordFormat {
     Udf,
-    Vfc,
-    Var,
     Fix,
+    Var,
+    Vfc,
+    Stm,
+    StmLf,
+    StmCr,
     Unknown(u8),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct RmsAttributes {
-    pub rfm: RecordFormat,
-    pub rattr: u16,
-    pub rattnr: u16,
-    pub rattnl: u16,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct VmsExtendedHeader {
-    pub backup_flags: Option<u16>,
-    pub high_precision_timestamp: Option<u64>,
-    pub acp_attributes: Option<u32>,
-    pub journaling_flags: Option<u16>,
-    pub file_id: Option<u32>,
-    pub sequence_number: Option<u32>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct VmsFileHeader {
-    pub file_name: String,
-    pub file_type: String,
-    pub version: u16,
-    pub record_format: RecordFormat,
-    pub record_attributes: u16,
-    pub protection_mask: u16,
-    pub creation_date: u64,
-    pub revision_date: u64,
-    pub file_size_high: u32,
-    pub file_size_low: u32,
-    pub owner_uic: u32,
-    pub rms: RmsAttributes,
-    pub extended: Option<VmsExtendedHeader>,
-}
-
-impl VmsFileHeader {
-    pub fn full_name(&self) -> String {
-        if self.file_type.is_empty() {
-            self.file_name.clone()
-        } else {
-            format!("{}.{}", self.file_name, self.file_type)
+impl RecordFormat {
+    /// RMS record format code (`FAT_*`), the inverse of
+    /// `parse_record_format`.
+    pub fn code(&self) -> u8 {
+        match self {
+            RecordFormat::Udf => FAT_UDF,
+            RecordFormat::Fix => FAT_FIX,
+            RecordFormat::Var => FAT_VAR,
+            RecordFormat::Vfc => FAT_VFC,
+            RecordFormat::Stm => FAT_STM,
+            RecordFormat::StmLf => FAT_STMLF,
+            RecordFormat::StmCr => FAT_STMCR,
+            RecordFormat::Unknown(code) => *code,
         }
     }
-}
-
-/// Read a Phase-1 VMS BACKUP block from raw bytes.
-///
-/// The expected layout is:
-/// - 0..2: little-endian block size (bytes within this block).
-/// - 2: format version byte.
-/// - 3: phase identifier (must be 1 for Phase-1 blocks).
-/// - 4..8: little-endian sequence number.
-/// - 8..10: little-endian checksum field (exposed but not validated here).
-/// - 10..N: payload bytes recorded by BACKUP.
-pub fn read_backup_block(data: &[u8]) -> TapeResult<BackupBlock> {
-    const HEADER_LEN: usize = 10;
-    // Minimum sanity bound: large enough that all header slices are in-bounds.
-    const MIN_VMS_BLOCK_HEADER: usize = 64;
 
-    if data.len() < MIN_VMS_BLOCK_HEADER {
-        return Err(TapeError::Parse(format!(
-            "VMS BACKUP block too short ({} bytes)",
-            data.len()
-        )));
-    }
-
-    if data.len() < HEADER_LEN {
-        return Err(TapeError::Parse(
-            "buffer too small for VMS BACKUP header".into(),
-        ));
-    }
-
-    let block_size = u16::from_le_bytes([data[0], data[1]]);
-    if block_size == 0 {
-        return Err(TapeError::Parse(
-            "block size must be greater than zero".into(),
-        ));
-    }
-
-    if (block_size as usize) < MIN_VMS_BLOCK_HEADER {
-        return Err(TapeError::Parse(
-            "block size smaller than VMS BACKUP header".into(),
-        ));
-    }
-
-    let block_size_usize = block_size as usize;
-    if block_size_usize > data.len() {
-        return Err(TapeError::Parse(format!(
-            "block size {} exceeds available data {}",
-            block_size,
-            data.len()
-        )));
-    }
-
-    let format_version = data[2];
-    let phase = data[3];
-    if phase != 1 {
-        return Err(TapeError::UnsupportedFormat(format!(
-            "unsupported phase {}, expected Phase-1",
-            phase
-        )));
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecordFormat::Udf => "UDF",
+            RecordFormat::Fix => "FIX",
+            RecordFormat::Var => "VAR",
+            RecordFormat::Vfc => "VFC",
+            RecordFormat::Stm => "STM",
+            RecordFormat::StmLf => "STMLF",
+            RecordFormat::StmCr => "STMCR",
+            RecordFormat::Unknown(_) => "UNKNOWN",
+        }
     }
-
-    let sequence_number = u32::from_le_bytes(data[4..8].try_into().unwrap());
-    let checksum = u16::from_le_bytes([data[8], data[9]]);
-
-    let payload = data[HEADER_LEN..block_size_usize].to_vec();
-
-    Ok(BackupBlock {
-        block_size,
-        format_version,
-        phase,
-        sequence_number,
-        checksum,
-        payload,
-    })
 }
 
-const FH2_CODE: u8 = 0x02;
-const XH2_CODE: u8 = 0x0C;
-const DIR_CODE: u8 = 0x04;
-
+/// Record format of an RMS `FAT_*` code.
 pub fn parse_record_format(code: u8) -> RecordFormat {
     match code {
-        0 => RecordFormat::Udf,
-        1 => RecordFormat::Vfc,
-        2 => RecordFormat::Var,
-        3 => RecordFormat::Fix,
+        FAT_UDF => RecordFormat::Udf,
+        FAT_FIX => RecordFormat::Fix,
+        FAT_VAR => RecordFormat::Var,
+        FAT_VFC => RecordFormat::Vfc,
+        FAT_STM => RecordFormat::Stm,
+        FAT_STMLF => RecordFormat::StmLf,
+        FAT_STMCR => RecordFormat::StmCr,
         other => RecordFormat::Unknown(other),
     }
 }
 
-/// Parse a VMS BACKUP FH2 header record.
-///
-/// Layout (all little-endian unless noted):
-/// 0: record code (0x02)
-/// 1: name length (u8)
-/// 2..: file name bytes (ASCII)
-/// after name: u16 version
-/// next: u8 record format code
-/// next: u16 record attributes
-/// next: u16 protection mask
-/// next: u64 creation date
-/// next: u64 revision date
-/// next: u32 file size high
-/// next: u32 file size low
-/// next: u32 owner UIC
-/// next: u16 rms rattr
-/// next: u16 rms rattnr
-/// next: u16 rms rattnl
-pub fn parse_fh2_record(data: &[u8]) -> TapeResult<VmsFileHeader> {
-    if data.is_empty() || data[0] != FH2_CODE {
-        return Err(TapeError::Parse("FH2 record code missing".into()));
-    }
-    if data.len() < 1 + 1 + 2 + 1 + 2 + 2 + 8 + 8 + 4 + 4 + 4 + 2 + 2 + 2 {
-        return Err(TapeError::Parse("FH2 record too short".into()));
-    }
-
-    let name_len = data[1] as usize;
-    if data.len() < 2 + name_len + 2 + 1 + 2 + 2 + 8 + 8 + 4 + 4 + 4 + 2 + 2 + 2 {
-        return Err(TapeError::Parse("FH2 record missing name bytes".into()));
-    }
-
-    let name_bytes = &data[2..2 + name_len];
-    let name_str = String::from_utf8_lossy(name_bytes).to_string();
-    let mut offset = 2 + name_len;
-
-    let version = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
-    offset += 2;
-
-    let record_format = parse_record_format(data[offset]);
-    offset += 1;
-
-    let record_attributes = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
-    offset += 2;
-
-    let protection_mask = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
-    offset += 2;
-
-    let creation_date = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
-    offset += 8;
-    let revision_date = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
-    offset += 8;
-
-    let file_size_high = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
-    offset += 4;
-    let file_size_low = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
-    offset += 4;
-
-    let owner_uic = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
-    offset += 4;
-
-    let rattr = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
-    offset += 2;
-    let rattnr = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
-    offset += 2;
-    let rattnl = u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap());
-    let rms = RmsAttributes {
-        rfm: record_format.clone(),
-        rattr,
-        rattnr,
-        rattnl,
-    };
-
-    let (file_name, file_type) = split_name_type(&name_str);
-
-    Ok(VmsFileHeader {
-        file_name,
-        file_type,
-        version,
-        record_format,
-        record_attributes,
-        protection_mask,
-        creation_date,
-        revision_date,
-        file_size_high,
-        file_size_low,
-        owner_uic,
-        rms,
-        extended: None,
-    })
-}
-
-fn split_name_type(name: &str) -> (String, String) {
-    match name.rsplit_once('.') {
-        Some((n, t)) => (n.to_string(), t.to_string()),
-        None => (name.to_string(), String::new()),
-    }
-}
-
-/// Parse an XH2 extended header record.
-pub fn parse_xh2_record(data: &[u8]) -> TapeResult<VmsExtendedHeader> {
-    if data.is_empty() || data[0] != XH2_CODE {
-        return Err(TapeError::Parse("XH2 record code missing".into()));
-    }
-    if data.len() < 1 + 2 + 8 + 4 + 2 + 4 + 4 {
-        return Err(TapeError::Parse("XH2 record too short".into()));
-    }
-
-    let mut offset = 1;
-    let backup_flags = Some(u16::from_le_bytes(
-        data[offset..offset + 2].try_into().unwrap(),
-    ));
-    offset += 2;
-    let high_precision_timestamp = Some(u64::from_le_bytes(
-        data[offset..offset + 8].try_into().unwrap(),
-    ));
-    offset += 8;
-    let acp_attributes = Some(u32::from_le_bytes(
-        data[offset..offset + 4].try_into().unwrap(),
-    ));
-    offset += 4;
-    let journaling_flags = Some(u16::from_le_bytes(
-        data[offset..offset + 2].try_into().unwrap(),
-    ));
-    offset += 2;
-    let file_id = Some(u32::from_le_bytes(
-        data[offset..offset + 4].try_into().unwrap(),
-    ));
-    offset += 4;
-    let sequence_number = Some(u32::from_le_bytes(
-        data[offset..offset + 4].try_into().unwrap(),
-    ));
-    let header = VmsExtendedHeader {
-        backup_flags,
-        high_precision_timestamp,
-        acp_attributes,
-        journaling_flags,
-        file_id,
-        sequence_number,
-    };
-    Ok(header)
-}
-
-/// Parse a directory record payload, returning the path if present.
-pub fn parse_directory_record(data: &[u8]) -> Option<String> {
-    if data.first().copied()? != DIR_CODE {
-        return None;
-    }
-    if data.len() < 2 {
-        return None;
-    }
-    let len = data[1] as usize;
-    if data.len() < 2 + len {
-        return None;
-    }
-    let path = String::from_utf8_lossy(&data[2..2 + len]).to_string();
-    Some(path)
-}
-
 /// Format a VMS protection mask into a four-field string.
 pub fn format_protection(mask: u16) -> String {
     let classes = ["System", "Owner", "Group", "World"];
@@ -344,124 +104,7 @@ fn format_rights(bits: u8) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{
-        format_protection, parse_directory_record, parse_fh2_record, parse_record_format,
-        parse_xh2_record, read_backup_block, RecordFormat,
-    };
-    use crate::TapeError;
-
-    #[test]
-    fn parses_minimal_block() {
-        let size = 70u16;
-        let mut raw = vec![0u8; size as usize];
-        raw[0..2].copy_from_slice(&size.to_le_bytes());
-        raw[2] = 2; // format version
-        raw[3] = 1; // phase
-        raw[4..8].copy_from_slice(&5u32.to_le_bytes());
-        raw[8..10].copy_from_slice(&0xABCDu16.to_le_bytes());
-        raw[10] = 0x11;
-        raw[11] = 0x22;
-
-        let block = read_backup_block(&raw).expect("should parse");
-
-        assert_eq!(block.block_size, size);
-        assert_eq!(block.format_version, 2);
-        assert_eq!(block.phase, 1);
-        assert_eq!(block.sequence_number, 5);
-        assert_eq!(block.checksum, 0xABCD);
-        assert_eq!(block.payload.len(), (size as usize) - 10);
-        assert_eq!(block.payload[0], 0x11);
-        assert_eq!(block.payload[1], 0x22);
-    }
-
-    #[test]
-    fn rejects_too_short_block() {
-        let raw = vec![0u8; 4];
-        let err = read_backup_block(&raw).unwrap_err();
-        match err {
-            TapeError::Parse(msg) => assert!(msg.contains("too short")),
-            other => panic!("unexpected error {other:?}"),
-        }
-    }
-
-    #[test]
-    fn rejects_non_phase_one() {
-        let size = 80u16;
-        let mut raw = vec![0u8; size as usize];
-        raw[0..2].copy_from_slice(&size.to_le_bytes());
-        raw[3] = 2;
-
-        let err = read_backup_block(&raw).unwrap_err();
-        match err {
-            TapeError::UnsupportedFormat(msg) => assert!(msg.contains("Phase-1")),
-            other => panic!("unexpected error {other:?}"),
-        }
-    }
-
-    #[test]
-    fn parses_fh2_record() {
-        let mut data = Vec::new();
-        data.push(0x02);
-        let name = "FILE.TXT";
-        data.push(name.len() as u8);
-        data.extend_from_slice(name.as_bytes());
-        data.extend_from_slice(&3u16.to_le_bytes()); // version
-        data.push(2); // record format VAR
-        data.extend_from_slice(&0x10u16.to_le_bytes()); // rattr
-        data.extend_from_slice(&0x1234u16.to_le_bytes()); // protection
-        data.extend_from_slice(&11u64.to_le_bytes()); // creation
-        data.extend_from_slice(&22u64.to_le_bytes()); // revision
-        data.extend_from_slice(&1u32.to_le_bytes()); // size high
-        data.extend_from_slice(&2u32.to_le_bytes()); // size low
-        data.extend_from_slice(&0xAAAAu32.to_le_bytes()); // owner
-        data.extend_from_slice(&0x20u16.to_le_bytes()); // rms rattr
-        data.extend_from_slice(&0x30u16.to_le_bytes()); // rms rattnr
-        data.extend_from_slice(&0x40u16.to_le_bytes()); // rms rattnl
-
-        let header = parse_fh2_record(&data).unwrap();
-        assert_eq!(header.file_name, "FILE");
-        assert_eq!(header.file_type, "TXT");
-        assert_eq!(header.version, 3);
-        assert!(matches!(header.record_format, RecordFormat::Var));
-        assert_eq!(header.record_attributes, 0x10);
-        assert_eq!(header.protection_mask, 0x1234);
-        assert_eq!(header.creation_date, 11);
-        assert_eq!(header.revision_date, 22);
-        assert_eq!(header.file_size_high, 1);
-        assert_eq!(header.file_size_low, 2);
-        assert_eq!(header.owner_uic, 0xAAAA);
-        assert_eq!(header.rms.rattr, 0x20);
-        assert_eq!(header.rms.rattnr, 0x30);
-        assert_eq!(header.rms.rattnl, 0x40);
-    }
-
-    #[test]
-    fn parses_xh2_record() {
-        let mut data = Vec::new();
-        data.push(0x0C);
-        data.extend_from_slice(&1u16.to_le_bytes());
-        data.extend_from_slice(&2u64.to_le_bytes());
-        data.extend_from_slice(&3u32.to_le_bytes());
-        data.extend_from_slice(&4u16.to_le_bytes());
-        data.extend_from_slice(&5u32.to_le_bytes());
-        data.extend_from_slice(&6u32.to_le_bytes());
-
-        let xh2 = parse_xh2_record(&data).unwrap();
-        assert_eq!(xh2.backup_flags, Some(1));
-        assert_eq!(xh2.high_precision_timestamp, Some(2));
-        assert_eq!(xh2.acp_attributes, Some(3));
-        assert_eq!(xh2.journaling_flags, Some(4));
-        assert_eq!(xh2.file_id, Some(5));
-        assert_eq!(xh2.sequence_number, Some(6));
-    }
-
-    #[test]
-    fn parses_directory_record() {
-        let mut data = vec![0x04, 5];
-        data.extend_from_slice(b"DIR1/");
-        let path = parse_directory_record(&data).unwrap();
-        assert_eq!(path, "DIR1/");
-    }
+    use super::{format_protection, parse_record_format, RecordFormat};
 
     #[test]
     fn formats_protection() {
@@ -476,7 +119,12 @@ mod tests {
     #[test]
     fn maps_record_format() {
         assert!(matches!(parse_record_format(0), RecordFormat::Udf));
-        assert!(matches!(parse_record_format(3), RecordFormat::Fix));
+        assert!(matches!(parse_record_format(1), RecordFormat::Fix));
+        assert!(matches!(parse_record_format(3), RecordFormat::Vfc));
+        assert!(matches!(parse_record_format(5), RecordFormat::StmLf));
         assert!(matches!(parse_record_format(9), RecordFormat::Unknown(_)));
+        for code in 0..=6 {
+            assert_eq!(parse_record_format(code).code(), code);
+        }
     }
 }
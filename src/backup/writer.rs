@@ -0,0 +1,652 @@
+//! VMS BACKUP saveset writer: packs a host directory tree into a saveset
+//! and wraps it in ANSI labels on a tape image.
+//!
+//! Blocks use the layout described in `backup::bbh`. The summary record
+//! opens the first block and each file contributes a file record followed
+//! by VBN records of whole 512-byte blocks. The block CRC and the header
+//! checksum are both computed with the CRC and checksum fields zero. Space
+//! left at the end of a block is zero, which reads as a null record.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::backup::bbh::{
+    bbh_checksum, BBH_OPSYS_VAX, BBH_SIZE, BRH_FILE, BRH_SIZE, BRH_SUMMARY, BRH_VBN, BSA_BACKFILES,
+    BSA_BACKVER, BSA_BLOCKSIZE, BSA_BUFFERS, BSA_COMMAND, BSA_CREDATE, BSA_DATE, BSA_FID,
+    BSA_FILENAME, BSA_FILESIZE, BSA_FPRO, BSA_NVOLS, BSA_OPSYS, BSA_RECATTR, BSA_REVDATE,
+    BSA_REVISION, BSA_SSNAME, BSA_STRUCLEV, BSA_SYSVER, BSA_UIC, BSA_USERNAME, BSA_USERUIC,
+    BSA_XORSIZE, STRUCLEV, VBN_SIZE,
+};
+use crate::backup::vms::{FAT_CR, FAT_FIX, FAT_STMLF, FAT_VAR};
+use crate::core::ansi::{format_label1, format_label2, format_vol1, FileLabel1, FileLabel2};
+use crate::core::block::{BlockClassification, TapeBlock};
+use crate::tap::container::ContainerFormat;
+use crate::tap::writer::write_tape_image;
+
+/// How host files are described to RMS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormatHint {
+    /// Text files as stream-LF, everything else as fixed 512-byte records.
+    Auto,
+    /// Every file as stream-LF with carriage-return attributes.
+    StreamLf,
+    /// Text lines converted to variable-length records.
+    Variable,
+    /// Every file as fixed 512-byte records.
+    Fixed512,
+}
+
+#[derive(Debug, Clone)]
+pub struct SavesetOptions {
+    /// Saveset name, used in every block header and as the tape file name.
+    pub saveset_name: String,
+    pub volume_label: String,
+    pub block_size: usize,
+    /// Recorded in the summary record; XOR blocks are not written.
+    pub group_size: u16,
+    pub record_format: RecordFormatHint,
+    /// Directory the host root maps to; `000000` is the master directory.
+    pub root_directory: String,
+    pub owner_uic: (u16, u16),
+    /// VMS protection mask (S:RWED,O:RWED,G:RE,W by default).
+    pub protection: u16,
+    pub date: NaiveDateTime,
+}
+
+impl Default for SavesetOptions {
+    fn default() -> Self {
+        Self {
+            saveset_name: "SAVE.BCK".into(),
+            volume_label: "BACKUP".into(),
+            block_size: 8192,
+            group_size: 0,
+            record_format: RecordFormatHint::Auto,
+            root_directory: "000000".into(),
+            owner_uic: (1, 4),
+            protection: 0xFA00,
+            date: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
+
+/// A host file to be saved, already named and formatted for VMS.
+#[derive(Debug, Clone)]
+pub struct SavesetFile {
+    /// `[DIR.SUB]NAME.TYP;1`
+    pub vms_name: String,
+    pub data: Vec<u8>,
+    pub record_format: u8,
+    pub record_attributes: u8,
+    pub max_record: u16,
+    pub modified: NaiveDateTime,
+}
+
+/// Convert a time to VMS format: 100 ns units since 17-Nov-1858.
+pub fn vms_time(t: NaiveDateTime) -> u64 {
+    let epoch = NaiveDate::from_ymd_opt(1858, 11, 17)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .unwrap_or_default();
+    let delta = t - epoch;
+    (delta.num_microseconds().unwrap_or(0).max(0) as u64) * 10
+}
+
+/// Map a host name component to VMS: upper case, `[A-Z0-9_$-]` only.
+pub fn vms_component(name: &str, max: usize) -> String {
+    let out: String = name
+        .chars()
+        .map(|c| c.to_ascii_uppercase())
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(max)
+        .collect();
+    if out.is_empty() {
+        "_".into()
+    } else {
+        out
+    }
+}
+
+/// Build the VMS file specification for a host path relative to the root.
+pub fn vms_file_name(relative: &Path, root_directory: &str) -> String {
+    let parts: Vec<String> = relative
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let (dirs, file) = parts.split_at(parts.len().saturating_sub(1));
+    let mut dir: Vec<String> = dirs.iter().map(|d| vms_component(d, 39)).collect();
+    if root_directory != "000000" || dir.is_empty() {
+        dir.insert(0, root_directory.to_string());
+    }
+    let file = file.first().map(String::as_str).unwrap_or("");
+    let (name, ty) = match file.rsplit_once('.') {
+        Some((n, t)) if !n.is_empty() => (n, t),
+        _ => (file, ""),
+    };
+    let ty = if ty.is_empty() {
+        String::new()
+    } else {
+        vms_component(ty, 39)
+    };
+    format!("[{}]{}.{};1", dir.join("."), vms_component(name, 39), ty)
+}
+
+fn looks_like_text(data: &[u8]) -> bool {
+    data.iter()
+        .all(|&b| matches!(b, b'\t' | b'\n' | b'\r' | 0x0C) || (0x20..0x7F).contains(&b))
+}
+
+/// Lay out host file contents according to the record format hint.
+/// Returns the file bytes, record format, attributes and longest record.
+pub fn format_file_data(data: &[u8], hint: RecordFormatHint) -> (Vec<u8>, u8, u8, u16) {
+    let longest_line = || {
+        data.split(|&b| b == b'\n')
+            .map(|l| l.len())
+            .max()
+            .unwrap_or(0)
+            .min(usize::from(u16::MAX)) as u16
+    };
+    match hint {
+        RecordFormatHint::Auto if looks_like_text(data) => {
+            (data.to_vec(), FAT_STMLF, FAT_CR, longest_line())
+        }
+        RecordFormatHint::Auto | RecordFormatHint::Fixed512 => {
+            (data.to_vec(), FAT_FIX, 0, VBN_SIZE as u16)
+        }
+        RecordFormatHint::StreamLf => (data.to_vec(), FAT_STMLF, FAT_CR, longest_line()),
+        RecordFormatHint::Variable => {
+            let mut out = Vec::with_capacity(data.len() + data.len() / 16);
+            let text = data.strip_suffix(b"\n").unwrap_or(data);
+            let mut longest = 0u16;
+            if !data.is_empty() {
+                for line in text.split(|&b| b == b'\n') {
+                    let line = line.strip_suffix(b"\r").unwrap_or(line);
+                    let line = &line[..line.len().min(usize::from(u16::MAX))];
+                    longest = longest.max(line.len() as u16);
+                    out.extend((line.len() as u16).to_le_bytes());
+                    out.extend_from_slice(line);
+                    if line.len() % 2 == 1 {
+                        out.push(0);
+                    }
+                }
+            }
+            // A record length of 0xFFFF marks the end of data within a block.
+            out.extend(0xFFFFu16.to_le_bytes());
+            (out, FAT_VAR, FAT_CR, longest)
+        }
+    }
+}
+
+/// Walk a host directory tree (sorted, files before subdirectories) into
+/// saveset files.
+pub fn collect_host_files(root: &Path, opts: &SavesetOptions) -> io::Result<Vec<SavesetFile>> {
+    let mut out = Vec::new();
+    collect_dir(root, root, opts, &mut out)?;
+    Ok(out)
+}
+
+fn collect_dir(
+    root: &Path,
+    dir: &Path,
+    opts: &SavesetOptions,
+    out: &mut Vec<SavesetFile>,
+) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    let (dirs, files): (Vec<_>, Vec<_>) = entries.into_iter().partition(|p| p.is_dir());
+    for path in files {
+        let raw = fs::read(&path)?;
+        let modified = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).naive_utc())
+            .unwrap_or(opts.date);
+        let (data, record_format, record_attributes, max_record) =
+            format_file_data(&raw, opts.record_format);
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        out.push(SavesetFile {
+            vms_name: vms_file_name(relative, &opts.root_directory),
+            data,
+            record_format,
+            record_attributes,
+            max_record,
+            modified,
+        });
+    }
+    for path in dirs {
+        collect_dir(root, &path, opts, out)?;
+    }
+    Ok(())
+}
+
+fn attribute(out: &mut Vec<u8>, kind: u16, data: &[u8]) {
+    out.extend((data.len() as u16).to_le_bytes());
+    out.extend(kind.to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+/// VMS stores longword block numbers in the FAT with the words swapped.
+fn swapped(v: u32) -> [u8; 4] {
+    let [a, b, c, d] = v.to_le_bytes();
+    [c, d, a, b]
+}
+
+fn summary_record(opts: &SavesetOptions, files: usize) -> Vec<u8> {
+    let mut out = STRUCLEV.to_le_bytes().to_vec();
+    let uic = (u32::from(opts.owner_uic.0) << 16) | u32::from(opts.owner_uic.1);
+    attribute(&mut out, BSA_SSNAME, opts.saveset_name.as_bytes());
+    let command = format!("BACKUP [...]*.*; MTA0:{}/SAVE_SET", opts.saveset_name);
+    attribute(&mut out, BSA_COMMAND, command.as_bytes());
+    attribute(&mut out, BSA_USERNAME, b"SYSTEM");
+    attribute(&mut out, BSA_USERUIC, &uic.to_le_bytes());
+    attribute(&mut out, BSA_DATE, &vms_time(opts.date).to_le_bytes());
+    attribute(&mut out, BSA_OPSYS, &BBH_OPSYS_VAX.to_le_bytes());
+    attribute(&mut out, BSA_SYSVER, b"V5.5");
+    attribute(&mut out, BSA_BACKVER, b"V5.5");
+    attribute(
+        &mut out,
+        BSA_BLOCKSIZE,
+        &(opts.block_size as u32).to_le_bytes(),
+    );
+    attribute(&mut out, BSA_XORSIZE, &opts.group_size.to_le_bytes());
+    attribute(&mut out, BSA_BUFFERS, &3u16.to_le_bytes());
+    attribute(&mut out, BSA_NVOLS, &1u16.to_le_bytes());
+    attribute(&mut out, BSA_BACKFILES, &(files as u32).to_le_bytes());
+    out
+}
+
+fn file_record(file: &SavesetFile, fid: u16, opts: &SavesetOptions) -> Vec<u8> {
+    let blocks = file.data.len().div_ceil(VBN_SIZE) as u32;
+    let ffbyte = (file.data.len() % VBN_SIZE) as u16;
+    // End of file: the block after the last full one, or the last block
+    // when it is partly used.
+    let efblk = if ffbyte == 0 { blocks + 1 } else { blocks };
+    let uic = (u32::from(opts.owner_uic.0) << 16) | u32::from(opts.owner_uic.1);
+
+    let mut fat = [0u8; 32];
+    fat[0] = file.record_format;
+    fat[1] = file.record_attributes;
+    let rsize = if file.record_format == FAT_FIX {
+        VBN_SIZE as u16
+    } else {
+        0
+    };
+    fat[2..4].copy_from_slice(&rsize.to_le_bytes());
+    fat[4..8].copy_from_slice(&swapped(blocks));
+    fat[8..12].copy_from_slice(&swapped(efblk.max(1)));
+    fat[12..14].copy_from_slice(&ffbyte.to_le_bytes());
+    fat[16..18].copy_from_slice(&file.max_record.to_le_bytes());
+
+    let mut fid_bytes = fid.to_le_bytes().to_vec();
+    fid_bytes.extend(1u16.to_le_bytes());
+    fid_bytes.extend(0u16.to_le_bytes());
+
+    let mut out = STRUCLEV.to_le_bytes().to_vec();
+    attribute(&mut out, BSA_FILENAME, file.vms_name.as_bytes());
+    attribute(&mut out, BSA_STRUCLEV, &0x0201u16.to_le_bytes());
+    attribute(&mut out, BSA_FID, &fid_bytes);
+    attribute(&mut out, BSA_FILESIZE, &blocks.to_le_bytes());
+    attribute(&mut out, BSA_UIC, &uic.to_le_bytes());
+    attribute(&mut out, BSA_FPRO, &opts.protection.to_le_bytes());
+    attribute(&mut out, BSA_RECATTR, &fat);
+    attribute(&mut out, BSA_REVISION, &1u16.to_le_bytes());
+    attribute(
+        &mut out,
+        BSA_CREDATE,
+        &vms_time(file.modified).to_le_bytes(),
+    );
+    attribute(
+        &mut out,
+        BSA_REVDATE,
+        &vms_time(file.modified).to_le_bytes(),
+    );
+    out
+}
+
+/// Accumulates records into saveset blocks.
+struct BlockBuilder<'a> {
+    opts: &'a SavesetOptions,
+    blocks: Vec<Vec<u8>>,
+    current: Vec<u8>,
+}
+
+impl<'a> BlockBuilder<'a> {
+    fn new(opts: &'a SavesetOptions) -> Self {
+        Self {
+            opts,
+            blocks: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    fn free(&self) -> usize {
+        if self.current.is_empty() {
+            self.opts.block_size - BBH_SIZE
+        } else {
+            self.opts.block_size - self.current.len()
+        }
+    }
+
+    fn record(&mut self, rtype: u16, address: u32, data: &[u8]) {
+        if BRH_SIZE + data.len() > self.free() {
+            self.flush();
+        }
+        if self.current.is_empty() {
+            self.current = vec![0u8; BBH_SIZE];
+        }
+        self.current.extend((data.len() as u16).to_le_bytes());
+        self.current.extend(rtype.to_le_bytes());
+        self.current.extend(0u32.to_le_bytes());
+        self.current.extend(address.to_le_bytes());
+        self.current.extend(0u32.to_le_bytes());
+        self.current.extend_from_slice(data);
+    }
+
+    fn flush(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        let mut block = std::mem::take(&mut self.current);
+        block.resize(self.opts.block_size, 0);
+        let number = self.blocks.len() as u32 + 1;
+        write_bbh(&mut block, number, self.opts);
+        self.blocks.push(block);
+    }
+}
+
+fn write_bbh(block: &mut [u8], number: u32, opts: &SavesetOptions) {
+    block[0..2].copy_from_slice(&(BBH_SIZE as u16).to_le_bytes());
+    block[2..4].copy_from_slice(&BBH_OPSYS_VAX.to_le_bytes());
+    block[8..12].copy_from_slice(&number.to_le_bytes());
+    block[32..34].copy_from_slice(&STRUCLEV.to_le_bytes());
+    block[34..36].copy_from_slice(&1u16.to_le_bytes());
+    block[40..44].copy_from_slice(&(opts.block_size as u32).to_le_bytes());
+    let name = opts.saveset_name.as_bytes();
+    let len = name.len().min(31);
+    block[48] = len as u8;
+    block[49..49 + len].copy_from_slice(&name[..len]);
+
+    let crc = crc32fast::hash(block);
+    block[36..40].copy_from_slice(&crc.to_le_bytes());
+    let checksum = bbh_checksum(block);
+    block[254..256].copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// Build the saveset blocks for a list of files.
+pub fn build_saveset(files: &[SavesetFile], opts: &SavesetOptions) -> io::Result<Vec<Vec<u8>>> {
+    if opts.block_size < 2048
+        || opts.block_size > 65024
+        || !opts.block_size.is_multiple_of(VBN_SIZE)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "block size {} is not a multiple of 512 in 2048..65024",
+                opts.block_size
+            ),
+        ));
+    }
+    let mut builder = BlockBuilder::new(opts);
+    builder.record(BRH_SUMMARY, 0, &summary_record(opts, files.len()));
+    for (i, file) in files.iter().enumerate() {
+        let record = file_record(file, i as u16 + 1, opts);
+        if BRH_SIZE + record.len() > opts.block_size - BBH_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("file record for {} does not fit a block", file.vms_name),
+            ));
+        }
+        builder.record(BRH_FILE, 0, &record);
+        let mut padded = file.data.clone();
+        padded.resize(padded.len().div_ceil(VBN_SIZE) * VBN_SIZE, 0);
+        let mut vbn = 1u32;
+        let mut rest = padded.as_slice();
+        while !rest.is_empty() {
+            let mut room = builder.free().saturating_sub(BRH_SIZE) / VBN_SIZE * VBN_SIZE;
+            if room == 0 {
+                builder.flush();
+                room = builder.free().saturating_sub(BRH_SIZE) / VBN_SIZE * VBN_SIZE;
+            }
+            let (chunk, tail) = rest.split_at(room.min(rest.len()));
+            builder.record(BRH_VBN, vbn, chunk);
+            vbn += (chunk.len() / VBN_SIZE) as u32;
+            rest = tail;
+        }
+    }
+    builder.flush();
+    Ok(builder.blocks)
+}
+
+/// Lay out a labelled saveset volume as tape blocks: VOL1/HDR1/HDR2, the
+/// saveset blocks, then EOF1/EOF2, each group in its own tape file.
+pub fn saveset_tape_blocks(saveset: &[Vec<u8>], opts: &SavesetOptions) -> Vec<TapeBlock> {
+    let date = opts.date.date();
+    let mut label1 = FileLabel1 {
+        file_id: opts.saveset_name.clone(),
+        file_set_id: opts.volume_label.clone(),
+        section: 1,
+        sequence: 1,
+        creation_date: None,
+        expiration_date: None,
+        block_count: 0,
+        system_code: "DECVMSBACKUP".into(),
+    };
+    let label2 = FileLabel2 {
+        record_format: 'F',
+        block_length: opts.block_size as u32,
+        record_length: opts.block_size as u32,
+    };
+    let mut records: Vec<(u32, Vec<u8>)> = vec![
+        (0, format_vol1(&opts.volume_label, "")),
+        (0, format_label1("HDR1", &label1, date)),
+        (0, format_label2("HDR2", &label2)),
+    ];
+    records.extend(saveset.iter().map(|b| (1, b.clone())));
+    label1.block_count = saveset.len() as u32;
+    records.push((2, format_label1("EOF1", &label1, date)));
+    records.push((2, format_label2("EOF2", &label2)));
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(i, (tape_file, data))| TapeBlock {
+            index: i as u32,
+            tape_file,
+            size: data.len(),
+            raw: data.into(),
+            classification: BlockClassification::Unknown,
+        })
+        .collect()
+}
+
+/// Save a host directory tree to a labelled saveset tape image.
+/// Returns the number of files written.
+pub fn write_saveset_tape(
+    root: &Path,
+    out: &Path,
+    format: ContainerFormat,
+    opts: &SavesetOptions,
+) -> io::Result<usize> {
+    let files = collect_host_files(root, opts)?;
+    let saveset = build_saveset(&files, opts)?;
+    write_tape_image(out, &saveset_tape_blocks(&saveset, opts), format)?;
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{
+        build_saveset, format_file_data, saveset_tape_blocks, vms_file_name, RecordFormatHint,
+        SavesetFile, SavesetOptions,
+    };
+    use crate::backup::bbh::{
+        has_bbh, verify_bbh_checksum, verify_block_crc, BBH_SIZE, BRH_FILE, BRH_SIZE, BRH_SUMMARY,
+        BRH_VBN, BSA_FILENAME,
+    };
+    use crate::backup::vms::{FAT_STMLF, FAT_VAR};
+    use crate::core::ansi::{parse_ansi_label, AnsiLabel};
+    use crate::tap::reader::read_tap_blocks;
+
+    fn options() -> SavesetOptions {
+        SavesetOptions {
+            block_size: 2048,
+            date: chrono::NaiveDate::from_ymd_opt(2024, 2, 1)
+                .and_then(|d| d.and_hms_opt(12, 0, 0))
+                .unwrap(),
+            ..SavesetOptions::default()
+        }
+    }
+
+    /// Walk the records of a block as (rtype, address, data).
+    fn records(block: &[u8]) -> Vec<(u16, u32, Vec<u8>)> {
+        let mut out = Vec::new();
+        let mut pos = BBH_SIZE;
+        while pos + BRH_SIZE <= block.len() {
+            let size = u16::from_le_bytes([block[pos], block[pos + 1]]) as usize;
+            let rtype = u16::from_le_bytes([block[pos + 2], block[pos + 3]]);
+            if rtype == 0 {
+                break;
+            }
+            let address = u32::from_le_bytes(block[pos + 8..pos + 12].try_into().unwrap());
+            let body = pos + BRH_SIZE;
+            out.push((rtype, address, block[body..body + size].to_vec()));
+            pos = body + size;
+        }
+        out
+    }
+
+    fn attribute(record: &[u8], kind: u16) -> Option<Vec<u8>> {
+        let mut pos = 2;
+        while pos + 4 <= record.len() {
+            let size = u16::from_le_bytes([record[pos], record[pos + 1]]) as usize;
+            let t = u16::from_le_bytes([record[pos + 2], record[pos + 3]]);
+            if t == kind {
+                return Some(record[pos + 4..pos + 4 + size].to_vec());
+            }
+            pos += 4 + size;
+        }
+        None
+    }
+
+    #[test]
+    fn maps_host_names_to_vms() {
+        assert_eq!(
+            vms_file_name(Path::new("readme.txt"), "000000"),
+            "[000000]README.TXT;1"
+        );
+        assert_eq!(
+            vms_file_name(Path::new("src/a-b.c"), "000000"),
+            "[SRC]A-B.C;1"
+        );
+        assert_eq!(
+            vms_file_name(Path::new("src/Makefile"), "USER"),
+            "[USER.SRC]MAKEFILE.;1"
+        );
+    }
+
+    #[test]
+    fn formats_variable_records() {
+        let (data, rfm, _, longest) = format_file_data(b"ab\r\nc\n", RecordFormatHint::Variable);
+        assert_eq!(rfm, FAT_VAR);
+        assert_eq!(longest, 2);
+        assert_eq!(data, b"\x02\x00ab\x01\x00c\x00\xFF\xFF");
+        let (_, rfm, _, _) = format_file_data(b"text\n", RecordFormatHint::Auto);
+        assert_eq!(rfm, FAT_STMLF);
+    }
+
+    #[test]
+    fn builds_verifiable_saveset() {
+        let opts = options();
+        let files = vec![
+            SavesetFile {
+                vms_name: "[000000]SMALL.TXT;1".into(),
+                data: b"hello\n".to_vec(),
+                record_format: FAT_STMLF,
+                record_attributes: 2,
+                max_record: 5,
+                modified: opts.date,
+            },
+            SavesetFile {
+                vms_name: "[000000]BIG.DAT;1".into(),
+                data: (0..5000u32).map(|i| i as u8).collect(),
+                record_format: 1,
+                record_attributes: 0,
+                max_record: 512,
+                modified: opts.date,
+            },
+        ];
+        let blocks = build_saveset(&files, &opts).unwrap();
+        assert!(blocks.len() > 2);
+        for (i, block) in blocks.iter().enumerate() {
+            assert_eq!(block.len(), 2048);
+            assert!(verify_block_crc(block), "block {i}");
+            assert!(verify_bbh_checksum(block), "block {i}");
+            assert_eq!(
+                u32::from_le_bytes(block[8..12].try_into().unwrap()),
+                i as u32 + 1
+            );
+            assert_eq!(&block[49..57], b"SAVE.BCK");
+        }
+        let all: Vec<_> = blocks.iter().flat_map(|b| records(b)).collect();
+        assert_eq!(all[0].0, BRH_SUMMARY);
+        let names: Vec<Vec<u8>> = all
+            .iter()
+            .filter(|r| r.0 == BRH_FILE)
+            .filter_map(|r| attribute(&r.2, BSA_FILENAME))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                b"[000000]SMALL.TXT;1".to_vec(),
+                b"[000000]BIG.DAT;1".to_vec()
+            ]
+        );
+
+        // The second file's VBN records reassemble to its data.
+        let big_start = all.iter().rposition(|r| r.0 == BRH_FILE).unwrap();
+        let mut data = Vec::new();
+        let mut next_vbn = 1;
+        for (rtype, vbn, body) in &all[big_start + 1..] {
+            assert_eq!(*rtype, BRH_VBN);
+            assert_eq!(*vbn, next_vbn);
+            next_vbn += (body.len() / 512) as u32;
+            data.extend_from_slice(body);
+        }
+        data.truncate(5000);
+        assert_eq!(data, files[1].data);
+
+        let tape = saveset_tape_blocks(&blocks, &opts);
+        assert!(
+            matches!(parse_ansi_label(&tape[1].raw), Some(AnsiLabel::Header1(h)) if h.file_id == "SAVE.BCK")
+        );
+        match parse_ansi_label(&tape[tape.len() - 2].raw) {
+            Some(AnsiLabel::EndOfFile1(h)) => assert_eq!(h.block_count as usize, blocks.len()),
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_eq!(tape.last().unwrap().tape_file, 2);
+    }
+
+    #[test]
+    fn header_checksum_matches_vms_savesets() {
+        for path in ["tests/data/TA0117.TAP", "tests/data/TA0114.TAP"] {
+            let blocks = read_tap_blocks(path).expect("missing VMS fixture");
+            let headers: Vec<_> = blocks.iter().filter(|b| has_bbh(&b.raw)).collect();
+            assert!(!headers.is_empty(), "{path}");
+            for block in headers {
+                assert!(
+                    verify_bbh_checksum(&block.raw),
+                    "{path} record {}",
+                    block.index
+                );
+            }
+        }
+    }
+}
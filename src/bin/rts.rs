@@ -0,0 +1,10 @@
+//! `rts`: headless tape inspection and extraction; see `cli` for usage.
+use std::io;
+
+use retro_tape_studio_v6_safe::cli::run;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = run(&args, &mut io::stdout().lock(), &mut io::stderr().lock());
+    std::process::exit(code);
+}
@@ -0,0 +1,530 @@
+//! Headless `rts` command line: the same loading, detection, reconstruction
+//! and extraction pipeline as the GUI, for batch work on many tapes.
+//!
+//! ```text
+//! rts info    TAPE [--log LOG] [--format NAME]
+//! rts list    TAPE [--log LOG] [--format NAME]
+//! rts extract TAPE OUTDIR [--include GLOB].. [--exclude GLOB].. [--log LOG] [--format NAME]
+//! rts find    TAPE [QUERY].. [--log LOG] [--format NAME]
+//! rts verify  TAPE [--log LOG]
+//! rts dump    TAPE RECORD
+//! ```
+//!
+//! Every subcommand runs the `verify` checks and exits with `EXIT_DAMAGED`
+//! when any fail, so scripts can sort tapes without parsing output.
+pub mod verify;
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::core::ansi::{parse_ansi_label, AnsiLabel};
+use crate::core::block::{block_at, TapeFormat};
+use crate::core::extract::{create_parent_dirs, extract_file, host_bytes, sanitize_filename};
+use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::handler::registry;
+use crate::core::rsts::format_ppn;
+use crate::core::search::{
+    file_date, match_context, record_format_code, search_tape_files, ContentPattern, FileQuery,
+    NamePattern, UicFilter,
+};
+use crate::gui::input::{load_log_file, set_format_override, set_tape_blocks};
+use crate::gui::state::AppState;
+use crate::tap::container::ContainerFormat;
+use crate::tap::stream::TapeIndex;
+use crate::utils::glob::glob_match;
+use crate::utils::hex::format_hex_with_ascii;
+use crate::utils::text::sanitize_display;
+
+use self::verify::{verify_tape, Problem};
+
+/// Command completed and no damage was found.
+pub const EXIT_OK: i32 = 0;
+/// Command completed but the tape or its log shows damage.
+pub const EXIT_DAMAGED: i32 = 1;
+/// Bad command line.
+pub const EXIT_USAGE: i32 = 2;
+/// The tape or log could not be read, or output could not be written.
+pub const EXIT_ERROR: i32 = 3;
+
+const USAGE: &str = "usage:
+  rts info    TAPE [--log LOG] [--format NAME]
+  rts list    TAPE [--log LOG] [--format NAME]
+  rts extract TAPE OUTDIR [--include GLOB].. [--exclude GLOB].. [--log LOG] [--format NAME]
+  rts find    TAPE [QUERY].. [--log LOG] [--format NAME]
+  rts verify  TAPE [--log LOG]
+  rts dump    TAPE RECORD
+query options for find:
+  --name GLOB  --regex RE  --uic [G,M]  --since YYYY-MM-DD  --until YYYY-MM-DD
+  --rfm FORMAT  --min-size N  --max-size N  --grep TEXT  --grep-regex RE";
+
+#[derive(Debug, Default)]
+struct Options {
+    positional: Vec<String>,
+    log: Option<PathBuf>,
+    format: Option<TapeFormat>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    query: FileQuery,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{arg} needs a value"))
+        };
+        match arg.as_str() {
+            "--log" => opts.log = Some(PathBuf::from(value()?)),
+            "--format" => {
+                let name = value()?;
+                opts.format =
+                    Some(parse_format(&name).ok_or_else(|| format!("unknown format {name}"))?);
+            }
+            "--include" => opts.include.push(value()?),
+            "--exclude" => opts.exclude.push(value()?),
+            "--name" => opts.query.name = Some(NamePattern::Glob(value()?)),
+            "--regex" => opts.query.name = Some(NamePattern::regex(&value()?)?),
+            "--uic" => {
+                let uic = value()?;
+                opts.query.owner =
+                    Some(UicFilter::parse(&uic).ok_or_else(|| format!("bad UIC {uic}"))?);
+            }
+            "--since" => opts.query.since = Some(parse_date(&value()?)?),
+            "--until" => opts.query.until = Some(parse_date(&value()?)?),
+            "--rfm" => {
+                let name = value()?;
+                opts.query.record_format = Some(
+                    record_format_code(&name)
+                        .ok_or_else(|| format!("unknown record format {name}"))?,
+                );
+            }
+            "--min-size" => opts.query.min_size = Some(parse_size(&value()?)?),
+            "--max-size" => opts.query.max_size = Some(parse_size(&value()?)?),
+            "--grep" => opts.query.content = Some(ContentPattern::text(&value()?)?),
+            "--grep-regex" => opts.query.content = Some(ContentPattern::regex(&value()?)?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => opts.positional.push(arg.clone()),
+        }
+    }
+    Ok(opts)
+}
+
+fn parse_date(text: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").map_err(|_| format!("bad date {text}"))
+}
+
+fn parse_size(text: &str) -> Result<u64, String> {
+    text.parse().map_err(|_| format!("bad size {text}"))
+}
+
+/// Registered format whose handler name or format name matches `name`,
+/// ignoring case and punctuation (`rt11`, `RT-11`, `vms`).
+fn parse_format(name: &str) -> Option<TapeFormat> {
+    let key = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    let wanted = key(name);
+    registry()
+        .handlers()
+        .find(|h| key(h.name()) == wanted || key(&format!("{:?}", h.format())) == wanted)
+        .map(|h| h.format())
+}
+
+/// A loaded tape with its log, decoded files and damage report.
+struct Tape {
+    container: ContainerFormat,
+    state: AppState,
+    problems: Vec<Problem>,
+}
+
+fn load_tape(path: &Path, opts: &Options) -> Result<Tape, String> {
+    let index = TapeIndex::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let blocks = index
+        .blocks()
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut state = AppState::default();
+    if let Some(log) = &opts.log {
+        load_log_file(log, &mut state).map_err(|e| format!("{}: {e}", log.display()))?;
+    }
+    set_tape_blocks(blocks, &mut state).map_err(|e| format!("{}: {e}", path.display()))?;
+    if opts.format.is_some() {
+        set_format_override(opts.format.clone(), &mut state);
+    }
+    let problems = verify_tape(&state.blocks, state.log_state.data.as_ref());
+    Ok(Tape {
+        container: index.format(),
+        state,
+        problems,
+    })
+}
+
+/// Run `rts` with `args` (without the program name), writing results to
+/// `out` and diagnostics to `err`. Returns the process exit code.
+pub fn run(args: &[String], out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let Some((command, rest)) = args.split_first() else {
+        let _ = writeln!(err, "{USAGE}");
+        return EXIT_USAGE;
+    };
+    if matches!(command.as_str(), "-h" | "--help" | "help") {
+        let _ = writeln!(out, "{USAGE}");
+        return EXIT_OK;
+    }
+    let opts = match parse_options(rest) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            let _ = writeln!(err, "rts: {msg}\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+    let arity = match command.as_str() {
+        "info" | "list" | "find" | "verify" => 1,
+        "extract" | "dump" => 2,
+        _ => {
+            let _ = writeln!(err, "rts: unknown command {command}\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+    if opts.positional.len() != arity {
+        let _ = writeln!(err, "rts {command}: wrong number of arguments\n{USAGE}");
+        return EXIT_USAGE;
+    }
+    let record = if command == "dump" {
+        match opts.positional[1].parse::<u32>() {
+            Ok(n) => Some(n),
+            Err(_) => {
+                let _ = writeln!(err, "rts dump: bad record number {}", opts.positional[1]);
+                return EXIT_USAGE;
+            }
+        }
+    } else {
+        None
+    };
+
+    let tape = match load_tape(Path::new(&opts.positional[0]), &opts) {
+        Ok(tape) => tape,
+        Err(msg) => {
+            let _ = writeln!(err, "rts: {msg}");
+            return EXIT_ERROR;
+        }
+    };
+    let result = match command.as_str() {
+        "info" => info(&tape, out),
+        "list" => list(&tape, out),
+        "find" => find(&tape, &opts.query, out),
+        "extract" => extract(&tape, Path::new(&opts.positional[1]), &opts, out, err),
+        "verify" => verify(&tape, out),
+        _ => dump(&tape, record.unwrap_or_default(), out),
+    };
+    match result {
+        Ok(true) if tape.problems.is_empty() => EXIT_OK,
+        Ok(complete) => {
+            if !tape.problems.is_empty() && command != "verify" {
+                let _ = writeln!(
+                    err,
+                    "rts: {} problem(s) found; run `rts verify` for details",
+                    tape.problems.len()
+                );
+            }
+            if complete {
+                EXIT_DAMAGED
+            } else {
+                EXIT_ERROR
+            }
+        }
+        Err(e) => {
+            let _ = writeln!(err, "rts: {e}");
+            EXIT_ERROR
+        }
+    }
+}
+
+/// Display name of a format: its handler's name when one is registered.
+fn format_name(format: &TapeFormat) -> String {
+    registry()
+        .get(format)
+        .map(|h| h.name().to_string())
+        .unwrap_or_else(|| format!("{format:?}"))
+}
+
+fn info(tape: &Tape, out: &mut dyn Write) -> io::Result<bool> {
+    let state = &tape.state;
+    let tape_files = state.blocks.last().map_or(0, |b| b.tape_file + 1);
+    writeln!(out, "Container: {}", tape.container.label())?;
+    writeln!(
+        out,
+        "Records:   {} in {} tape file(s)",
+        state.blocks.len(),
+        tape_files
+    )?;
+    match &state.format_override {
+        Some(format) => writeln!(out, "Format:    {} (override)", format_name(format))?,
+        None => {
+            let confidence = state
+                .detection
+                .as_ref()
+                .map_or(0.0, |d| d.confidence(&state.detected_format));
+            writeln!(
+                out,
+                "Format:    {} ({:.0}%)",
+                format_name(&state.detected_format),
+                confidence * 100.0
+            )?;
+        }
+    }
+    if let Some(detection) = &state.detection {
+        for c in &detection.candidates {
+            writeln!(
+                out,
+                "  {:<22} {:>4.0}%  {} tape file(s)",
+                c.name,
+                c.confidence * 100.0,
+                c.tape_files
+            )?;
+        }
+    }
+
+    let labels: Vec<(u32, AnsiLabel)> = state
+        .blocks
+        .iter()
+        .filter_map(|b| parse_ansi_label(&b.raw).map(|l| (b.index, l)))
+        .collect();
+    if !labels.is_empty() {
+        writeln!(out, "Labels:")?;
+        for (record, label) in &labels {
+            writeln!(
+                out,
+                "  {record:>6}  {}",
+                sanitize_display(&label_text(label))
+            )?;
+        }
+    }
+
+    if let Some(summary) = &state.summary {
+        writeln!(
+            out,
+            "Files:     {} in {} director(ies), {} bytes in {} record(s), {:.1}% efficiency",
+            summary.total_files,
+            summary.total_directories,
+            summary.total_bytes,
+            summary.total_blocks,
+            summary.block_efficiency * 100.0
+        )?;
+        if let Some(largest) = &summary.largest_file {
+            writeln!(out, "Largest:   {largest}")?;
+        }
+        if let Some(log) = &state.log_state.data {
+            writeln!(
+                out,
+                "Log:       {}, {} warning(s), {} error(s)",
+                log.dialect.label(),
+                summary.log_warnings,
+                summary.log_errors
+            )?;
+            for (key, value) in summary.tape.iter().flat_map(|t| t.fields()) {
+                writeln!(out, "  {key}: {value}")?;
+            }
+            for (key, value) in [
+                ("Tracks", &summary.tracks),
+                ("Density", &summary.density),
+                ("Blocks read", &summary.blocks_read),
+            ] {
+                if let Some(value) = value {
+                    writeln!(out, "  {key}: {value}")?;
+                }
+            }
+        }
+    }
+    writeln!(out, "Problems:  {}", tape.problems.len())?;
+    Ok(true)
+}
+
+fn label_text(label: &AnsiLabel) -> String {
+    match label {
+        AnsiLabel::Volume(v) => format!("VOL1 volume {} owner {}", v.volume_id, v.owner),
+        AnsiLabel::Header1(l) | AnsiLabel::EndOfFile1(l) | AnsiLabel::EndOfVolume1(l) => {
+            let id = match label {
+                AnsiLabel::Header1(_) => "HDR1",
+                AnsiLabel::EndOfFile1(_) => "EOF1",
+                _ => "EOV1",
+            };
+            format!(
+                "{id} {} set {} seq {} created {} blocks {} system {}",
+                l.file_id,
+                l.file_set_id,
+                l.sequence,
+                l.creation_date.as_deref().unwrap_or("-"),
+                l.block_count,
+                l.system_code
+            )
+        }
+        AnsiLabel::Header2(l) | AnsiLabel::EndOfFile2(l) => {
+            let id = if matches!(label, AnsiLabel::Header2(_)) {
+                "HDR2"
+            } else {
+                "EOF2"
+            };
+            format!(
+                "{id} format {} block {} record {}",
+                l.record_format, l.block_length, l.record_length
+            )
+        }
+        AnsiLabel::Other(id) => id.clone(),
+    }
+}
+
+/// Leaves of a reconstructed tree in tape order.
+fn leaves<'a>(nodes: &'a [TapeFile], out: &mut Vec<&'a TapeFile>) {
+    for node in nodes {
+        if node.children.is_empty() {
+            out.push(node);
+        } else {
+            leaves(&node.children, out);
+        }
+    }
+}
+
+/// Owner in each system's own notation.
+fn file_owner(meta: &FileMetadata) -> Option<String> {
+    match meta {
+        FileMetadata::Vms(m) => Some(format!("[{:o},{:o}]", m.owner_uic.0, m.owner_uic.1)),
+        FileMetadata::Rsx(m) => Some(format!("[{:o},{:o}]", m.uic.0, m.uic.1)),
+        FileMetadata::Rsts(m) => Some(format_ppn(m.owner_uic)),
+        FileMetadata::Unix(m) => Some(format!("{}/{}", m.uid, m.gid)),
+        FileMetadata::Rt11(_) | FileMetadata::Pdp10(_) | FileMetadata::Raw => None,
+    }
+}
+
+/// Size, date, owner and path of a file as `list` and `find` print it.
+fn file_row(file: &TapeFile) -> String {
+    format!(
+        "{:>10}  {:<19}  {:<12}  {}",
+        file.size_bytes,
+        file_date(&file.metadata).unwrap_or("-"),
+        file_owner(&file.metadata).as_deref().unwrap_or("-"),
+        sanitize_display(&file.path.to_string_path())
+    )
+}
+
+fn list(tape: &Tape, out: &mut dyn Write) -> io::Result<bool> {
+    let mut files = Vec::new();
+    leaves(&tape.state.files, &mut files);
+    for file in &files {
+        writeln!(out, "{}", file_row(file))?;
+    }
+    writeln!(out, "{} file(s)", files.len())?;
+    Ok(true)
+}
+
+/// List the files matching `query`, with the offset and surroundings of
+/// each content match.
+fn find(tape: &Tape, query: &FileQuery, out: &mut dyn Write) -> io::Result<bool> {
+    let mut files = Vec::new();
+    leaves(&tape.state.files, &mut files);
+    let blocks = &tape.state.blocks;
+    let hits = search_tape_files(files.iter().copied(), blocks, query);
+    for hit in &hits {
+        let file = files[hit.index];
+        writeln!(out, "{}", file_row(file))?;
+        if hit.matches.is_empty() {
+            continue;
+        }
+        let data = host_bytes(file, blocks);
+        for found in &hit.matches {
+            writeln!(
+                out,
+                "{:>10}  {}",
+                format!("@{}", found.offset),
+                match_context(&data, found, 24)
+            )?;
+        }
+    }
+    writeln!(out, "{} file(s) found", hits.len())?;
+    Ok(true)
+}
+
+fn selected(path: &str, opts: &Options) -> bool {
+    (opts.include.is_empty() || opts.include.iter().any(|p| glob_match(p, path)))
+        && !opts.exclude.iter().any(|p| glob_match(p, path))
+}
+
+/// Extract the selected files; `Ok(false)` when any could not be written.
+fn extract(
+    tape: &Tape,
+    outdir: &Path,
+    opts: &Options,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> io::Result<bool> {
+    let mut files = Vec::new();
+    leaves(&tape.state.files, &mut files);
+    let mut written = 0usize;
+    let mut failed = 0usize;
+    for file in files {
+        let path = file.path.to_string_path();
+        if !selected(&path, opts) {
+            continue;
+        }
+        let target = outdir.join(sanitize_filename(&path));
+        let result = create_parent_dirs(outdir, &target)
+            .and_then(|_| extract_file(file, &tape.state.blocks, outdir));
+        match result {
+            Ok(()) => {
+                written += 1;
+                writeln!(out, "{}", sanitize_display(&path))?;
+            }
+            Err(e) => {
+                failed += 1;
+                writeln!(err, "rts: {}: {e}", sanitize_display(&path))?;
+            }
+        }
+    }
+    writeln!(
+        out,
+        "{written} file(s) extracted to {}",
+        sanitize_display(&outdir.display().to_string())
+    )?;
+    Ok(failed == 0)
+}
+
+fn verify(tape: &Tape, out: &mut dyn Write) -> io::Result<bool> {
+    for problem in &tape.problems {
+        writeln!(out, "{}", sanitize_display(&problem.to_string()))?;
+    }
+    let status = if tape.problems.is_empty() {
+        "OK"
+    } else {
+        "DAMAGED"
+    };
+    writeln!(
+        out,
+        "{status}: {} record(s), {} problem(s)",
+        tape.state.blocks.len(),
+        tape.problems.len()
+    )?;
+    Ok(true)
+}
+
+fn dump(tape: &Tape, record: u32, out: &mut dyn Write) -> io::Result<bool> {
+    let Some(block) = block_at(&tape.state.blocks, record) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "record {record} not on tape ({} records)",
+                tape.state.blocks.len()
+            ),
+        ));
+    };
+    writeln!(
+        out,
+        "Record {}: {} bytes, tape file {}, {:?}",
+        block.index, block.size, block.tape_file, block.classification
+    )?;
+    writeln!(out, "{}", format_hex_with_ascii(&block.raw))?;
+    Ok(true)
+}
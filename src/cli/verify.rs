@@ -0,0 +1,207 @@
+//! Damage checks shared by every `rts` subcommand: BACKUP block CRCs on
+//! the tape and consistency between the tape and its STP2TAP log.
+use std::fmt;
+
+use crate::backup::bbh::{has_bbh, verify_block_crc};
+use crate::core::block::TapeBlock;
+use crate::log::crosscheck::{cross_check, LayoutFinding};
+use crate::log::parse::{flagged_records, LogData, LogLevel};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// VMS BACKUP block whose CRC does not verify.
+    BadChecksum { record: u32 },
+    /// VMS BACKUP block cut off before its `l_blocksize`.
+    ShortBlock { record: u32 },
+    /// Log line reporting a read error.
+    LogError { line: String },
+    /// Warning or error in the log about a record the tape does not hold.
+    MissingRecord { record: usize },
+    /// Logged record layout disagrees with the tape.
+    Layout(LayoutFinding),
+    /// "Blocks read" in the log disagrees with the records on tape.
+    RecordCount { logged: usize, found: usize },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::BadChecksum { record } => {
+                write!(f, "record {record}: BACKUP block CRC mismatch")
+            }
+            Problem::ShortBlock { record } => {
+                write!(
+                    f,
+                    "record {record}: BACKUP block shorter than its block size"
+                )
+            }
+            Problem::LogError { line } => write!(f, "log error: {}", line.trim()),
+            Problem::MissingRecord { record } => {
+                write!(f, "log refers to record {record}, not on tape")
+            }
+            Problem::Layout(finding) => write!(f, "log layout: {finding}"),
+            Problem::RecordCount { logged, found } => {
+                write!(f, "log read {logged} blocks, tape holds {found} records")
+            }
+        }
+    }
+}
+
+/// Every damage indication found on the tape and in its log. A BACKUP
+/// block shorter than its `l_blocksize` is reported as short; its CRC
+/// cannot match and is not checked.
+pub fn verify_tape(blocks: &[TapeBlock], log: Option<&LogData>) -> Vec<Problem> {
+    let mut problems: Vec<Problem> = blocks
+        .iter()
+        .filter(|b| has_bbh(&b.raw))
+        .filter_map(|b| {
+            if is_short_block(&b.raw) {
+                Some(Problem::ShortBlock { record: b.index })
+            } else if !verify_block_crc(&b.raw) {
+                Some(Problem::BadChecksum { record: b.index })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let Some(log) = log else {
+        return problems;
+    };
+    problems.extend(
+        log.entries
+            .iter()
+            .filter(|e| e.level == LogLevel::Error)
+            .map(|e| Problem::LogError {
+                line: e.line.clone(),
+            }),
+    );
+    problems.extend(
+        flagged_records(log, &blocks.iter().map(|b| b.size).collect::<Vec<_>>())
+            .into_iter()
+            .filter(|(record, _)| *record >= blocks.len())
+            .map(|(record, _)| Problem::MissingRecord { record }),
+    );
+    problems.extend(cross_check(log, blocks).into_iter().map(Problem::Layout));
+    if let Some(logged) = log
+        .metadata
+        .get("Blocks read")
+        .and_then(|v| v.split_whitespace().next())
+        .and_then(|v| v.parse::<usize>().ok())
+    {
+        if logged != blocks.len() {
+            problems.push(Problem::RecordCount {
+                logged,
+                found: blocks.len(),
+            });
+        }
+    }
+    problems
+}
+
+/// BACKUP block cut off before its `l_blocksize`.
+fn is_short_block(raw: &[u8]) -> bool {
+    let size = u32::from_le_bytes([raw[40], raw[41], raw[42], raw[43]]);
+    raw.len() < size as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{has_bbh, verify_tape, Problem};
+    use crate::backup::writer::{build_saveset, SavesetOptions};
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::log::parse::{parse_log_text, LogData, LogEntry, LogLevel};
+    use crate::tap::reader::read_tap_blocks;
+
+    fn block(index: u32, data: Vec<u8>) -> TapeBlock {
+        TapeBlock {
+            index,
+            tape_file: 0,
+            size: data.len(),
+            raw: data.into(),
+            classification: BlockClassification::Unknown,
+        }
+    }
+
+    #[test]
+    fn flags_corrupt_backup_blocks() {
+        let opts = SavesetOptions {
+            block_size: 2048,
+            ..SavesetOptions::default()
+        };
+        let saveset = build_saveset(&[], &opts).unwrap();
+        let mut blocks: Vec<_> = saveset
+            .into_iter()
+            .enumerate()
+            .map(|(i, b)| block(i as u32, b))
+            .collect();
+        assert!(verify_tape(&blocks, None).is_empty());
+
+        let mut bad = blocks[0].raw.to_vec();
+        bad[300] ^= 0xFF;
+        blocks[0] = block(0, bad);
+        assert_eq!(
+            verify_tape(&blocks, None),
+            vec![Problem::BadChecksum { record: 0 }]
+        );
+    }
+
+    #[test]
+    fn accepts_backup_blocks_written_by_vms() {
+        let blocks = read_tap_blocks("tests/data/TA0114.TAP").expect("missing VMS fixture");
+        assert!(blocks.iter().any(|b| has_bbh(&b.raw)));
+        assert!(verify_tape(&blocks, None).is_empty());
+
+        // One block of this capture was cut short; the rest verify.
+        let blocks = read_tap_blocks("tests/data/TA0117.TAP").expect("missing VMS fixture");
+        assert_eq!(
+            verify_tape(&blocks, None),
+            vec![Problem::ShortBlock { record: 9 }]
+        );
+    }
+
+    #[test]
+    fn reports_layout_mismatches() {
+        let blocks = vec![block(0, vec![0; 512])];
+        let log = parse_log_text("Reading...\n2 x 512 bytes\nEnd of data hit at 10\n");
+        let problems = verify_tape(&blocks, Some(&log));
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].to_string(),
+            "log layout: image truncated: 1 records, 2 logged"
+        );
+    }
+
+    #[test]
+    fn cross_checks_the_log() {
+        let blocks = vec![block(0, vec![0; 512]), block(1, vec![0; 512])];
+        let entry = |line: &str, level| LogEntry {
+            line: line.into(),
+            level,
+        };
+        let log = LogData {
+            entries: vec![
+                entry("Blocks read = 3", LogLevel::Info),
+                entry("WARNING: record 2 retried", LogLevel::Warning),
+                entry("SKIP record 3", LogLevel::Warning),
+                entry("ERROR reading record 1", LogLevel::Error),
+            ],
+            metadata: HashMap::from([("Blocks read".to_string(), "3".to_string())]),
+            ..Default::default()
+        };
+        let problems = verify_tape(&blocks, Some(&log));
+        assert_eq!(problems.len(), 3);
+        assert!(matches!(problems[0], Problem::LogError { .. }));
+        // "SKIP record 3" is the third record, index 2.
+        assert_eq!(problems[1], Problem::MissingRecord { record: 2 });
+        assert_eq!(
+            problems[2],
+            Problem::RecordCount {
+                logged: 3,
+                found: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! ANSI X3.27 magtape labels (VOL1, HDR1/HDR2, EOF1/EOF2, EOV1/EOV2).
+//! Labels are 80-byte ASCII records; fields are fixed-column and space padded.
+
+use crate::core::fields::{Field, FieldKind};
+
+pub const LABEL_SIZE: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnsiLabel {
+    Volume(VolumeLabel),
+    Header1(FileLabel1),
+    Header2(FileLabel2),
+    EndOfFile1(FileLabel1),
+    EndOfFile2(FileLabel2),
+    EndOfVolume1(FileLabel1),
+    /// HDR3-9, EOF3-9, UHL/UTL and other labels we only report by identifier.
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeLabel {
+    pub volume_id: String,
+    pub owner: String,
+}
+
+/// HDR1/EOF1/EOV1 fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLabel1 {
+    pub file_id: String,
+    pub file_set_id: String,
+    pub section: u32,
+    pub sequence: u32,
+    pub creation_date: Option<String>,
+    pub expiration_date: Option<String>,
+    pub block_count: u32,
+    pub system_code: String,
+}
+
+/// HDR2/EOF2 fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLabel2 {
+    pub record_format: char,
+    pub block_length: u32,
+    pub record_length: u32,
+}
+
+/// Parse an ANSI label record. Returns `None` for anything that is not an
+/// 80-byte record starting with a known label identifier.
+pub fn parse_ansi_label(data: &[u8]) -> Option<AnsiLabel> {
+    if data.len() != LABEL_SIZE || !data.iter().all(|b| (0x20..0x7F).contains(b)) {
+        return None;
+    }
+    let id = field(data, 0, 4);
+    let label = match id.as_str() {
+        "VOL1" => AnsiLabel::Volume(VolumeLabel {
+            volume_id: field(data, 4, 10),
+            owner: field(data, 37, 51),
+        }),
+        "HDR1" => AnsiLabel::Header1(parse_label1(data)),
+        "EOF1" => AnsiLabel::EndOfFile1(parse_label1(data)),
+        "EOV1" => AnsiLabel::EndOfVolume1(parse_label1(data)),
+        "HDR2" => AnsiLabel::Header2(parse_label2(data)),
+        "EOF2" => AnsiLabel::EndOfFile2(parse_label2(data)),
+        _ if is_label_id(&id) => AnsiLabel::Other(id),
+        _ => return None,
+    };
+    Some(label)
+}
+
+fn is_label_id(id: &str) -> bool {
+    ["HDR", "EOF", "EOV", "VOL", "UHL", "UTL", "UVL"]
+        .iter()
+        .any(|p| id.starts_with(p))
+        && id.len() == 4
+        && id.as_bytes()[3].is_ascii_digit()
+}
+
+fn parse_label1(data: &[u8]) -> FileLabel1 {
+    FileLabel1 {
+        file_id: field(data, 4, 21),
+        file_set_id: field(data, 21, 27),
+        section: number(data, 27, 31),
+        sequence: number(data, 31, 35),
+        creation_date: julian_date(&data[41..47]),
+        expiration_date: julian_date(&data[47..53]),
+        block_count: number(data, 54, 60),
+        system_code: field(data, 60, 73),
+    }
+}
+
+fn parse_label2(data: &[u8]) -> FileLabel2 {
+    FileLabel2 {
+        record_format: data[4] as char,
+        block_length: number(data, 5, 10),
+        record_length: number(data, 10, 15),
+    }
+}
+
+/// Named columns of an ANSI label record, for the hex viewer; empty for
+/// anything `parse_ansi_label` rejects.
+pub fn label_fields(data: &[u8]) -> Vec<Field> {
+    let Some(label) = parse_ansi_label(data) else {
+        return Vec::new();
+    };
+    let columns: &[(usize, usize, &str)] = match label {
+        AnsiLabel::Volume(_) => &[
+            (4, 10, "volume identifier"),
+            (10, 11, "accessibility"),
+            (37, 51, "owner identifier"),
+            (79, 80, "label standard version"),
+        ],
+        AnsiLabel::Header1(_) | AnsiLabel::EndOfFile1(_) | AnsiLabel::EndOfVolume1(_) => &[
+            (4, 21, "file identifier"),
+            (21, 27, "file-set identifier"),
+            (27, 31, "file section number"),
+            (31, 35, "file sequence number"),
+            (35, 39, "generation number"),
+            (39, 41, "generation version"),
+            (41, 47, "creation date"),
+            (47, 53, "expiration date"),
+            (53, 54, "accessibility"),
+            (54, 60, "block count"),
+            (60, 73, "system code"),
+        ],
+        AnsiLabel::Header2(_) | AnsiLabel::EndOfFile2(_) => &[
+            (4, 5, "record format"),
+            (5, 10, "block length"),
+            (10, 15, "record length"),
+            (50, 52, "buffer offset length"),
+        ],
+        AnsiLabel::Other(_) => &[],
+    };
+    let id = field(data, 0, 4);
+    let mut fields = vec![Field::new(
+        0..4,
+        format!("{id} label identifier"),
+        FieldKind::Label,
+    )];
+    fields.extend(columns.iter().map(|&(start, end, name)| {
+        Field::new(start..end, format!("{id} {name}"), FieldKind::Label)
+    }));
+    fields
+}
+
+/// Decode a `cyyddd` Julian date (century flag ' ' = 19xx, '0' = 20xx).
+pub fn julian_date(raw: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(raw).ok()?;
+    let century = match text.as_bytes().first()? {
+        b' ' => 1900,
+        c @ b'0'..=b'9' => 2000 + 100 * i32::from(c - b'0'),
+        _ => return None,
+    };
+    let yy: i32 = text.get(1..3)?.trim().parse().ok()?;
+    let ddd: u32 = text.get(3..6)?.trim().parse().ok()?;
+    let date = chrono::NaiveDate::from_yo_opt(century + yy, ddd)?;
+    Some(date.format("%Y-%m-%d").to_string())
+}
+
+/// Encode a date as `cyyddd` (the inverse of `julian_date`).
+pub fn format_julian_date(date: chrono::NaiveDate) -> String {
+    use chrono::Datelike;
+    let century = match date.year() / 100 {
+        19 => ' ',
+        c => char::from(b'0' + (c - 20).clamp(0, 9) as u8),
+    };
+    format!("{century}{:02}{:03}", date.year() % 100, date.ordinal())
+}
+
+/// Build a VOL1 label.
+pub fn format_vol1(volume_id: &str, owner: &str) -> Vec<u8> {
+    let mut raw = blank_label("VOL1");
+    put(&mut raw, 4, 10, volume_id);
+    put(&mut raw, 37, 51, owner);
+    raw[79] = b'3';
+    raw
+}
+
+/// Build a HDR1, EOF1 or EOV1 label; `id` selects which.
+pub fn format_label1(id: &str, label: &FileLabel1, date: chrono::NaiveDate) -> Vec<u8> {
+    let mut raw = blank_label(id);
+    put(&mut raw, 4, 21, &label.file_id);
+    put(&mut raw, 21, 27, &label.file_set_id);
+    put(&mut raw, 27, 31, &format!("{:04}", label.section));
+    put(&mut raw, 31, 35, &format!("{:04}", label.sequence));
+    put(&mut raw, 35, 39, "0001");
+    put(&mut raw, 39, 41, "00");
+    put(&mut raw, 41, 47, &format_julian_date(date));
+    put(&mut raw, 47, 53, &format_julian_date(date));
+    put(&mut raw, 54, 60, &format!("{:06}", label.block_count));
+    put(&mut raw, 60, 73, &label.system_code);
+    raw
+}
+
+/// Build a HDR2 or EOF2 label.
+pub fn format_label2(id: &str, label: &FileLabel2) -> Vec<u8> {
+    let mut raw = blank_label(id);
+    raw[4] = label.record_format as u8;
+    put(&mut raw, 5, 10, &format!("{:05}", label.block_length));
+    put(&mut raw, 10, 15, &format!("{:05}", label.record_length));
+    put(&mut raw, 50, 52, "00");
+    raw
+}
+
+fn blank_label(id: &str) -> Vec<u8> {
+    let mut raw = vec![b' '; LABEL_SIZE];
+    put(&mut raw, 0, 4, id);
+    raw
+}
+
+fn put(raw: &mut [u8], start: usize, end: usize, text: &str) {
+    for (dst, src) in raw[start..end].iter_mut().zip(text.bytes()) {
+        *dst = src.to_ascii_uppercase();
+    }
+}
+
+fn field(data: &[u8], start: usize, end: usize) -> String {
+    String::from_utf8_lossy(&data[start..end])
+        .trim()
+        .to_string()
+}
+
+fn number(data: &[u8], start: usize, end: usize) -> u32 {
+    field(data, start, end).parse().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_julian_date, format_label1, format_label2, format_vol1, parse_ansi_label, AnsiLabel,
+        FileLabel1, FileLabel2,
+    };
+
+    fn label(text: &str) -> Vec<u8> {
+        let mut raw = text.as_bytes().to_vec();
+        raw.resize(80, b' ');
+        raw
+    }
+
+    #[test]
+    fn parses_hdr1_fields() {
+        let raw = label("HDR1VMSMUP050.A      VMSMUP00010001000100 88124 88124 000000DECVMSBACKUP");
+        match parse_ansi_label(&raw) {
+            Some(AnsiLabel::Header1(h)) => {
+                assert_eq!(h.file_id, "VMSMUP050.A");
+                assert_eq!(h.file_set_id, "VMSMUP");
+                assert_eq!(h.section, 1);
+                assert_eq!(h.sequence, 1);
+                assert_eq!(h.creation_date.as_deref(), Some("1988-05-03"));
+                assert_eq!(h.system_code, "DECVMSBACKUP");
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_vol1_and_hdr2() {
+        assert!(matches!(
+            parse_ansi_label(&label("VOL1VMSMUP")),
+            Some(AnsiLabel::Volume(v)) if v.volume_id == "VMSMUP"
+        ));
+        assert!(matches!(
+            parse_ansi_label(&label("HDR2F0819208192")),
+            Some(AnsiLabel::Header2(h)) if h.block_length == 8192 && h.record_format == 'F'
+        ));
+    }
+
+    #[test]
+    fn formatted_labels_parse_back() {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        assert_eq!(format_julian_date(date), "024032");
+        assert!(matches!(
+            parse_ansi_label(&format_vol1("tape01", "")),
+            Some(AnsiLabel::Volume(v)) if v.volume_id == "TAPE01"
+        ));
+        let hdr1 = FileLabel1 {
+            file_id: "SAVE.BCK".into(),
+            file_set_id: "TAPE01".into(),
+            section: 1,
+            sequence: 1,
+            creation_date: Some("2024-02-01".into()),
+            expiration_date: Some("2024-02-01".into()),
+            block_count: 12,
+            system_code: "DECVMSBACKUP".into(),
+        };
+        let raw = format_label1("EOF1", &hdr1, date);
+        assert_eq!(parse_ansi_label(&raw), Some(AnsiLabel::EndOfFile1(hdr1)));
+        let hdr2 = FileLabel2 {
+            record_format: 'F',
+            block_length: 8192,
+            record_length: 8192,
+        };
+        let raw = format_label2("HDR2", &hdr2);
+        assert_eq!(parse_ansi_label(&raw), Some(AnsiLabel::Header2(hdr2)));
+    }
+
+    #[test]
+    fn rejects_non_labels() {
+        assert!(parse_ansi_label(&label("HELLO WORLD")).is_none());
+        assert!(parse_ansi_label(b"HDR1").is_none());
+    }
+}
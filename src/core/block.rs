@@ -1,13 +1,79 @@
+use std::fmt;
+use std::ops::{Deref, Range};
 use std::sync::Arc;
 
+use crate::core::file::BlockExtent;
+
 #[derive(Debug, Clone)]
 pub struct TapeBlock {
     pub index: u32,
+    /// Tape file number: incremented at every tape mark.
+    pub tape_file: u32,
     pub size: usize,
-    pub raw: Arc<[u8]>,
+    pub raw: RecordBytes,
     pub classification: BlockClassification,
 }
 
+/// Tape image bytes shared by the records read from it: a memory mapping or
+/// an image held in memory.
+pub type SharedImage = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+/// Payload of a tape record. Records of an indexed image are ranges of the
+/// shared image, so loading a tape copies no payloads; records built in
+/// memory own their bytes.
+#[derive(Clone)]
+pub struct RecordBytes {
+    image: SharedImage,
+    range: Range<usize>,
+}
+
+impl RecordBytes {
+    /// Bytes `range` of `image`.
+    pub fn shared(image: SharedImage, range: Range<usize>) -> Self {
+        assert!(
+            range.end <= (*image).as_ref().len(),
+            "record past end of image"
+        );
+        Self { image, range }
+    }
+}
+
+impl Deref for RecordBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &(*self.image).as_ref()[self.range.clone()]
+    }
+}
+
+impl AsRef<[u8]> for RecordBytes {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl fmt::Debug for RecordBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl From<Vec<u8>> for RecordBytes {
+    fn from(data: Vec<u8>) -> Self {
+        let len = data.len();
+        Self {
+            image: Arc::new(data),
+            range: 0..len,
+        }
+    }
+}
+
+impl From<&[u8]> for RecordBytes {
+    fn from(data: &[u8]) -> Self {
+        data.to_vec().into()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TapeFormat {
     Unknown,
@@ -15,7 +81,11 @@ pub enum TapeFormat {
     Rsx,
     Rt11,
     Rsts,
+    Unix,
+    Pdp10,
     Raw,
+    /// Format supplied by a registered `FormatHandler`, keyed by its name.
+    Other(&'static str),
 }
 
 #[derive(Debug, Clone)]
@@ -25,7 +95,33 @@ pub enum BlockClassification {
     Rsx(RsxBlockKind),
     Rt11(Rt11BlockKind),
     Rsts(RstsBlockKind),
+    Unix(UnixBlockKind),
+    Pdp10(Pdp10BlockKind),
     Raw,
+    /// Block claimed by a registered `FormatHandler`, keyed by its name.
+    Other(&'static str),
+}
+
+impl BlockClassification {
+    /// Tape format this block belongs to.
+    pub fn format(&self) -> TapeFormat {
+        match self {
+            BlockClassification::Unknown => TapeFormat::Unknown,
+            BlockClassification::Vms(_) => TapeFormat::Vms,
+            BlockClassification::Rsx(_) => TapeFormat::Rsx,
+            BlockClassification::Rt11(_) => TapeFormat::Rt11,
+            BlockClassification::Rsts(_) => TapeFormat::Rsts,
+            BlockClassification::Unix(_) => TapeFormat::Unix,
+            BlockClassification::Pdp10(_) => TapeFormat::Pdp10,
+            BlockClassification::Raw => TapeFormat::Raw,
+            BlockClassification::Other(name) => TapeFormat::Other(name),
+        }
+    }
+
+    /// True for loose guesses that no structure check backs up.
+    pub fn is_guess(&self) -> bool {
+        matches!(self, BlockClassification::Rsx(RsxBlockKind::Guess))
+    }
 }
 
 // For now, create empty placeholder enums.
@@ -38,14 +134,94 @@ pub enum VmsBlockKind {
 #[derive(Debug, Clone)]
 pub enum RsxBlockKind {
     Placeholder,
+    /// Loose size-and-signature match on a bare 512-byte block.
+    Guess,
 }
 
 #[derive(Debug, Clone)]
 pub enum Rt11BlockKind {
     Placeholder,
+    /// ANSI label record written by the RT-11 magtape handler.
+    MagtapeLabel,
+    /// Data record of an RT-11 file-structured magtape file.
+    MagtapeData,
+    /// Data record of a BUP volume image.
+    BupData,
 }
 
 #[derive(Debug, Clone)]
 pub enum RstsBlockKind {
     Placeholder,
+    /// 14-byte DOS-11 magtape file label.
+    DosLabel,
+    /// Data record following a DOS-11 label.
+    DosData,
+}
+
+#[derive(Debug, Clone)]
+pub enum UnixBlockKind {
+    /// V6/V7 `tp` boot, directory or data block.
+    Tp,
+    /// `tar` archive record.
+    Tar,
+    /// `dump` header or data record.
+    Dump,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pdp10BlockKind {
+    /// TOPS-20 DUMPER record.
+    Dumper,
+    /// TOPS-10 BACKUP record.
+    Backup,
+}
+
+/// Block with record index `index`. Readers number blocks by position, so
+/// this is a direct lookup; lists with records missing fall back to a scan.
+pub fn block_at(blocks: &[TapeBlock], index: u32) -> Option<&TapeBlock> {
+    match blocks.get(index as usize) {
+        Some(block) if block.index == index => Some(block),
+        _ => blocks.iter().find(|block| block.index == index),
+    }
+}
+
+/// Split blocks into tape files (runs of blocks between tape marks).
+pub fn tape_files(blocks: &[TapeBlock]) -> Vec<&[TapeBlock]> {
+    let mut out = Vec::new();
+    let mut start = 0usize;
+    for i in 1..=blocks.len() {
+        if i == blocks.len() || blocks[i].tape_file != blocks[start].tape_file {
+            if start < i {
+                out.push(&blocks[start..i]);
+            }
+            start = i;
+        }
+    }
+    out
+}
+
+/// Map a byte range of the concatenated records in `blocks` onto per-record
+/// extents. Used by formats whose logical stream ignores record boundaries.
+pub fn byte_extents(blocks: &[TapeBlock], start: usize, len: usize) -> Vec<BlockExtent> {
+    let end = start + len;
+    let mut extents = Vec::new();
+    let mut pos = 0usize;
+    for block in blocks {
+        let (lo, hi) = (pos, pos + block.size);
+        pos = hi;
+        if lo >= end {
+            break;
+        }
+        if hi <= start {
+            continue;
+        }
+        let from = start.max(lo);
+        let to = end.min(hi);
+        extents.push(BlockExtent {
+            block: block.index,
+            offset: from - lo,
+            len: to - from,
+        });
+    }
+    extents
 }
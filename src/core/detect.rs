@@ -1,76 +1,153 @@
+//! Record classification shared by every view: one classifier for tape
+//! blocks and the per-record entries derived from them.
+use crate::core::ansi::{parse_ansi_label, AnsiLabel};
 use crate::core::block::{
     BlockClassification, RstsBlockKind, RsxBlockKind, Rt11BlockKind, TapeBlock, TapeFormat,
     VmsBlockKind,
 };
+use crate::core::handler::registry;
+use crate::core::rsts::tape::parse_dos11_label;
+use crate::core::rt11::tape::is_rt11_hdr1;
+use crate::core::vms::has_backup_block;
 
-pub fn detect_block_format(block: &TapeBlock) -> BlockClassification {
-    let data = block.raw.as_ref();
+/// Classify one tape record. Structural checks run first, in order: RSX-11M
+/// UFD blocks, RT-11 labels and directories, DOS-11 labels, then records
+/// that are VMS BACKUP blocks. Bare 512-byte blocks starting with an
+/// RSX-11M word fall back to a loose guess, marked `RsxBlockKind::Guess`.
+/// Tape-level classifiers in `analyze_blocks` refine the result.
+pub fn classify_record(data: &[u8]) -> BlockClassification {
+    if detect_rsx11m(data) {
+        return BlockClassification::Rsx(RsxBlockKind::Placeholder);
+    }
+    if detect_rt11(data) {
+        return BlockClassification::Rt11(Rt11BlockKind::Placeholder);
+    }
+    if parse_dos11_label(data).is_some() {
+        return BlockClassification::Rsts(RstsBlockKind::DosLabel);
+    }
+    if has_backup_block(data) {
+        return BlockClassification::Vms(VmsBlockKind::Placeholder);
+    }
 
-    if let Some(_vms) = crate::core::vms::block::classify_vms_block(block.raw.as_ref(), block.index)
-    {
-        return BlockClassification::Vms(crate::core::block::VmsBlockKind::Placeholder);
+    // Loose guess for bare 512-byte blocks; detection weights it low.
+    if is_rsx_guess(data) {
+        return BlockClassification::Rsx(RsxBlockKind::Guess);
     }
 
-    if data.len() >= 20 {
-        // VMS backup detection
-        let record_type = data[0];
-        let class = data[2];
+    BlockClassification::Raw
+}
 
-        if record_type == 0x02 && (class == 0x44 || class == 0x45 || class == 0x46) {
-            return BlockClassification::Vms(VmsBlockKind::Placeholder);
-        }
+/// RSX-11M UFD block: whole 512-byte blocks with a count word of one and an
+/// `RSX` or `UFD` tag.
+pub(crate) fn detect_rsx11m(block: &[u8]) -> bool {
+    if block.len() < 512 || !block.len().is_multiple_of(512) {
+        return false;
     }
+    let has_count = block.get(0..4) == Some(&[0x01, 0x00, 0x00, 0x00]);
+    let has_tag = block.windows(3).any(|w| w == b"RSX" || w == b"UFD");
+    has_count && has_tag
+}
 
-    // RSX-11M block detection
-    if data.len() == 512 {
-        if data.starts_with(&[0x31, 0x00]) || data.starts_with(&[0x40, 0x00]) {
-            return BlockClassification::Rsx(RsxBlockKind::Placeholder);
-        }
-    }
+/// Bare 512-byte block starting with an RSX-11M word; no structure checked.
+pub(crate) fn is_rsx_guess(data: &[u8]) -> bool {
+    data.len() == 512 && (data.starts_with(&[0x31, 0x00]) || data.starts_with(&[0x40, 0x00]))
+}
 
-    // RT-11 directory block detection
-    if data.len() == 512 {
-        if data[0] != 0 && data[2] != 0 {
-            return BlockClassification::Rt11(Rt11BlockKind::Placeholder);
-        }
+fn detect_rt11(block: &[u8]) -> bool {
+    if let Some(AnsiLabel::Header1(h)) = parse_ansi_label(block) {
+        return is_rt11_hdr1(&h);
     }
+    if block.len() < 512 || !block.len().is_multiple_of(512) {
+        return false;
+    }
+    let dir_words = u16::from_le_bytes([block[0], block[1]]);
+    let plausible_dir = dir_words > 0 && dir_words < 0x0400;
+    let has_tag = block.windows(4).any(|w| w == b"RT11");
+    plausible_dir && has_tag
+}
+
+/// Confidence in one candidate format over the whole tape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatScore {
+    pub format: TapeFormat,
+    pub name: &'static str,
+    /// Per-tape-file confidences averaged with each file weighted by its
+    /// block count, 0.0..=1.0.
+    pub confidence: f32,
+    /// Tape files where this format scored best.
+    pub tape_files: usize,
+}
+
+/// Best format of one tape file (the records between two tape marks).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapeFileVote {
+    pub tape_file: u32,
+    pub blocks: usize,
+    /// `TapeFormat::Unknown` when no format reached the file threshold.
+    pub format: TapeFormat,
+    pub confidence: f32,
+}
 
-    // RSTS/E UFD/MFD detection (very loose)
-    if data.len() == 512 && data[1] == 0 && data[4] == 0 {
-        return BlockClassification::Rsts(RstsBlockKind::Placeholder);
+/// Outcome of format detection: the winner, every candidate with a
+/// non-zero confidence (best first) and the vote of each tape file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub winner: TapeFormat,
+    pub candidates: Vec<FormatScore>,
+    pub votes: Vec<TapeFileVote>,
+}
+
+impl Detection {
+    /// Confidence of a candidate, 0.0 when it scored nothing.
+    pub fn confidence(&self, format: &TapeFormat) -> f32 {
+        self.candidates
+            .iter()
+            .find(|c| &c.format == format)
+            .map(|c| c.confidence)
+            .unwrap_or(0.0)
     }
+}
 
-    BlockClassification::Raw
+/// Classify blocks through the built-in format handlers and score every
+/// candidate format.
+pub fn detect_formats(blocks: &mut [TapeBlock]) -> Detection {
+    registry().classify(blocks)
 }
 
+/// Classify blocks and return the winning format.
 pub fn analyze_blocks(blocks: &mut [TapeBlock]) -> TapeFormat {
-    let mut vms = 0usize;
-    let mut rsx = 0usize;
-    let mut rt11 = 0usize;
-    let mut rsts = 0usize;
-
-    for blk in blocks.iter_mut() {
-        let c = detect_block_format(blk);
-        match &c {
-            BlockClassification::Vms(_) => vms += 1,
-            BlockClassification::Rsx(_) => rsx += 1,
-            BlockClassification::Rt11(_) => rt11 += 1,
-            BlockClassification::Rsts(_) => rsts += 1,
-            _ => {}
-        }
-        blk.classification = c;
+    detect_formats(blocks).winner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify_record, detect_rsx11m, detect_rt11};
+    use crate::core::block::BlockClassification;
+    use std::fs;
+
+    #[test]
+    fn test_detect_rt11() {
+        let data = fs::read("tests/data/rt11.tap").expect("missing rt11 fixture");
+        assert!(detect_rt11(&data));
+    }
+
+    #[test]
+    fn test_detect_rsx11m() {
+        let data = fs::read("tests/data/rsx.tap").expect("missing rsx fixture");
+        assert!(detect_rsx11m(&data));
     }
 
-    // Winner selection (simple strongest-match)
-    if vms > rsx && vms > rt11 && vms > rsts {
-        TapeFormat::Vms
-    } else if rsx > rt11 && rsx > rsts {
-        TapeFormat::Rsx
-    } else if rt11 > rsts {
-        TapeFormat::Rt11
-    } else if rsts > 0 {
-        TapeFormat::Rsts
-    } else {
-        TapeFormat::Unknown
+    #[test]
+    fn classifies_backup_blocks() {
+        let opts = crate::backup::writer::SavesetOptions::default();
+        let record = crate::core::vms::tests::saveset(&[("[000000]A.TXT;1", b"a\n")], &opts);
+        assert!(matches!(
+            classify_record(&record[0]),
+            BlockClassification::Vms(_)
+        ));
+        assert!(matches!(
+            classify_record(&[1, 2, 3]),
+            BlockClassification::Raw
+        ));
     }
 }
@@ -1,35 +1,138 @@
+pub mod pdp10;
 pub mod raw;
 pub mod rsts;
 pub mod rsx;
 pub mod rt11;
+pub mod unix;
 pub mod vms;
 
+use std::fs;
 use std::io;
 use std::path::Path;
 
-use crate::core::block::TapeBlock;
+use crate::core::block::{block_at, TapeBlock};
 use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::handler::registry;
+use crate::core::pdp10::convert_file_data;
 
-/// Select the correct extraction backend based on metadata.
+/// Extract one file through the handler registered for its format.
 pub fn extract_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
-    std::fs::create_dir_all(outdir)?;
+    registry().extract(file, blocks, outdir)
+}
+
+/// Extract a reconstructed tree depth-first, parents before children, so
+/// directories exist and hard-link targets are written before their links.
+/// Nodes with children become directories. Returns the number of entries
+/// written.
+pub fn extract_tree(files: &[TapeFile], blocks: &[TapeBlock], outdir: &Path) -> io::Result<usize> {
+    let mut written = 0usize;
+    for file in files {
+        if file.children.is_empty() {
+            extract_file(file, blocks, outdir)?;
+        } else {
+            create_dirs(
+                outdir,
+                &outdir.join(sanitize_filename(&file.path.to_string_path())),
+            )?;
+            written += extract_tree(&file.children, blocks, outdir)?;
+        }
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Gather a file's bytes: extents when the format packs data inside records
+/// (holes read as zeros), otherwise the whole of each listed block in order.
+pub fn file_bytes(file: &TapeFile, blocks: &[TapeBlock]) -> Vec<u8> {
+    let find = |idx: u32| block_at(blocks, idx);
+    let mut data = Vec::new();
+    if file.extents.is_empty() {
+        for idx in &file.blocks {
+            if let Some(b) = find(*idx) {
+                data.extend_from_slice(b.raw.as_ref());
+            }
+        }
+    } else {
+        for ext in &file.extents {
+            if ext.is_hole() {
+                data.resize(data.len() + ext.len, 0);
+            } else if let Some(chunk) =
+                find(ext.block).and_then(|b| b.raw.get(ext.offset..ext.offset + ext.len))
+            {
+                data.extend_from_slice(chunk);
+            }
+        }
+    }
+    data
+}
+
+/// A file's contents as extraction writes them: PDP-10 files unpacked from
+/// their 36-bit framing, everything else as `file_bytes`.
+pub fn host_bytes(file: &TapeFile, blocks: &[TapeBlock]) -> Vec<u8> {
+    let framed = file_bytes(file, blocks);
     match &file.metadata {
-        FileMetadata::Rsx(_) => rsx::extract_rsx_file(file, blocks, outdir),
-        FileMetadata::Rt11(_) => rt11::extract_rt11_file(file, blocks, outdir),
-        FileMetadata::Rsts(_) => rsts::extract_rsts_file(file, blocks, outdir),
-        FileMetadata::Vms(_) => vms::extract_vms_dispatch(file, blocks, outdir),
-        FileMetadata::Raw => raw::extract_raw_file(file, blocks, outdir),
+        FileMetadata::Pdp10(meta) => convert_file_data(&framed, meta.byte_size, meta.byte_count),
+        _ => framed,
     }
 }
 
+/// Map a tape path to a relative host path. Characters other than ASCII
+/// letters, digits, `_`, `.`, `-` and `/` become `_`; empty, `.` and `..`
+/// components are dropped so the result stays below the output directory.
 pub(crate) fn sanitize_filename(s: &str) -> String {
-    let mut out = String::new();
-    for ch in s.chars() {
-        if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '-' || ch == '/' {
-            out.push(ch);
-        } else {
-            out.push('_');
+    let clean: String = s
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '-' || ch == '/' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    clean
+        .split('/')
+        .filter(|p| !p.is_empty() && *p != "." && *p != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Fail when `path` or any directory between `outdir` and it is a symbolic
+/// link, so a link extracted earlier cannot redirect writes outside `outdir`.
+pub(crate) fn check_no_symlinks(outdir: &Path, path: &Path) -> io::Result<()> {
+    let rel = path.strip_prefix(outdir).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is outside {}", path.display(), outdir.display()),
+        )
+    })?;
+    let mut at = outdir.to_path_buf();
+    for part in rel.components() {
+        at.push(part);
+        if at
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_symlink())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is a symbolic link", at.display()),
+            ));
         }
     }
-    out
+    Ok(())
+}
+
+/// `fs::create_dir_all` for a directory inside `outdir`, refusing to pass
+/// through symbolic links.
+pub(crate) fn create_dirs(outdir: &Path, dir: &Path) -> io::Result<()> {
+    check_no_symlinks(outdir, dir)?;
+    fs::create_dir_all(dir)
+}
+
+/// Create the directories leading to `path`, as `create_dirs`.
+pub(crate) fn create_parent_dirs(outdir: &Path, path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => create_dirs(outdir, parent),
+        None => Ok(()),
+    }
 }
@@ -0,0 +1,22 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::core::block::TapeBlock;
+use crate::core::file::TapeFile;
+
+use super::{host_bytes, sanitize_filename};
+
+/// Write a DUMPER/BACKUP file, unpacking 7-bit text and 8-bit byte files.
+/// Files of any other byte size keep their core-dump framing.
+pub fn extract_pdp10_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+    let data = host_bytes(file, blocks);
+
+    let path = outdir.join(sanitize_filename(&file.path.to_string_path()));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut f = File::create(path)?;
+    f.write_all(&data)?;
+    Ok(())
+}
@@ -2,7 +2,7 @@ use std::fs;
 use std::io;
 use std::path::Path;
 
-use crate::core::block::TapeBlock;
+use crate::core::block::{block_at, TapeBlock};
 use crate::core::file::TapeFile;
 
 use super::sanitize_filename;
@@ -10,7 +10,7 @@ use super::sanitize_filename;
 pub fn extract_raw_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
     let mut data = Vec::new();
     for idx in &file.blocks {
-        if let Some(b) = blocks.iter().find(|blk| blk.index == *idx) {
+        if let Some(b) = block_at(blocks, *idx) {
             data.extend_from_slice(b.raw.as_ref());
         }
     }
@@ -4,22 +4,18 @@ use std::path::Path;
 
 use crate::core::block::TapeBlock;
 use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::rsts::format_ppn;
 
-use super::sanitize_filename;
+use super::{file_bytes, sanitize_filename};
 
 pub fn extract_rsts_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
-    let mut data = Vec::new();
-    for idx in &file.blocks {
-        if let Some(b) = blocks.iter().find(|blk| blk.index == *idx) {
-            data.extend_from_slice(b.raw.as_ref());
-        }
-    }
+    let mut data = file_bytes(file, blocks);
 
     let (is_dir, expected_len, prefix_path) = match &file.metadata {
         FileMetadata::Rsts(meta) => {
             let is_dir = meta.blocks == 0 || (meta.status & 0x8000) != 0;
             let expected_len = meta.blocks as usize * 512;
-            let uic = format!("[{:o},{:o}]", meta.owner_uic.0, meta.owner_uic.1);
+            let uic = format_ppn(meta.owner_uic);
             (is_dir, expected_len, Some(uic))
         }
         _ => (false, 0, None),
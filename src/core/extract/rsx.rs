@@ -2,7 +2,7 @@ use std::fs::{self, File};
 use std::io::{self, Write};
 use std::path::Path;
 
-use crate::core::block::TapeBlock;
+use crate::core::block::{block_at, TapeBlock};
 use crate::core::file::{FileMetadata, TapeFile};
 
 use super::sanitize_filename;
@@ -19,7 +19,7 @@ pub fn extract_rsx_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) ->
 
     let mut data = Vec::new();
     for idx in &file.blocks {
-        if let Some(b) = blocks.iter().find(|blk| blk.index == *idx) {
+        if let Some(b) = block_at(blocks, *idx) {
             data.extend_from_slice(b.raw.as_ref());
         }
     }
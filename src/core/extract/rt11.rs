@@ -5,15 +5,10 @@ use std::path::Path;
 use crate::core::block::TapeBlock;
 use crate::core::file::{FileMetadata, TapeFile};
 
-use super::sanitize_filename;
+use super::{file_bytes, sanitize_filename};
 
 pub fn extract_rt11_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
-    let mut data = Vec::new();
-    for idx in &file.blocks {
-        if let Some(b) = blocks.iter().find(|blk| blk.index == *idx) {
-            data.extend_from_slice(b.raw.as_ref());
-        }
-    }
+    let mut data = file_bytes(file, blocks);
 
     let filename = match &file.metadata {
         FileMetadata::Rt11(meta) => {
@@ -21,11 +16,7 @@ pub fn extract_rt11_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -
             if expected_len < data.len() {
                 data.truncate(expected_len);
             }
-            if meta.ext.is_empty() {
-                file.path.to_string_path()
-            } else {
-                format!("{}.{}", file.path.to_string_path(), meta.ext)
-            }
+            rt11_file_name(file, &meta.ext)
         }
         _ => file.path.to_string_path(),
     };
@@ -38,3 +29,13 @@ pub fn extract_rt11_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -
     f.write_all(&data)?;
     Ok(())
 }
+
+/// Path with the RT-11 type appended, unless the name already carries it.
+pub fn rt11_file_name(file: &TapeFile, ext: &str) -> String {
+    let path = file.path.to_string_path();
+    if ext.is_empty() || path.ends_with(&format!(".{ext}")) {
+        path
+    } else {
+        format!("{path}.{ext}")
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::core::block::TapeBlock;
+use crate::core::file::{FileMetadata, TapeFile, UnixLink};
+use crate::core::unix::{S_IFDIR, S_IFMT};
+
+use super::{check_no_symlinks, create_dirs, create_parent_dirs, file_bytes, sanitize_filename};
+
+/// Extract a Unix archive member. Directories are created, symbolic links
+/// are recreated as links, and hard links are linked to their target when
+/// it has already been extracted (otherwise the shared data is written).
+/// Members are never written through a symbolic link extracted earlier.
+pub fn extract_unix_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+    let path = outdir.join(sanitize_filename(&file.path.to_string_path()));
+    let FileMetadata::Unix(meta) = &file.metadata else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a Unix archive member",
+        ));
+    };
+
+    if meta.mode & S_IFMT == S_IFDIR {
+        return create_dirs(outdir, &path);
+    }
+    create_parent_dirs(outdir, &path)?;
+    if path.symlink_metadata().is_ok() {
+        fs::remove_file(&path)?;
+    }
+
+    match &meta.link {
+        Some(UnixLink::Symbolic(target)) => symlink(target, &path),
+        Some(UnixLink::Hard(target)) => {
+            let existing = outdir.join(sanitize_filename(target));
+            if check_no_symlinks(outdir, &existing).is_ok() && existing.is_file() {
+                fs::hard_link(existing, &path)
+            } else {
+                write_data(file, blocks, &path)
+            }
+        }
+        None => write_data(file, blocks, &path),
+    }
+}
+
+fn write_data(file: &TapeFile, blocks: &[TapeBlock], path: &Path) -> io::Result<()> {
+    let mut data = file_bytes(file, blocks);
+    data.truncate(file.size_bytes as usize);
+    fs::write(path, data)
+}
+
+#[cfg(unix)]
+fn symlink(target: &str, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+/// Without symlink support, leave the link target in a small text file.
+#[cfg(not(unix))]
+fn symlink(target: &str, path: &Path) -> io::Result<()> {
+    fs::write(path, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::extract_unix_file;
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::core::extract::extract_tree;
+    use crate::core::unix::tar::tests::header;
+    use crate::core::unix::tar::{decode_tar, TAR_BLOCK};
+
+    fn archive(members: &[Vec<u8>]) -> Vec<TapeBlock> {
+        let mut raw = members.concat();
+        raw.resize(raw.len() + 2 * TAR_BLOCK, 0);
+        vec![TapeBlock {
+            index: 0,
+            tape_file: 0,
+            size: raw.len(),
+            raw: raw.into(),
+            classification: BlockClassification::Unknown,
+        }]
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rts_unix_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/out")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn keeps_dot_dot_members_inside_outdir() {
+        let base = scratch("dotdot");
+        let out = base.join("a/out");
+        let mut data = vec![b'x'; 5];
+        data.resize(TAR_BLOCK, 0);
+        let blocks = archive(&[
+            header("../../escape.txt", 0o644, 5, b'0', ""),
+            data,
+            header("hard", 0o644, 0, b'1', "../../escape.txt"),
+        ]);
+        let tree = decode_tar(&blocks);
+        extract_tree(&tree, &blocks, &out).unwrap();
+        assert_eq!(fs::read(out.join("escape.txt")).unwrap(), b"xxxxx");
+        assert_eq!(fs::read(out.join("hard")).unwrap(), b"xxxxx");
+        assert!(!base.join("escape.txt").exists());
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_to_write_through_extracted_symlinks() {
+        let base = scratch("symlink");
+        let out = base.join("a/out");
+        let outside = base.join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        let mut data = vec![b'y'; 3];
+        data.resize(TAR_BLOCK, 0);
+        let blocks = archive(&[
+            header("link", 0o777, 0, b'2', outside.to_str().unwrap()),
+            header("link/owned.txt", 0o644, 3, b'0', ""),
+            data,
+        ]);
+        let tree = decode_tar(&blocks);
+        let link = &tree[0];
+        extract_unix_file(link, &blocks, &out).unwrap();
+        assert!(out.join("link").symlink_metadata().unwrap().is_symlink());
+        let err = extract_unix_file(&link.children[0], &blocks, &out).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!outside.join("owned.txt").exists());
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -1,15 +1,16 @@
 //! VMS extraction layer.
-//! Takes reconstructed TapeFile (VMS) + VBN blocks -> real output files.
+//! Takes reconstructed TapeFile (VMS) + tape records -> real output files.
 
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
 
 use crate::core::block::TapeBlock;
-use crate::core::extract::sanitize_filename;
 use crate::core::file::{FileMetadata, TapeFile};
 
-/// Extract a VMS file by concatenating VBN payloads in sorted VBN order.
+use super::{file_bytes, sanitize_filename};
+
+/// Extract a VMS file by concatenating its BACKUP block payloads in tape order.
 pub fn extract_vms_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
     match &file.metadata {
         FileMetadata::Vms(_) => {}
@@ -28,20 +29,8 @@ pub fn extract_vms_file(file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) ->
         fs::create_dir_all(parent)?;
     }
 
-    let mut block_indices = file.blocks.clone();
-    block_indices.sort_unstable();
-
-    let mut buffer = Vec::new();
-    for idx in block_indices {
-        if let Some(b) = blocks.iter().find(|blk| blk.index == idx) {
-            buffer.extend_from_slice(b.raw.as_ref());
-        } else {
-            eprintln!("Warning: missing VBN block index {}", idx);
-        }
-    }
-
     let mut f = fs::File::create(&path)?;
-    f.write_all(&buffer)?;
+    f.write_all(&file_bytes(file, blocks))?;
     Ok(())
 }
 
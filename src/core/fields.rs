@@ -0,0 +1,79 @@
+//! Named byte ranges inside a record or file, taken from the decoders, for
+//! the hex viewer's overlays.
+use std::ops::Range;
+
+use crate::backup::bbh::block_fields;
+use crate::core::ansi::label_fields;
+use crate::core::rt11::directory::directory_fields;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldKind {
+    /// ANSI label field.
+    Label,
+    /// VMS BACKUP block header (BBH).
+    BlockHeader,
+    /// VMS BACKUP record header (BRH).
+    RecordHeader,
+    /// VMS BACKUP summary or file attribute (BSA).
+    Attribute,
+    /// RT-11 home block field.
+    HomeBlock,
+    /// RT-11 directory segment header or entry word.
+    DirectoryEntry,
+}
+
+/// One decoded field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub range: Range<usize>,
+    pub name: String,
+    pub kind: FieldKind,
+}
+
+impl Field {
+    pub fn new(range: Range<usize>, name: impl Into<String>, kind: FieldKind) -> Self {
+        Field {
+            range,
+            name: name.into(),
+            kind,
+        }
+    }
+}
+
+/// Fields every decoder recognises in `data`, sorted by offset. Ranges do
+/// not overlap and stay inside `data`.
+pub fn data_fields(data: &[u8]) -> Vec<Field> {
+    let mut fields = label_fields(data);
+    if fields.is_empty() {
+        fields = block_fields(data);
+    }
+    if fields.is_empty() {
+        fields = directory_fields(data);
+    }
+    fields.retain(|f| f.range.end <= data.len() && !f.range.is_empty());
+    fields.sort_by_key(|f| f.range.start);
+    fields
+}
+
+/// Field covering byte `offset`, from fields sorted by offset.
+pub fn field_at(fields: &[Field], offset: usize) -> Option<&Field> {
+    let idx = fields.partition_point(|f| f.range.start <= offset);
+    fields[..idx].last().filter(|f| f.range.contains(&offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{data_fields, field_at, FieldKind};
+
+    #[test]
+    fn finds_label_fields_by_offset() {
+        let mut raw = b"VOL1TAPE01".to_vec();
+        raw.resize(80, b' ');
+        let fields = data_fields(&raw);
+        let id = field_at(&fields, 5).unwrap();
+        assert_eq!(id.range, 4..10);
+        assert_eq!(id.kind, FieldKind::Label);
+        assert!(field_at(&fields, 60).is_none());
+        assert!(data_fields(&[0u8; 100]).is_empty());
+    }
+}
@@ -21,20 +21,51 @@ pub struct TapeFile {
     pub path: TapePath,
     pub size_bytes: u64,
     pub blocks: Vec<u32>,
+    /// Byte ranges inside `blocks` for formats that pack file data within
+    /// records. Empty means the file is the whole of each listed block.
+    pub extents: Vec<BlockExtent>,
     pub metadata: FileMetadata,
     pub children: Vec<TapeFile>,
 }
 
+/// A byte range inside one tape block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockExtent {
+    pub block: u32,
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl BlockExtent {
+    /// Block number marking a run of zeros that is not stored on tape
+    /// (a hole in a sparse file).
+    pub const HOLE: u32 = u32::MAX;
+
+    pub fn hole(len: usize) -> Self {
+        BlockExtent {
+            block: Self::HOLE,
+            offset: 0,
+            len,
+        }
+    }
+
+    pub fn is_hole(&self) -> bool {
+        self.block == Self::HOLE
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum FileMetadata {
     Vms(VmsFileMetadata),
     Rsx(RsxFileMetadata),
     Rt11(Rt11FileMetadata),
     Rsts(RstsFileMetadata),
+    Unix(UnixFileMetadata),
+    Pdp10(Pdp10FileMetadata),
     Raw,
 }
 
-/// Fully decoded VMS header metadata (FH2 + optional XH2)
+/// VMS file metadata from the save attributes of a BACKUP file record.
 #[derive(Debug, Clone)]
 pub struct VmsFileMetadata {
     pub file_id: (u16, u16, u16),
@@ -64,11 +95,64 @@ pub struct Rt11FileMetadata {
     pub start_block: u16,
     pub length_blocks: u16,
     pub ext: String,
+    pub creation_date: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct RstsFileMetadata {
     pub owner_uic: (u16, u16),
-    pub blocks: u16,
+    pub blocks: u32,
     pub status: u16,
+    pub protection: u8,
+    pub creation_date: Option<String>,
+    pub access_date: Option<String>,
+    pub cluster_size: u16,
+}
+
+/// Unix archive layout a file came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnixArchive {
+    Tp,
+    Tar,
+    Dump,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnixLink {
+    Symbolic(String),
+    /// Hard link to an earlier archive member (path relative to the archive root).
+    Hard(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct UnixFileMetadata {
+    pub archive: UnixArchive,
+    /// Full `st_mode`, including the `S_IFMT` type bits.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub mtime: Option<String>,
+    pub inode: Option<u32>,
+    pub link: Option<UnixLink>,
+}
+
+/// Operating system whose saveset format a PDP-10 file came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pdp10System {
+    /// TOPS-10 BACKUP.
+    Tops10,
+    /// TOPS-20 DUMPER.
+    Tops20,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pdp10FileMetadata {
+    pub system: Pdp10System,
+    pub saveset: String,
+    /// Bits per byte as recorded by the monitor (7 for text, 36 for binaries).
+    pub byte_size: u8,
+    /// File length in bytes of `byte_size` bits.
+    pub byte_count: u64,
+    pub protection: u32,
+    pub write_date: Option<String>,
 }
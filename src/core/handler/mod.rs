@@ -0,0 +1,420 @@
+//! Format handlers: each tape format bundles its tape-level classification,
+//! file reconstruction, extraction and metadata description behind
+//! `FormatHandler`. A `HandlerRegistry` holds handlers in priority order and
+//! drives detection, reconstruction and extraction; `registry()` is the
+//! built-in set used by the app. A new decoder is one module implementing the
+//! trait plus a line in `HandlerRegistry::builtin`.
+pub mod pdp10;
+pub mod rsts;
+pub mod rsx;
+pub mod rt11;
+pub mod unix;
+pub mod vms;
+
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::core::block::{tape_files, TapeBlock, TapeFormat};
+use crate::core::detect::{classify_record, Detection, FormatScore, TapeFileVote};
+use crate::core::extract::raw::extract_raw_file;
+use crate::core::file::TapeFile;
+
+pub trait FormatHandler: Send + Sync {
+    /// Format this handler decodes; files and blocks it produces carry it.
+    fn format(&self) -> TapeFormat;
+
+    /// Display name, e.g. for the Input tab.
+    fn name(&self) -> &'static str;
+
+    /// Confidence, 0.0..=1.0, that classified blocks (normally one tape
+    /// file) are in this format. Built-in handlers check the structure of
+    /// the records themselves; the default is the share of blocks
+    /// classified as `format()`, with loose guesses counting `GUESS_WEIGHT`.
+    fn probe(&self, blocks: &[TapeBlock]) -> f32 {
+        if blocks.is_empty() {
+            return 0.0;
+        }
+        let format = self.format();
+        let score: f32 = blocks
+            .iter()
+            .filter(|b| b.classification.format() == format)
+            .map(|b| {
+                if b.classification.is_guess() {
+                    GUESS_WEIGHT
+                } else {
+                    1.0
+                }
+            })
+            .sum();
+        score / blocks.len() as f32
+    }
+
+    /// Tape-level classification run after the per-record classifier;
+    /// recognised structures override per-block guesses.
+    fn classify(&self, _blocks: &mut [TapeBlock]) {}
+
+    /// Rebuild the file tree of this format from classified blocks.
+    fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile>;
+
+    /// Whether this format can follow another one on the same tape, so its
+    /// files are reconstructed even when it is not the dominant format.
+    fn appends(&self) -> bool {
+        false
+    }
+
+    /// Write one (non-directory) file below `outdir`.
+    fn extract(&self, file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+        extract_raw_file(file, blocks, outdir)
+    }
+
+    /// Human-readable metadata lines for the Files and Contents tabs.
+    fn describe(&self, _file: &TapeFile) -> Vec<String> {
+        describe_raw()
+    }
+}
+
+/// Weight of a block classified by a loose guess rather than by structure.
+pub const GUESS_WEIGHT: f32 = 0.25;
+
+/// Confidence a tape file needs before it votes for a format. Files made of
+/// loose guesses alone never reach it.
+pub const MIN_FILE_CONFIDENCE: f32 = 0.5;
+
+/// Share of `blocks` vouched for by their own record: `weight` gives 1.0
+/// for a structural match, `GUESS_WEIGHT` for a loose one, 0.0 otherwise.
+pub fn record_share(blocks: &[TapeBlock], weight: impl Fn(&[u8]) -> f32) -> f32 {
+    if blocks.is_empty() {
+        return 0.0;
+    }
+    let score: f32 = blocks.iter().map(|b| weight(&b.raw)).sum();
+    score / blocks.len() as f32
+}
+
+/// Share of `blocks` lying in tape files whose structure `check` accepts.
+pub fn tape_file_share(blocks: &[TapeBlock], check: impl Fn(&[TapeBlock]) -> bool) -> f32 {
+    if blocks.is_empty() {
+        return 0.0;
+    }
+    let matched: usize = tape_files(blocks)
+        .into_iter()
+        .filter(|file| check(file))
+        .map(|file| file.len())
+        .sum();
+    matched as f32 / blocks.len() as f32
+}
+
+/// Description of a file without format metadata.
+pub fn describe_raw() -> Vec<String> {
+    vec!["Raw data, no metadata".to_string()]
+}
+
+/// Ordered set of format handlers. Earlier handlers run their tape-level
+/// classification first and win ties in detection.
+#[derive(Default)]
+pub struct HandlerRegistry {
+    handlers: Vec<Box<dyn FormatHandler>>,
+}
+
+impl HandlerRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every format decoded by this crate.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(rsts::RstsHandler));
+        registry.register(Box::new(rt11::Rt11Handler));
+        registry.register(Box::new(rsx::RsxHandler));
+        registry.register(Box::new(vms::VmsHandler));
+        registry.register(Box::new(unix::UnixHandler));
+        registry.register(Box::new(pdp10::Pdp10Handler));
+        registry
+    }
+
+    pub fn register(&mut self, handler: Box<dyn FormatHandler>) {
+        self.handlers.push(handler);
+    }
+
+    pub fn handlers(&self) -> impl Iterator<Item = &dyn FormatHandler> {
+        self.handlers.iter().map(|h| h.as_ref())
+    }
+
+    /// Handler for a format, if one is registered.
+    pub fn get(&self, format: &TapeFormat) -> Option<&dyn FormatHandler> {
+        self.handlers().find(|h| &h.format() == format)
+    }
+
+    /// Score every handler on each tape file separately. A tape file votes
+    /// for its best format once that reaches `MIN_FILE_CONFIDENCE`; the
+    /// winner is the format with the highest block-weighted confidence among
+    /// those that won at least one tape file. Ties go to the handler
+    /// registered first.
+    pub fn detect(&self, blocks: &[TapeBlock]) -> Detection {
+        let count = self.handlers.len();
+        let mut weighted = vec![0f32; count];
+        let mut won = vec![0usize; count];
+        let mut votes = Vec::new();
+        for file in tape_files(blocks) {
+            let mut best: Option<(usize, f32)> = None;
+            for (i, handler) in self.handlers().enumerate() {
+                let confidence = handler.probe(file).clamp(0.0, 1.0);
+                weighted[i] += confidence * file.len() as f32;
+                if confidence >= MIN_FILE_CONFIDENCE && best.is_none_or(|(_, c)| confidence > c) {
+                    best = Some((i, confidence));
+                }
+            }
+            let (format, confidence) = match best {
+                Some((i, confidence)) => {
+                    won[i] += 1;
+                    (self.handlers[i].format(), confidence)
+                }
+                None => (TapeFormat::Unknown, 0.0),
+            };
+            votes.push(TapeFileVote {
+                tape_file: file[0].tape_file,
+                blocks: file.len(),
+                format,
+                confidence,
+            });
+        }
+
+        let total = blocks.len().max(1) as f32;
+        let mut candidates: Vec<(FormatScore, bool)> = self
+            .handlers()
+            .enumerate()
+            .filter(|(i, _)| weighted[*i] > 0.0)
+            .map(|(i, h)| {
+                let score = FormatScore {
+                    format: h.format(),
+                    name: h.name(),
+                    confidence: weighted[i] / total,
+                    tape_files: won[i],
+                };
+                (score, won[i] > 0)
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.confidence.total_cmp(&a.0.confidence));
+        let winner = candidates
+            .iter()
+            .find(|(_, eligible)| *eligible)
+            .map(|(c, _)| c.format.clone())
+            .unwrap_or(TapeFormat::Unknown);
+        Detection {
+            winner,
+            candidates: candidates.into_iter().map(|(c, _)| c).collect(),
+            votes,
+        }
+    }
+
+    /// Classify every record, let each handler refine the result, and score
+    /// the candidate formats.
+    pub fn classify(&self, blocks: &mut [TapeBlock]) -> Detection {
+        for blk in blocks.iter_mut() {
+            blk.classification = classify_record(&blk.raw);
+        }
+        for handler in self.handlers() {
+            handler.classify(blocks);
+        }
+        self.detect(blocks)
+    }
+
+    /// Files of the detected format, followed by those of any appending
+    /// format also present on the tape.
+    pub fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile> {
+        self.reconstruct_as(blocks, &self.detect(blocks).winner)
+    }
+
+    /// Files decoded as `format` (e.g. a user override), followed by those of
+    /// any appending format also present on the tape.
+    pub fn reconstruct_as(&self, blocks: &[TapeBlock], format: &TapeFormat) -> Vec<TapeFile> {
+        let mut out = self
+            .get(format)
+            .map(|h| h.reconstruct(blocks))
+            .unwrap_or_default();
+        for handler in self.handlers() {
+            if handler.appends() && &handler.format() != format && handler.probe(blocks) > 0.0 {
+                out.extend(handler.reconstruct(blocks));
+            }
+        }
+        out
+    }
+
+    /// Extract one file through the handler of its format.
+    pub fn extract(&self, file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(outdir)?;
+        match self.get(&file.format) {
+            Some(handler) => handler.extract(file, blocks, outdir),
+            None => extract_raw_file(file, blocks, outdir),
+        }
+    }
+
+    pub fn describe(&self, file: &TapeFile) -> Vec<String> {
+        match self.get(&file.format) {
+            Some(handler) => handler.describe(file),
+            None => describe_raw(),
+        }
+    }
+}
+
+/// The built-in registry.
+pub fn registry() -> &'static HandlerRegistry {
+    static REGISTRY: OnceLock<HandlerRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(HandlerRegistry::builtin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{registry, FormatHandler, HandlerRegistry};
+    use crate::core::block::{BlockClassification, TapeBlock, TapeFormat};
+    use crate::core::file::{FileMetadata, TapeFile, TapePath};
+
+    /// A self-contained handler for records starting with "DEMO".
+    struct DemoHandler;
+
+    impl FormatHandler for DemoHandler {
+        fn format(&self) -> TapeFormat {
+            TapeFormat::Other("demo")
+        }
+
+        fn name(&self) -> &'static str {
+            "Demo"
+        }
+
+        fn classify(&self, blocks: &mut [TapeBlock]) {
+            for b in blocks.iter_mut().filter(|b| b.raw.starts_with(b"DEMO")) {
+                b.classification = BlockClassification::Other("demo");
+            }
+        }
+
+        fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile> {
+            blocks
+                .iter()
+                .filter(|b| self.probe(std::slice::from_ref(b)) > 0.0)
+                .map(|b| TapeFile {
+                    format: self.format(),
+                    path: TapePath::new(vec![format!("demo{}", b.index)]),
+                    size_bytes: b.size as u64,
+                    blocks: vec![b.index],
+                    extents: Vec::new(),
+                    metadata: FileMetadata::Raw,
+                    children: Vec::new(),
+                })
+                .collect()
+        }
+
+        fn describe(&self, _file: &TapeFile) -> Vec<String> {
+            vec!["Demo file".to_string()]
+        }
+    }
+
+    fn block(index: u32, data: &[u8]) -> TapeBlock {
+        file_block(index, 0, data)
+    }
+
+    fn file_block(index: u32, tape_file: u32, data: &[u8]) -> TapeBlock {
+        TapeBlock {
+            index,
+            tape_file,
+            size: data.len(),
+            raw: data.to_vec().into(),
+            classification: BlockClassification::Unknown,
+        }
+    }
+
+    #[test]
+    fn registered_handler_drives_the_pipeline() {
+        let mut reg = HandlerRegistry::builtin();
+        reg.register(Box::new(DemoHandler));
+        let mut blocks = vec![block(0, b"DEMO one"), block(1, b"DEMO two"), block(2, b"x")];
+        assert_eq!(reg.classify(&mut blocks).winner, TapeFormat::Other("demo"));
+
+        let files = reg.reconstruct(&blocks);
+        assert_eq!(files.len(), 2);
+        assert_eq!(reg.describe(&files[0]), vec!["Demo file".to_string()]);
+
+        let dir = std::env::temp_dir().join(format!("rts_handler_{}", std::process::id()));
+        reg.extract(&files[1], &blocks, &dir).unwrap();
+        assert_eq!(std::fs::read(dir.join("demo1")).unwrap(), b"DEMO two");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // The built-in registry knows nothing about it.
+        assert!(registry().get(&TapeFormat::Other("demo")).is_none());
+        assert_eq!(registry().describe(&files[0]), super::describe_raw());
+    }
+
+    /// A bare 512-byte block only the loose RSX-11M heuristic matches.
+    fn guess_block() -> Vec<u8> {
+        let mut data = vec![0x55u8; 512];
+        data[0..2].copy_from_slice(&[0x31, 0x00]);
+        data
+    }
+
+    #[test]
+    fn loose_guesses_alone_do_not_win() {
+        let mut blocks: Vec<_> = (0..4).map(|i| block(i, &guess_block())).collect();
+        let detection = registry().classify(&mut blocks);
+        assert_eq!(detection.winner, TapeFormat::Unknown);
+        assert!(detection.confidence(&TapeFormat::Rsx) <= super::GUESS_WEIGHT);
+        assert_eq!(detection.votes.len(), 1);
+        assert_eq!(detection.votes[0].format, TapeFormat::Unknown);
+    }
+
+    #[test]
+    fn vms_probe_checks_block_headers() {
+        use crate::backup::writer::SavesetOptions;
+        use crate::core::vms::tests::saveset;
+
+        let opts = SavesetOptions {
+            block_size: 2048,
+            ..SavesetOptions::default()
+        };
+        let saveset = saveset(&[("[000000]A.TXT;1", &[b'a'; 3000])], &opts);
+        let mut blocks: Vec<_> = saveset
+            .iter()
+            .enumerate()
+            .map(|(i, raw)| block(i as u32, raw))
+            .collect();
+        let vms = registry().get(&TapeFormat::Vms).unwrap();
+        assert_eq!(vms.probe(&blocks), 1.0);
+
+        // A damaged header keeps its layout but fails the checksum.
+        let mut raw = saveset[0].clone();
+        raw[20] ^= 0xFF;
+        blocks[0] = block(0, &raw);
+        assert_eq!(vms.probe(&blocks[..1]), 0.0);
+        assert_eq!(vms.probe(&blocks[1..]), 1.0);
+        let rsx = registry().get(&TapeFormat::Rsx).unwrap();
+        assert_eq!(rsx.probe(&blocks), 0.0);
+    }
+
+    #[test]
+    fn tape_files_vote_separately() {
+        use crate::backup::writer::SavesetOptions;
+        use crate::core::vms::tests::saveset;
+
+        let opts = SavesetOptions {
+            block_size: 2048,
+            ..SavesetOptions::default()
+        };
+        let saveset = saveset(&[("[000000]A.TXT;1", &[b'a'; 3000])], &opts);
+        let mut blocks = vec![
+            file_block(0, 0, &guess_block()),
+            file_block(1, 0, &guess_block()),
+            file_block(2, 1, &saveset[0]),
+            file_block(3, 1, &saveset[1]),
+        ];
+        let detection = registry().classify(&mut blocks);
+        assert_eq!(detection.winner, TapeFormat::Vms);
+        let votes: Vec<_> = detection
+            .votes
+            .iter()
+            .map(|v| (v.tape_file, v.format.clone()))
+            .collect();
+        assert_eq!(votes, vec![(0, TapeFormat::Unknown), (1, TapeFormat::Vms)]);
+        assert_eq!(detection.confidence(&TapeFormat::Vms), 0.5);
+        assert_eq!(detection.candidates[0].tape_files, 1);
+    }
+}
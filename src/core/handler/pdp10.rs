@@ -0,0 +1,66 @@
+//! PDP-10: TOPS-20 DUMPER and TOPS-10 BACKUP savesets.
+use std::io;
+use std::path::Path;
+
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::extract::pdp10::extract_pdp10_file;
+use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::pdp10::backup::is_backup10_block;
+use crate::core::pdp10::dumper::is_dumper_block;
+use crate::core::pdp10::{classify_pdp10_tape, reconstruct_pdp10_tape};
+use crate::utils::text::sanitize_display;
+
+use super::{describe_raw, record_share, FormatHandler};
+
+pub struct Pdp10Handler;
+
+impl FormatHandler for Pdp10Handler {
+    fn format(&self) -> TapeFormat {
+        TapeFormat::Pdp10
+    }
+
+    fn name(&self) -> &'static str {
+        "PDP-10 DUMPER/BACKUP"
+    }
+
+    /// Share of records made of whole DUMPER or BACKUP records with sane
+    /// headers.
+    fn probe(&self, blocks: &[TapeBlock]) -> f32 {
+        record_share(blocks, |raw| {
+            if is_dumper_block(raw) || is_backup10_block(raw) {
+                1.0
+            } else {
+                0.0
+            }
+        })
+    }
+
+    fn classify(&self, blocks: &mut [TapeBlock]) {
+        classify_pdp10_tape(blocks);
+    }
+
+    fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile> {
+        reconstruct_pdp10_tape(blocks)
+    }
+
+    fn extract(&self, file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+        extract_pdp10_file(file, blocks, outdir)
+    }
+
+    fn describe(&self, file: &TapeFile) -> Vec<String> {
+        let FileMetadata::Pdp10(meta) = &file.metadata else {
+            return describe_raw();
+        };
+        let mut out = vec![
+            format!("System: {:?}", meta.system),
+            format!("Saveset: {}", sanitize_display(&meta.saveset)),
+            format!("Byte size: {} bits", meta.byte_size),
+            format!("Length: {} bytes", meta.byte_count),
+            format!("Protection: <{:o}>", meta.protection),
+        ];
+        if let Some(t) = &meta.write_date {
+            out.push(format!("Written: {t}"));
+        }
+        out
+    }
+}
@@ -0,0 +1,69 @@
+//! RSTS/E: DOS-11 labelled tapes, falling back to UFD blocks found in bare
+//! records.
+use std::io;
+use std::path::Path;
+
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::extract::rsts::extract_rsts_file;
+use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::reconstruct::builder::reconstruct_rsts;
+use crate::core::rsts::format_ppn;
+use crate::core::rsts::tape::{classify_rsts_tape, is_dos11_tape_file, reconstruct_rsts_tape};
+
+use super::{describe_raw, tape_file_share, FormatHandler};
+
+pub struct RstsHandler;
+
+impl FormatHandler for RstsHandler {
+    fn format(&self) -> TapeFormat {
+        TapeFormat::Rsts
+    }
+
+    fn name(&self) -> &'static str {
+        "RSTS/E"
+    }
+
+    /// Share of blocks in tape files laid out as DOS-11 label and data.
+    fn probe(&self, blocks: &[TapeBlock]) -> f32 {
+        tape_file_share(blocks, is_dos11_tape_file)
+    }
+
+    fn classify(&self, blocks: &mut [TapeBlock]) {
+        classify_rsts_tape(blocks);
+    }
+
+    fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile> {
+        let files = reconstruct_rsts_tape(blocks);
+        if files.is_empty() {
+            reconstruct_rsts(blocks)
+        } else {
+            files
+        }
+    }
+
+    fn extract(&self, file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+        extract_rsts_file(file, blocks, outdir)
+    }
+
+    fn describe(&self, file: &TapeFile) -> Vec<String> {
+        let FileMetadata::Rsts(meta) = &file.metadata else {
+            return describe_raw();
+        };
+        let mut out = vec![
+            format!("Account: {}", format_ppn(meta.owner_uic)),
+            format!("Protection: <{}>", meta.protection),
+            format!("Status: 0x{:04X}", meta.status),
+            format!("Blocks: {}", meta.blocks),
+        ];
+        if meta.cluster_size != 0 {
+            out.push(format!("Cluster size: {}", meta.cluster_size));
+        }
+        if let Some(t) = &meta.creation_date {
+            out.push(format!("Created: {}", t));
+        }
+        if let Some(t) = &meta.access_date {
+            out.push(format!("Accessed: {}", t));
+        }
+        out
+    }
+}
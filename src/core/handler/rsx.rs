@@ -0,0 +1,56 @@
+//! RSX-11M: UFD blocks, reconstructed from classified directory records.
+use std::io;
+use std::path::Path;
+
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::detect::{detect_rsx11m, is_rsx_guess};
+use crate::core::extract::rsx::extract_rsx_file;
+use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::reconstruct::builder::reconstruct_rsx;
+
+use super::{describe_raw, record_share, FormatHandler, GUESS_WEIGHT};
+
+pub struct RsxHandler;
+
+impl FormatHandler for RsxHandler {
+    fn format(&self) -> TapeFormat {
+        TapeFormat::Rsx
+    }
+
+    fn name(&self) -> &'static str {
+        "RSX-11M"
+    }
+
+    /// Share of records that are UFD blocks, with bare blocks only starting
+    /// with an RSX-11M word counting `GUESS_WEIGHT`.
+    fn probe(&self, blocks: &[TapeBlock]) -> f32 {
+        record_share(blocks, |raw| {
+            if detect_rsx11m(raw) {
+                1.0
+            } else if is_rsx_guess(raw) {
+                GUESS_WEIGHT
+            } else {
+                0.0
+            }
+        })
+    }
+
+    fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile> {
+        reconstruct_rsx(blocks)
+    }
+
+    fn extract(&self, file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+        extract_rsx_file(file, blocks, outdir)
+    }
+
+    fn describe(&self, file: &TapeFile) -> Vec<String> {
+        match &file.metadata {
+            FileMetadata::Rsx(meta) => vec![
+                format!("Directory: {}", meta.is_directory),
+                format!("UIC {:03o},{:03o}", meta.uic.0, meta.uic.1),
+                format!("Protection: {:o}", meta.protection),
+            ],
+            _ => describe_raw(),
+        }
+    }
+}
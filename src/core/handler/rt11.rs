@@ -0,0 +1,67 @@
+//! RT-11: file-structured magtape and BUP volumes, falling back to
+//! directory segments found in bare blocks.
+use std::io;
+use std::path::Path;
+
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::extract::rt11::{extract_rt11_file, rt11_file_name};
+use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::reconstruct::builder::reconstruct_rt11;
+use crate::core::rt11::tape::{classify_rt11_tape, is_rt11_tape_file, reconstruct_rt11_tape};
+use crate::utils::text::sanitize_display;
+
+use super::{describe_raw, tape_file_share, FormatHandler};
+
+pub struct Rt11Handler;
+
+impl FormatHandler for Rt11Handler {
+    fn format(&self) -> TapeFormat {
+        TapeFormat::Rt11
+    }
+
+    fn name(&self) -> &'static str {
+        "RT-11"
+    }
+
+    /// Share of blocks in RT-11 label, FSM data or volume image tape files.
+    /// Directory blocks found in bare records carry no home block to check
+    /// and do not count.
+    fn probe(&self, blocks: &[TapeBlock]) -> f32 {
+        tape_file_share(blocks, is_rt11_tape_file)
+    }
+
+    fn classify(&self, blocks: &mut [TapeBlock]) {
+        classify_rt11_tape(blocks);
+    }
+
+    fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile> {
+        let files = reconstruct_rt11_tape(blocks);
+        if files.is_empty() {
+            reconstruct_rt11(blocks)
+        } else {
+            files
+        }
+    }
+
+    fn extract(&self, file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+        extract_rt11_file(file, blocks, outdir)
+    }
+
+    fn describe(&self, file: &TapeFile) -> Vec<String> {
+        let FileMetadata::Rt11(meta) = &file.metadata else {
+            return describe_raw();
+        };
+        let mut out = vec![
+            format!(
+                "File: {}",
+                sanitize_display(&rt11_file_name(file, &meta.ext))
+            ),
+            format!("Start block: {}", meta.start_block),
+            format!("Length (blocks): {}", meta.length_blocks),
+        ];
+        if let Some(date) = &meta.creation_date {
+            out.push(format!("Created: {date}"));
+        }
+        out
+    }
+}
@@ -0,0 +1,70 @@
+//! Unix: V6/V7 `tp`, `tar` and `dump` archives.
+use std::io;
+use std::path::Path;
+
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::extract::unix::extract_unix_file;
+use crate::core::file::{FileMetadata, TapeFile, UnixLink};
+use crate::core::unix::{
+    classify_unix_tape, detect_unix_archive, format_mode, reconstruct_unix_tape,
+};
+use crate::utils::text::sanitize_display;
+
+use super::{describe_raw, tape_file_share, FormatHandler};
+
+pub struct UnixHandler;
+
+impl FormatHandler for UnixHandler {
+    fn format(&self) -> TapeFormat {
+        TapeFormat::Unix
+    }
+
+    fn name(&self) -> &'static str {
+        "Unix tp/tar/dump"
+    }
+
+    /// Share of blocks in tape files opening with a tar header whose
+    /// checksum verifies, a dump tape header, or a tp directory.
+    fn probe(&self, blocks: &[TapeBlock]) -> f32 {
+        tape_file_share(blocks, |file| detect_unix_archive(file).is_some())
+    }
+
+    fn classify(&self, blocks: &mut [TapeBlock]) {
+        classify_unix_tape(blocks);
+    }
+
+    fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile> {
+        reconstruct_unix_tape(blocks)
+    }
+
+    fn extract(&self, file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+        extract_unix_file(file, blocks, outdir)
+    }
+
+    fn describe(&self, file: &TapeFile) -> Vec<String> {
+        let FileMetadata::Unix(meta) = &file.metadata else {
+            return describe_raw();
+        };
+        let mut out = vec![
+            format!("Archive: {:?}", meta.archive),
+            format!("Mode: {} ({:06o})", format_mode(meta.mode), meta.mode),
+            format!("Owner: uid {} gid {}", meta.uid, meta.gid),
+        ];
+        if let Some(ino) = meta.inode {
+            out.push(format!("Inode: {ino}"));
+        }
+        if let Some(t) = &meta.mtime {
+            out.push(format!("Modified: {t}"));
+        }
+        match &meta.link {
+            Some(UnixLink::Symbolic(target)) => {
+                out.push(format!("Symlink to: {}", sanitize_display(target)))
+            }
+            Some(UnixLink::Hard(target)) => {
+                out.push(format!("Hard link to: {}", sanitize_display(target)))
+            }
+            None => {}
+        }
+        out
+    }
+}
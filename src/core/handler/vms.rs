@@ -0,0 +1,84 @@
+//! VMS BACKUP Phase-1 savesets. They may follow another format on the same
+//! tape, so they are reconstructed whenever present.
+use std::io;
+use std::path::Path;
+
+use crate::backup::bbh::verify_bbh_checksum;
+use crate::backup::vms::{format_protection, parse_record_format};
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::extract::vms::extract_vms_file;
+use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::vms::{has_backup_block, reconstruct_vms};
+
+use super::{describe_raw, record_share, FormatHandler};
+
+pub struct VmsHandler;
+
+impl FormatHandler for VmsHandler {
+    fn format(&self) -> TapeFormat {
+        TapeFormat::Vms
+    }
+
+    fn name(&self) -> &'static str {
+        "VMS BACKUP"
+    }
+
+    /// Share of records that are BACKUP blocks whose header checksum
+    /// verifies. A failing block CRC marks damage, not another format, so
+    /// it is left to the health checks.
+    fn probe(&self, blocks: &[TapeBlock]) -> f32 {
+        record_share(blocks, |raw| {
+            if has_backup_block(raw) && verify_bbh_checksum(raw) {
+                1.0
+            } else {
+                0.0
+            }
+        })
+    }
+
+    fn reconstruct(&self, blocks: &[TapeBlock]) -> Vec<TapeFile> {
+        reconstruct_vms(blocks)
+    }
+
+    fn appends(&self) -> bool {
+        true
+    }
+
+    fn extract(&self, file: &TapeFile, blocks: &[TapeBlock], outdir: &Path) -> io::Result<()> {
+        extract_vms_file(file, blocks, outdir)
+    }
+
+    fn describe(&self, file: &TapeFile) -> Vec<String> {
+        let FileMetadata::Vms(meta) = &file.metadata else {
+            return describe_raw();
+        };
+        let mut out = vec![
+            format!(
+                "File ID: ({}, {}, {})",
+                meta.file_id.0, meta.file_id.1, meta.file_id.2
+            ),
+            format!("Revision: {}", meta.rev),
+            format!("Sequence: {}", meta.seq),
+            format!("Owner UIC: {:o},{:o}", meta.owner_uic.0, meta.owner_uic.1),
+            format!("Protection: {}", format_protection(meta.protection)),
+            format!(
+                "Record Format: {}",
+                parse_record_format(meta.record_format).label()
+            ),
+            format!("Record Attributes: 0x{:02X}", meta.record_attributes),
+            format!("Record Length: {}", meta.record_length),
+            format!("File Type: {}", meta.file_type),
+            format!("Backup Flags: 0x{:04X}", meta.backup_flags),
+        ];
+        if let Some(t) = &meta.creation_time {
+            out.push(format!("Created: {}", t));
+        }
+        if let Some(t) = &meta.revision_time {
+            out.push(format!("Revised: {}", t));
+        }
+        if let Some(t) = &meta.expiration_time {
+            out.push(format!("Expires: {}", t));
+        }
+        out
+    }
+}
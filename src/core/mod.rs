@@ -1,8 +1,18 @@
+pub mod ansi;
 pub mod block;
 pub mod detect;
 pub mod extract;
+pub mod fields;
 pub mod file;
+pub mod handler;
 pub mod parse;
+pub mod pdp10;
+pub mod preview;
 pub mod reconstruct;
+pub mod rsts;
+pub mod rt11;
+pub mod search;
+pub mod timeline;
+pub mod unix;
 pub mod vms;
 pub use extract::extract_file;
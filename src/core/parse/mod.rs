@@ -1,7 +1,6 @@
 pub mod rsts;
 pub mod rsx;
 pub mod rt11;
-pub mod vms;
 
 use crate::core::block::{BlockClassification, TapeBlock};
 
@@ -21,8 +20,9 @@ pub fn parse_classified_block(block: &TapeBlock) -> Option<ParsedBlock> {
     }
 }
 
-fn decode_rad50_word(word: u16) -> String {
-    const RAD50_TABLE: &[u8] = b" ABCDEFGHIJKLMNOPQRSTUVWXYZ$.%0123456789";
+const RAD50_TABLE: &[u8] = b" ABCDEFGHIJKLMNOPQRSTUVWXYZ$.%0123456789";
+
+pub(crate) fn decode_rad50_word(word: u16) -> String {
     let a = (word / 1600) as usize;
     let b = ((word % 1600) / 40) as usize;
     let c = (word % 40) as usize;
@@ -36,3 +36,76 @@ fn decode_rad50_word(word: u16) -> String {
     }
     out
 }
+
+/// The three RAD50 characters of `word`, spaces kept in place; `None` for
+/// words above the largest RAD50 value.
+pub(crate) fn rad50_chars(word: u16) -> Option<String> {
+    if word >= 40 * 40 * 40 {
+        return None;
+    }
+    let word = word as usize;
+    Some(
+        [word / 1600, (word / 40) % 40, word % 40]
+            .iter()
+            .map(|&i| RAD50_TABLE[i] as char)
+            .collect(),
+    )
+}
+
+/// Encode up to three characters as one RAD50 word, padding with spaces.
+/// Lower case is folded to upper case; returns `None` for characters
+/// outside the RAD50 set or text longer than three characters.
+pub(crate) fn encode_rad50_word(text: &str) -> Option<u16> {
+    if text.len() > 3 {
+        return None;
+    }
+    let mut word = 0u16;
+    let mut chars = text.bytes().map(|c| c.to_ascii_uppercase());
+    for _ in 0..3 {
+        let idx = match chars.next() {
+            Some(c) => RAD50_TABLE.iter().position(|&t| t == c)?,
+            None => 0,
+        };
+        word = word * 40 + idx as u16;
+    }
+    Some(word)
+}
+
+/// Encode text as consecutive RAD50 words, three characters per word.
+/// Returns `None` for non-ASCII text.
+pub(crate) fn encode_rad50(text: &str, words: usize) -> Option<Vec<u16>> {
+    if !text.is_ascii() || text.len() > words * 3 {
+        return None;
+    }
+    (0..words)
+        .map(|i| {
+            let start = (i * 3).min(text.len());
+            let end = (start + 3).min(text.len());
+            encode_rad50_word(&text[start..end])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_rad50_word, encode_rad50, encode_rad50_word};
+
+    #[test]
+    fn rad50_round_trips() {
+        assert_eq!(encode_rad50_word("HEL"), Some(8 * 1600 + 5 * 40 + 12));
+        assert_eq!(encode_rad50_word("p"), Some(16 * 1600));
+        assert_eq!(encode_rad50_word(""), Some(0));
+        assert_eq!(encode_rad50_word("A!"), None);
+        assert_eq!(encode_rad50_word("ABCD"), None);
+        for text in ["SWA", "$.%", "Z9", "0"] {
+            assert_eq!(decode_rad50_word(encode_rad50_word(text).unwrap()), text);
+        }
+        let words = encode_rad50("HELLO", 2).unwrap();
+        assert_eq!(
+            decode_rad50_word(words[0]) + &decode_rad50_word(words[1]),
+            "HELLO"
+        );
+        assert_eq!(encode_rad50("TOOLONG", 2), None);
+        assert_eq!(encode_rad50("AB\u{c9}", 2), None);
+    }
+}
@@ -15,7 +15,7 @@ pub fn parse_block(block: &TapeBlock) -> Option<RsxBlockInfo> {
         return None;
     }
 
-    if data.get(0)? != &0x31 || data.get(1)? != &0x00 {
+    if data.first()? != &0x31 || data.get(1)? != &0x00 {
         return None;
     }
 
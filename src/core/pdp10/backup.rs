@@ -0,0 +1,352 @@
+//! TOPS-10 BACKUP savesets.
+//!
+//! Every record is 544 words: a 32-word header followed by 512 data words.
+//! The header fields used here are:
+//!
+//! | word | field                                                  |
+//! |------|--------------------------------------------------------|
+//! | 0    | G$TYPE record type                                     |
+//! | 1    | G$SEQ record sequence number                           |
+//! | 2    | G$RTNM tape number                                     |
+//! | 3    | G$FLAG flags (GF$EOF bit 0, GF$SOF bit 3)              |
+//! | 4    | G$CHK checksum                                         |
+//! | 5    | G$SIZ file data words in this record                   |
+//! | 6    | G$LND leading non-data words before the file data      |
+//! | 13   | F$RDW word offset of this record's data in the file    |
+//!
+//! A start-of-saveset record carries the saveset date in data word 0 and
+//! the ASCIZ saveset name from data word 1. File records lead with a name
+//! block (LH 1, RH length) whose sub-blocks hold the device (1), name (2),
+//! extension (3), UFD (40) and SFDs (41 onwards) as ASCIZ text, each
+//! introduced by a word with the code in LH and the length in RH. The
+//! attribute block (LH 2, RH length) follows; counting from the word after
+//! its header, word 2 is the write date, 4 the length in bytes, 5 the byte
+//! size and 6 the protection code. Data words follow the G$LND leading
+//! words and are placed in the file at F$RDW.
+
+use std::collections::BTreeMap;
+
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::file::{
+    BlockExtent, FileMetadata, Pdp10FileMetadata, Pdp10System, TapeFile, TapePath,
+};
+
+use super::{
+    asciz, decode_udt, host_size, insert_pdp10_file, left_half, logical_records, right_half,
+    unpack_words, CORE_DUMP_BYTES, PAGE_WORDS,
+};
+
+pub const BACKUP_HEADER_WORDS: usize = 32;
+pub const BACKUP_RECORD_WORDS: usize = BACKUP_HEADER_WORDS + PAGE_WORDS;
+
+pub const T_LBL: u64 = 1;
+pub const T_SSS: u64 = 2;
+pub const T_ESS: u64 = 3;
+pub const T_FIL: u64 = 4;
+pub const T_UFD: u64 = 5;
+pub const T_EOV: u64 = 6;
+pub const T_COM: u64 = 7;
+pub const T_CON: u64 = 8;
+
+pub const GF_EOF: u64 = 1 << 35;
+pub const GF_SOF: u64 = 1 << 32;
+
+const G_TYPE: usize = 0;
+const G_FLAG: usize = 3;
+const G_SIZ: usize = 5;
+const G_LND: usize = 6;
+const F_RDW: usize = 13;
+
+const BLOCK_NAME: u32 = 1;
+const BLOCK_ATTR: u32 = 2;
+const NAME_DEVICE: u32 = 1;
+const NAME_FILE: u32 = 2;
+const NAME_EXT: u32 = 3;
+const NAME_UFD: u32 = 0o40;
+
+const A_WRIT: usize = 2;
+const A_LENG: usize = 4;
+const A_BSIZ: usize = 5;
+const A_PROT: usize = 6;
+
+/// True when a tape block holds whole BACKUP records with sane headers.
+pub fn is_backup10_block(data: &[u8]) -> bool {
+    let len = BACKUP_RECORD_WORDS * CORE_DUMP_BYTES;
+    if data.is_empty() || !data.len().is_multiple_of(len) {
+        return false;
+    }
+    data.chunks(len).all(|rec| {
+        let header = unpack_words(&rec[..BACKUP_HEADER_WORDS * CORE_DUMP_BYTES]);
+        let size = header[G_SIZ] as usize;
+        let lead = header[G_LND] as usize;
+        (T_LBL..=T_CON).contains(&header[G_TYPE]) && size + lead <= PAGE_WORDS
+    })
+}
+
+/// File name and attributes from the leading words of a file record.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Backup10Name {
+    pub device: String,
+    /// UFD as `[p,pn]`, followed by any SFDs.
+    pub directory: Vec<String>,
+    /// `NAME.EXT`
+    pub name: String,
+    pub write_date: Option<String>,
+    pub byte_count: u64,
+    pub byte_size: u8,
+    pub protection: u32,
+}
+
+/// Parse the name and attribute blocks at the start of a file record.
+pub fn parse_backup10_name(lead: &[u64]) -> Backup10Name {
+    let mut out = Backup10Name::default();
+    let mut ext = String::new();
+    let mut pos = 0usize;
+    while pos < lead.len() {
+        let (code, len) = (left_half(lead[pos]), right_half(lead[pos]) as usize);
+        if len == 0 || pos + len > lead.len() {
+            break;
+        }
+        let block = &lead[pos + 1..pos + len];
+        match code {
+            BLOCK_NAME => {
+                let mut sub = 0usize;
+                while sub < block.len() {
+                    let (kind, n) = (left_half(block[sub]), right_half(block[sub]) as usize);
+                    if n == 0 || sub + n > block.len() {
+                        break;
+                    }
+                    let text = asciz(&block[sub + 1..sub + n]);
+                    match kind {
+                        NAME_DEVICE => out.device = text,
+                        NAME_FILE => out.name = text,
+                        NAME_EXT => ext = text,
+                        NAME_UFD => out.directory.insert(0, format!("[{text}]")),
+                        k if k > NAME_UFD => out.directory.push(text),
+                        _ => {}
+                    }
+                    sub += n;
+                }
+            }
+            BLOCK_ATTR => {
+                let attr = |i: usize| block.get(i).copied().unwrap_or(0);
+                out.write_date = decode_udt(attr(A_WRIT));
+                out.byte_count = attr(A_LENG);
+                out.byte_size = attr(A_BSIZ) as u8;
+                out.protection = attr(A_PROT) as u32;
+            }
+            _ => {}
+        }
+        pos += len;
+    }
+    if !ext.is_empty() {
+        out.name = format!("{}.{ext}", out.name);
+    }
+    out
+}
+
+struct PendingFile {
+    name: Backup10Name,
+    /// File word offset -> (block, byte offset, byte length).
+    chunks: BTreeMap<u64, (u32, usize, usize)>,
+}
+
+/// Decode the BACKUP savesets of one tape file, one node per saveset.
+pub fn decode_backup10(group: &[TapeBlock]) -> Vec<TapeFile> {
+    let mut tree = Vec::new();
+    let mut saveset = String::new();
+    let mut pending: Option<PendingFile> = None;
+    for (block, offset, words) in logical_records(group, BACKUP_RECORD_WORDS) {
+        let data = &words[BACKUP_HEADER_WORDS..];
+        match words[G_TYPE] {
+            T_SSS | T_CON => {
+                saveset = asciz(&data[1..]);
+                if saveset.is_empty() {
+                    saveset = format!("saveset_{}", words[2]);
+                }
+            }
+            T_FIL => {
+                let flags = words[G_FLAG];
+                let lead = (words[G_LND] as usize).min(PAGE_WORDS);
+                let size = (words[G_SIZ] as usize).min(PAGE_WORDS - lead);
+                if flags & GF_SOF != 0 {
+                    if let Some(file) = pending.take() {
+                        finish(&mut tree, &saveset, file);
+                    }
+                    pending = Some(PendingFile {
+                        name: parse_backup10_name(&data[..lead]),
+                        chunks: BTreeMap::new(),
+                    });
+                }
+                if let Some(file) = pending.as_mut() {
+                    if size > 0 {
+                        let start = offset + (BACKUP_HEADER_WORDS + lead) * CORE_DUMP_BYTES;
+                        file.chunks
+                            .insert(words[F_RDW], (block, start, size * CORE_DUMP_BYTES));
+                    }
+                }
+                if flags & GF_EOF != 0 {
+                    if let Some(file) = pending.take() {
+                        finish(&mut tree, &saveset, file);
+                    }
+                }
+            }
+            T_ESS | T_EOV => {
+                if let Some(file) = pending.take() {
+                    finish(&mut tree, &saveset, file);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(file) = pending.take() {
+        finish(&mut tree, &saveset, file);
+    }
+    tree
+}
+
+fn finish(tree: &mut Vec<TapeFile>, saveset: &str, file: PendingFile) {
+    let name = file.name;
+    let mut extents = Vec::new();
+    let mut next = 0u64;
+    for (&word, &(block, offset, len)) in &file.chunks {
+        if word > next {
+            extents.push(BlockExtent::hole((word - next) as usize * CORE_DUMP_BYTES));
+        }
+        extents.push(BlockExtent { block, offset, len });
+        next = word + (len / CORE_DUMP_BYTES) as u64;
+    }
+    let mut blocks: Vec<u32> = file.chunks.values().map(|(b, _, _)| *b).collect();
+    blocks.dedup();
+
+    let mut path = vec![saveset.to_string()];
+    path.extend(name.directory);
+    path.push(name.name);
+    insert_pdp10_file(
+        tree,
+        TapeFile {
+            format: TapeFormat::Pdp10,
+            path: TapePath::new(path),
+            size_bytes: host_size(name.byte_size, name.byte_count),
+            blocks,
+            extents,
+            metadata: FileMetadata::Pdp10(Pdp10FileMetadata {
+                system: Pdp10System::Tops10,
+                saveset: saveset.to_string(),
+                byte_size: name.byte_size,
+                byte_count: name.byte_count,
+                protection: name.protection,
+                write_date: name.write_date,
+            }),
+            children: Vec::new(),
+        },
+        Pdp10System::Tops10,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_backup10, is_backup10_block, parse_backup10_name, BACKUP_HEADER_WORDS,
+        BACKUP_RECORD_WORDS, GF_EOF, GF_SOF, T_FIL, T_SSS,
+    };
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::core::extract::file_bytes;
+    use crate::core::file::FileMetadata;
+    use crate::core::pdp10::tests::ascii_words;
+    use crate::core::pdp10::{convert_file_data, pack_word};
+
+    fn sub_block(code: u64, text: &str) -> Vec<u64> {
+        let body = ascii_words(text);
+        let mut out = vec![(code << 18) | (body.len() as u64 + 1)];
+        out.extend(body);
+        out
+    }
+
+    fn lead_words(name: &str, ext: &str, bytes: u64) -> Vec<u64> {
+        let mut names = sub_block(1, "DSKB");
+        names.extend(sub_block(2, name));
+        names.extend(sub_block(3, ext));
+        names.extend(sub_block(0o40, "10,7"));
+        names.extend(sub_block(0o41, "SUB"));
+        let mut out = vec![(1 << 18) | (names.len() as u64 + 1)];
+        out.extend(names);
+        let mut attr = vec![0u64; 8];
+        attr[2] = 44239 << 18;
+        attr[4] = bytes;
+        attr[5] = 7;
+        attr[6] = 0o057;
+        out.push((2 << 18) | (attr.len() as u64 + 1));
+        out.extend(attr);
+        out
+    }
+
+    fn record(index: u32, header: &[(usize, u64)], data: &[u64]) -> TapeBlock {
+        let mut words = vec![0u64; BACKUP_RECORD_WORDS];
+        for (at, value) in header {
+            words[*at] = *value;
+        }
+        words[BACKUP_HEADER_WORDS..BACKUP_HEADER_WORDS + data.len()].copy_from_slice(data);
+        let raw: Vec<u8> = words.iter().flat_map(|w| pack_word(*w)).collect();
+        TapeBlock {
+            index,
+            tape_file: 0,
+            size: raw.len(),
+            raw: raw.into(),
+            classification: BlockClassification::Unknown,
+        }
+    }
+
+    #[test]
+    fn parses_name_and_attribute_blocks() {
+        let name = parse_backup10_name(&lead_words("TEST", "MAC", 12));
+        assert_eq!(name.device, "DSKB");
+        assert_eq!(name.directory, vec!["[10,7]", "SUB"]);
+        assert_eq!(name.name, "TEST.MAC");
+        assert_eq!(name.byte_count, 12);
+        assert_eq!(name.protection, 0o057);
+        assert_eq!(name.write_date.as_deref(), Some("1980-01-01 00:00:00"));
+    }
+
+    #[test]
+    fn decodes_file_split_across_records() {
+        // Ten words of text: the first record carries five, the second five.
+        let text = "ABCDEFGHIJKLMNOPQRSTUVWXY\r\nZYXWVUTSRQPONMLKJIHGFEDCBA";
+        let body = ascii_words(text);
+        let lead = lead_words("TEST", "TXT", text.len() as u64);
+        let mut first = lead.clone();
+        first.extend(&body[..5]);
+        let mut sss = vec![44239 << 18];
+        sss.extend(ascii_words("DAILY"));
+        let blocks = vec![
+            record(0, &[(0, T_SSS)], &sss),
+            record(
+                1,
+                &[(0, T_FIL), (3, GF_SOF), (5, 5), (6, lead.len() as u64)],
+                &first,
+            ),
+            record(
+                2,
+                &[
+                    (0, T_FIL),
+                    (3, GF_EOF),
+                    (5, (body.len() - 5) as u64),
+                    (13, 5),
+                ],
+                &body[5..],
+            ),
+        ];
+        assert!(is_backup10_block(&blocks[1].raw));
+        let tree = decode_backup10(&blocks);
+        assert_eq!(tree[0].path.to_string_path(), "DAILY");
+        let file = &tree[0].children[0].children[0].children[0];
+        assert_eq!(file.path.to_string_path(), "DAILY/[10,7]/SUB/TEST.TXT");
+        match &file.metadata {
+            FileMetadata::Pdp10(meta) => {
+                let host = convert_file_data(&file_bytes(file, &blocks), 7, meta.byte_count);
+                assert_eq!(host, text.replace("\r\n", "\n").as_bytes());
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! TOPS-20 DUMPER savesets.
+//!
+//! Every record is 518 words: a six-word header followed by one page of data.
+//!
+//! | word | field                                        |
+//! |------|----------------------------------------------|
+//! | 0    | checksum                                     |
+//! | 1    | page access bits                             |
+//! | 2    | LH saveset number, RH file number            |
+//! | 3    | page number within the file (data records)   |
+//! | 4    | record type, stored negated                  |
+//! | 5    | record sequence number                       |
+//!
+//! The saveset header carries its date in word 8 and the ASCIZ saveset name
+//! from word 9. A file header holds the ASCIZ file specification
+//! (`DEV:<DIR.SUB>NAME.EXT.GEN;attributes`) from word 6 and a copy of the
+//! FDB from word 134. Data records follow, then a file trailer.
+
+use std::collections::BTreeMap;
+
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::file::{
+    BlockExtent, FileMetadata, Pdp10FileMetadata, Pdp10System, TapeFile, TapePath,
+};
+
+use super::{
+    asciz, decode_udt, host_size, insert_pdp10_file, left_half, logical_records, right_half,
+    signed, unpack_words, CORE_DUMP_BYTES, PAGE_WORDS,
+};
+
+pub const DUMPER_HEADER_WORDS: usize = 6;
+pub const DUMPER_RECORD_WORDS: usize = DUMPER_HEADER_WORDS + PAGE_WORDS;
+
+pub const RECTYPE_DATA: i64 = 0;
+pub const RECTYPE_TPHD: i64 = 1;
+pub const RECTYPE_FLHD: i64 = 2;
+pub const RECTYPE_FLTR: i64 = 3;
+pub const RECTYPE_TPTR: i64 = 4;
+pub const RECTYPE_USR: i64 = 5;
+pub const RECTYPE_CTPH: i64 = 6;
+pub const RECTYPE_FILL: i64 = 7;
+
+const SAVESET_NAME_WORD: usize = 9;
+const FILE_SPEC_WORD: usize = 6;
+const FDB_WORD: usize = 134;
+// FDB offsets.
+const FBPRT: usize = 0o4;
+const FBBYV: usize = 0o11;
+const FBSIZ: usize = 0o12;
+const FBWRT: usize = 0o14;
+
+/// Record type of a DUMPER record header (accepts either sign convention).
+pub fn dumper_record_type(words: &[u64]) -> Option<i64> {
+    let t = signed(*words.get(4)?).abs();
+    (RECTYPE_DATA..=RECTYPE_FILL).contains(&t).then_some(t)
+}
+
+/// True when a tape block holds whole DUMPER records with sane headers.
+pub fn is_dumper_block(data: &[u8]) -> bool {
+    let len = DUMPER_RECORD_WORDS * CORE_DUMP_BYTES;
+    if data.is_empty() || !data.len().is_multiple_of(len) {
+        return false;
+    }
+    data.chunks(len).all(|rec| {
+        let header = unpack_words(&rec[..DUMPER_HEADER_WORDS * CORE_DUMP_BYTES]);
+        dumper_record_type(&header).is_some() && header[3] < (1 << 18)
+    })
+}
+
+/// Parsed TOPS-20 file specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tops20Spec {
+    pub device: String,
+    pub directory: Vec<String>,
+    /// `NAME.EXT.GEN`
+    pub name: String,
+}
+
+/// Split `DEV:<DIR.SUB>NAME.EXT.GEN;P777700;A...` into its parts.
+pub fn parse_tops20_spec(spec: &str) -> Tops20Spec {
+    let spec = spec.split(';').next().unwrap_or_default();
+    let (device, rest) = match spec.split_once(':') {
+        Some((d, r)) => (d.to_string(), r),
+        None => (String::new(), spec),
+    };
+    let (directory, name) = match rest.strip_prefix('<').and_then(|r| r.split_once('>')) {
+        Some((dir, name)) => (dir.split('.').map(str::to_string).collect(), name),
+        None => (Vec::new(), rest),
+    };
+    Tops20Spec {
+        device,
+        directory,
+        name: name.to_string(),
+    }
+}
+
+struct PendingFile {
+    spec: String,
+    fdb: Vec<u64>,
+    pages: BTreeMap<u64, (u32, usize)>,
+}
+
+/// Decode the DUMPER savesets of one tape file, one node per saveset.
+pub fn decode_dumper(group: &[TapeBlock]) -> Vec<TapeFile> {
+    let mut tree = Vec::new();
+    let mut saveset = String::new();
+    let mut pending: Option<PendingFile> = None;
+    for (block, offset, words) in logical_records(group, DUMPER_RECORD_WORDS) {
+        let Some(kind) = dumper_record_type(&words) else {
+            continue;
+        };
+        match kind {
+            RECTYPE_TPHD | RECTYPE_CTPH => {
+                saveset = asciz(&words[SAVESET_NAME_WORD..]);
+                if saveset.is_empty() {
+                    saveset = format!("saveset_{}", left_half(words[2]));
+                }
+            }
+            RECTYPE_FLHD => {
+                if let Some(file) = pending.take() {
+                    finish(&mut tree, &saveset, file);
+                }
+                pending = Some(PendingFile {
+                    spec: asciz(&words[FILE_SPEC_WORD..FDB_WORD]),
+                    fdb: words[FDB_WORD..].to_vec(),
+                    pages: BTreeMap::new(),
+                });
+            }
+            RECTYPE_DATA => {
+                if let Some(file) = pending.as_mut() {
+                    file.pages.insert(words[3], (block, offset));
+                }
+            }
+            RECTYPE_FLTR | RECTYPE_TPTR => {
+                if let Some(file) = pending.take() {
+                    finish(&mut tree, &saveset, file);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(file) = pending.take() {
+        finish(&mut tree, &saveset, file);
+    }
+    tree
+}
+
+fn finish(tree: &mut Vec<TapeFile>, saveset: &str, file: PendingFile) {
+    let spec = parse_tops20_spec(&file.spec);
+    let fdb = |i: usize| file.fdb.get(i).copied().unwrap_or(0);
+    let byte_size = ((fdb(FBBYV) >> 24) & 0o77) as u8;
+    let byte_count = fdb(FBSIZ);
+
+    let page_bytes = PAGE_WORDS * CORE_DUMP_BYTES;
+    let mut extents = Vec::new();
+    let mut next_page = 0u64;
+    for (&page, &(block, offset)) in &file.pages {
+        while next_page < page {
+            extents.push(BlockExtent::hole(page_bytes));
+            next_page += 1;
+        }
+        extents.push(BlockExtent {
+            block,
+            offset: offset + DUMPER_HEADER_WORDS * CORE_DUMP_BYTES,
+            len: page_bytes,
+        });
+        next_page = page + 1;
+    }
+    let mut blocks: Vec<u32> = file.pages.values().map(|(b, _)| *b).collect();
+    blocks.dedup();
+
+    let mut path = vec![saveset.to_string()];
+    path.extend(spec.directory);
+    path.push(spec.name);
+    insert_pdp10_file(
+        tree,
+        TapeFile {
+            format: TapeFormat::Pdp10,
+            path: TapePath::new(path),
+            size_bytes: host_size(byte_size, byte_count),
+            blocks,
+            extents,
+            metadata: FileMetadata::Pdp10(Pdp10FileMetadata {
+                system: Pdp10System::Tops20,
+                saveset: saveset.to_string(),
+                byte_size,
+                byte_count,
+                protection: right_half(fdb(FBPRT)),
+                write_date: decode_udt(fdb(FBWRT)),
+            }),
+            children: Vec::new(),
+        },
+        Pdp10System::Tops20,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_dumper, is_dumper_block, parse_tops20_spec, DUMPER_RECORD_WORDS, FDB_WORD,
+        RECTYPE_DATA, RECTYPE_FLHD, RECTYPE_FLTR, RECTYPE_TPHD,
+    };
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::core::extract::file_bytes;
+    use crate::core::file::FileMetadata;
+    use crate::core::pdp10::tests::ascii_words;
+    use crate::core::pdp10::{convert_file_data, pack_word};
+
+    fn record(index: u32, kind: i64, page: u64, body: &[(usize, Vec<u64>)]) -> TapeBlock {
+        let mut words = vec![0u64; DUMPER_RECORD_WORDS];
+        words[3] = page;
+        words[4] = ((1i64 << 36) - kind) as u64 & ((1 << 36) - 1);
+        for (at, values) in body {
+            words[*at..*at + values.len()].copy_from_slice(values);
+        }
+        let raw: Vec<u8> = words.iter().flat_map(|w| pack_word(*w)).collect();
+        TapeBlock {
+            index,
+            tape_file: 0,
+            size: raw.len(),
+            raw: raw.into(),
+            classification: BlockClassification::Unknown,
+        }
+    }
+
+    #[test]
+    fn splits_file_specs() {
+        let spec = parse_tops20_spec("PS:<SRC.LIB>FOO.MAC.3;P777700;A1");
+        assert_eq!(spec.device, "PS");
+        assert_eq!(spec.directory, vec!["SRC", "LIB"]);
+        assert_eq!(spec.name, "FOO.MAC.3");
+    }
+
+    #[test]
+    fn decodes_text_file_from_saveset() {
+        let text = "HELLO\r\nWORLD\r\n";
+        let mut fdb = vec![0u64; 0o15];
+        fdb[0o4] = 0o777700;
+        fdb[0o11] = 7 << 24;
+        fdb[0o12] = text.len() as u64;
+        let blocks = vec![
+            record(0, RECTYPE_TPHD, 0, &[(9, ascii_words("WEEKLY"))]),
+            record(
+                1,
+                RECTYPE_FLHD,
+                0,
+                &[
+                    (6, ascii_words("PS:<SRC>HELLO.TXT.1;P777700")),
+                    (FDB_WORD, fdb),
+                ],
+            ),
+            record(2, RECTYPE_DATA, 0, &[(6, ascii_words(text))]),
+            record(3, RECTYPE_FLTR, 0, &[]),
+        ];
+        assert!(is_dumper_block(&blocks[2].raw));
+        let tree = decode_dumper(&blocks);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].path.to_string_path(), "WEEKLY");
+        let file = &tree[0].children[0].children[0];
+        assert_eq!(file.path.to_string_path(), "WEEKLY/SRC/HELLO.TXT.1");
+        match &file.metadata {
+            FileMetadata::Pdp10(meta) => {
+                assert_eq!(meta.byte_size, 7);
+                assert_eq!(meta.protection, 0o777700);
+                let host = convert_file_data(&file_bytes(file, &blocks), 7, meta.byte_count);
+                assert_eq!(host, b"HELLO\nWORLD\n");
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! DECsystem-10/20 tapes: TOPS-20 DUMPER and TOPS-10 BACKUP savesets.
+//!
+//! Both are written in core-dump framing: every 36-bit word occupies five
+//! tape bytes, bits 0-31 in the first four bytes (most significant first)
+//! and bits 32-35 in the low nibble of the fifth. Bit numbers follow the
+//! PDP-10 convention where bit 0 is the most significant bit.
+pub mod backup;
+pub mod dumper;
+
+pub use backup::*;
+pub use dumper::*;
+
+use crate::core::block::{tape_files, BlockClassification, Pdp10BlockKind, TapeBlock, TapeFormat};
+use crate::core::file::{FileMetadata, Pdp10FileMetadata, Pdp10System, TapeFile, TapePath};
+
+/// Tape bytes per 36-bit word in core-dump framing.
+pub const CORE_DUMP_BYTES: usize = 5;
+/// Data words per DUMPER/BACKUP record (one TOPS-20 page).
+pub const PAGE_WORDS: usize = 512;
+const WORD_MASK: u64 = (1 << 36) - 1;
+
+/// Unpack one core-dump framed word.
+pub fn unpack_word(b: &[u8]) -> u64 {
+    (u64::from(b[0]) << 28)
+        | (u64::from(b[1]) << 20)
+        | (u64::from(b[2]) << 12)
+        | (u64::from(b[3]) << 4)
+        | u64::from(b[4] & 0x0F)
+}
+
+/// Frame one 36-bit word as five core-dump bytes.
+pub fn pack_word(w: u64) -> [u8; CORE_DUMP_BYTES] {
+    [
+        (w >> 28) as u8,
+        (w >> 20) as u8,
+        (w >> 12) as u8,
+        (w >> 4) as u8,
+        (w & 0x0F) as u8,
+    ]
+}
+
+/// Unpack a core-dump byte stream; a trailing partial word is dropped.
+pub fn unpack_words(data: &[u8]) -> Vec<u64> {
+    data.chunks_exact(CORE_DUMP_BYTES)
+        .map(unpack_word)
+        .collect()
+}
+
+/// Interpret a word as a 36-bit two's complement value.
+pub fn signed(w: u64) -> i64 {
+    let w = w & WORD_MASK;
+    if w & (1 << 35) != 0 {
+        w as i64 - (1 << 36)
+    } else {
+        w as i64
+    }
+}
+
+pub fn left_half(w: u64) -> u32 {
+    ((w >> 18) & 0o777777) as u32
+}
+
+pub fn right_half(w: u64) -> u32 {
+    (w & 0o777777) as u32
+}
+
+/// Unpack five 7-bit characters per word (bits 0-34; bit 35 is unused).
+pub fn words_to_ascii7(words: &[u64]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(words.len() * 5);
+    for w in words {
+        for shift in [29, 22, 15, 8, 1] {
+            out.push(((w >> shift) & 0x7F) as u8);
+        }
+    }
+    out
+}
+
+/// Unpack four 8-bit bytes per word (bits 0-31; bits 32-35 are unused).
+pub fn words_to_bytes8(words: &[u64]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(words.len() * 4);
+    for w in words {
+        for shift in [28, 20, 12, 4] {
+            out.push(((w >> shift) & 0xFF) as u8);
+        }
+    }
+    out
+}
+
+/// Read an ASCIZ string packed 7 bits per character.
+pub fn asciz(words: &[u64]) -> String {
+    let text = words_to_ascii7(words);
+    let end = text.iter().position(|&c| c == 0).unwrap_or(text.len());
+    String::from_utf8_lossy(&text[..end]).to_string()
+}
+
+/// Decode six SIXBIT characters.
+pub fn sixbit(w: u64) -> String {
+    (0..6)
+        .map(|i| (((w >> (30 - 6 * i)) & 0o77) as u8 + 0x20) as char)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// Decode a universal date-time word: days since 17-Nov-1858 in the left
+/// half, fraction of a day in the right half.
+pub fn decode_udt(w: u64) -> Option<String> {
+    let days = left_half(w);
+    if days == 0 {
+        return None;
+    }
+    let epoch = chrono::NaiveDate::from_ymd_opt(1858, 11, 17)?;
+    let date = epoch.checked_add_days(chrono::Days::new(u64::from(days)))?;
+    let secs = (u64::from(right_half(w)) * 86_400) >> 18;
+    let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs as u32, 0)?;
+    Some(date.and_time(time).format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// Convert 7-bit file text to host text: CRLF becomes LF and NUL padding
+/// is dropped.
+pub fn host_text(ascii: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(ascii.len());
+    let mut i = 0usize;
+    while i < ascii.len() {
+        match ascii[i] {
+            0 => {}
+            b'\r' if ascii.get(i + 1) == Some(&b'\n') => {}
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Host-side contents of a file from its framed data words: 7-bit files as
+/// host text, 8-bit files as bytes, anything else as framed words.
+pub fn convert_file_data(framed: &[u8], byte_size: u8, byte_count: u64) -> Vec<u8> {
+    let words = unpack_words(framed);
+    match byte_size {
+        7 => {
+            let mut text = words_to_ascii7(&words);
+            text.truncate(byte_count as usize);
+            host_text(&text)
+        }
+        8 => {
+            let mut bytes = words_to_bytes8(&words);
+            bytes.truncate(byte_count as usize);
+            bytes
+        }
+        _ => {
+            let mut raw = framed.to_vec();
+            raw.truncate(host_size(byte_size, byte_count) as usize);
+            raw
+        }
+    }
+}
+
+/// Size of a file once unpacked for the host, before any CRLF folding.
+pub fn host_size(byte_size: u8, byte_count: u64) -> u64 {
+    match byte_size {
+        7 | 8 => byte_count,
+        0 | 36 => byte_count * CORE_DUMP_BYTES as u64,
+        bits => {
+            let per_word = u64::from(36 / bits);
+            byte_count.div_ceil(per_word) * CORE_DUMP_BYTES as u64
+        }
+    }
+}
+
+/// Reclassify blocks of DUMPER and BACKUP tape files.
+/// Returns the number of blocks claimed.
+pub fn classify_pdp10_tape(blocks: &mut [TapeBlock]) -> usize {
+    let mut claimed = 0usize;
+    for b in blocks.iter_mut() {
+        let kind = if is_dumper_block(&b.raw) {
+            Pdp10BlockKind::Dumper
+        } else if is_backup10_block(&b.raw) {
+            Pdp10BlockKind::Backup
+        } else {
+            continue;
+        };
+        b.classification = BlockClassification::Pdp10(kind);
+        claimed += 1;
+    }
+    claimed
+}
+
+/// Decode every DUMPER or BACKUP saveset on the tape, one node per saveset.
+pub fn reconstruct_pdp10_tape(blocks: &[TapeBlock]) -> Vec<TapeFile> {
+    let mut tree = Vec::new();
+    for group in tape_files(blocks) {
+        let savesets = if group.iter().any(|b| is_dumper_block(&b.raw)) {
+            decode_dumper(group)
+        } else if group.iter().any(|b| is_backup10_block(&b.raw)) {
+            decode_backup10(group)
+        } else {
+            continue;
+        };
+        tree.extend(savesets);
+    }
+    tree
+}
+
+/// Iterate the fixed-size logical records packed into each tape block as
+/// `(block index, byte offset, words)`.
+pub(crate) fn logical_records(
+    group: &[TapeBlock],
+    record_words: usize,
+) -> impl Iterator<Item = (u32, usize, Vec<u64>)> + '_ {
+    let len = record_words * CORE_DUMP_BYTES;
+    group.iter().flat_map(move |b| {
+        let count = b.raw.len() / len;
+        (0..count).map(move |i| {
+            let off = i * len;
+            (b.index, off, unpack_words(&b.raw[off..off + len]))
+        })
+    })
+}
+
+/// Insert a file below `parents`, creating directory nodes as needed.
+pub(crate) fn insert_pdp10_file(tree: &mut Vec<TapeFile>, file: TapeFile, system: Pdp10System) {
+    let path = file.path.elements.clone();
+    let mut level = tree;
+    for depth in 0..path.len().saturating_sub(1) {
+        let prefix = &path[..=depth];
+        let idx = match level.iter().position(|n| n.path.elements == prefix) {
+            Some(i) => i,
+            None => {
+                level.push(directory_node(prefix.to_vec(), system));
+                level.len() - 1
+            }
+        };
+        level[idx].size_bytes += file.size_bytes;
+        level = &mut level[idx].children;
+    }
+    level.push(file);
+}
+
+fn directory_node(path: Vec<String>, system: Pdp10System) -> TapeFile {
+    TapeFile {
+        format: TapeFormat::Pdp10,
+        path: TapePath::new(path),
+        size_bytes: 0,
+        blocks: Vec::new(),
+        extents: Vec::new(),
+        metadata: FileMetadata::Pdp10(Pdp10FileMetadata {
+            system,
+            saveset: String::new(),
+            byte_size: 0,
+            byte_count: 0,
+            protection: 0,
+            write_date: None,
+        }),
+        children: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{asciz, decode_udt, host_text, pack_word, sixbit, unpack_word, words_to_ascii7};
+
+    /// Pack ASCII text five characters per word, NUL padded.
+    pub(crate) fn ascii_words(text: &str) -> Vec<u64> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize(bytes.len() / 5 * 5 + 5, 0);
+        bytes
+            .chunks(5)
+            .map(|c| c.iter().fold(0u64, |w, &ch| (w << 7) | u64::from(ch)) << 1)
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_core_dump_words() {
+        let w = 0o123456_701234;
+        assert_eq!(unpack_word(&pack_word(w)), w);
+        assert_eq!(pack_word(0o777777_777777), [0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+    }
+
+    #[test]
+    fn unpacks_text_and_sixbit() {
+        let words = ascii_words("HELLO, WORLD");
+        assert_eq!(asciz(&words), "HELLO, WORLD");
+        assert_eq!(&words_to_ascii7(&words)[..5], b"HELLO");
+        assert_eq!(host_text(b"A\r\nB\0\0"), b"A\nB");
+        // SIXBIT "DSK   ".
+        assert_eq!(sixbit(0o446353_000000), "DSK");
+    }
+
+    #[test]
+    fn decodes_universal_dates() {
+        // 1-Jan-1980 is day 44239 after 17-Nov-1858; half a day is 400000.
+        let w = (44239u64 << 18) | 0o400000;
+        assert_eq!(decode_udt(w).as_deref(), Some("1980-01-01 12:00:00"));
+        assert_eq!(decode_udt(0), None);
+    }
+}
@@ -0,0 +1,465 @@
+//! Text preview of tape files: RMS records split out and joined into lines,
+//! FORTRAN or print-file carriage control applied, and bytes decoded from
+//! the DEC Multinational Character Set or as parity-stripped 7-bit text.
+use crate::backup::vms::{
+    FAT_CR, FAT_FIX, FAT_FTN, FAT_PRN, FAT_STMCR, FAT_STMLF, FAT_VAR, FAT_VFC,
+};
+use crate::core::file::{FileMetadata, TapeFile};
+
+/// Variable-length records never cross a disk block after a 0xFFFF count.
+const DISK_BLOCK: usize = 512;
+/// Share of printable characters a file needs to be previewed as text.
+const TEXT_SHARE: usize = 95;
+/// Shown for bytes the character set leaves undefined.
+const REPLACEMENT: char = '\u{FFFD}';
+
+/// How a file's bytes divide into records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordLayout {
+    /// Lines ended by LF, with a CR before it dropped. Also used for
+    /// undefined record formats and files from other systems.
+    Stream,
+    /// Records of a fixed length.
+    Fixed(u16),
+    /// Records led by a 16-bit byte count and padded to a word.
+    Variable,
+    /// Variable records whose first bytes are a fixed control area.
+    Vfc(u8),
+    /// Records ended by LF.
+    StreamLf,
+    /// Records ended by CR.
+    StreamCr,
+}
+
+impl RecordLayout {
+    pub const ALL: [RecordLayout; 7] = [
+        RecordLayout::Stream,
+        RecordLayout::Variable,
+        RecordLayout::Vfc(2),
+        RecordLayout::StreamLf,
+        RecordLayout::StreamCr,
+        RecordLayout::Fixed(80),
+        RecordLayout::Fixed(512),
+    ];
+
+    /// Layout of an RMS record format code. Fixed records of unknown length
+    /// and undefined formats read as a stream.
+    pub fn from_rms(format: u8, record_length: u16) -> Self {
+        match format {
+            FAT_FIX if record_length > 0 => RecordLayout::Fixed(record_length),
+            FAT_VAR => RecordLayout::Variable,
+            FAT_VFC => RecordLayout::Vfc(2),
+            FAT_STMLF => RecordLayout::StreamLf,
+            FAT_STMCR => RecordLayout::StreamCr,
+            _ => RecordLayout::Stream,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            RecordLayout::Stream => "stream".into(),
+            RecordLayout::Fixed(len) => format!("fixed {len}"),
+            RecordLayout::Variable => "variable".into(),
+            RecordLayout::Vfc(len) => format!("VFC {len}"),
+            RecordLayout::StreamLf => "stream-LF".into(),
+            RecordLayout::StreamCr => "stream-CR".into(),
+        }
+    }
+
+    /// Records end at a terminator rather than a count or length.
+    fn is_stream(&self) -> bool {
+        matches!(
+            self,
+            RecordLayout::Stream | RecordLayout::StreamLf | RecordLayout::StreamCr
+        )
+    }
+}
+
+/// How records map to lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarriageControl {
+    /// Counted records run together; stream records are still lines.
+    None,
+    /// Each record is a line.
+    Implied,
+    /// The first byte of each record moves the paper: ' ' next line, '0'
+    /// one blank line, '-' two, '1' new page, '+' and '$' as a new line.
+    Fortran,
+    /// The VFC prefix and postfix bytes count new lines or name a control
+    /// character.
+    Print,
+}
+
+impl CarriageControl {
+    pub const ALL: [CarriageControl; 4] = [
+        CarriageControl::None,
+        CarriageControl::Implied,
+        CarriageControl::Fortran,
+        CarriageControl::Print,
+    ];
+
+    /// Carriage control named by RMS record attributes.
+    pub fn from_rms(attributes: u8) -> Self {
+        if attributes & FAT_FTN != 0 {
+            CarriageControl::Fortran
+        } else if attributes & FAT_PRN != 0 {
+            CarriageControl::Print
+        } else if attributes & FAT_CR != 0 {
+            CarriageControl::Implied
+        } else {
+            CarriageControl::None
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CarriageControl::None => "none",
+            CarriageControl::Implied => "implied",
+            CarriageControl::Fortran => "FORTRAN",
+            CarriageControl::Print => "print file",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// DEC Multinational Character Set: ASCII plus Latin letters in the
+    /// upper half.
+    DecMcs,
+    /// 7-bit ASCII with the parity bit stripped.
+    SevenBit,
+}
+
+impl Charset {
+    pub const ALL: [Charset; 2] = [Charset::DecMcs, Charset::SevenBit];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Charset::DecMcs => "DEC MCS",
+            Charset::SevenBit => "7-bit (parity stripped)",
+        }
+    }
+}
+
+/// Settings a preview is decoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewOptions {
+    pub layout: RecordLayout,
+    pub carriage: CarriageControl,
+    pub charset: Charset,
+}
+
+impl PreviewOptions {
+    /// Options from a file's RMS attributes (stream with implied carriage
+    /// control for other systems), with the character set guessed from
+    /// `data`.
+    pub fn for_file(file: &TapeFile, data: &[u8]) -> Self {
+        let (layout, carriage) = match &file.metadata {
+            FileMetadata::Vms(meta) => (
+                RecordLayout::from_rms(meta.record_format, meta.record_length),
+                CarriageControl::from_rms(meta.record_attributes),
+            ),
+            _ => (RecordLayout::Stream, CarriageControl::Implied),
+        };
+        PreviewOptions {
+            layout,
+            carriage,
+            charset: detect_charset(data),
+        }
+    }
+}
+
+/// Decoded text of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextPreview {
+    pub text: String,
+    pub records: usize,
+    /// Enough of the text is printable to show it rather than a hex dump.
+    pub is_text: bool,
+}
+
+/// Parity-stripped 7-bit text when a quarter or more of the bytes have the
+/// top bit set and every one of them is text without it; DEC MCS otherwise.
+pub fn detect_charset(data: &[u8]) -> Charset {
+    let high: Vec<u8> = data
+        .iter()
+        .filter(|&&b| b >= 0x80)
+        .map(|b| b & 0x7F)
+        .collect();
+    let parity = !high.is_empty()
+        && high.len() * 4 >= data.len()
+        && high
+            .iter()
+            .all(|&b| matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x20..=0x7E));
+    if parity {
+        Charset::SevenBit
+    } else {
+        Charset::DecMcs
+    }
+}
+
+/// Split `data` into records. VFC records keep their control area; a
+/// trailing partial record is kept as it is. NULs after the last stream
+/// record are dropped.
+pub fn split_records(data: &[u8], layout: RecordLayout) -> Vec<&[u8]> {
+    match layout {
+        RecordLayout::Fixed(len) => data.chunks(usize::from(len.max(1))).collect(),
+        RecordLayout::Variable | RecordLayout::Vfc(_) => {
+            let mut records = Vec::new();
+            let mut pos = 0;
+            while pos + 2 <= data.len() {
+                let len = usize::from(u16::from_le_bytes([data[pos], data[pos + 1]]));
+                if len == 0xFFFF {
+                    pos = (pos / DISK_BLOCK + 1) * DISK_BLOCK;
+                    continue;
+                }
+                let body = pos + 2;
+                let end = (body + len).min(data.len());
+                records.push(&data[body..end]);
+                pos = end + len % 2;
+            }
+            records
+        }
+        RecordLayout::Stream | RecordLayout::StreamLf | RecordLayout::StreamCr => {
+            let end = if layout == RecordLayout::StreamCr {
+                b'\r'
+            } else {
+                b'\n'
+            };
+            // Block-structured volumes pad the last block with NULs.
+            let used = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+            let data = &data[..used];
+            let data = data.strip_suffix(&[end]).unwrap_or(data);
+            if data.is_empty() {
+                return Vec::new();
+            }
+            data.split(|&b| b == end)
+                .map(|line| match layout {
+                    RecordLayout::Stream => line.strip_suffix(b"\r").unwrap_or(line),
+                    _ => line,
+                })
+                .collect()
+        }
+    }
+}
+
+/// Decode `data` under `opts`. Control characters other than tab show in
+/// caret notation (^L for form feed) and page breaks as a ^L line.
+pub fn text_preview(data: &[u8], opts: &PreviewOptions) -> TextPreview {
+    // Parity covers record terminators too, so strip it before splitting.
+    let stripped: Vec<u8>;
+    let data = match opts.charset {
+        Charset::SevenBit => {
+            stripped = data.iter().map(|b| b & 0x7F).collect();
+            &stripped
+        }
+        Charset::DecMcs => data,
+    };
+    let records = split_records(data, opts.layout);
+    let mut out = Text::default();
+    for (i, record) in records.iter().enumerate() {
+        let (control, body) = match opts.layout {
+            RecordLayout::Vfc(len) => record.split_at(usize::from(len).min(record.len())),
+            _ => (&[][..], *record),
+        };
+        let first = i == 0;
+        match opts.carriage {
+            CarriageControl::Fortran if !body.is_empty() => {
+                match body[0] {
+                    b'0' => out.lines(2, first),
+                    b'-' => out.lines(3, first),
+                    b'1' => {
+                        out.lines(1, first);
+                        out.page();
+                        out.lines(1, false);
+                    }
+                    _ => out.lines(1, first),
+                }
+                out.bytes(&body[1..]);
+            }
+            CarriageControl::Print if control.len() == 2 => {
+                out.print_control(control[0], first);
+                out.bytes(body);
+                out.print_control(control[1], false);
+            }
+            CarriageControl::None if !opts.layout.is_stream() => out.bytes(body),
+            _ => {
+                out.lines(1, first);
+                out.bytes(body);
+            }
+        }
+    }
+    let is_text = out.chars > 0 && out.printable * 100 >= out.chars * TEXT_SHARE;
+    TextPreview {
+        text: out.text,
+        records: records.len(),
+        is_text,
+    }
+}
+
+/// Text being decoded, with counts for the text/binary decision.
+#[derive(Default)]
+struct Text {
+    text: String,
+    chars: usize,
+    printable: usize,
+}
+
+impl Text {
+    /// Move down `count` lines; the first line of the file needs no break.
+    fn lines(&mut self, count: usize, first: bool) {
+        for _ in 0..count - usize::from(first) {
+            self.text.push('\n');
+        }
+    }
+
+    fn page(&mut self) {
+        self.text.push_str("^L");
+    }
+
+    /// One print-file control byte: a new line count when the top bit is
+    /// clear, else a control character; only form and line feeds of those
+    /// move the text.
+    fn print_control(&mut self, byte: u8, first: bool) {
+        match byte {
+            0x01..=0x7F => self.lines(usize::from(byte), first),
+            0x8C => {
+                self.lines(1, first);
+                self.page();
+                self.lines(1, false);
+            }
+            0x8A => self.lines(1, first),
+            _ => {}
+        }
+    }
+
+    fn bytes(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.chars += 1;
+            match decode_mcs(byte) {
+                Some(ch) => {
+                    self.printable += 1;
+                    self.text.push(ch);
+                }
+                None if byte < 0x20 || byte == 0x7F => {
+                    // Form feeds are usual in listings and count as text.
+                    self.printable += usize::from(byte == 0x0C);
+                    self.text.push('^');
+                    self.text.push((byte ^ 0x40) as char);
+                }
+                None => self.text.push(REPLACEMENT),
+            }
+        }
+    }
+}
+
+/// Printable character of a DEC MCS byte; `None` for control characters
+/// and the positions MCS leaves undefined.
+fn decode_mcs(byte: u8) -> Option<char> {
+    Some(match byte {
+        b'\t' | 0x20..=0x7E => byte as char,
+        0xA8 => '\u{A4}',
+        0xD7 => '\u{152}',
+        0xDD => '\u{178}',
+        0xF7 => '\u{153}',
+        0xFD => '\u{FF}',
+        0xA4 | 0xA6 | 0xAC..=0xAF | 0xB4 | 0xB8 | 0xBE | 0xD0 | 0xDE | 0xF0 | 0xFE | 0xFF => {
+            return None
+        }
+        0xA0..=0xFF => byte as char,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        detect_charset, split_records, text_preview, CarriageControl, Charset, PreviewOptions,
+        RecordLayout,
+    };
+    use crate::backup::writer::{format_file_data, RecordFormatHint};
+
+    fn preview(data: &[u8], layout: RecordLayout, carriage: CarriageControl) -> String {
+        let opts = PreviewOptions {
+            layout,
+            carriage,
+            charset: detect_charset(data),
+        };
+        text_preview(data, &opts).text
+    }
+
+    #[test]
+    fn joins_variable_records_written_by_the_saveset_writer() {
+        let (data, ..) = format_file_data(b"first\r\nodd\n\nlast\n", RecordFormatHint::Variable);
+        assert_eq!(
+            split_records(&data, RecordLayout::Variable),
+            [&b"first"[..], b"odd", b"", b"last"]
+        );
+        let text = preview(&data, RecordLayout::Variable, CarriageControl::Implied);
+        assert_eq!(text, "first\nodd\n\nlast");
+        assert_eq!(
+            preview(&data, RecordLayout::Variable, CarriageControl::None),
+            "firstoddlast"
+        );
+    }
+
+    #[test]
+    fn applies_fortran_and_print_carriage_control() {
+        let mut data = Vec::new();
+        for record in [&b"1TITLE"[..], b" A", b"0B", b"+C"] {
+            data.extend((record.len() as u16).to_le_bytes());
+            data.extend_from_slice(record);
+            if record.len() % 2 == 1 {
+                data.push(0);
+            }
+        }
+        assert_eq!(
+            preview(&data, RecordLayout::Variable, CarriageControl::Fortran),
+            "^L\nTITLE\nA\n\nB\nC"
+        );
+
+        // Print file: one new line before each record, a form feed after
+        // the second.
+        let mut data = Vec::new();
+        for (prefix, postfix, text) in [(1u8, 0u8, &b"ONE"[..]), (1, 0x8C, b"TWO"), (1, 0, b"")] {
+            data.extend(((text.len() + 2) as u16).to_le_bytes());
+            data.extend([prefix, postfix]);
+            data.extend_from_slice(text);
+            if text.len() % 2 == 1 {
+                data.push(0);
+            }
+        }
+        assert_eq!(
+            preview(&data, RecordLayout::Vfc(2), CarriageControl::Print),
+            "ONE\nTWO\n^L\n\n"
+        );
+    }
+
+    #[test]
+    fn decodes_dec_mcs_and_parity_text() {
+        let data = b"Stra\xDFe \xD7uvre\x0C\r\n\0\0\0";
+        assert_eq!(detect_charset(data), Charset::DecMcs);
+        let opts = PreviewOptions {
+            layout: RecordLayout::Stream,
+            carriage: CarriageControl::Implied,
+            charset: Charset::DecMcs,
+        };
+        let shown = text_preview(data, &opts);
+        assert_eq!(shown.text, "Stra\u{DF}e \u{152}uvre^L");
+        assert!(shown.is_text);
+
+        let parity: Vec<u8> = b"HELLO, WORLD\r\n".iter().map(|b| b | 0x80).collect();
+        assert_eq!(detect_charset(&parity), Charset::SevenBit);
+        assert_eq!(
+            preview(&parity, RecordLayout::Stream, CarriageControl::Implied),
+            "HELLO, WORLD"
+        );
+
+        let binary: Vec<u8> = (0..=255u8).collect();
+        let opts = PreviewOptions {
+            charset: detect_charset(&binary),
+            ..opts
+        };
+        assert!(!text_preview(&binary, &opts).is_text);
+    }
+}
@@ -3,6 +3,7 @@ use crate::core::file::{
     FileMetadata, RstsFileMetadata, RsxFileMetadata, Rt11FileMetadata, TapeFile, TapePath,
 };
 use crate::core::parse::{parse_classified_block, ParsedBlock};
+use crate::core::rsts::format_ppn;
 
 pub fn reconstruct_rsx(blocks: &[TapeBlock]) -> Vec<TapeFile> {
     let mut tree = Vec::new();
@@ -27,6 +28,7 @@ pub fn reconstruct_rsx(blocks: &[TapeBlock]) -> Vec<TapeFile> {
                         path: TapePath::new(path),
                         size_bytes: block.size as u64,
                         blocks: vec![block.index],
+                        extents: Vec::new(),
                         metadata,
                         children: Vec::new(),
                     },
@@ -53,6 +55,7 @@ pub fn reconstruct_rt11(blocks: &[TapeBlock]) -> Vec<TapeFile> {
                         start_block: entry.start_block,
                         length_blocks: entry.length_blocks,
                         ext: entry.ext,
+                        creation_date: None,
                     });
                     insert_into_tree(
                         &mut tree,
@@ -61,6 +64,7 @@ pub fn reconstruct_rt11(blocks: &[TapeBlock]) -> Vec<TapeFile> {
                             path,
                             size_bytes,
                             blocks: vec![block.index],
+                            extents: Vec::new(),
                             metadata,
                             children: Vec::new(),
                         },
@@ -80,7 +84,7 @@ pub fn reconstruct_rsts(blocks: &[TapeBlock]) -> Vec<TapeFile> {
             Some(ParsedBlock::Rsts(info)) if !info.entries.is_empty() => {
                 for entry in info.entries {
                     let mut path = Vec::new();
-                    path.push(format!("[{:o},{:o}]", entry.owner_uic.0, entry.owner_uic.1));
+                    path.push(format_ppn(entry.owner_uic));
                     if entry.name.is_empty() {
                         path.push(format!("entry_{:05}", block.index));
                     } else {
@@ -88,8 +92,12 @@ pub fn reconstruct_rsts(blocks: &[TapeBlock]) -> Vec<TapeFile> {
                     }
                     let metadata = FileMetadata::Rsts(RstsFileMetadata {
                         owner_uic: entry.owner_uic,
-                        blocks: entry.blocks,
+                        blocks: u32::from(entry.blocks),
                         status: entry.status,
+                        protection: 0,
+                        creation_date: None,
+                        access_date: None,
+                        cluster_size: 0,
                     });
                     let size_bytes = entry.blocks as u64 * 512;
                     insert_into_tree(
@@ -99,6 +107,7 @@ pub fn reconstruct_rsts(blocks: &[TapeBlock]) -> Vec<TapeFile> {
                             path: TapePath::new(path),
                             size_bytes,
                             blocks: vec![block.index],
+                            extents: Vec::new(),
                             metadata,
                             children: Vec::new(),
                         },
@@ -117,6 +126,7 @@ fn raw_block_entry(block: &TapeBlock) -> TapeFile {
         path: TapePath::new(vec![format!("block_{:05}", block.index)]),
         size_bytes: block.size as u64,
         blocks: vec![block.index],
+        extents: Vec::new(),
         metadata: FileMetadata::Raw,
         children: Vec::new(),
     }
@@ -142,6 +152,7 @@ fn insert_into_tree(tree: &mut Vec<TapeFile>, file: TapeFile) {
                     path: TapePath::new(prefix.clone()),
                     size_bytes: 0,
                     blocks: Vec::new(),
+                    extents: Vec::new(),
                     metadata: FileMetadata::Raw,
                     children: Vec::new(),
                 });
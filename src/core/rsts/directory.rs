@@ -0,0 +1,236 @@
+//! RSTS/E directory blockettes: link words, label, name, accounting and retrieval entries.
+
+use crate::core::parse::decode_rad50_word;
+
+/// Size of one directory blockette in bytes.
+pub const BLOCKETTE_SIZE: usize = 16;
+/// Byte offset of the cluster map blockette inside every directory block.
+pub const CLUSTER_MAP_OFFSET: usize = 0o760;
+
+/// Status byte: entry is marked for deletion.
+pub const US_DEL: u8 = 0o040;
+/// Status byte: MFD entry describes a UFD (account), not a file.
+pub const US_UFD: u8 = 0o100;
+/// Status byte: file may not be deleted or renamed.
+pub const US_NOK: u8 = 0o020;
+
+/// Decoded directory link word.
+///
+/// Layout (low to high bits): 4 flag bits, 5-bit blockette number within the
+/// block, 3-bit index into the directory cluster map, 4-bit block within the
+/// cluster. A link with no address bits set terminates a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RstsLink {
+    pub flags: u8,
+    pub entry: u8,
+    pub cluster: u8,
+    pub block: u8,
+}
+
+impl RstsLink {
+    pub fn decode(word: u16) -> Self {
+        Self {
+            flags: (word & 0x000F) as u8,
+            entry: ((word >> 4) & 0x1F) as u8,
+            cluster: ((word >> 9) & 0x07) as u8,
+            block: ((word >> 12) & 0x0F) as u8,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.entry == 0 && self.cluster == 0 && self.block == 0
+    }
+}
+
+/// Directory cluster map stored in the last blockette of each directory block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClusterMap {
+    pub cluster_size: u16,
+    pub dcns: [u16; 7],
+}
+
+impl ClusterMap {
+    pub fn parse(block: &[u8]) -> Option<Self> {
+        let raw = block.get(CLUSTER_MAP_OFFSET..CLUSTER_MAP_OFFSET + BLOCKETTE_SIZE)?;
+        let cluster_size = u16::from(raw[0]);
+        if cluster_size == 0 {
+            return None;
+        }
+        let mut dcns = [0u16; 7];
+        for (i, dcn) in dcns.iter_mut().enumerate() {
+            *dcn = word(raw, 2 + i * 2);
+        }
+        Some(Self { cluster_size, dcns })
+    }
+}
+
+/// Pack label blockette (first blockette of DCN 1).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackLabel {
+    pub mfd_dcn: u16,
+    pub revision: u16,
+    pub pack_cluster_size: u16,
+    pub status: u16,
+    pub pack_id: String,
+}
+
+impl PackLabel {
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        if raw.len() < BLOCKETTE_SIZE || word(raw, 2) != 0xFFFF {
+            return None;
+        }
+        Some(Self {
+            mfd_dcn: word(raw, 4),
+            revision: word(raw, 6),
+            pack_cluster_size: word(raw, 8),
+            status: word(raw, 10),
+            pack_id: format!(
+                "{}{}",
+                decode_rad50_word(word(raw, 12)),
+                decode_rad50_word(word(raw, 14))
+            ),
+        })
+    }
+
+    /// RDS 0 packs (pre-V8) have no revision level and keep the MFD at DCN 1.
+    pub fn is_rds0(&self) -> bool {
+        self.revision == 0
+    }
+}
+
+/// UFD name entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameEntry {
+    pub link: u16,
+    pub name: String,
+    pub ext: String,
+    pub status: u8,
+    pub protection: u8,
+    pub access_count: u16,
+    pub accounting_link: u16,
+    pub retrieval_link: u16,
+}
+
+impl NameEntry {
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        if raw.len() < BLOCKETTE_SIZE {
+            return None;
+        }
+        Some(Self {
+            link: word(raw, 0),
+            name: format!(
+                "{}{}",
+                decode_rad50_word(word(raw, 2)),
+                decode_rad50_word(word(raw, 4))
+            ),
+            ext: decode_rad50_word(word(raw, 6)),
+            status: raw[8],
+            protection: raw[9],
+            access_count: word(raw, 10),
+            accounting_link: word(raw, 12),
+            retrieval_link: word(raw, 14),
+        })
+    }
+
+    pub fn file_name(&self) -> String {
+        if self.ext.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.name, self.ext)
+        }
+    }
+}
+
+/// RDS 0 MFD name entry: an account with the DCN of its UFD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MfdEntry {
+    pub link: u16,
+    pub ppn: (u16, u16),
+    pub status: u8,
+    pub protection: u8,
+    pub accounting_link: u16,
+    pub ufd_dcn: u16,
+}
+
+impl MfdEntry {
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        if raw.len() < BLOCKETTE_SIZE {
+            return None;
+        }
+        let ppn = word(raw, 2);
+        Some(Self {
+            link: word(raw, 0),
+            ppn: (ppn >> 8, ppn & 0xFF),
+            status: raw[8],
+            protection: raw[9],
+            accounting_link: word(raw, 12),
+            ufd_dcn: word(raw, 14),
+        })
+    }
+}
+
+/// Accounting entry: dates, size and file cluster size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountingEntry {
+    pub link: u16,
+    pub access_date: u16,
+    pub size_lsb: u16,
+    pub creation_date: u16,
+    pub creation_time: u16,
+    pub rts: [u16; 2],
+    pub cluster_size: u16,
+}
+
+impl AccountingEntry {
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        if raw.len() < BLOCKETTE_SIZE {
+            return None;
+        }
+        Some(Self {
+            link: word(raw, 0),
+            access_date: word(raw, 2),
+            size_lsb: word(raw, 4),
+            creation_date: word(raw, 6),
+            creation_time: word(raw, 8),
+            rts: [word(raw, 10), word(raw, 12)],
+            cluster_size: word(raw, 14),
+        })
+    }
+
+    /// File size in blocks. Large files keep the size MSB in the first RTS
+    /// word and leave the second RTS word zero.
+    pub fn size_blocks(&self) -> u32 {
+        if self.rts[1] == 0 {
+            (u32::from(self.rts[0]) << 16) | u32::from(self.size_lsb)
+        } else {
+            u32::from(self.size_lsb)
+        }
+    }
+}
+
+/// Retrieval entry: up to seven file-cluster DCNs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetrievalEntry {
+    pub link: u16,
+    pub dcns: [u16; 7],
+}
+
+impl RetrievalEntry {
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        if raw.len() < BLOCKETTE_SIZE {
+            return None;
+        }
+        let mut dcns = [0u16; 7];
+        for (i, dcn) in dcns.iter_mut().enumerate() {
+            *dcn = word(raw, 2 + i * 2);
+        }
+        Some(Self {
+            link: word(raw, 0),
+            dcns,
+        })
+    }
+}
+
+fn word(raw: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([raw[off], raw[off + 1]])
+}
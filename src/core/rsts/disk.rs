@@ -0,0 +1,498 @@
+//! RSTS/E disk walker: pack label -> MFD (-> GFDs) -> UFDs -> files.
+//! Files are returned as `TapeFile`s whose `blocks` are disk LBNs, so the
+//! regular extraction path can pull them from `rsts_disk_blocks`.
+
+use crate::core::block::{BlockClassification, TapeBlock, TapeFormat};
+use crate::core::file::{FileMetadata, RstsFileMetadata, TapeFile, TapePath};
+use crate::{TapeError, TapeResult};
+
+use super::directory::{
+    AccountingEntry, ClusterMap, MfdEntry, NameEntry, PackLabel, RetrievalEntry, RstsLink,
+    BLOCKETTE_SIZE, US_DEL, US_UFD,
+};
+use super::{format_ppn, format_rsts_timestamp, group_by_account};
+
+pub const DISK_BLOCK_SIZE: usize = 512;
+
+/// Upper bound on blockettes in one directory (7 clusters of 16 blocks, 31 entries each).
+const MAX_CHAIN: usize = 7 * 16 * 31;
+
+/// An account found in the MFD/GFD structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RstsAccount {
+    pub ppn: (u16, u16),
+    pub ufd_dcn: u16,
+}
+
+/// A file found in a UFD, with its retrieval chain resolved to LBNs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RstsDiskFile {
+    pub ppn: (u16, u16),
+    pub entry: NameEntry,
+    pub accounting: Option<AccountingEntry>,
+    pub lbns: Vec<u32>,
+}
+
+impl RstsDiskFile {
+    pub fn size_blocks(&self) -> u32 {
+        self.accounting
+            .as_ref()
+            .map(AccountingEntry::size_blocks)
+            .unwrap_or(self.lbns.len() as u32)
+    }
+
+    pub fn metadata(&self) -> RstsFileMetadata {
+        let acc = self.accounting.as_ref();
+        RstsFileMetadata {
+            owner_uic: self.ppn,
+            blocks: self.size_blocks(),
+            status: u16::from(self.entry.status),
+            protection: self.entry.protection,
+            creation_date: acc
+                .and_then(|a| format_rsts_timestamp(a.creation_date, Some(a.creation_time))),
+            access_date: acc.and_then(|a| format_rsts_timestamp(a.access_date, None)),
+            cluster_size: acc.map(|a| a.cluster_size).unwrap_or(0),
+        }
+    }
+}
+
+/// A mounted view over an RSTS/E disk image.
+#[derive(Debug, Clone)]
+pub struct RstsDisk<'a> {
+    data: &'a [u8],
+    pub device_cluster_size: u32,
+    pub label: PackLabel,
+}
+
+/// A directory (MFD, GFD or UFD) located through its cluster map.
+#[derive(Debug, Clone, Copy)]
+struct Directory {
+    map: ClusterMap,
+}
+
+impl<'a> RstsDisk<'a> {
+    /// Validate the pack label and derive the device cluster size from the image size.
+    pub fn open(data: &'a [u8]) -> TapeResult<Self> {
+        let total_blocks = (data.len() / DISK_BLOCK_SIZE) as u32;
+        if total_blocks < 2 {
+            return Err(TapeError::Parse("RSTS/E disk image too small".into()));
+        }
+        let device_cluster_size = device_cluster_size(total_blocks);
+        let label_block = block_at(data, device_cluster_size)
+            .ok_or_else(|| TapeError::Parse("RSTS/E pack label block missing".into()))?;
+        let label = PackLabel::parse(label_block)
+            .ok_or_else(|| TapeError::Parse("RSTS/E pack label not found".into()))?;
+        let pcs = u32::from(label.pack_cluster_size);
+        if pcs == 0 || !pcs.is_power_of_two() || pcs < device_cluster_size || pcs > 64 {
+            return Err(TapeError::Parse(format!(
+                "implausible RSTS/E pack cluster size {}",
+                pcs
+            )));
+        }
+        Ok(Self {
+            data,
+            device_cluster_size,
+            label,
+        })
+    }
+
+    pub fn total_blocks(&self) -> u32 {
+        (self.data.len() / DISK_BLOCK_SIZE) as u32
+    }
+
+    /// Enumerate accounts, using the MFD name chain on RDS 0 and the GFD tables on RDS 1.x.
+    pub fn accounts(&self) -> Vec<RstsAccount> {
+        if self.label.is_rds0() {
+            self.rds0_accounts()
+        } else {
+            self.rds1_accounts()
+        }
+    }
+
+    /// List the files of one account.
+    pub fn files(&self, account: &RstsAccount) -> Vec<RstsDiskFile> {
+        let Some(dir) = self.directory(account.ufd_dcn) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        let Some(label) = self.dir_block(&dir, 0) else {
+            return out;
+        };
+        let mut link = u16::from_le_bytes([label[0], label[1]]);
+        for _ in 0..MAX_CHAIN {
+            let Some(raw) = self.blockette(&dir, link) else {
+                break;
+            };
+            let Some(entry) = NameEntry::parse(raw) else {
+                break;
+            };
+            link = entry.link;
+            if entry.status & (US_UFD | US_DEL) != 0 {
+                continue;
+            }
+            let accounting = self
+                .blockette(&dir, entry.accounting_link)
+                .and_then(AccountingEntry::parse);
+            let cluster = accounting
+                .as_ref()
+                .map(|a| u32::from(a.cluster_size))
+                .filter(|c| *c > 0)
+                .unwrap_or(self.device_cluster_size);
+            let mut lbns = self.retrieval_lbns(&dir, entry.retrieval_link, cluster);
+            if let Some(acc) = &accounting {
+                lbns.truncate(acc.size_blocks() as usize);
+            }
+            out.push(RstsDiskFile {
+                ppn: account.ppn,
+                entry,
+                accounting,
+                lbns,
+            });
+        }
+        out
+    }
+
+    /// Read a file's contents by following its retrieval clusters.
+    pub fn read_file(&self, file: &RstsDiskFile) -> Vec<u8> {
+        let mut data = Vec::with_capacity(file.lbns.len() * DISK_BLOCK_SIZE);
+        for lbn in &file.lbns {
+            if let Some(b) = block_at(self.data, *lbn) {
+                data.extend_from_slice(b);
+            }
+        }
+        data
+    }
+
+    fn rds0_accounts(&self) -> Vec<RstsAccount> {
+        let Some(mfd) = self.directory(1) else {
+            return Vec::new();
+        };
+        let Some(label) = self.dir_block(&mfd, 0) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        let mut link = u16::from_le_bytes([label[0], label[1]]);
+        for _ in 0..MAX_CHAIN {
+            let Some(entry) = self.blockette(&mfd, link).and_then(MfdEntry::parse) else {
+                break;
+            };
+            link = entry.link;
+            if entry.status & US_UFD != 0 && entry.ufd_dcn != 0 {
+                out.push(RstsAccount {
+                    ppn: entry.ppn,
+                    ufd_dcn: entry.ufd_dcn,
+                });
+            }
+        }
+        out
+    }
+
+    fn rds1_accounts(&self) -> Vec<RstsAccount> {
+        let Some(mfd) = self.directory(self.label.mfd_dcn) else {
+            return Vec::new();
+        };
+        let Some(gfd_table) = self.dir_block(&mfd, 1) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        for proj in 0..255u16 {
+            let gfd_dcn = table_word(gfd_table, proj);
+            if gfd_dcn == 0 {
+                continue;
+            }
+            let Some(gfd) = self.directory(gfd_dcn) else {
+                continue;
+            };
+            let Some(ufd_table) = self.dir_block(&gfd, 1) else {
+                continue;
+            };
+            for prog in 0..255u16 {
+                let ufd_dcn = table_word(ufd_table, prog);
+                if ufd_dcn != 0 {
+                    out.push(RstsAccount {
+                        ppn: (proj, prog),
+                        ufd_dcn,
+                    });
+                }
+            }
+        }
+        out
+    }
+
+    fn retrieval_lbns(&self, dir: &Directory, first: u16, cluster: u32) -> Vec<u32> {
+        let mut lbns = Vec::new();
+        let mut link = first;
+        for _ in 0..MAX_CHAIN {
+            let Some(entry) = self.blockette(dir, link).and_then(RetrievalEntry::parse) else {
+                break;
+            };
+            for dcn in entry.dcns.iter().take_while(|d| **d != 0) {
+                let start = u32::from(*dcn) * self.device_cluster_size;
+                lbns.extend(start..start + cluster);
+            }
+            link = entry.link;
+        }
+        lbns
+    }
+
+    fn directory(&self, dcn: u16) -> Option<Directory> {
+        if dcn == 0 {
+            return None;
+        }
+        let first = block_at(self.data, u32::from(dcn) * self.device_cluster_size)?;
+        let map = ClusterMap::parse(first)?;
+        Some(Directory { map })
+    }
+
+    /// Map a directory-relative block number to its data through the cluster map.
+    fn dir_block(&self, dir: &Directory, n: u32) -> Option<&'a [u8]> {
+        let size = u32::from(dir.map.cluster_size);
+        let dcn = *dir.map.dcns.get((n / size) as usize)?;
+        if dcn == 0 {
+            return None;
+        }
+        block_at(
+            self.data,
+            u32::from(dcn) * self.device_cluster_size + n % size,
+        )
+    }
+
+    fn blockette(&self, dir: &Directory, link: u16) -> Option<&'a [u8]> {
+        let l = RstsLink::decode(link);
+        if l.is_null() || u16::from(l.block) >= dir.map.cluster_size {
+            return None;
+        }
+        // Links carry a 3-bit cluster index but the map holds seven DCNs.
+        let dcn = dir.map.dcns.get(l.cluster as usize).copied()?;
+        if dcn == 0 {
+            return None;
+        }
+        let block = block_at(
+            self.data,
+            u32::from(dcn) * self.device_cluster_size + u32::from(l.block),
+        )?;
+        let off = l.entry as usize * BLOCKETTE_SIZE;
+        block.get(off..off + BLOCKETTE_SIZE)
+    }
+}
+
+/// Smallest power of two that keeps every DCN within 16 bits.
+pub fn device_cluster_size(total_blocks: u32) -> u32 {
+    let mut dcs = 1u32;
+    let mut size = total_blocks;
+    while size > 0xFFFF {
+        dcs <<= 1;
+        size >>= 1;
+    }
+    dcs
+}
+
+/// Split a disk image into 512-byte `TapeBlock`s indexed by LBN.
+pub fn rsts_disk_blocks(data: &[u8]) -> Vec<TapeBlock> {
+    data.chunks(DISK_BLOCK_SIZE)
+        .enumerate()
+        .map(|(i, chunk)| TapeBlock {
+            index: i as u32,
+            tape_file: 0,
+            size: chunk.len(),
+            raw: chunk.into(),
+            classification: BlockClassification::Unknown,
+        })
+        .collect()
+}
+
+/// Walk an RSTS/E disk image and build one directory node per account.
+pub fn reconstruct_rsts_disk(data: &[u8]) -> TapeResult<Vec<TapeFile>> {
+    let disk = RstsDisk::open(data)?;
+    let mut files = Vec::new();
+    for account in disk.accounts() {
+        let ppn = format_ppn(account.ppn);
+        files.extend(disk.files(&account).into_iter().map(|f| TapeFile {
+            format: TapeFormat::Rsts,
+            path: TapePath::new(vec![ppn.clone(), f.entry.file_name()]),
+            size_bytes: u64::from(f.size_blocks()) * DISK_BLOCK_SIZE as u64,
+            blocks: f.lbns.clone(),
+            extents: Vec::new(),
+            metadata: FileMetadata::Rsts(f.metadata()),
+            children: Vec::new(),
+        }));
+    }
+    Ok(group_by_account(files))
+}
+
+fn block_at(data: &[u8], lbn: u32) -> Option<&[u8]> {
+    let start = lbn as usize * DISK_BLOCK_SIZE;
+    data.get(start..start + DISK_BLOCK_SIZE)
+}
+
+fn table_word(block: &[u8], idx: u16) -> u16 {
+    let off = idx as usize * 2;
+    u16::from_le_bytes([block[off], block[off + 1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reconstruct_rsts_disk, RstsDisk, DISK_BLOCK_SIZE};
+    use crate::core::file::FileMetadata;
+    use crate::core::rsts::directory::{CLUSTER_MAP_OFFSET, US_UFD};
+
+    fn rad50(s: &str) -> u16 {
+        const TABLE: &[u8] = b" ABCDEFGHIJKLMNOPQRSTUVWXYZ$.%0123456789";
+        let mut v = 0u16;
+        for i in 0..3 {
+            let c = s.as_bytes().get(i).copied().unwrap_or(b' ');
+            let idx = TABLE.iter().position(|t| *t == c).unwrap() as u16;
+            v = v * 40 + idx;
+        }
+        v
+    }
+
+    fn link(block: u16, cluster: u16, entry: u16) -> u16 {
+        (block << 12) | (cluster << 9) | (entry << 4)
+    }
+
+    fn put(img: &mut [u8], lbn: usize, entry: usize, words: &[u16]) {
+        let off = lbn * DISK_BLOCK_SIZE + entry * 16;
+        for (i, w) in words.iter().enumerate() {
+            img[off + i * 2..off + i * 2 + 2].copy_from_slice(&w.to_le_bytes());
+        }
+    }
+
+    fn put_map(img: &mut [u8], lbn: usize, dcn: u16) {
+        let off = lbn * DISK_BLOCK_SIZE + CLUSTER_MAP_OFFSET;
+        img[off] = 1;
+        img[off + 2..off + 4].copy_from_slice(&dcn.to_le_bytes());
+    }
+
+    fn ufd(img: &mut [u8], lbn: usize) {
+        put_map(img, lbn, lbn as u16);
+        put(img, lbn, 0, &[link(0, 0, 1), 0xFFFF]);
+        // Name entry: HELLO.TXT, protection 60, accounting @2, retrieval @3.
+        put(
+            img,
+            lbn,
+            1,
+            &[
+                0,
+                rad50("HEL"),
+                rad50("LO "),
+                rad50("TXT"),
+                60 << 8,
+                0,
+                link(0, 0, 2),
+                link(0, 0, 3),
+            ],
+        );
+        // Accounting: accessed 1985-002, size 2 blocks, created 1985-001 at 12:00.
+        put(img, lbn, 2, &[0, 15002, 2, 15001, 720, rad50("BAS"), 1, 1]);
+        // Retrieval: two single-block clusters at DCN 20 and 22.
+        put(img, lbn, 3, &[0, 20, 22]);
+        img[20 * DISK_BLOCK_SIZE] = b'A';
+        img[22 * DISK_BLOCK_SIZE] = b'B';
+    }
+
+    /// RDS 0 pack with account [1,2] whose UFD sits at DCN 10.
+    fn rds0_image() -> Vec<u8> {
+        let mut img = vec![0u8; 64 * DISK_BLOCK_SIZE];
+        put_map(&mut img, 1, 1);
+        put(
+            &mut img,
+            1,
+            0,
+            &[
+                link(0, 0, 1),
+                0xFFFF,
+                0,
+                0,
+                1,
+                0,
+                rad50("SYS"),
+                rad50("DSK"),
+            ],
+        );
+        put(
+            &mut img,
+            1,
+            1,
+            &[0, (1 << 8) | 2, 0, 0, u16::from(US_UFD), 0, 0, 10],
+        );
+        ufd(&mut img, 10);
+        img
+    }
+
+    #[test]
+    fn walks_rds0_mfd_and_ufd() {
+        let img = rds0_image();
+        let disk = RstsDisk::open(&img).expect("label");
+        assert!(disk.label.is_rds0());
+        assert_eq!(disk.label.pack_id, "SYSDSK");
+        let tree = reconstruct_rsts_disk(&img).expect("walk");
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].path.to_string_path(), "[1,2]");
+        let file = &tree[0].children[0];
+        assert_eq!(file.path.to_string_path(), "[1,2]/HELLO.TXT");
+        assert_eq!(file.blocks, vec![20, 22]);
+        match &file.metadata {
+            FileMetadata::Rsts(meta) => {
+                assert_eq!(meta.protection, 60);
+                assert_eq!(meta.blocks, 2);
+                assert_eq!(meta.creation_date.as_deref(), Some("1985-01-01 12:00"));
+                assert_eq!(meta.access_date.as_deref(), Some("1985-01-02"));
+            }
+            other => panic!("unexpected metadata {other:?}"),
+        }
+        let account = &disk.accounts()[0];
+        let data = disk.read_file(&disk.files(account)[0]);
+        assert_eq!(data.len(), 1024);
+        assert_eq!((data[0], data[512]), (b'A', b'B'));
+    }
+
+    #[test]
+    fn walks_rds1_gfd_tables() {
+        let mut img = vec![0u8; 64 * DISK_BLOCK_SIZE];
+        // Pack label at DCN 1 pointing to an MFD at DCN 4 (two-block cluster).
+        put(
+            &mut img,
+            1,
+            0,
+            &[0, 0xFFFF, 4, 0x0402, 1, 0, rad50("USR"), 0],
+        );
+        let map = |img: &mut [u8], lbn: usize| {
+            let off = lbn * DISK_BLOCK_SIZE + CLUSTER_MAP_OFFSET;
+            img[off] = 2;
+            img[off + 2..off + 4].copy_from_slice(&(lbn as u16).to_le_bytes());
+        };
+        map(&mut img, 4);
+        // GFD table: project 200 -> DCN 6.
+        img[5 * DISK_BLOCK_SIZE + 400..5 * DISK_BLOCK_SIZE + 402]
+            .copy_from_slice(&6u16.to_le_bytes());
+        map(&mut img, 6);
+        // UFD table: programmer 7 -> DCN 30.
+        img[7 * DISK_BLOCK_SIZE + 14..7 * DISK_BLOCK_SIZE + 16]
+            .copy_from_slice(&30u16.to_le_bytes());
+        ufd(&mut img, 30);
+
+        let disk = RstsDisk::open(&img).expect("label");
+        assert!(!disk.label.is_rds0());
+        let tree = reconstruct_rsts_disk(&img).expect("walk");
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].path.to_string_path(), "[200,7]");
+        assert_eq!(tree[0].children[0].path.elements[1], "HELLO.TXT");
+    }
+
+    #[test]
+    fn ignores_links_past_the_cluster_map() {
+        let mut img = rds0_image();
+        // UFD label pointing into cluster 7, one past the seven-entry map.
+        put(&mut img, 10, 0, &[link(0, 7, 1), 0xFFFF]);
+        let disk = RstsDisk::open(&img).expect("label");
+        let account = &disk.accounts()[0];
+        assert!(disk.files(account).is_empty());
+    }
+
+    #[test]
+    fn rejects_missing_label() {
+        let img = vec![0u8; 8 * DISK_BLOCK_SIZE];
+        assert!(RstsDisk::open(&img).is_err());
+    }
+}
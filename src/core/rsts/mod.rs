@@ -0,0 +1,93 @@
+//! RSTS/E on-disk structures (RDS 0 and RDS 1.x) and magtape formats.
+//! Directories are chains of 16-byte blockettes addressed through link words;
+//! `directory` decodes the blockettes and `disk` walks MFD/GFD/UFD chains.
+//! `tape` handles DOS-11 labelled tapes; `writer` builds them from host files.
+pub mod directory;
+pub mod disk;
+pub mod tape;
+pub mod writer;
+
+pub use directory::*;
+pub use disk::*;
+pub use tape::*;
+
+use crate::core::block::TapeFormat;
+use crate::core::file::{FileMetadata, RstsFileMetadata, TapeFile, TapePath};
+
+/// Format a project/programmer number pair the way RSTS/E prints it: `[p,pn]`.
+/// RSTS/E account numbers are decimal, unlike RSX UICs.
+pub fn format_ppn(ppn: (u16, u16)) -> String {
+    format!("[{},{}]", ppn.0, ppn.1)
+}
+
+/// Decode an RSTS/E date word: `(year - 1970) * 1000 + day_of_year`.
+pub fn decode_rsts_date(word: u16) -> Option<chrono::NaiveDate> {
+    if word == 0 {
+        return None;
+    }
+    let year = 1970 + i32::from(word / 1000);
+    let day = u32::from(word % 1000);
+    chrono::NaiveDate::from_yo_opt(year, day)
+}
+
+/// Decode an RSTS/E time word (minutes until midnight, low 11 bits).
+pub fn decode_rsts_time(word: u16) -> Option<chrono::NaiveTime> {
+    let until_midnight = u32::from(word & 0x07FF);
+    if until_midnight == 0 || until_midnight > 1440 {
+        return None;
+    }
+    let minutes = 1440 - until_midnight;
+    chrono::NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0)
+}
+
+/// Render a date/time word pair as `YYYY-MM-DD HH:MM` (date only when the time is unset).
+pub fn format_rsts_timestamp(date: u16, time: Option<u16>) -> Option<String> {
+    let d = decode_rsts_date(date)?;
+    match time.and_then(decode_rsts_time) {
+        Some(t) => Some(format!("{} {}", d.format("%Y-%m-%d"), t.format("%H:%M"))),
+        None => Some(d.format("%Y-%m-%d").to_string()),
+    }
+}
+
+/// Group per-file entries (paths `[p,pn]/NAME.EXT`) under one node per account,
+/// keeping the order in which accounts were first seen.
+pub fn group_by_account(files: Vec<TapeFile>) -> Vec<TapeFile> {
+    let mut tree: Vec<TapeFile> = Vec::new();
+    for file in files {
+        let ppn = match &file.metadata {
+            FileMetadata::Rsts(meta) => meta.owner_uic,
+            _ => (0, 0),
+        };
+        let label = format_ppn(ppn);
+        let idx = match tree.iter().position(|n| n.path.elements == [label.clone()]) {
+            Some(i) => i,
+            None => {
+                tree.push(account_node(ppn));
+                tree.len() - 1
+            }
+        };
+        tree[idx].size_bytes += file.size_bytes;
+        tree[idx].children.push(file);
+    }
+    tree
+}
+
+fn account_node(ppn: (u16, u16)) -> TapeFile {
+    TapeFile {
+        format: TapeFormat::Rsts,
+        path: TapePath::new(vec![format_ppn(ppn)]),
+        size_bytes: 0,
+        blocks: Vec::new(),
+        extents: Vec::new(),
+        metadata: FileMetadata::Rsts(RstsFileMetadata {
+            owner_uic: ppn,
+            blocks: 0,
+            status: u16::from(US_UFD),
+            protection: 0,
+            creation_date: None,
+            access_date: None,
+            cluster_size: 0,
+        }),
+        children: Vec::new(),
+    }
+}
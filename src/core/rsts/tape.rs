@@ -0,0 +1,229 @@
+//! RSTS/E DOS-11 labelled magtapes.
+//!
+//! DOS-11 labelled tapes (pre-V9 RSTS/E, PIP/FIT) write every file as one
+//! tape file: a 14-byte label record followed by data records and a tape mark.
+//!
+//! Savesets written by the V9+ BACKUP package are not decoded yet: no
+//! description of their record layout or sample tape is at hand, so their
+//! records are left unclaimed rather than guessed at. Tracked in the README
+//! roadmap.
+
+use crate::core::block::{tape_files, BlockClassification, RstsBlockKind, TapeBlock, TapeFormat};
+use crate::core::file::{FileMetadata, RstsFileMetadata, TapeFile, TapePath};
+use crate::core::parse::decode_rad50_word;
+
+use super::{format_ppn, format_rsts_timestamp, group_by_account};
+
+pub const DOS11_LABEL_SIZE: usize = 14;
+
+/// Largest value a RAD50 word can hold (three characters of radix 40).
+const RAD50_MAX: u16 = 40 * 40 * 40 - 1;
+
+/// Decoded 14-byte DOS-11 magtape label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dos11Label {
+    pub name: String,
+    pub ext: String,
+    pub ppn: (u16, u16),
+    pub protection: u8,
+    pub date: u16,
+}
+
+impl Dos11Label {
+    pub fn file_name(&self) -> String {
+        if self.ext.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.name, self.ext)
+        }
+    }
+}
+
+/// Parse a DOS-11 label: RAD50 name (2 words), ext, PPN, protection, date,
+/// and RAD50 name characters 7-9.
+pub fn parse_dos11_label(data: &[u8]) -> Option<Dos11Label> {
+    if data.len() != DOS11_LABEL_SIZE {
+        return None;
+    }
+    let w = |i: usize| u16::from_le_bytes([data[i * 2], data[i * 2 + 1]]);
+    if w(0) == 0 || [0, 1, 2, 6].iter().any(|i| w(*i) > RAD50_MAX) {
+        return None;
+    }
+    if w(4) > 0xFF {
+        return None;
+    }
+    let date = w(5);
+    if date != 0 && !(1..=366).contains(&(date % 1000)) {
+        return None;
+    }
+    let ppn = w(3);
+    Some(Dos11Label {
+        name: format!(
+            "{}{}{}",
+            decode_rad50_word(w(0)),
+            decode_rad50_word(w(1)),
+            decode_rad50_word(w(6))
+        ),
+        ext: decode_rad50_word(w(2)),
+        ppn: (ppn >> 8, ppn & 0xFF),
+        protection: w(4) as u8,
+        date,
+    })
+}
+
+/// True when a tape file follows the DOS-11 layout: a label opening it and
+/// only data records, no further label, up to the tape mark.
+pub fn is_dos11_tape_file(group: &[TapeBlock]) -> bool {
+    let Some((label, data)) = group.split_first() else {
+        return false;
+    };
+    parse_dos11_label(&label.raw).is_some()
+        && data.iter().all(|b| parse_dos11_label(&b.raw).is_none())
+}
+
+/// Reclassify blocks belonging to DOS-11 tape files.
+/// Returns the number of blocks claimed.
+pub fn classify_rsts_tape(blocks: &mut [TapeBlock]) -> usize {
+    let mut claimed = 0usize;
+    let mut start = 0usize;
+    while start < blocks.len() {
+        let tf = blocks[start].tape_file;
+        let end = blocks[start..]
+            .iter()
+            .position(|b| b.tape_file != tf)
+            .map(|p| start + p)
+            .unwrap_or(blocks.len());
+        let group = &mut blocks[start..end];
+
+        if parse_dos11_label(&group[0].raw).is_some() {
+            group[0].classification = BlockClassification::Rsts(RstsBlockKind::DosLabel);
+            set_kind(&mut group[1..], RstsBlockKind::DosData);
+            claimed += group.len();
+        }
+        start = end;
+    }
+    claimed
+}
+
+/// Build RSTS/E file listings from DOS-11 tape files.
+/// Returns an empty list when no DOS-11 label is present.
+pub fn reconstruct_rsts_tape(blocks: &[TapeBlock]) -> Vec<TapeFile> {
+    let mut files = Vec::new();
+    for group in tape_files(blocks) {
+        if let Some(label) = parse_dos11_label(&group[0].raw) {
+            files.push(dos11_file(&label, &group[1..]));
+        }
+    }
+    group_by_account(files)
+}
+
+fn dos11_file(label: &Dos11Label, data: &[TapeBlock]) -> TapeFile {
+    let size_bytes: u64 = data.iter().map(|b| b.size as u64).sum();
+    TapeFile {
+        format: TapeFormat::Rsts,
+        path: TapePath::new(vec![format_ppn(label.ppn), label.file_name()]),
+        size_bytes,
+        blocks: data.iter().map(|b| b.index).collect(),
+        extents: Vec::new(),
+        metadata: FileMetadata::Rsts(RstsFileMetadata {
+            owner_uic: label.ppn,
+            blocks: size_bytes.div_ceil(512) as u32,
+            status: 0,
+            protection: label.protection,
+            creation_date: format_rsts_timestamp(label.date, None),
+            access_date: None,
+            cluster_size: 0,
+        }),
+        children: Vec::new(),
+    }
+}
+
+fn set_kind(blocks: &mut [TapeBlock], kind: RstsBlockKind) {
+    for b in blocks {
+        b.classification = BlockClassification::Rsts(kind.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify_rsts_tape, is_dos11_tape_file, parse_dos11_label, reconstruct_rsts_tape};
+    use crate::core::block::{BlockClassification, RstsBlockKind, TapeBlock};
+    use crate::core::file::FileMetadata;
+
+    fn block(index: u32, tape_file: u32, raw: Vec<u8>) -> TapeBlock {
+        TapeBlock {
+            index,
+            tape_file,
+            size: raw.len(),
+            raw: raw.into(),
+            classification: BlockClassification::Unknown,
+        }
+    }
+
+    fn words(w: &[u16]) -> Vec<u8> {
+        w.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    // RAD50 "HEL", "LO ", "BAS".
+    const HEL: u16 = 8 * 1600 + 5 * 40 + 12;
+    const LO: u16 = 12 * 1600 + 15 * 40;
+    const BAS: u16 = 2 * 1600 + 40 + 19;
+
+    #[test]
+    fn parses_dos11_label() {
+        let raw = words(&[HEL, LO, BAS, (1 << 8) | 2, 60, 15001, 0]);
+        let label = parse_dos11_label(&raw).expect("label");
+        assert_eq!(label.file_name(), "HELLO.BAS");
+        assert_eq!(label.ppn, (1, 2));
+        assert_eq!(label.protection, 60);
+        assert!(parse_dos11_label(&raw[..12]).is_none());
+        assert!(parse_dos11_label(&words(&[0, 0, 0, 0, 0, 0, 0])).is_none());
+    }
+
+    #[test]
+    fn lists_dos11_tape_files() {
+        let mut blocks = vec![
+            block(0, 0, vec![0xAA; 2062]),
+            block(1, 1, words(&[HEL, LO, BAS, (1 << 8) | 2, 60, 15001, 0])),
+            block(2, 1, vec![b'A'; 512]),
+            block(3, 1, vec![b'B'; 512]),
+        ];
+        assert_eq!(classify_rsts_tape(&mut blocks), 3);
+        assert!(matches!(
+            blocks[1].classification,
+            BlockClassification::Rsts(RstsBlockKind::DosLabel)
+        ));
+        let tree = reconstruct_rsts_tape(&blocks);
+        assert_eq!(tree.len(), 1);
+        let file = &tree[0].children[0];
+        assert_eq!(file.path.to_string_path(), "[1,2]/HELLO.BAS");
+        assert_eq!(file.blocks, vec![2, 3]);
+        match &file.metadata {
+            FileMetadata::Rsts(meta) => {
+                assert_eq!(meta.blocks, 2);
+                assert_eq!(meta.creation_date.as_deref(), Some("1985-01-01"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn checks_dos11_tape_file_layout() {
+        let label = || words(&[HEL, LO, BAS, (1 << 8) | 2, 60, 15001, 0]);
+        let file = [block(1, 1, label()), block(2, 1, vec![b'A'; 512])];
+        assert!(is_dos11_tape_file(&file));
+        assert!(is_dos11_tape_file(&file[..1]));
+        assert!(!is_dos11_tape_file(&file[1..]));
+        // Two labels without a tape mark between them break the chain.
+        let merged = [block(1, 1, label()), block(2, 1, label())];
+        assert!(!is_dos11_tape_file(&merged));
+    }
+
+    #[test]
+    fn ignores_unlabelled_tape_files() {
+        let mut vol1 = b"VOL1SAVE".to_vec();
+        vol1.resize(80, b' ');
+        let blocks = vec![block(0, 0, vol1), block(1, 1, vec![0; 64])];
+        assert!(reconstruct_rsts_tape(&blocks).is_empty());
+    }
+}
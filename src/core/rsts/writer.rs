@@ -0,0 +1,184 @@
+//! DOS-11 magtape writer: one tape file per host file, each a 14-byte
+//! label record followed by 512-byte data records, as PIP and FLX wrote
+//! them. The result reads back through `tape::reconstruct_rsts_tape`.
+use std::io;
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::core::block::{BlockClassification, TapeBlock};
+use crate::core::parse::encode_rad50;
+use crate::core::rt11::writer::{host_text_to_crlf, pdp11_file_name, read_host_files, HostFile};
+use crate::tap::container::ContainerFormat;
+use crate::tap::writer::write_tape_image;
+
+pub const DOS11_RECORD_SIZE: usize = 512;
+
+#[derive(Debug, Clone)]
+pub struct Dos11TapeOptions {
+    /// Owner as (project, programmer).
+    pub ppn: (u8, u8),
+    /// DOS-11 protection code.
+    pub protection: u8,
+    /// Convert LF line ends in text files to CR LF.
+    pub convert_text: bool,
+}
+
+impl Default for Dos11TapeOptions {
+    fn default() -> Self {
+        Self {
+            ppn: (1, 1),
+            protection: 0o233,
+            convert_text: true,
+        }
+    }
+}
+
+/// Encode a date as an RSTS/E (DOS-11) date word; 0 when out of range.
+pub fn encode_rsts_date(date: NaiveDate) -> u16 {
+    let years = date.year() - 1970;
+    if !(0..=65).contains(&years) {
+        return 0;
+    }
+    (years * 1000 + date.ordinal() as i32) as u16
+}
+
+/// Build the 14-byte label for a host file.
+pub fn format_dos11_label(file: &HostFile, opts: &Dos11TapeOptions) -> io::Result<[u8; 14]> {
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: {what}", file.name),
+        )
+    };
+    let (name, ext) =
+        pdp11_file_name(&file.name, 9).ok_or_else(|| invalid("no RAD50 characters in name"))?;
+    let name_words = encode_rad50(&name, 3).ok_or_else(|| invalid("name is not RAD50"))?;
+    let ext_word = encode_rad50(&ext, 1).ok_or_else(|| invalid("type is not RAD50"))?;
+    let words = [
+        name_words[0],
+        name_words[1],
+        ext_word[0],
+        u16::from(opts.ppn.0) << 8 | u16::from(opts.ppn.1),
+        u16::from(opts.protection),
+        encode_rsts_date(file.date),
+        name_words[2],
+    ];
+    let mut label = [0u8; 14];
+    for (i, w) in words.iter().enumerate() {
+        label[i * 2..i * 2 + 2].copy_from_slice(&w.to_le_bytes());
+    }
+    Ok(label)
+}
+
+/// Lay out host files as DOS-11 tape records, one tape file each.
+pub fn build_dos11_tape(files: &[HostFile], opts: &Dos11TapeOptions) -> io::Result<Vec<TapeBlock>> {
+    let mut blocks = Vec::new();
+    let mut names = Vec::with_capacity(files.len());
+    for (tape_file, file) in files.iter().enumerate() {
+        let label = format_dos11_label(file, opts)?;
+        // Name words 0-2 plus the characters 7-9 word identify the file.
+        let name = [&label[..6], &label[12..]].concat();
+        if names.contains(&name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: duplicate DOS-11 name", file.name),
+            ));
+        }
+        names.push(name);
+        let data = if opts.convert_text {
+            host_text_to_crlf(&file.data)
+        } else {
+            file.data.clone()
+        };
+        let mut push = |raw: Vec<u8>| {
+            blocks.push(TapeBlock {
+                index: blocks.len() as u32,
+                tape_file: tape_file as u32,
+                size: raw.len(),
+                raw: raw.into(),
+                classification: BlockClassification::Unknown,
+            })
+        };
+        push(label.to_vec());
+        for record in data.chunks(DOS11_RECORD_SIZE) {
+            push(record.to_vec());
+        }
+    }
+    Ok(blocks)
+}
+
+/// Build a DOS-11 tape from a host directory and write it as a tape image.
+pub fn write_dos11_tape(
+    dir: &Path,
+    out: &Path,
+    format: ContainerFormat,
+    opts: &Dos11TapeOptions,
+) -> io::Result<()> {
+    let files = read_host_files(dir)?;
+    write_tape_image(out, &build_dos11_tape(&files, opts)?, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{build_dos11_tape, encode_rsts_date, Dos11TapeOptions, DOS11_RECORD_SIZE};
+    use crate::core::extract::file_bytes;
+    use crate::core::file::FileMetadata;
+    use crate::core::rsts::{decode_rsts_date, parse_dos11_label, reconstruct_rsts_tape};
+    use crate::core::rt11::writer::HostFile;
+
+    fn host(name: &str, data: &[u8]) -> HostFile {
+        HostFile {
+            name: name.into(),
+            data: data.to_vec(),
+            date: NaiveDate::from_ymd_opt(1984, 2, 29).unwrap(),
+        }
+    }
+
+    #[test]
+    fn encodes_rsts_dates() {
+        let date = NaiveDate::from_ymd_opt(1984, 2, 29).unwrap();
+        assert_eq!(encode_rsts_date(date), 14060);
+        assert_eq!(decode_rsts_date(encode_rsts_date(date)), Some(date));
+        assert_eq!(
+            encode_rsts_date(NaiveDate::from_ymd_opt(2040, 1, 1).unwrap()),
+            0
+        );
+    }
+
+    #[test]
+    fn round_trips_through_reader() {
+        let big = vec![0xA5u8; DOS11_RECORD_SIZE + 10];
+        let files = [
+            host("longname1.mac", b"MOV R0,R1\n"),
+            host("image.sav", &big),
+        ];
+        let blocks = build_dos11_tape(&files, &Dos11TapeOptions::default()).unwrap();
+        assert_eq!(blocks.len(), 5);
+        let label = parse_dos11_label(&blocks[0].raw).expect("label");
+        assert_eq!(label.file_name(), "LONGNAME1.MAC");
+        assert_eq!(label.ppn, (1, 1));
+        assert_eq!(label.protection, 0o233);
+
+        let tree = reconstruct_rsts_tape(&blocks);
+        assert_eq!(tree[0].path.to_string_path(), "[1,1]");
+        let mac = &tree[0].children[0];
+        assert_eq!(mac.path.to_string_path(), "[1,1]/LONGNAME1.MAC");
+        assert_eq!(file_bytes(mac, &blocks), b"MOV R0,R1\r\n");
+        match &mac.metadata {
+            FileMetadata::Rsts(meta) => {
+                assert_eq!(meta.creation_date.as_deref(), Some("1984-02-29"))
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_eq!(file_bytes(&tree[0].children[1], &blocks), big);
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let files = [host("a.txt", b"1"), host("A.TXT", b"2")];
+        assert!(build_dos11_tape(&files, &Dos11TapeOptions::default()).is_err());
+    }
+}
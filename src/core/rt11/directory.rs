@@ -0,0 +1,378 @@
+//! RT-11 volume directory: home block plus a chain of two-block segments.
+//!
+//! Each segment starts with a five-word header (total segments, next segment,
+//! highest segment in use, extra bytes per entry, first data block) followed
+//! by 7-word entries (status, RAD50 name x2, RAD50 type, length, job/channel,
+//! date) and any extra bytes. Files are contiguous and laid out in directory
+//! order from the segment's first data block.
+
+use crate::core::fields::{Field, FieldKind};
+use crate::core::parse::decode_rad50_word;
+
+use super::format_rt11_file_name;
+
+pub const RT11_BLOCK_SIZE: usize = 512;
+/// Directory segments are two blocks long.
+pub const SEGMENT_SIZE: usize = 2 * RT11_BLOCK_SIZE;
+/// First directory segment when the home block leaves the field blank.
+pub const DEFAULT_FIRST_SEGMENT: u16 = 6;
+/// RT-11 allows at most 31 directory segments.
+pub const MAX_SEGMENTS: u16 = 31;
+
+/// Tentative file (open for output).
+pub const E_TENT: u16 = 0o400;
+/// Empty area.
+pub const E_MPTY: u16 = 0o1000;
+/// Permanent file.
+pub const E_PERM: u16 = 0o2000;
+/// End-of-segment marker.
+pub const E_EOS: u16 = 0o4000;
+/// Protected permanent file.
+pub const E_PROT: u16 = 0o100000;
+
+/// Decoded RT-11 home block (block 1 of the volume).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HomeBlock {
+    pub first_segment: u16,
+    pub system_version: String,
+    pub volume_id: String,
+    pub owner: String,
+    pub system_id: String,
+}
+
+impl HomeBlock {
+    /// Parse a home block; requires the `DECRT11` system identification.
+    pub fn parse(block: &[u8]) -> Option<Self> {
+        if block.len() < RT11_BLOCK_SIZE {
+            return None;
+        }
+        let system_id = ascii_field(&block[0o760..0o774]);
+        if !system_id.starts_with("DECRT11") {
+            return None;
+        }
+        let first_segment = match word(block, 0o724) {
+            0 => DEFAULT_FIRST_SEGMENT,
+            n => n,
+        };
+        Some(HomeBlock {
+            first_segment,
+            system_version: decode_rad50_word(word(block, 0o726)),
+            volume_id: ascii_field(&block[0o730..0o744]),
+            owner: ascii_field(&block[0o744..0o760]),
+            system_id,
+        })
+    }
+}
+
+/// One directory entry with its start block resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    pub status: u16,
+    pub name: String,
+    pub ext: String,
+    pub start_block: u32,
+    pub length_blocks: u16,
+    pub date: u16,
+}
+
+impl DirectoryEntry {
+    pub fn file_name(&self) -> String {
+        format_rt11_file_name(&self.name, &self.ext)
+    }
+
+    pub fn is_permanent(&self) -> bool {
+        self.status & E_PERM != 0
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.status & E_PROT != 0
+    }
+}
+
+/// Decoded RT-11 volume directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rt11Volume {
+    pub home: HomeBlock,
+    pub entries: Vec<DirectoryEntry>,
+}
+
+impl Rt11Volume {
+    /// Permanent files, in directory order.
+    pub fn files(&self) -> impl Iterator<Item = &DirectoryEntry> {
+        self.entries.iter().filter(|e| e.is_permanent())
+    }
+}
+
+/// Parse the directory of an RT-11 volume image (block 0 at offset 0).
+/// Returns `None` when the home block or first segment does not look valid.
+pub fn parse_rt11_volume(image: &[u8]) -> Option<Rt11Volume> {
+    let home = HomeBlock::parse(image.get(RT11_BLOCK_SIZE..2 * RT11_BLOCK_SIZE)?)?;
+    let first = usize::from(home.first_segment) * RT11_BLOCK_SIZE;
+    let total = word(image.get(first..first + 2)?, 0);
+    if !(1..=MAX_SEGMENTS).contains(&total) {
+        return None;
+    }
+
+    let mut entries = Vec::new();
+    let mut segment = 1u16;
+    let mut visited = 0u16;
+    while segment != 0 && visited < total {
+        visited += 1;
+        let off = first + usize::from(segment - 1) * SEGMENT_SIZE;
+        let Some(seg) = image.get(off..off + SEGMENT_SIZE) else {
+            break;
+        };
+        let next = word(seg, 2);
+        let entry_size = 14 + usize::from(word(seg, 6));
+        let mut block = u32::from(word(seg, 8));
+        let mut pos = 10usize;
+        while pos + 14 <= SEGMENT_SIZE {
+            let status = word(seg, pos);
+            if status & E_EOS != 0 {
+                break;
+            }
+            let length_blocks = word(seg, pos + 8);
+            entries.push(DirectoryEntry {
+                status,
+                name: format!(
+                    "{}{}",
+                    decode_rad50_word(word(seg, pos + 2)),
+                    decode_rad50_word(word(seg, pos + 4))
+                ),
+                ext: decode_rad50_word(word(seg, pos + 6)),
+                start_block: block,
+                length_blocks,
+                date: word(seg, pos + 12),
+            });
+            block += u32::from(length_blocks);
+            pos += entry_size;
+        }
+        if next > total {
+            break;
+        }
+        segment = next;
+    }
+    Some(Rt11Volume { home, entries })
+}
+
+/// Home block and directory segment fields for the hex viewer. `data` may
+/// be a volume image (home block at block 1), a lone home block, or a lone
+/// directory segment; empty when it is none of these.
+pub fn directory_fields(data: &[u8]) -> Vec<Field> {
+    let image_home = data
+        .get(RT11_BLOCK_SIZE..2 * RT11_BLOCK_SIZE)
+        .and_then(HomeBlock::parse);
+    if let Some(home) = image_home {
+        let mut fields = home_fields(RT11_BLOCK_SIZE);
+        let first = usize::from(home.first_segment) * RT11_BLOCK_SIZE;
+        if let Some(seg) = data.get(first..) {
+            fields.extend(segment_fields(seg, first));
+        }
+        return fields;
+    }
+    if HomeBlock::parse(data).is_some() {
+        return home_fields(0);
+    }
+    segment_fields(data, 0)
+}
+
+fn home_fields(base: usize) -> Vec<Field> {
+    [
+        (0o724, 0o726, "first directory segment"),
+        (0o726, 0o730, "system version (RAD50)"),
+        (0o730, 0o744, "volume identification"),
+        (0o744, 0o760, "owner name"),
+        (0o760, 0o774, "system identification"),
+    ]
+    .iter()
+    .map(|&(start, end, name)| {
+        Field::new(
+            base + start..base + end,
+            format!("home block {name}"),
+            FieldKind::HomeBlock,
+        )
+    })
+    .collect()
+}
+
+/// Fields of the directory segment at the start of `seg`, found at `base`.
+/// Only a segment whose header is in range and whose entries carry valid
+/// status words up to an end-of-segment marker is annotated.
+fn segment_fields(seg: &[u8], base: usize) -> Vec<Field> {
+    let seg = &seg[..seg.len().min(SEGMENT_SIZE)];
+    if seg.len() < 12 {
+        return Vec::new();
+    }
+    let total = word(seg, 0);
+    let extra = usize::from(word(seg, 6));
+    if !(1..=MAX_SEGMENTS).contains(&total)
+        || word(seg, 2) > total
+        || !(1..=total).contains(&word(seg, 4))
+        || extra % 2 != 0
+        || extra > 64
+    {
+        return Vec::new();
+    }
+    let header = [
+        "total segments",
+        "next segment",
+        "highest segment in use",
+        "extra bytes per entry",
+        "first data block",
+    ];
+    let mut fields: Vec<Field> = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            Field::new(
+                base + i * 2..base + i * 2 + 2,
+                format!("segment {name}"),
+                FieldKind::DirectoryEntry,
+            )
+        })
+        .collect();
+    let words = [
+        "status",
+        "name (RAD50)",
+        "name (RAD50)",
+        "type (RAD50)",
+        "length",
+        "job/channel",
+        "date",
+    ];
+    let valid = E_TENT | E_MPTY | E_PERM | E_PROT;
+    let mut pos = 10usize;
+    let mut entry = 0usize;
+    while pos + 2 <= seg.len() {
+        let status = word(seg, pos);
+        if status & E_EOS != 0 {
+            fields.push(Field::new(
+                base + pos..base + pos + 2,
+                "end-of-segment marker",
+                FieldKind::DirectoryEntry,
+            ));
+            return fields;
+        }
+        if status & !valid != 0 || status == 0 || pos + 14 + extra > seg.len() {
+            break;
+        }
+        for (i, name) in words.iter().enumerate() {
+            let at = base + pos + i * 2;
+            fields.push(Field::new(
+                at..at + 2,
+                format!("entry {entry} {name}"),
+                FieldKind::DirectoryEntry,
+            ));
+        }
+        if extra > 0 {
+            let at = base + pos + 14;
+            fields.push(Field::new(
+                at..at + extra,
+                format!("entry {entry} extra bytes"),
+                FieldKind::DirectoryEntry,
+            ));
+        }
+        pos += 14 + extra;
+        entry += 1;
+    }
+    Vec::new()
+}
+
+fn ascii_field(raw: &[u8]) -> String {
+    raw.iter()
+        .map(|&b| {
+            if (0x20..0x7F).contains(&b) {
+                b as char
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn word(data: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([data[off], data[off + 1]])
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{directory_fields, parse_rt11_volume, E_EOS, E_MPTY, E_PERM, RT11_BLOCK_SIZE};
+
+    // RAD50 "HEL", "LO ", "SAV", "SWA", "P  ", "SYS".
+    pub(crate) const HEL: u16 = 8 * 1600 + 5 * 40 + 12;
+    pub(crate) const LO: u16 = 12 * 1600 + 15 * 40;
+    pub(crate) const SAV: u16 = 19 * 1600 + 40 + 22;
+    const SWA: u16 = 19 * 1600 + 23 * 40 + 1;
+    const P: u16 = 16 * 1600;
+    const SYS: u16 = 19 * 1600 + 25 * 40 + 19;
+
+    fn put(image: &mut [u8], off: usize, words: &[u16]) {
+        for (i, w) in words.iter().enumerate() {
+            image[off + i * 2..off + i * 2 + 2].copy_from_slice(&w.to_le_bytes());
+        }
+    }
+
+    /// A 20-block volume: one segment at block 6, data from block 8 holding
+    /// SWAP.SYS (2 blocks), an empty area (1 block) and HELLO.SAV (1 block).
+    pub(crate) fn sample_volume() -> Vec<u8> {
+        let mut image = vec![0u8; 20 * RT11_BLOCK_SIZE];
+        let home = RT11_BLOCK_SIZE;
+        image[home + 0o730..home + 0o744].copy_from_slice(b"RT11A       ");
+        image[home + 0o760..home + 0o774].copy_from_slice(b"DECRT11A    ");
+        let date = (3 << 10) | (14 << 5) | 13;
+        let dir = 6 * RT11_BLOCK_SIZE;
+        put(&mut image, dir, &[1, 0, 1, 0, 8]);
+        put(&mut image, dir + 10, &[E_PERM, SWA, P, SYS, 2, 0, date]);
+        put(&mut image, dir + 24, &[E_MPTY, 0, 0, 0, 1, 0, 0]);
+        put(&mut image, dir + 38, &[E_PERM, HEL, LO, SAV, 1, 0, date]);
+        put(&mut image, dir + 52, &[E_EOS]);
+        image[8 * RT11_BLOCK_SIZE..10 * RT11_BLOCK_SIZE].fill(b'S');
+        image[11 * RT11_BLOCK_SIZE..12 * RT11_BLOCK_SIZE].fill(b'H');
+        image
+    }
+
+    #[test]
+    fn walks_directory_segment() {
+        let volume = parse_rt11_volume(&sample_volume()).expect("volume");
+        assert_eq!(volume.home.volume_id, "RT11A");
+        assert_eq!(volume.entries.len(), 3);
+        let files: Vec<_> = volume.files().collect();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].file_name(), "SWAP.SYS");
+        assert_eq!(files[0].start_block, 8);
+        assert_eq!(files[1].file_name(), "HELLO.SAV");
+        assert_eq!(files[1].start_block, 11);
+    }
+
+    #[test]
+    fn names_home_block_and_entry_words() {
+        let image = sample_volume();
+        let fields = directory_fields(&image);
+        let name = |offset: usize| {
+            fields
+                .iter()
+                .find(|f| f.range.contains(&offset))
+                .map(|f| f.name.as_str())
+        };
+        assert_eq!(
+            name(RT11_BLOCK_SIZE + 0o730),
+            Some("home block volume identification")
+        );
+        let dir = 6 * RT11_BLOCK_SIZE;
+        assert_eq!(name(dir + 8), Some("segment first data block"));
+        assert_eq!(name(dir + 38 + 2), Some("entry 2 name (RAD50)"));
+        assert_eq!(name(dir + 52), Some("end-of-segment marker"));
+        // The segment alone, as one record of a tape.
+        let segment = directory_fields(&image[dir..dir + 1024]);
+        assert_eq!(segment.len(), fields.len() - 5);
+        assert!(directory_fields(&[0u8; 1024]).is_empty());
+    }
+
+    #[test]
+    fn rejects_images_without_home_block() {
+        assert!(parse_rt11_volume(&vec![0u8; 20 * RT11_BLOCK_SIZE]).is_none());
+        assert!(parse_rt11_volume(&[0u8; 100]).is_none());
+    }
+}
@@ -0,0 +1,81 @@
+//! RT-11 volume directories and magtape formats.
+//! `directory` decodes the home block and directory segments of an RT-11
+//! volume image; `tape` handles file-structured magtape (FSM) written by the
+//! MT/MM handlers and BUP backup volumes; `writer` builds volume images from
+//! host files.
+pub mod directory;
+pub mod tape;
+pub mod writer;
+
+pub use directory::*;
+pub use tape::*;
+
+/// Decode an RT-11 directory date word.
+///
+/// Bits 13-10 hold the month, 9-5 the day and 4-0 the year since 1972;
+/// RT-11 V5.5 uses bits 15-14 as an "age" adding 32 years per step.
+pub fn decode_rt11_date(word: u16) -> Option<String> {
+    if word == 0 {
+        return None;
+    }
+    let age = i32::from(word >> 14);
+    let month = u32::from((word >> 10) & 0o17);
+    let day = u32::from((word >> 5) & 0o37);
+    let year = 1972 + i32::from(word & 0o37) + 32 * age;
+    let date = chrono::NaiveDate::from_ymd_opt(year, month, day)?;
+    Some(date.format("%Y-%m-%d").to_string())
+}
+
+/// Encode a date as an RT-11 directory date word (the inverse of
+/// `decode_rt11_date`). Dates before 1972 or after 2099 are not representable.
+pub fn encode_rt11_date(date: chrono::NaiveDate) -> Option<u16> {
+    use chrono::Datelike;
+    let years = date.year() - 1972;
+    if !(0..128).contains(&years) {
+        return None;
+    }
+    let age = (years / 32) as u16;
+    Some(
+        (age << 14)
+            | ((date.month() as u16) << 10)
+            | ((date.day() as u16) << 5)
+            | (years % 32) as u16,
+    )
+}
+
+/// Join an RT-11 name and extension as `NAME.EXT` (just `NAME` when the
+/// extension is blank).
+pub fn format_rt11_file_name(name: &str, ext: &str) -> String {
+    if ext.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name}.{ext}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_rt11_date, encode_rt11_date};
+
+    #[test]
+    fn decodes_directory_dates() {
+        // 14-MAR-85: month 3, day 14, year 85 - 72.
+        let word = (3 << 10) | (14 << 5) | 13;
+        assert_eq!(decode_rt11_date(word).as_deref(), Some("1985-03-14"));
+        // Age 1 moves the same word forward 32 years.
+        assert_eq!(
+            decode_rt11_date(word | (1 << 14)).as_deref(),
+            Some("2017-03-14")
+        );
+        assert_eq!(decode_rt11_date(0), None);
+    }
+
+    #[test]
+    fn encodes_directory_dates() {
+        let date = chrono::NaiveDate::from_ymd_opt(2017, 3, 14).unwrap();
+        let word = encode_rt11_date(date).unwrap();
+        assert_eq!(decode_rt11_date(word).as_deref(), Some("2017-03-14"));
+        let old = chrono::NaiveDate::from_ymd_opt(1971, 1, 1).unwrap();
+        assert_eq!(encode_rt11_date(old), None);
+    }
+}
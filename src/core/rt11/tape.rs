@@ -0,0 +1,308 @@
+//! RT-11 magtape formats.
+//!
+//! The MT/MM handlers write file-structured magtape (FSM): an optional VOL1,
+//! then for every file a HDR1 tape file, a data tape file of 512-byte records
+//! and an EOF1 tape file. HDR1 carries the RT-11 `NAME.EXT` in the file
+//! identifier, the creation date, and `DECRT11A` as system code.
+//!
+//! BUP backs a whole volume up into FSM files. Such a data file is an image
+//! of the volume from block 0, so its RT-11 directory is decoded in place and
+//! the saved files are listed below the BUP file.
+
+use crate::core::ansi::{parse_ansi_label, AnsiLabel, FileLabel1};
+use crate::core::block::{
+    byte_extents, tape_files, BlockClassification, Rt11BlockKind, TapeBlock, TapeFormat,
+};
+use crate::core::file::{FileMetadata, Rt11FileMetadata, TapeFile, TapePath};
+
+use super::decode_rt11_date;
+use super::directory::{parse_rt11_volume, Rt11Volume, RT11_BLOCK_SIZE};
+
+/// Smallest data file worth probing for a BUP volume image (home block and
+/// the default first directory segment).
+const MIN_BUP_IMAGE: usize = 8 * RT11_BLOCK_SIZE;
+
+/// True for a HDR1 written by the RT-11 magtape handlers.
+pub fn is_rt11_hdr1(label: &FileLabel1) -> bool {
+    label.system_code.starts_with("DECRT11")
+        || label.system_code.contains("RT11")
+        || label.system_code.contains("RT-11")
+}
+
+/// Reclassify blocks belonging to RT-11 FSM or BUP tape files.
+/// Returns the number of blocks claimed.
+pub fn classify_rt11_tape(blocks: &mut [TapeBlock]) -> usize {
+    let mut claimed = 0usize;
+    let mut pending = false;
+    let mut start = 0usize;
+    while start < blocks.len() {
+        let tf = blocks[start].tape_file;
+        let end = blocks[start..]
+            .iter()
+            .position(|b| b.tape_file != tf)
+            .map(|p| start + p)
+            .unwrap_or(blocks.len());
+        let group = &mut blocks[start..end];
+
+        if let Some(labels) = label_group(group) {
+            let rt11 = labels.iter().any(label_is_rt11);
+            pending = rt11 && last_hdr1(&labels).is_some();
+            if rt11 {
+                set_kind(group, Rt11BlockKind::MagtapeLabel);
+                claimed += group.len();
+            }
+        } else if pending {
+            let kind = if parse_rt11_volume(&group_bytes(group)).is_some() {
+                Rt11BlockKind::BupData
+            } else {
+                Rt11BlockKind::MagtapeData
+            };
+            set_kind(group, kind);
+            claimed += group.len();
+            pending = false;
+        }
+        start = end;
+    }
+    claimed
+}
+
+/// True when a tape file has RT-11 structure: labels written by the RT-11
+/// magtape handlers, a volume image whose home block and directory parse,
+/// or data that `classify_rt11_tape` tied to a preceding RT-11 HDR1.
+pub fn is_rt11_tape_file(group: &[TapeBlock]) -> bool {
+    if let Some(labels) = label_group(group) {
+        return labels.iter().any(label_is_rt11);
+    }
+    let chained = group.iter().all(|b| {
+        matches!(
+            b.classification,
+            BlockClassification::Rt11(Rt11BlockKind::MagtapeData | Rt11BlockKind::BupData)
+        )
+    });
+    chained || parse_rt11_volume(&group_bytes(group)).is_some()
+}
+
+/// Build RT-11 file listings from FSM tape files, expanding BUP volume images.
+/// Returns an empty list when no RT-11 HDR1 labels are present.
+pub fn reconstruct_rt11_tape(blocks: &[TapeBlock]) -> Vec<TapeFile> {
+    let mut files = Vec::new();
+    let mut pending: Option<FileLabel1> = None;
+    for group in tape_files(blocks) {
+        if let Some(labels) = label_group(group) {
+            pending = last_hdr1(&labels).filter(|h| is_rt11_hdr1(h)).cloned();
+            continue;
+        }
+        if let Some(hdr1) = pending.take() {
+            files.push(magtape_file(&hdr1, group));
+        }
+    }
+    files
+}
+
+fn magtape_file(hdr1: &FileLabel1, group: &[TapeBlock]) -> TapeFile {
+    let size_bytes: u64 = group.iter().map(|b| b.size as u64).sum();
+    let name = hdr1.file_id.clone();
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, e)| e.to_string())
+        .unwrap_or_default();
+    let children = match parse_rt11_volume(&group_bytes(group)) {
+        Some(volume) => bup_files(&name, &volume, group),
+        None => Vec::new(),
+    };
+    TapeFile {
+        format: TapeFormat::Rt11,
+        path: TapePath::new(vec![name]),
+        size_bytes,
+        blocks: group.iter().map(|b| b.index).collect(),
+        extents: Vec::new(),
+        metadata: FileMetadata::Rt11(Rt11FileMetadata {
+            start_block: 0,
+            length_blocks: size_bytes
+                .div_ceil(RT11_BLOCK_SIZE as u64)
+                .min(u16::MAX as u64) as u16,
+            ext,
+            creation_date: hdr1.creation_date.clone(),
+        }),
+        children,
+    }
+}
+
+fn bup_files(parent: &str, volume: &Rt11Volume, group: &[TapeBlock]) -> Vec<TapeFile> {
+    volume
+        .files()
+        .map(|entry| {
+            let start = entry.start_block as usize * RT11_BLOCK_SIZE;
+            let len = usize::from(entry.length_blocks) * RT11_BLOCK_SIZE;
+            let extents = byte_extents(group, start, len);
+            let mut blocks: Vec<u32> = extents.iter().map(|e| e.block).collect();
+            blocks.dedup();
+            TapeFile {
+                format: TapeFormat::Rt11,
+                path: TapePath::new(vec![parent.to_string(), entry.file_name()]),
+                size_bytes: extents.iter().map(|e| e.len as u64).sum(),
+                blocks,
+                extents,
+                metadata: FileMetadata::Rt11(Rt11FileMetadata {
+                    start_block: entry.start_block.min(u16::MAX as u32) as u16,
+                    length_blocks: entry.length_blocks,
+                    ext: entry.ext.clone(),
+                    creation_date: decode_rt11_date(entry.date),
+                }),
+                children: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+fn label_group(group: &[TapeBlock]) -> Option<Vec<AnsiLabel>> {
+    group.iter().map(|b| parse_ansi_label(&b.raw)).collect()
+}
+
+fn last_hdr1(labels: &[AnsiLabel]) -> Option<&FileLabel1> {
+    labels.iter().rev().find_map(|l| match l {
+        AnsiLabel::Header1(h) => Some(h),
+        _ => None,
+    })
+}
+
+fn label_is_rt11(label: &AnsiLabel) -> bool {
+    match label {
+        AnsiLabel::Header1(h) | AnsiLabel::EndOfFile1(h) | AnsiLabel::EndOfVolume1(h) => {
+            is_rt11_hdr1(h)
+        }
+        _ => false,
+    }
+}
+
+fn group_bytes(group: &[TapeBlock]) -> Vec<u8> {
+    let total: usize = group.iter().map(|b| b.size).sum();
+    if total < MIN_BUP_IMAGE {
+        return Vec::new();
+    }
+    let mut out = Vec::with_capacity(total);
+    for b in group {
+        out.extend_from_slice(&b.raw);
+    }
+    out
+}
+
+fn set_kind(blocks: &mut [TapeBlock], kind: Rt11BlockKind) {
+    for b in blocks {
+        b.classification = BlockClassification::Rt11(kind.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify_rt11_tape, is_rt11_tape_file, reconstruct_rt11_tape};
+    use crate::core::block::{BlockClassification, Rt11BlockKind, TapeBlock};
+    use crate::core::extract::file_bytes;
+    use crate::core::file::FileMetadata;
+    use crate::core::rt11::directory::tests::sample_volume;
+
+    fn block(index: u32, tape_file: u32, raw: Vec<u8>) -> TapeBlock {
+        TapeBlock {
+            index,
+            tape_file,
+            size: raw.len(),
+            raw: raw.into(),
+            classification: BlockClassification::Unknown,
+        }
+    }
+
+    fn label(id: &str, file_id: &str, system: &str) -> Vec<u8> {
+        let mut raw = format!(
+            "{id}{file_id:<17}{:<6}00010001000100 85073 85073 000000{system}",
+            "RT11A"
+        )
+        .into_bytes();
+        raw.resize(80, b' ');
+        raw
+    }
+
+    #[test]
+    fn lists_fsm_files_by_hdr1_name() {
+        let mut blocks = vec![
+            block(0, 0, label("HDR1", "HELLO.MAC", "DECRT11A")),
+            block(1, 1, vec![b'A'; 512]),
+            block(2, 1, vec![b'B'; 512]),
+            block(3, 2, label("EOF1", "HELLO.MAC", "DECRT11A")),
+        ];
+        assert_eq!(classify_rt11_tape(&mut blocks), 4);
+        assert!(matches!(
+            blocks[1].classification,
+            BlockClassification::Rt11(Rt11BlockKind::MagtapeData)
+        ));
+        let files = reconstruct_rt11_tape(&blocks);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path.to_string_path(), "HELLO.MAC");
+        assert_eq!(files[0].blocks, vec![1, 2]);
+        match &files[0].metadata {
+            FileMetadata::Rt11(meta) => {
+                assert_eq!(meta.ext, "MAC");
+                assert_eq!(meta.length_blocks, 2);
+                assert_eq!(meta.creation_date.as_deref(), Some("1985-03-14"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn checks_rt11_tape_file_structure() {
+        let mut blocks = vec![
+            block(0, 0, label("HDR1", "HELLO.MAC", "DECRT11A")),
+            block(1, 1, vec![b'A'; 512]),
+            block(2, 2, label("EOF1", "HELLO.MAC", "DECRT11A")),
+        ];
+        assert!(is_rt11_tape_file(&blocks[0..1]));
+        // Bare data is RT-11 only once its HDR1 has claimed it.
+        assert!(!is_rt11_tape_file(&blocks[1..2]));
+        classify_rt11_tape(&mut blocks);
+        assert!(is_rt11_tape_file(&blocks[1..2]));
+
+        let image: Vec<_> = sample_volume()
+            .chunks(512)
+            .enumerate()
+            .map(|(i, chunk)| block(i as u32, 0, chunk.to_vec()))
+            .collect();
+        assert!(is_rt11_tape_file(&image));
+        assert!(!is_rt11_tape_file(&[block(
+            0,
+            0,
+            label("HDR1", "SAVE.BCK", "DECVMSBACKUP")
+        )]));
+    }
+
+    #[test]
+    fn expands_bup_volume_image() {
+        let image = sample_volume();
+        // Write the image in 2048-byte records so files straddle records.
+        let mut blocks = vec![block(0, 0, label("HDR1", "DK.BUP", "DECRT11A"))];
+        for (i, chunk) in image.chunks(2048).enumerate() {
+            blocks.push(block(i as u32 + 1, 1, chunk.to_vec()));
+        }
+        classify_rt11_tape(&mut blocks);
+        assert!(matches!(
+            blocks[1].classification,
+            BlockClassification::Rt11(Rt11BlockKind::BupData)
+        ));
+        let files = reconstruct_rt11_tape(&blocks);
+        let children = &files[0].children;
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].path.to_string_path(), "DK.BUP/SWAP.SYS");
+        assert_eq!(file_bytes(&children[0], &blocks), vec![b'S'; 1024]);
+        assert_eq!(children[1].path.to_string_path(), "DK.BUP/HELLO.SAV");
+        assert_eq!(file_bytes(&children[1], &blocks), vec![b'H'; 512]);
+    }
+
+    #[test]
+    fn ignores_other_ansi_volumes() {
+        let mut blocks = vec![
+            block(0, 0, label("HDR1", "SAVE.BCK", "DECVMSBACKUP")),
+            block(1, 1, vec![0; 512]),
+        ];
+        assert_eq!(classify_rt11_tape(&mut blocks), 0);
+        assert!(reconstruct_rt11_tape(&blocks).is_empty());
+    }
+}
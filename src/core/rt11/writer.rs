@@ -0,0 +1,405 @@
+//! RT-11 volume writer: lays host files out as a freshly initialised
+//! RT-11 disk image that `directory::parse_rt11_volume` (and RT-11 itself)
+//! can read.
+//!
+//! Block 0 is left as a non-bootable boot block and block 1 is the home
+//! block. Directory segments start at block 6; file data follows the last
+//! segment in directory order, and the rest of the volume is one empty area.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::core::parse::encode_rad50;
+
+use super::directory::{
+    DEFAULT_FIRST_SEGMENT, E_EOS, E_MPTY, E_PERM, MAX_SEGMENTS, RT11_BLOCK_SIZE, SEGMENT_SIZE,
+};
+use super::encode_rt11_date;
+
+const SEGMENT_HEADER: usize = 10;
+const ENTRY_SIZE: usize = 14;
+/// Entries per segment, leaving room for the end-of-segment word.
+const SEGMENT_ENTRIES: usize = (SEGMENT_SIZE - SEGMENT_HEADER - 2) / ENTRY_SIZE;
+
+/// Disk geometries RT-11 users typically attach to an emulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rt11Geometry {
+    /// Single-density 8" floppy.
+    Rx01,
+    /// Double-density 8" floppy.
+    Rx02,
+    /// 10 MB cartridge disk.
+    Rl02,
+}
+
+impl Rt11Geometry {
+    pub fn total_blocks(self) -> u32 {
+        match self {
+            Rt11Geometry::Rx01 => 494,
+            Rt11Geometry::Rx02 => 988,
+            Rt11Geometry::Rl02 => 20480,
+        }
+    }
+
+    /// Directory segments `INIT` allocates by default.
+    pub fn default_segments(self) -> u16 {
+        match self {
+            Rt11Geometry::Rx01 => 1,
+            Rt11Geometry::Rx02 => 4,
+            Rt11Geometry::Rl02 => MAX_SEGMENTS,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Rt11Geometry::Rx01 => "RX01",
+            Rt11Geometry::Rx02 => "RX02",
+            Rt11Geometry::Rl02 => "RL02",
+        }
+    }
+}
+
+/// A host file to be written to a PDP-11 volume.
+#[derive(Debug, Clone)]
+pub struct HostFile {
+    /// Host file name; mapped to a RAD50 `NAME.EXT` with `pdp11_file_name`.
+    pub name: String,
+    pub data: Vec<u8>,
+    pub date: NaiveDate,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rt11VolumeOptions {
+    pub geometry: Rt11Geometry,
+    /// Directory segments; `None` uses the geometry default.
+    pub segments: Option<u16>,
+    pub volume_id: String,
+    pub owner: String,
+    /// Convert LF line ends in text files to CR LF.
+    pub convert_text: bool,
+}
+
+impl Default for Rt11VolumeOptions {
+    fn default() -> Self {
+        Self {
+            geometry: Rt11Geometry::Rx02,
+            segments: None,
+            volume_id: "RT11A".into(),
+            owner: String::new(),
+            convert_text: true,
+        }
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Map a host file name to a RAD50 name (up to `name_len` characters) and
+/// a three-character type. Characters outside `A-Z0-9$` are dropped.
+pub fn pdp11_file_name(host: &str, name_len: usize) -> Option<(String, String)> {
+    let (name, ext) = match host.rsplit_once('.') {
+        Some((n, e)) if !n.is_empty() => (n, e),
+        _ => (host, ""),
+    };
+    let clean = |s: &str, max: usize| -> String {
+        s.chars()
+            .map(|c| c.to_ascii_uppercase())
+            .filter(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || *c == '$')
+            .take(max)
+            .collect()
+    };
+    let name = clean(name, name_len);
+    (!name.is_empty()).then(|| (name, clean(ext, 3)))
+}
+
+fn looks_like_text(data: &[u8]) -> bool {
+    !data.is_empty()
+        && data
+            .iter()
+            .all(|&b| matches!(b, b'\t' | b'\n' | b'\r' | 0x0C) || (0x20..0x7F).contains(&b))
+}
+
+/// Convert bare LF line ends in a text file to CR LF; binary data and
+/// files that already use CR LF are returned unchanged.
+pub fn host_text_to_crlf(data: &[u8]) -> Vec<u8> {
+    if !looks_like_text(data) {
+        return data.to_vec();
+    }
+    let mut out = Vec::with_capacity(data.len() + data.len() / 32);
+    let mut prev = 0u8;
+    for &b in data {
+        if b == b'\n' && prev != b'\r' {
+            out.push(b'\r');
+        }
+        out.push(b);
+        prev = b;
+    }
+    out
+}
+
+/// Read the regular files directly inside `dir`, sorted by name.
+pub fn read_host_files(dir: &Path) -> io::Result<Vec<HostFile>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.retain(|p| p.is_file());
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let date = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).date_naive())
+                .unwrap_or_else(|_| chrono::Utc::now().date_naive());
+            Ok(HostFile {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                data: fs::read(&path)?,
+                date,
+            })
+        })
+        .collect()
+}
+
+struct Entry {
+    status: u16,
+    name: [u16; 3],
+    length: u16,
+    date: u16,
+}
+
+/// Build an RT-11 volume image holding `files` in the given order.
+pub fn build_rt11_volume(files: &[HostFile], opts: &Rt11VolumeOptions) -> io::Result<Vec<u8>> {
+    let total_blocks = opts.geometry.total_blocks();
+    let segments = opts.segments.unwrap_or(opts.geometry.default_segments());
+    if !(1..=MAX_SEGMENTS).contains(&segments) {
+        return Err(invalid(format!(
+            "{segments} directory segments (1-31 allowed)"
+        )));
+    }
+    let first_data = u32::from(DEFAULT_FIRST_SEGMENT) + 2 * u32::from(segments);
+
+    let mut entries = Vec::with_capacity(files.len() + 1);
+    let mut data = Vec::with_capacity(files.len());
+    let mut next_block = first_data;
+    for file in files {
+        let (name, ext) = pdp11_file_name(&file.name, 6)
+            .ok_or_else(|| invalid(format!("{}: no RAD50 characters in name", file.name)))?;
+        let words = encode_rad50(&name, 2)
+            .zip(encode_rad50(&ext, 1))
+            .map(|(n, e)| [n[0], n[1], e[0]])
+            .ok_or_else(|| invalid(format!("{}: name is not RAD50", file.name)))?;
+        if entries.iter().any(|e: &Entry| e.name == words) {
+            return Err(invalid(format!(
+                "{}: duplicate RT-11 name {name}.{ext}",
+                file.name
+            )));
+        }
+        let bytes = if opts.convert_text {
+            host_text_to_crlf(&file.data)
+        } else {
+            file.data.clone()
+        };
+        let length = bytes.len().div_ceil(RT11_BLOCK_SIZE) as u32;
+        if next_block + length > total_blocks {
+            return Err(invalid(format!(
+                "{}: {} volume is full",
+                file.name,
+                opts.geometry.label()
+            )));
+        }
+        entries.push(Entry {
+            status: E_PERM,
+            name: words,
+            length: length as u16,
+            date: encode_rt11_date(file.date).unwrap_or(0),
+        });
+        data.push((next_block, bytes));
+        next_block += length;
+    }
+    entries.push(Entry {
+        status: E_MPTY,
+        name: [0; 3],
+        length: (total_blocks - next_block) as u16,
+        date: 0,
+    });
+    if entries.len() > SEGMENT_ENTRIES * usize::from(segments) {
+        return Err(invalid(format!(
+            "{} files do not fit in {segments} directory segments",
+            files.len()
+        )));
+    }
+
+    let mut image = vec![0u8; total_blocks as usize * RT11_BLOCK_SIZE];
+    write_home_block(&mut image[RT11_BLOCK_SIZE..2 * RT11_BLOCK_SIZE], opts);
+
+    let chunks: Vec<&[Entry]> = entries.chunks(SEGMENT_ENTRIES).collect();
+    let used = chunks.len() as u16;
+    let mut block = first_data;
+    for (i, chunk) in chunks.iter().enumerate() {
+        let off = usize::from(DEFAULT_FIRST_SEGMENT) * RT11_BLOCK_SIZE + i * SEGMENT_SIZE;
+        let seg = &mut image[off..off + SEGMENT_SIZE];
+        let next = if i + 1 < chunks.len() {
+            i as u16 + 2
+        } else {
+            0
+        };
+        put(seg, 0, &[segments, next, used, 0, block as u16]);
+        let mut pos = SEGMENT_HEADER;
+        for e in chunk.iter() {
+            put(
+                seg,
+                pos,
+                &[
+                    e.status, e.name[0], e.name[1], e.name[2], e.length, 0, e.date,
+                ],
+            );
+            block += u32::from(e.length);
+            pos += ENTRY_SIZE;
+        }
+        put(seg, pos, &[E_EOS]);
+    }
+
+    for (start, bytes) in data {
+        let off = start as usize * RT11_BLOCK_SIZE;
+        image[off..off + bytes.len()].copy_from_slice(&bytes);
+    }
+    Ok(image)
+}
+
+/// Build an RT-11 volume from a host directory and write it to `out`.
+pub fn write_rt11_volume(dir: &Path, out: &Path, opts: &Rt11VolumeOptions) -> io::Result<()> {
+    let files = read_host_files(dir)?;
+    fs::write(out, build_rt11_volume(&files, opts)?)
+}
+
+fn write_home_block(home: &mut [u8], opts: &Rt11VolumeOptions) {
+    // Pack cluster size, first directory segment and system version ("V3A").
+    put(home, 0o722, &[1, DEFAULT_FIRST_SEGMENT]);
+    put(home, 0o726, &encode_rad50("V3A", 1).unwrap_or_default());
+    ascii_field(&mut home[0o730..0o744], &opts.volume_id);
+    ascii_field(&mut home[0o744..0o760], &opts.owner);
+    ascii_field(&mut home[0o760..0o774], "DECRT11A");
+    let sum = home[..0o776].chunks(2).fold(0u16, |s, w| {
+        s.wrapping_add(u16::from_le_bytes([w[0], w[1]]))
+    });
+    put(home, 0o776, &[sum]);
+}
+
+fn ascii_field(field: &mut [u8], text: &str) {
+    field.fill(b' ');
+    for (dst, c) in field
+        .iter_mut()
+        .zip(text.bytes().filter(u8::is_ascii_graphic))
+    {
+        *dst = c;
+    }
+}
+
+fn put(data: &mut [u8], off: usize, words: &[u16]) {
+    for (i, w) in words.iter().enumerate() {
+        data[off + i * 2..off + i * 2 + 2].copy_from_slice(&w.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{
+        build_rt11_volume, host_text_to_crlf, pdp11_file_name, HostFile, Rt11Geometry,
+        Rt11VolumeOptions, SEGMENT_ENTRIES,
+    };
+    use crate::core::rt11::decode_rt11_date;
+    use crate::core::rt11::directory::{parse_rt11_volume, E_MPTY, RT11_BLOCK_SIZE};
+
+    fn host(name: &str, data: &[u8]) -> HostFile {
+        HostFile {
+            name: name.into(),
+            data: data.to_vec(),
+            date: NaiveDate::from_ymd_opt(1985, 6, 1).unwrap(),
+        }
+    }
+
+    #[test]
+    fn maps_host_names() {
+        assert_eq!(
+            pdp11_file_name("hello_world.mac", 6),
+            Some(("HELLOW".into(), "MAC".into()))
+        );
+        assert_eq!(
+            pdp11_file_name("Makefile", 6),
+            Some(("MAKEFI".into(), "".into()))
+        );
+        assert_eq!(
+            pdp11_file_name(".profile", 9),
+            Some(("PROFILE".into(), "".into()))
+        );
+        assert_eq!(pdp11_file_name("___.c", 6), None);
+        assert_eq!(host_text_to_crlf(b"A\nB\r\n"), b"A\r\nB\r\n");
+        assert_eq!(host_text_to_crlf(&[0, 10]), vec![0, 10]);
+    }
+
+    #[test]
+    fn builds_readable_volume() {
+        let big = vec![0x55u8; 3 * RT11_BLOCK_SIZE + 1];
+        let files = [host("hello.mac", b"START: HALT\n"), host("data.bin", &big)];
+        let opts = Rt11VolumeOptions {
+            geometry: Rt11Geometry::Rx01,
+            ..Default::default()
+        };
+        let image = build_rt11_volume(&files, &opts).unwrap();
+        assert_eq!(image.len(), 494 * RT11_BLOCK_SIZE);
+
+        let volume = parse_rt11_volume(&image).expect("volume");
+        assert_eq!(volume.home.volume_id, "RT11A");
+        let names: Vec<_> = volume.files().map(|e| e.file_name()).collect();
+        assert_eq!(names, vec!["HELLO.MAC", "DATA.BIN"]);
+        let hello = &volume.entries[0];
+        assert_eq!(hello.start_block, 8);
+        assert_eq!(decode_rt11_date(hello.date).as_deref(), Some("1985-06-01"));
+        let off = hello.start_block as usize * RT11_BLOCK_SIZE;
+        assert_eq!(&image[off..off + 13], b"START: HALT\r\n");
+        assert_eq!(volume.entries[1].length_blocks, 4);
+        let free = volume.entries.last().unwrap();
+        assert_eq!(free.status, E_MPTY);
+        assert_eq!(free.start_block + u32::from(free.length_blocks), 494);
+    }
+
+    #[test]
+    fn spreads_entries_over_segments() {
+        let files: Vec<_> = (0..SEGMENT_ENTRIES + 5)
+            .map(|i| host(&format!("F{i}.TXT"), b"x"))
+            .collect();
+        let opts = Rt11VolumeOptions {
+            geometry: Rt11Geometry::Rl02,
+            ..Default::default()
+        };
+        let volume = parse_rt11_volume(&build_rt11_volume(&files, &opts).unwrap()).unwrap();
+        assert_eq!(volume.files().count(), files.len());
+        let last = volume.files().last().unwrap();
+        assert_eq!(last.file_name(), format!("F{}.TXT", files.len() - 1));
+        assert_eq!(last.start_block, 6 + 2 * 31 + files.len() as u32 - 1);
+    }
+
+    #[test]
+    fn rejects_duplicates_and_overflow() {
+        let opts = Rt11VolumeOptions {
+            geometry: Rt11Geometry::Rx01,
+            ..Default::default()
+        };
+        let dup = [host("a.txt", b"1"), host("A.TXT", b"2")];
+        assert!(build_rt11_volume(&dup, &opts).is_err());
+        let huge = [host("big.dat", &vec![1u8; 600 * RT11_BLOCK_SIZE])];
+        assert!(build_rt11_volume(&huge, &opts).is_err());
+        let many: Vec<_> = (0..SEGMENT_ENTRIES)
+            .map(|i| host(&format!("F{i}"), b""))
+            .collect();
+        assert!(build_rt11_volume(&many, &opts).is_err());
+    }
+}
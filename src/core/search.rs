@@ -0,0 +1,569 @@
+//! File search over reconstructed trees: name globs or regular expressions,
+//! owner, date, record format and size filters, and an optional grep through
+//! file contents. Shared by the Files tab and `rts find`.
+use chrono::NaiveDate;
+use regex::{bytes, Regex, RegexBuilder};
+
+use crate::backup::vms::RecordFormat;
+use crate::core::block::TapeBlock;
+use crate::core::extract::host_bytes;
+use crate::core::file::{FileMetadata, TapeFile};
+use crate::utils::glob::glob_match;
+
+/// Content matches kept per file.
+pub const MAX_MATCHES: usize = 100;
+
+/// RMS record formats accepted by `record_format_code`, by label.
+pub const RECORD_FORMATS: [RecordFormat; 7] = [
+    RecordFormat::Udf,
+    RecordFormat::Fix,
+    RecordFormat::Var,
+    RecordFormat::Vfc,
+    RecordFormat::Stm,
+    RecordFormat::StmLf,
+    RecordFormat::StmCr,
+];
+
+/// RMS record format code of a name such as `VAR` or `stmlf`. Searches
+/// compare these codes with the `FAT_*` codes the files carry.
+pub fn record_format_code(name: &str) -> Option<u8> {
+    RECORD_FORMATS
+        .iter()
+        .find(|f| f.label().eq_ignore_ascii_case(name))
+        .map(RecordFormat::code)
+}
+
+/// Date a file was created or last written, as the format records it.
+pub fn file_date(meta: &FileMetadata) -> Option<&str> {
+    match meta {
+        FileMetadata::Vms(m) => m.creation_time.as_deref(),
+        FileMetadata::Rt11(m) => m.creation_date.as_deref(),
+        FileMetadata::Rsts(m) => m.creation_date.as_deref(),
+        FileMetadata::Unix(m) => m.mtime.as_deref(),
+        FileMetadata::Pdp10(m) => m.write_date.as_deref(),
+        FileMetadata::Rsx(_) | FileMetadata::Raw => None,
+    }
+}
+
+/// Calendar date at the start of a `YYYY-MM-DD[ ...]` timestamp.
+fn leading_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// What a query reads from a file.
+pub trait Searchable {
+    /// Path the name pattern is matched against.
+    fn search_path(&self) -> String;
+    fn search_size(&self) -> u64;
+    /// Owner UIC, or PPN on RSTS/E, when the system records one.
+    fn search_owner(&self) -> Option<(u16, u16)>;
+    /// Creation or last-write date.
+    fn search_date(&self) -> Option<NaiveDate>;
+    /// RMS record format code of VMS files.
+    fn search_record_format(&self) -> Option<u8>;
+    /// Directories are never returned.
+    fn is_directory(&self) -> bool {
+        false
+    }
+}
+
+impl Searchable for TapeFile {
+    fn search_path(&self) -> String {
+        self.path.to_string_path()
+    }
+
+    fn search_size(&self) -> u64 {
+        self.size_bytes
+    }
+
+    fn search_owner(&self) -> Option<(u16, u16)> {
+        match &self.metadata {
+            FileMetadata::Vms(m) => Some(m.owner_uic),
+            FileMetadata::Rsx(m) => Some(m.uic),
+            FileMetadata::Rsts(m) => Some(m.owner_uic),
+            _ => None,
+        }
+    }
+
+    fn search_date(&self) -> Option<NaiveDate> {
+        file_date(&self.metadata).and_then(leading_date)
+    }
+
+    fn search_record_format(&self) -> Option<u8> {
+        match &self.metadata {
+            FileMetadata::Vms(m) => Some(m.record_format),
+            _ => None,
+        }
+    }
+
+    fn is_directory(&self) -> bool {
+        !self.children.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NamePattern {
+    /// Shell wildcards as in `rts extract --include`.
+    Glob(String),
+    /// Regular expression found anywhere in the path, ignoring case.
+    Regex(Regex),
+}
+
+impl NamePattern {
+    pub fn regex(pattern: &str) -> Result<Self, String> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(NamePattern::Regex)
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        match self {
+            NamePattern::Glob(glob) => glob_match(glob, path),
+            NamePattern::Regex(re) => re.is_match(path),
+        }
+    }
+}
+
+/// Owner filter written `[group,member]` in octal; `*` matches any value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UicFilter {
+    pub group: Option<u16>,
+    pub member: Option<u16>,
+}
+
+impl UicFilter {
+    /// Parse `[g,m]` or `g,m`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let inner = text
+            .strip_prefix('[')
+            .and_then(|t| t.strip_suffix(']'))
+            .unwrap_or(text);
+        let (group, member) = inner.split_once(',')?;
+        let part = |p: &str| -> Option<Option<u16>> {
+            match p.trim() {
+                "*" => Some(None),
+                p => u16::from_str_radix(p, 8).ok().map(Some),
+            }
+        };
+        Some(UicFilter {
+            group: part(group)?,
+            member: part(member)?,
+        })
+    }
+
+    pub fn matches(&self, (group, member): (u16, u16)) -> bool {
+        self.group.is_none_or(|g| g == group) && self.member.is_none_or(|m| m == member)
+    }
+}
+
+/// Pattern searched for in file contents.
+#[derive(Debug, Clone)]
+pub struct ContentPattern(bytes::Regex);
+
+impl ContentPattern {
+    /// Literal text, ignoring ASCII case.
+    pub fn text(text: &str) -> Result<Self, String> {
+        Self::build(&regex::escape(text))
+    }
+
+    /// Regular expression over bytes; `.` matches any byte and case is
+    /// ignored.
+    pub fn regex(pattern: &str) -> Result<Self, String> {
+        Self::build(pattern)
+    }
+
+    fn build(pattern: &str) -> Result<Self, String> {
+        bytes::RegexBuilder::new(pattern)
+            .unicode(false)
+            .case_insensitive(true)
+            .build()
+            .map(ContentPattern)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Matches in `data`, at most `MAX_MATCHES`.
+    pub fn find_all(&self, data: &[u8]) -> Vec<ContentMatch> {
+        self.0
+            .find_iter(data)
+            .filter(|m| !m.is_empty())
+            .take(MAX_MATCHES)
+            .map(|m| ContentMatch {
+                offset: m.start(),
+                len: m.len(),
+            })
+            .collect()
+    }
+}
+
+/// Filters a file must pass; unset fields accept every file. Files lacking
+/// the metadata a set filter needs do not match it.
+#[derive(Debug, Clone, Default)]
+pub struct FileQuery {
+    pub name: Option<NamePattern>,
+    pub owner: Option<UicFilter>,
+    /// First date accepted, inclusive.
+    pub since: Option<NaiveDate>,
+    /// Last date accepted, inclusive.
+    pub until: Option<NaiveDate>,
+    pub record_format: Option<u8>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub content: Option<ContentPattern>,
+}
+
+impl FileQuery {
+    /// Whether `item` passes every filter except the content pattern.
+    pub fn matches_metadata<T: Searchable + ?Sized>(&self, item: &T) -> bool {
+        if item.is_directory() {
+            return false;
+        }
+        if let Some(name) = &self.name {
+            if !name.matches(&item.search_path()) {
+                return false;
+            }
+        }
+        if let Some(owner) = &self.owner {
+            if !item.search_owner().is_some_and(|o| owner.matches(o)) {
+                return false;
+            }
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let Some(date) = item.search_date() else {
+                return false;
+            };
+            if self.since.is_some_and(|d| date < d) || self.until.is_some_and(|d| date > d) {
+                return false;
+            }
+        }
+        if let Some(format) = self.record_format {
+            if item.search_record_format() != Some(format) {
+                return false;
+            }
+        }
+        let size = item.search_size();
+        !(self.min_size.is_some_and(|min| size < min)
+            || self.max_size.is_some_and(|max| size > max))
+    }
+}
+
+/// Byte range of a content match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentMatch {
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// A file that passed a query.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    /// Position of the file in the searched sequence.
+    pub index: usize,
+    pub path: String,
+    /// Content matches; empty when the query has no content pattern.
+    pub matches: Vec<ContentMatch>,
+}
+
+/// Run `query` over `items`. `contents` supplies a file's bytes and is only
+/// called for files that pass the other filters when a content pattern is
+/// set.
+pub fn search<'a, T, I, F>(items: I, query: &FileQuery, mut contents: F) -> Vec<SearchHit>
+where
+    T: Searchable + 'a,
+    I: IntoIterator<Item = &'a T>,
+    F: FnMut(&T) -> Vec<u8>,
+{
+    let mut hits = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        if !query.matches_metadata(item) {
+            continue;
+        }
+        let matches = match &query.content {
+            Some(pattern) => {
+                let found = pattern.find_all(&contents(item));
+                if found.is_empty() {
+                    continue;
+                }
+                found
+            }
+            None => Vec::new(),
+        };
+        hits.push(SearchHit {
+            index,
+            path: item.search_path(),
+            matches,
+        });
+    }
+    hits
+}
+
+/// `search` over reconstructed files, reading contents from `blocks` as
+/// they would be extracted.
+pub fn search_tape_files<'a>(
+    files: impl IntoIterator<Item = &'a TapeFile>,
+    blocks: &[TapeBlock],
+    query: &FileQuery,
+) -> Vec<SearchHit> {
+    search(files, query, |file| host_bytes(file, blocks))
+}
+
+/// Up to `width` bytes either side of a match, with the match in brackets
+/// and anything unprintable shown as `.`.
+pub fn match_context(data: &[u8], found: &ContentMatch, width: usize) -> String {
+    let end = (found.offset + found.len).min(data.len());
+    let start = found.offset.min(end);
+    let show = |bytes: &[u8]| -> String {
+        bytes
+            .iter()
+            .map(|&b| match b {
+                0x20..=0x7E => b as char,
+                _ => '.',
+            })
+            .collect()
+    };
+    format!(
+        "{}[{}]{}",
+        show(&data[start.saturating_sub(width)..start]),
+        show(&data[start..end]),
+        show(&data[end..(end + width).min(data.len())])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{
+        match_context, record_format_code, search, search_tape_files, ContentPattern, FileQuery,
+        NamePattern, Searchable, UicFilter,
+    };
+    use crate::backup::vms::{RecordFormat, FAT_FIX, FAT_VAR, FAT_VFC};
+    use crate::backup::writer::{build_saveset, saveset_tape_blocks, SavesetFile, SavesetOptions};
+    use crate::core::block::{BlockClassification, TapeBlock, TapeFormat};
+    use crate::core::file::{FileMetadata, Pdp10FileMetadata, Pdp10System, TapeFile, TapePath};
+    use crate::core::handler::registry;
+    use crate::core::pdp10::pack_word;
+    use crate::core::pdp10::tests::ascii_words;
+
+    struct Item {
+        path: &'static str,
+        size: u64,
+        owner: Option<(u16, u16)>,
+        date: Option<NaiveDate>,
+        data: &'static [u8],
+    }
+
+    impl Searchable for Item {
+        fn search_path(&self) -> String {
+            self.path.into()
+        }
+        fn search_size(&self) -> u64 {
+            self.size
+        }
+        fn search_owner(&self) -> Option<(u16, u16)> {
+            self.owner
+        }
+        fn search_date(&self) -> Option<NaiveDate> {
+            self.date
+        }
+        fn search_record_format(&self) -> Option<u8> {
+            None
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        let date = |d| NaiveDate::from_ymd_opt(1988, 3, d);
+        vec![
+            Item {
+                path: "SYS/README.TXT",
+                size: 120,
+                owner: Some((0o1, 0o4)),
+                date: date(1),
+                data: b"Release notes for V5.0\n",
+            },
+            Item {
+                path: "USER/NOTES.TXT",
+                size: 4000,
+                owner: Some((0o200, 0o10)),
+                date: date(20),
+                data: b"see the release NOTES\n",
+            },
+            Item {
+                path: "USER/PROG.EXE",
+                size: 9000,
+                owner: Some((0o200, 0o11)),
+                date: None,
+                data: b"\0\x01\x02",
+            },
+        ]
+    }
+
+    fn paths(query: &FileQuery) -> Vec<String> {
+        let items = items();
+        search(&items, query, |item| item.data.to_vec())
+            .into_iter()
+            .map(|hit| hit.path)
+            .collect()
+    }
+
+    #[test]
+    fn filters_on_names_owners_dates_and_sizes() {
+        let query = FileQuery {
+            name: Some(NamePattern::Glob("*.TXT".into())),
+            ..FileQuery::default()
+        };
+        assert_eq!(paths(&query), ["SYS/README.TXT", "USER/NOTES.TXT"]);
+
+        let query = FileQuery {
+            name: Some(NamePattern::regex("^user/").unwrap()),
+            owner: UicFilter::parse("[200,*]"),
+            min_size: Some(5000),
+            ..FileQuery::default()
+        };
+        assert_eq!(paths(&query), ["USER/PROG.EXE"]);
+
+        let query = FileQuery {
+            since: NaiveDate::from_ymd_opt(1988, 3, 10),
+            ..FileQuery::default()
+        };
+        assert_eq!(paths(&query), ["USER/NOTES.TXT"], "undated files fail");
+
+        assert_eq!(
+            UicFilter::parse("1,4"),
+            Some(UicFilter {
+                group: Some(1),
+                member: Some(4)
+            })
+        );
+        assert!(UicFilter::parse("[8,1]").is_none(), "octal");
+        assert_eq!(record_format_code("stmlf"), Some(5));
+    }
+
+    #[test]
+    fn greps_contents_with_offsets() {
+        let query = FileQuery {
+            content: Some(ContentPattern::text("release notes").unwrap()),
+            ..FileQuery::default()
+        };
+        let items = items();
+        let hits = search(&items, &query, |item| item.data.to_vec());
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[1].index, 1);
+        let found = hits[1].matches[0];
+        assert_eq!((found.offset, found.len), (8, 13));
+        assert_eq!(
+            match_context(items[1].data, &found, 4),
+            "the [release NOTES]."
+        );
+
+        let query = FileQuery {
+            content: Some(ContentPattern::regex(r"V\d\.\d").unwrap()),
+            ..FileQuery::default()
+        };
+        assert_eq!(paths(&query), ["SYS/README.TXT"]);
+        assert!(ContentPattern::regex("(").is_err());
+    }
+
+    #[test]
+    fn greps_pdp10_files_as_host_text() {
+        let text = "HELLO\r\nWORLD\r\n";
+        let raw: Vec<u8> = ascii_words(text)
+            .iter()
+            .flat_map(|w| pack_word(*w))
+            .collect();
+        let blocks = vec![TapeBlock {
+            index: 0,
+            tape_file: 0,
+            size: raw.len(),
+            raw: raw.into(),
+            classification: BlockClassification::Unknown,
+        }];
+        let file = TapeFile {
+            format: TapeFormat::Pdp10,
+            path: TapePath::new(vec!["HELLO.TXT".into()]),
+            size_bytes: 12,
+            blocks: vec![0],
+            extents: Vec::new(),
+            metadata: FileMetadata::Pdp10(Pdp10FileMetadata {
+                system: Pdp10System::Tops20,
+                saveset: "S".into(),
+                byte_size: 7,
+                byte_count: text.len() as u64,
+                protection: 0,
+                write_date: None,
+            }),
+            children: Vec::new(),
+        };
+        let query = FileQuery {
+            content: Some(ContentPattern::text("world").unwrap()),
+            ..FileQuery::default()
+        };
+        let hits = search_tape_files([&file], &blocks, &query);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].matches[0].offset, 6);
+    }
+
+    #[test]
+    fn searches_vms_backup_files() {
+        let opts = SavesetOptions {
+            owner_uic: (0o200, 0o10),
+            ..SavesetOptions::default()
+        };
+        let file = SavesetFile {
+            vms_name: "[USER]LOGIN.COM;1".into(),
+            data: b"$ SET TERMINAL/VT100\n".to_vec(),
+            record_format: FAT_VAR,
+            record_attributes: 2,
+            max_record: 20,
+            modified: opts.date,
+        };
+        let mut blocks = saveset_tape_blocks(&build_saveset(&[file], &opts).unwrap(), &opts);
+        registry().classify(&mut blocks);
+        let tree = registry().reconstruct(&blocks);
+        let query = FileQuery {
+            owner: UicFilter::parse("[200,10]"),
+            record_format: Some(RecordFormat::Var.code()),
+            content: Some(ContentPattern::text("terminal").unwrap()),
+            ..FileQuery::default()
+        };
+        let hits = search_tape_files(&tree[0].children, &blocks, &query);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "USER/LOGIN.COM;1");
+        assert_eq!(hits[0].matches[0].offset, 6);
+    }
+
+    #[test]
+    fn matches_record_formats_by_rms_code() {
+        let opts = SavesetOptions::default();
+        let file = |name: &str, record_format| SavesetFile {
+            vms_name: name.into(),
+            data: vec![b'x'; 10],
+            record_format,
+            record_attributes: 0,
+            max_record: 10,
+            modified: opts.date,
+        };
+        let files = [
+            file("[000000]FIXED.DAT;1", FAT_FIX),
+            file("[000000]PRINT.LIS;1", FAT_VFC),
+        ];
+        let mut blocks = saveset_tape_blocks(&build_saveset(&files, &opts).unwrap(), &opts);
+        registry().classify(&mut blocks);
+        let tree = registry().reconstruct(&blocks);
+        let found = |name| {
+            let query = FileQuery {
+                record_format: record_format_code(name),
+                ..FileQuery::default()
+            };
+            search_tape_files(&tree, &blocks, &query)
+                .into_iter()
+                .map(|hit| hit.path)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found("fix"), ["FIXED.DAT;1"]);
+        assert_eq!(found("VFC"), ["PRINT.LIS;1"]);
+        assert!(found("VAR").is_empty());
+    }
+}
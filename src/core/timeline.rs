@@ -0,0 +1,242 @@
+//! Tape layout timeline: the records of a tape in physical order, grouped
+//! into label groups, savesets, DOS-11 files and unattributed data, with
+//! the tape marks between tape files.
+use std::ops::Range;
+
+use crate::backup::bbh::has_bbh;
+use crate::core::ansi::{parse_ansi_label, AnsiLabel};
+use crate::core::block::{BlockClassification, RstsBlockKind, TapeBlock};
+use crate::core::file::TapeFile;
+use crate::core::rsts::tape::parse_dos11_label;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    /// Consecutive ANSI label records (VOL1, HDR1/2, EOF1/2, ...).
+    Labels,
+    /// Data of a saveset or other file named by its HDR1 label or by the
+    /// VMS BACKUP block header.
+    Saveset,
+    /// DOS-11 file: its 14-byte label record and the data after it.
+    File,
+    /// Records no label or header names.
+    Data,
+}
+
+/// A run of records belonging together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineSpan {
+    pub kind: SpanKind,
+    /// Label identifiers for `Labels`, otherwise the saveset or file name;
+    /// empty when unknown.
+    pub name: String,
+    pub tape_file: u32,
+    /// Record indices covered.
+    pub records: Range<u32>,
+    pub bytes: u64,
+}
+
+impl TimelineSpan {
+    /// Text shown on the span.
+    pub fn title(&self) -> String {
+        match (self.kind, self.name.is_empty()) {
+            (SpanKind::Data, true) => format!("{} records", self.records.len()),
+            (_, true) => format!("{:?}", self.kind),
+            _ => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineItem {
+    TapeMark,
+    Span(TimelineSpan),
+}
+
+/// Lay out `blocks` in tape order. Tape marks come from the tape file
+/// numbers the readers assign, so a run of several marks shows as several
+/// separators. Record indices are `TapeBlock::index`.
+pub fn tape_timeline(blocks: &[TapeBlock]) -> Vec<TimelineItem> {
+    let mut items = Vec::new();
+    let mut tape_file = 0u32;
+    // HDR1 file identifier naming the data after the current label group.
+    let mut labelled = String::new();
+    for block in blocks {
+        for _ in tape_file..block.tape_file {
+            items.push(TimelineItem::TapeMark);
+        }
+        tape_file = tape_file.max(block.tape_file);
+
+        let (kind, name) = if let Some(label) = parse_ansi_label(&block.raw) {
+            match &label {
+                AnsiLabel::Header1(hdr) => labelled = hdr.file_id.clone(),
+                AnsiLabel::EndOfFile1(_) | AnsiLabel::EndOfVolume1(_) => labelled.clear(),
+                _ => {}
+            }
+            (SpanKind::Labels, label_id(&block.raw).to_string())
+        } else if matches!(
+            block.classification,
+            BlockClassification::Rsts(RstsBlockKind::DosLabel)
+        ) {
+            let name = parse_dos11_label(&block.raw)
+                .map(|l| l.file_name())
+                .unwrap_or_default();
+            (SpanKind::File, name)
+        } else if let Some(name) = bbh_saveset_name(&block.raw) {
+            (SpanKind::Saveset, name)
+        } else if !labelled.is_empty() {
+            (SpanKind::Saveset, labelled.clone())
+        } else {
+            (SpanKind::Data, String::new())
+        };
+
+        let end = block.index + 1;
+        if let Some(TimelineItem::Span(span)) = items.last_mut() {
+            if span.records.end == block.index && span.tape_file == block.tape_file {
+                let extends = match (span.kind, kind) {
+                    (SpanKind::Labels, SpanKind::Labels) => {
+                        span.name.push(' ');
+                        span.name.push_str(&name);
+                        true
+                    }
+                    // Data records after a DOS-11 label belong to its file.
+                    (SpanKind::File, SpanKind::Data) => true,
+                    (a, b) => a == b && span.name == name,
+                };
+                if extends {
+                    span.records.end = end;
+                    span.bytes += block.size as u64;
+                    continue;
+                }
+            }
+        }
+        items.push(TimelineItem::Span(TimelineSpan {
+            kind,
+            name,
+            tape_file: block.tape_file,
+            records: block.index..end,
+            bytes: block.size as u64,
+        }));
+    }
+    items
+}
+
+/// Saveset name a VMS BACKUP block header carries, if any.
+fn bbh_saveset_name(raw: &[u8]) -> Option<String> {
+    if !has_bbh(raw) {
+        return None;
+    }
+    let len = (raw[48] as usize).min(31);
+    let name = String::from_utf8_lossy(&raw[49..49 + len])
+        .trim()
+        .to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+fn label_id(raw: &[u8]) -> &str {
+    std::str::from_utf8(&raw[..4]).unwrap_or("?")
+}
+
+/// `files` pruned to the nodes with data in `records`; directories stay
+/// when any of their children does.
+pub fn files_in_records(files: &[TapeFile], records: &Range<u32>) -> Vec<TapeFile> {
+    files
+        .iter()
+        .filter_map(|file| {
+            let children = files_in_records(&file.children, records);
+            let own = file.blocks.iter().any(|b| records.contains(b))
+                || file.extents.iter().any(|e| records.contains(&e.block));
+            (own || !children.is_empty()).then(|| TapeFile {
+                children,
+                ..file.clone()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{files_in_records, tape_timeline, SpanKind, TimelineItem};
+    use crate::core::ansi::{format_label1, format_vol1, FileLabel1};
+    use crate::core::block::{BlockClassification, TapeBlock, TapeFormat};
+    use crate::core::file::{FileMetadata, TapeFile, TapePath};
+
+    fn block(index: u32, tape_file: u32, raw: Vec<u8>) -> TapeBlock {
+        TapeBlock {
+            index,
+            tape_file,
+            size: raw.len(),
+            raw: raw.into(),
+            classification: BlockClassification::Unknown,
+        }
+    }
+
+    fn hdr1(id: &str) -> Vec<u8> {
+        let label = FileLabel1 {
+            file_id: id.into(),
+            file_set_id: "SET".into(),
+            section: 1,
+            sequence: 1,
+            creation_date: None,
+            expiration_date: None,
+            block_count: 0,
+            system_code: String::new(),
+        };
+        let date = chrono::NaiveDate::from_ymd_opt(1988, 1, 1).unwrap();
+        format_label1("HDR1", &label, date)
+    }
+
+    #[test]
+    fn groups_labels_savesets_and_tape_marks() {
+        let blocks = vec![
+            block(0, 0, format_vol1("TAPE01", "OWNER")),
+            block(1, 0, hdr1("SAVE.BCK")),
+            block(2, 1, vec![0; 2048]),
+            block(3, 1, vec![0; 1024]),
+            block(4, 3, vec![0; 512]),
+        ];
+        let items = tape_timeline(&blocks);
+        let kinds: Vec<_> = items
+            .iter()
+            .map(|i| match i {
+                TimelineItem::TapeMark => None,
+                TimelineItem::Span(s) => Some((s.kind, s.title(), s.records.clone(), s.bytes)),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Some((SpanKind::Labels, "VOL1 HDR1".into(), 0..2, 160)),
+                None,
+                Some((SpanKind::Saveset, "SAVE.BCK".into(), 2..4, 3072)),
+                None,
+                None,
+                Some((SpanKind::Saveset, "SAVE.BCK".into(), 4..5, 512)),
+            ]
+        );
+    }
+
+    #[test]
+    fn prunes_files_to_a_record_range() {
+        let file = |name: &str, blocks: Vec<u32>, children: Vec<TapeFile>| TapeFile {
+            format: TapeFormat::Raw,
+            path: TapePath::new(vec![name.into()]),
+            size_bytes: 0,
+            blocks,
+            extents: Vec::new(),
+            metadata: FileMetadata::Raw,
+            children,
+        };
+        let tree = vec![
+            file(
+                "DIR",
+                vec![],
+                vec![file("A", vec![1, 2], vec![]), file("B", vec![5], vec![])],
+            ),
+            file("C", vec![7], vec![]),
+        ];
+        let kept = files_in_records(&tree, &(2..6));
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].children.len(), 2);
+        assert!(files_in_records(&tree, &(3..5)).is_empty());
+    }
+}
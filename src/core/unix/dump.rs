@@ -0,0 +1,722 @@
+//! `dump` tapes of V7 (PDP-11) and 4.xBSD/Ultrix (VAX) inode filesystems.
+//!
+//! A dump is a sequence of header records, each followed by the data blocks
+//! it describes. Headers carry a record type (`TS_*`), the magic number and
+//! a copy of the on-disk inode; `c_addr[i]` is non-zero when the i-th block
+//! of the inode was written to tape (zero marks a hole).
+//!
+//! | variant | record | ints   | inode | magic at | checksum                  |
+//! |---------|--------|--------|-------|----------|---------------------------|
+//! | V7      | 512    | 16-bit | 64 B  | 18       | u16 words sum to 84446    |
+//! | BSD     | 1024   | 32-bit | 128 B | 24       | i32 words sum to 84446    |
+//!
+//! After the TS_TAPE label come the TS_CLRI and TS_BITS inode maps, then
+//! every inode (directories first) as TS_INODE plus TS_ADDR continuation
+//! headers, and finally TS_END. The tree is rebuilt from the directory inodes
+//! the way `restore` does, starting at the root inode.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::core::block::{byte_extents, TapeBlock, TapeFormat};
+use crate::core::file::{
+    BlockExtent, FileMetadata, TapeFile, TapePath, UnixArchive, UnixFileMetadata, UnixLink,
+};
+
+use super::{format_unix_time, stream_bytes, S_IFDIR, S_IFLNK, S_IFMT};
+
+pub const TS_TAPE: u32 = 1;
+pub const TS_INODE: u32 = 2;
+pub const TS_BITS: u32 = 3;
+pub const TS_ADDR: u32 = 4;
+pub const TS_END: u32 = 5;
+pub const TS_CLRI: u32 = 6;
+
+/// V7 and 4.2BSD ("old filesystem") magic.
+pub const OFS_MAGIC: u32 = 60011;
+/// 4.3BSD and Ultrix ("new filesystem") magic.
+pub const NFS_MAGIC: u32 = 60012;
+const CHECKSUM: u32 = 84446;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpVariant {
+    /// PDP-11 V7: 512-byte records, 16-bit ints, PDP-11 longs.
+    V7,
+    /// VAX 4.xBSD/Ultrix: 1024-byte records, little-endian 32-bit ints.
+    Bsd,
+}
+
+impl DumpVariant {
+    pub fn record_size(self) -> usize {
+        match self {
+            DumpVariant::V7 => 512,
+            DumpVariant::Bsd => 1024,
+        }
+    }
+}
+
+/// The inode fields copied into a dump header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DumpInode {
+    pub mode: u32,
+    pub nlink: u16,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub mtime: i64,
+}
+
+/// Decoded dump header record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpHeader {
+    pub variant: DumpVariant,
+    pub kind: u32,
+    pub date: i64,
+    pub dump_date: i64,
+    pub volume: u32,
+    pub inumber: u32,
+    pub inode: DumpInode,
+    pub count: usize,
+    /// One byte per block described by this header; non-zero = on tape.
+    pub addr: Vec<u8>,
+}
+
+impl DumpHeader {
+    /// Number of data records following this header on tape.
+    pub fn data_records(&self) -> usize {
+        match self.kind {
+            TS_INODE | TS_ADDR => self.addr.iter().filter(|&&a| a != 0).count(),
+            TS_BITS | TS_CLRI => self.count,
+            _ => 0,
+        }
+    }
+}
+
+/// Parse a dump header record of either variant.
+pub fn parse_dump_header(rec: &[u8]) -> Option<DumpHeader> {
+    parse_bsd_header(rec).or_else(|| parse_v7_header(rec))
+}
+
+fn parse_bsd_header(rec: &[u8]) -> Option<DumpHeader> {
+    let size = DumpVariant::Bsd.record_size();
+    if rec.len() < size {
+        return None;
+    }
+    let l = |off: usize| u32::from_le_bytes([rec[off], rec[off + 1], rec[off + 2], rec[off + 3]]);
+    let magic = l(24);
+    if magic != OFS_MAGIC && magic != NFS_MAGIC {
+        return None;
+    }
+    let sum = (0..size / 4).fold(0u32, |acc, i| acc.wrapping_add(l(i * 4)));
+    if sum != CHECKSUM {
+        return None;
+    }
+    let d = 32;
+    let count = (l(160) as usize).min(size - 164);
+    Some(DumpHeader {
+        variant: DumpVariant::Bsd,
+        kind: l(0),
+        date: i64::from(l(4) as i32),
+        dump_date: i64::from(l(8) as i32),
+        volume: l(12),
+        inumber: l(20),
+        inode: DumpInode {
+            mode: u32::from(word(rec, d)),
+            nlink: word(rec, d + 2),
+            uid: u32::from(word(rec, d + 4)),
+            gid: u32::from(word(rec, d + 6)),
+            size: u64::from_le_bytes(rec[d + 8..d + 16].try_into().ok()?),
+            mtime: i64::from(l(d + 24) as i32),
+        },
+        count,
+        addr: rec[164..164 + count].to_vec(),
+    })
+}
+
+fn parse_v7_header(rec: &[u8]) -> Option<DumpHeader> {
+    let size = DumpVariant::V7.record_size();
+    if rec.len() < size || u32::from(word(rec, 18)) != OFS_MAGIC {
+        return None;
+    }
+    let sum = (0..size / 2).fold(0u16, |acc, i| acc.wrapping_add(word(rec, i * 2)));
+    if sum != CHECKSUM as u16 {
+        return None;
+    }
+    let d = 22;
+    let count = usize::from(word(rec, 86)).min(size - 88);
+    Some(DumpHeader {
+        variant: DumpVariant::V7,
+        kind: u32::from(word(rec, 0)),
+        date: i64::from(pdp_long(rec, 2)),
+        dump_date: i64::from(pdp_long(rec, 6)),
+        volume: u32::from(word(rec, 10)),
+        inumber: u32::from(word(rec, 16)),
+        inode: DumpInode {
+            mode: u32::from(word(rec, d)),
+            nlink: word(rec, d + 2),
+            uid: u32::from(word(rec, d + 4)),
+            gid: u32::from(word(rec, d + 6)),
+            size: u64::from(pdp_long(rec, d + 8)),
+            mtime: i64::from(pdp_long(rec, d + 56)),
+        },
+        count,
+        addr: rec[88..88 + count].to_vec(),
+    })
+}
+
+/// Inode number of the root directory.
+pub const ROOT_INO: u32 = 2;
+
+/// A run of an inode's data within the concatenated dump stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpChunk {
+    /// `len` bytes stored at `pos` in the dump stream.
+    Data { pos: usize, len: usize },
+    /// A block never allocated on disk (`c_addr[i] == 0`); reads as zeros.
+    Hole(usize),
+}
+
+/// One inode recovered from a dump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpedInode {
+    pub inumber: u32,
+    pub inode: DumpInode,
+    pub chunks: Vec<DumpChunk>,
+}
+
+impl DumpedInode {
+    pub fn is_dir(&self) -> bool {
+        self.inode.mode & S_IFMT == S_IFDIR
+    }
+}
+
+/// Everything recovered from one dump, across all of its volumes.
+#[derive(Debug, Clone)]
+pub struct DumpSet {
+    pub variant: DumpVariant,
+    pub date: i64,
+    /// Highest volume number seen in a TS_TAPE header.
+    pub volumes: u32,
+    /// TS_BITS map: inodes written to this dump.
+    pub dumped: Vec<u8>,
+    /// TS_CLRI map: inodes that were free when the dump was taken.
+    pub cleared: Vec<u8>,
+    pub inodes: BTreeMap<u32, DumpedInode>,
+    stream: Vec<u8>,
+}
+
+impl DumpSet {
+    /// True when the TS_BITS map lists `ino` (or no map was written).
+    pub fn is_dumped(&self, ino: u32) -> bool {
+        self.dumped.is_empty() || bit_set(&self.dumped, ino)
+    }
+
+    pub fn is_cleared(&self, ino: u32) -> bool {
+        bit_set(&self.cleared, ino)
+    }
+
+    /// Contents of an inode with holes zero-filled, cut to the inode size.
+    pub fn inode_data(&self, ino: u32) -> Vec<u8> {
+        let Some(entry) = self.inodes.get(&ino) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        for chunk in &entry.chunks {
+            match *chunk {
+                DumpChunk::Data { pos, len } => {
+                    let end = (pos + len).min(self.stream.len());
+                    out.extend_from_slice(&self.stream[pos.min(end)..end]);
+                }
+                DumpChunk::Hole(len) => out.resize(out.len() + len, 0),
+            }
+            if out.len() as u64 >= entry.inode.size {
+                break;
+            }
+        }
+        out.truncate(entry.inode.size as usize);
+        out
+    }
+
+    /// Map an inode's chunks onto tape block extents, cut to the inode size.
+    pub fn inode_extents(&self, group: &[TapeBlock], ino: u32) -> Vec<BlockExtent> {
+        let Some(entry) = self.inodes.get(&ino) else {
+            return Vec::new();
+        };
+        let mut extents = Vec::new();
+        for chunk in &entry.chunks {
+            match *chunk {
+                DumpChunk::Data { pos, len } => extents.extend(byte_extents(group, pos, len)),
+                DumpChunk::Hole(len) => extents.push(BlockExtent::hole(len)),
+            }
+        }
+        truncate_extents(extents, entry.inode.size)
+    }
+}
+
+/// Inode maps number inodes from 1: bit 0 of byte 0 is inode 1.
+fn bit_set(map: &[u8], ino: u32) -> bool {
+    let Some(bit) = (ino as usize).checked_sub(1) else {
+        return false;
+    };
+    map.get(bit / 8).is_some_and(|b| b & (1 << (bit % 8)) != 0)
+}
+
+/// Walk the header/data sequence of a dump. `group` may span several tape
+/// files when a multi-volume dump was captured into one image: each volume
+/// starts with a TS_TAPE header, after which TS_ADDR records continue the
+/// inode that was cut off at the end of the previous volume.
+pub fn read_dump(group: &[TapeBlock]) -> Option<DumpSet> {
+    let stream = stream_bytes(group);
+    let first = parse_dump_header(&stream)?;
+    let rec = first.variant.record_size();
+    let mut set = DumpSet {
+        variant: first.variant,
+        date: first.date,
+        volumes: first.volume.max(1),
+        dumped: Vec::new(),
+        cleared: Vec::new(),
+        inodes: BTreeMap::new(),
+        stream: Vec::new(),
+    };
+    let mut current: Option<u32> = None;
+    let mut pos = 0usize;
+    while pos + rec <= stream.len() {
+        let Some(header) = parse_dump_header(&stream[pos..pos + rec]) else {
+            // Not a header: resynchronise on the next record.
+            pos += rec;
+            continue;
+        };
+        pos += rec;
+        let records = header.data_records();
+        let data_end = (pos + records * rec).min(stream.len());
+        match header.kind {
+            TS_END => break,
+            TS_TAPE => {
+                set.volumes = set.volumes.max(header.volume);
+                if header.volume <= 1 {
+                    current = None;
+                }
+            }
+            TS_BITS => {
+                current = None;
+                set.dumped.extend_from_slice(&stream[pos..data_end]);
+            }
+            TS_CLRI => {
+                current = None;
+                set.cleared.extend_from_slice(&stream[pos..data_end]);
+            }
+            TS_INODE | TS_ADDR => {
+                if header.kind == TS_INODE {
+                    current = Some(header.inumber);
+                    set.inodes.insert(
+                        header.inumber,
+                        DumpedInode {
+                            inumber: header.inumber,
+                            inode: header.inode.clone(),
+                            chunks: Vec::new(),
+                        },
+                    );
+                }
+                if let Some(entry) = current.and_then(|ino| set.inodes.get_mut(&ino)) {
+                    let mut at = pos;
+                    for &present in &header.addr {
+                        if present != 0 {
+                            entry.chunks.push(DumpChunk::Data { pos: at, len: rec });
+                            at += rec;
+                        } else {
+                            entry.chunks.push(DumpChunk::Hole(rec));
+                        }
+                    }
+                }
+            }
+            _ => current = None,
+        }
+        pos += records * rec;
+    }
+    set.stream = stream;
+    Some(set)
+}
+
+/// Decode the entries of a directory inode as `(inode, name)` pairs, skipping
+/// `.`, `..` and empty slots. BSD dumps use 4.2BSD variable-length entries;
+/// V7 (and 4.1BSD) directories are 16-byte `u16 ino, name[14]` slots.
+pub fn parse_dump_directory(variant: DumpVariant, data: &[u8]) -> Vec<(u32, String)> {
+    let entries = match variant {
+        DumpVariant::Bsd if is_bsd_directory(data) => bsd_directory(data),
+        _ => v7_directory(data),
+    };
+    entries
+        .into_iter()
+        .filter(|(ino, name)| *ino != 0 && name != "." && name != ".." && !name.is_empty())
+        .collect()
+}
+
+fn is_bsd_directory(data: &[u8]) -> bool {
+    data.len() >= 12 && word(data, 4) == 12 && word(data, 6) == 1 && data[8] == b'.'
+}
+
+fn bsd_directory(data: &[u8]) -> Vec<(u32, String)> {
+    const DIRBLKSIZ: usize = 512;
+    let mut out = Vec::new();
+    for chunk in data.chunks(DIRBLKSIZ) {
+        let mut pos = 0usize;
+        while pos + 8 <= chunk.len() {
+            let ino = u32::from_le_bytes(chunk[pos..pos + 4].try_into().unwrap_or_default());
+            let reclen = usize::from(word(chunk, pos + 4));
+            let namlen = usize::from(word(chunk, pos + 6));
+            if reclen < 8 || pos + reclen > chunk.len() || 8 + namlen > reclen {
+                break;
+            }
+            let name = &chunk[pos + 8..pos + 8 + namlen];
+            out.push((ino, String::from_utf8_lossy(name).to_string()));
+            pos += reclen;
+        }
+    }
+    out
+}
+
+fn v7_directory(data: &[u8]) -> Vec<(u32, String)> {
+    data.chunks_exact(16)
+        .map(|slot| {
+            let end = slot[2..].iter().position(|&b| b == 0).map_or(16, |p| p + 2);
+            (
+                u32::from(word(slot, 0)),
+                String::from_utf8_lossy(&slot[2..end]).to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Decode one dump into a directory tree rooted at inode 2. Files reached by
+/// more than one name become hard links to the first path seen; inodes not
+/// reachable from the root are listed at the top level as `#<inumber>`.
+pub fn decode_dump(group: &[TapeBlock]) -> Vec<TapeFile> {
+    let Some(set) = read_dump(group) else {
+        return Vec::new();
+    };
+    let mut walk = DumpWalk {
+        set: &set,
+        group,
+        first_path: HashMap::new(),
+        visited: HashSet::new(),
+    };
+    let mut tree = match set.inodes.get(&ROOT_INO) {
+        Some(root) if root.is_dir() => walk.directory(ROOT_INO, &[]),
+        _ => Vec::new(),
+    };
+    let orphans: Vec<u32> = set
+        .inodes
+        .keys()
+        .copied()
+        .filter(|ino| !walk.visited.contains(ino) && !walk.first_path.contains_key(ino))
+        .collect();
+    for ino in orphans {
+        let path = vec![format!("#{ino}")];
+        let mut node = walk.file(ino, path.clone(), None);
+        if set.inodes[&ino].is_dir() {
+            node.children = walk.directory(ino, &path);
+            node.size_bytes = node.children.iter().map(|c| c.size_bytes).sum();
+        }
+        tree.push(node);
+    }
+    tree
+}
+
+struct DumpWalk<'a> {
+    set: &'a DumpSet,
+    group: &'a [TapeBlock],
+    first_path: HashMap<u32, String>,
+    visited: HashSet<u32>,
+}
+
+impl DumpWalk<'_> {
+    fn directory(&mut self, ino: u32, path: &[String]) -> Vec<TapeFile> {
+        if !self.visited.insert(ino) {
+            return Vec::new();
+        }
+        let data = self.set.inode_data(ino);
+        let mut out = Vec::new();
+        for (child, name) in parse_dump_directory(self.set.variant, &data) {
+            let Some(entry) = self.set.inodes.get(&child) else {
+                // Not on this tape (incremental dump) or lost.
+                continue;
+            };
+            if !self.set.is_dumped(child) {
+                continue;
+            }
+            let mut child_path = path.to_vec();
+            child_path.push(name);
+            if entry.is_dir() {
+                if self.visited.contains(&child) {
+                    continue;
+                }
+                let mut node = self.file(child, child_path.clone(), None);
+                node.children = self.directory(child, &child_path);
+                node.size_bytes = node.children.iter().map(|c| c.size_bytes).sum();
+                out.push(node);
+            } else {
+                let joined = child_path.join("/");
+                let link = match self.first_path.get(&child) {
+                    Some(first) => Some(UnixLink::Hard(first.clone())),
+                    None => {
+                        self.first_path.insert(child, joined);
+                        None
+                    }
+                };
+                out.push(self.file(child, child_path, link));
+            }
+        }
+        out
+    }
+
+    fn file(&self, ino: u32, path: Vec<String>, link: Option<UnixLink>) -> TapeFile {
+        let entry = &self.set.inodes[&ino];
+        let is_symlink = entry.inode.mode & S_IFMT == S_IFLNK;
+        let link = if is_symlink {
+            let target = self.set.inode_data(ino);
+            Some(UnixLink::Symbolic(
+                String::from_utf8_lossy(&target).to_string(),
+            ))
+        } else {
+            link
+        };
+        let extents = if entry.is_dir() || is_symlink {
+            Vec::new()
+        } else {
+            self.set.inode_extents(self.group, ino)
+        };
+        let mut blocks: Vec<u32> = extents
+            .iter()
+            .filter(|e| !e.is_hole())
+            .map(|e| e.block)
+            .collect();
+        blocks.dedup();
+        TapeFile {
+            format: TapeFormat::Unix,
+            path: TapePath::new(path),
+            size_bytes: if entry.is_dir() { 0 } else { entry.inode.size },
+            blocks,
+            extents,
+            metadata: FileMetadata::Unix(UnixFileMetadata {
+                archive: UnixArchive::Dump,
+                mode: entry.inode.mode,
+                uid: entry.inode.uid,
+                gid: entry.inode.gid,
+                mtime: format_unix_time(entry.inode.mtime),
+                inode: Some(ino),
+                link,
+            }),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Drop extent bytes beyond `size` (the last dump block is zero padded).
+pub(crate) fn truncate_extents(extents: Vec<BlockExtent>, size: u64) -> Vec<BlockExtent> {
+    let mut left = size as usize;
+    let mut out = Vec::new();
+    for mut ext in extents {
+        if left == 0 {
+            break;
+        }
+        ext.len = ext.len.min(left);
+        left -= ext.len;
+        out.push(ext);
+    }
+    out
+}
+
+fn word(data: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([data[off], data[off + 1]])
+}
+
+/// PDP-11 32-bit value: high word first, each word little-endian.
+fn pdp_long(data: &[u8], off: usize) -> u32 {
+    (u32::from(word(data, off)) << 16) | u32::from(word(data, off + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_dump, parse_dump_header, DumpVariant, NFS_MAGIC, TS_ADDR, TS_BITS, TS_END, TS_INODE,
+        TS_TAPE,
+    };
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::core::extract::file_bytes;
+    use crate::core::file::{FileMetadata, TapeFile, UnixArchive, UnixLink};
+    use crate::core::unix::reconstruct_unix_tape;
+    use crate::core::unix::tar::tests::sample_archive;
+
+    /// Build a BSD header record with a valid checksum.
+    fn bsd_header(kind: u32, ino: u32, mode: u16, size: u64, addr: &[u8]) -> Vec<u8> {
+        volume_header(kind, 1, ino, mode, size, addr)
+    }
+
+    fn volume_header(kind: u32, vol: u32, ino: u32, mode: u16, size: u64, addr: &[u8]) -> Vec<u8> {
+        let mut rec = vec![0u8; 1024];
+        let mut put = |off: usize, v: u32| rec[off..off + 4].copy_from_slice(&v.to_le_bytes());
+        put(0, kind);
+        put(4, 315_532_800);
+        put(12, vol);
+        put(20, ino);
+        put(24, NFS_MAGIC);
+        put(160, addr.len() as u32);
+        rec[32..34].copy_from_slice(&mode.to_le_bytes());
+        rec[34..36].copy_from_slice(&1u16.to_le_bytes());
+        rec[36..38].copy_from_slice(&7u16.to_le_bytes());
+        rec[38..40].copy_from_slice(&8u16.to_le_bytes());
+        rec[40..48].copy_from_slice(&size.to_le_bytes());
+        rec[56..60].copy_from_slice(&315_532_800u32.to_le_bytes());
+        rec[164..164 + addr.len()].copy_from_slice(addr);
+        let sum = (0..256).fold(0u32, |acc, i| {
+            acc.wrapping_add(u32::from_le_bytes(
+                rec[i * 4..i * 4 + 4].try_into().unwrap(),
+            ))
+        });
+        rec[28..32].copy_from_slice(&84446u32.wrapping_sub(sum).to_le_bytes());
+        rec
+    }
+
+    fn records(stream: &[u8], size: usize, first: u32, tape_file: u32) -> Vec<TapeBlock> {
+        stream
+            .chunks(size)
+            .enumerate()
+            .map(|(i, c)| TapeBlock {
+                index: first + i as u32,
+                tape_file,
+                size: c.len(),
+                raw: c.to_vec().into(),
+                classification: BlockClassification::Unknown,
+            })
+            .collect()
+    }
+
+    /// One 1024-byte directory record in 4.2BSD format.
+    fn directory(entries: &[(u32, &str)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (i, (ino, name)) in entries.iter().enumerate() {
+            let mut reclen = (8 + name.len() + 1).div_ceil(4) * 4;
+            if i == entries.len() - 1 {
+                reclen = 512 - out.len();
+            }
+            out.extend_from_slice(&ino.to_le_bytes());
+            out.extend_from_slice(&(reclen as u16).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.resize(out.len() + reclen - 8 - name.len(), 0);
+        }
+        out.resize(1024, 0);
+        out
+    }
+
+    fn meta(file: &TapeFile) -> &crate::core::file::UnixFileMetadata {
+        match &file.metadata {
+            FileMetadata::Unix(meta) => meta,
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    /// Volume 1 ends after the first data block of `hello`; volume 2 carries
+    /// the rest of it in a TS_ADDR record.
+    fn two_volume_dump() -> (Vec<u8>, Vec<u8>) {
+        let mut v1 = bsd_header(TS_TAPE, 0, 0, 0, &[]);
+        let mut bits = vec![0u8; 1024];
+        bits[0] = 0b11110; // inodes 2-5
+        v1.extend(bsd_header(TS_BITS, 0, 0, 0, &[1]));
+        v1.extend(bits);
+        v1.extend(bsd_header(TS_INODE, 2, 0o40755, 512, &[1]));
+        v1.extend(directory(&[
+            (2, "."),
+            (2, ".."),
+            (3, "bin"),
+            (4, "hello"),
+            (4, "hi"),
+        ]));
+        v1.extend(bsd_header(TS_INODE, 3, 0o40755, 512, &[1]));
+        v1.extend(directory(&[(3, "."), (2, ".."), (5, "sh")]));
+        v1.extend(bsd_header(TS_INODE, 4, 0o100644, 3000, &[1, 0]));
+        v1.extend(vec![b'a'; 1024]);
+
+        let mut v2 = volume_header(TS_TAPE, 2, 0, 0, 0, &[]);
+        v2.extend(bsd_header(TS_ADDR, 4, 0o100644, 3000, &[1]));
+        v2.extend(vec![b'c'; 1024]);
+        let mut target = b"../hello".to_vec();
+        target.resize(1024, 0);
+        v2.extend(bsd_header(TS_INODE, 5, 0o120777, 8, &[1]));
+        v2.extend(target);
+        v2.extend(bsd_header(TS_END, 0, 0, 0, &[]));
+        (v1, v2)
+    }
+
+    #[test]
+    fn parses_bsd_header() {
+        let h = parse_dump_header(&bsd_header(TS_TAPE, 0, 0, 0, &[])).expect("header");
+        assert_eq!(h.variant, DumpVariant::Bsd);
+        assert_eq!(h.kind, TS_TAPE);
+        let mut bad = bsd_header(TS_TAPE, 0, 0, 0, &[]);
+        bad[100] ^= 1;
+        assert!(parse_dump_header(&bad).is_none());
+    }
+
+    #[test]
+    fn lists_unreachable_inodes_by_number() {
+        let mut stream = bsd_header(TS_TAPE, 0, 0, 0, &[]);
+        stream.extend(bsd_header(TS_INODE, 5, 0o100644, 1500, &[1, 1]));
+        stream.extend(vec![b'x'; 2048]);
+        stream.extend(bsd_header(TS_END, 0, 0, 0, &[]));
+        let blocks = records(&stream, 2048, 0, 0);
+        let files = decode_dump(&blocks);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path.to_string_path(), "#5");
+        assert_eq!(file_bytes(&files[0], &blocks), vec![b'x'; 1500]);
+        assert_eq!(meta(&files[0]).mode, 0o100644);
+        assert_eq!((meta(&files[0]).uid, meta(&files[0]).gid), (7, 8));
+        assert_eq!(meta(&files[0]).inode, Some(5));
+    }
+
+    #[test]
+    fn rebuilds_tree_across_volumes() {
+        let (v1, v2) = two_volume_dump();
+        let mut blocks = records(&v1, 2048, 0, 0);
+        let next = blocks.len() as u32;
+        blocks.extend(records(&v2, 2048, next, 1));
+
+        let tree = reconstruct_unix_tape(&blocks);
+        let names: Vec<String> = tree.iter().map(|f| f.path.to_string_path()).collect();
+        assert_eq!(names, ["bin", "hello", "hi"]);
+
+        let sh = &tree[0].children[0];
+        assert_eq!(sh.path.to_string_path(), "bin/sh");
+        assert_eq!(meta(sh).link, Some(UnixLink::Symbolic("../hello".into())));
+
+        let hello = &tree[1];
+        let mut expected = vec![b'a'; 1024];
+        expected.extend(vec![0u8; 1024]);
+        expected.extend(vec![b'c'; 952]);
+        assert_eq!(file_bytes(hello, &blocks), expected);
+        assert_eq!(meta(&tree[2]).link, Some(UnixLink::Hard("hello".into())));
+        assert_eq!(file_bytes(&tree[2], &blocks), expected);
+    }
+
+    #[test]
+    fn labels_each_tape_file_with_its_archive() {
+        let (v1, v2) = two_volume_dump();
+        let mut blocks = records(&sample_archive(), 512, 0, 0);
+        let next = blocks.len() as u32;
+        blocks.extend(records(&v1, 2048, next, 1));
+        let next = blocks.len() as u32;
+        blocks.extend(records(&v2, 2048, next, 2));
+
+        let tree = reconstruct_unix_tape(&blocks);
+        let wrappers: Vec<(String, UnixArchive)> = tree
+            .iter()
+            .map(|f| (f.path.to_string_path(), meta(f).archive))
+            .collect();
+        assert_eq!(
+            wrappers,
+            [
+                ("file00".to_string(), UnixArchive::Tar),
+                ("file01".to_string(), UnixArchive::Dump)
+            ]
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! PDP-11 and VAX Unix tape formats.
+//! `tp` is the V6/V7 directory-at-front archiver, `tar` the V7/4.xBSD tape
+//! archiver, and `dump` the V7/BSD inode-level backup read by `restor`.
+//! Each decoder works on one tape file; `reconstruct_unix_tape` stitches the
+//! results into a single tree.
+pub mod dump;
+pub mod tar;
+pub mod tp;
+
+pub use dump::*;
+pub use tar::*;
+pub use tp::*;
+
+use crate::core::block::{tape_files, BlockClassification, TapeBlock, TapeFormat, UnixBlockKind};
+use crate::core::file::{FileMetadata, TapeFile, TapePath, UnixArchive, UnixFileMetadata};
+
+pub const S_IFMT: u32 = 0o170000;
+pub const S_IFIFO: u32 = 0o010000;
+pub const S_IFCHR: u32 = 0o020000;
+pub const S_IFDIR: u32 = 0o040000;
+pub const S_IFBLK: u32 = 0o060000;
+pub const S_IFREG: u32 = 0o100000;
+pub const S_IFLNK: u32 = 0o120000;
+pub const S_IFSOCK: u32 = 0o140000;
+
+/// Render a mode the way `ls -l` does, e.g. `drwxr-xr-x`.
+pub fn format_mode(mode: u32) -> String {
+    let kind = match mode & S_IFMT {
+        S_IFDIR => 'd',
+        S_IFLNK => 'l',
+        S_IFCHR => 'c',
+        S_IFBLK => 'b',
+        S_IFIFO => 'p',
+        S_IFSOCK => 's',
+        _ => '-',
+    };
+    let mut out = String::from(kind);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 7;
+        out.push(if bits & 4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 2 != 0 { 'w' } else { '-' });
+        out.push(if bits & 1 != 0 { 'x' } else { '-' });
+    }
+    let special = [
+        (0o4000, 3, 's', 'S'),
+        (0o2000, 6, 's', 'S'),
+        (0o1000, 9, 't', 'T'),
+    ];
+    for (bit, pos, set, unset) in special {
+        if mode & bit != 0 {
+            let exec = out.as_bytes()[pos] == b'x';
+            out.replace_range(pos..pos + 1, &(if exec { set } else { unset }).to_string());
+        }
+    }
+    out
+}
+
+/// Format seconds since the Unix epoch as `YYYY-MM-DD HH:MM:SS` (UTC).
+pub fn format_unix_time(secs: i64) -> Option<String> {
+    if secs <= 0 {
+        return None;
+    }
+    let ts = chrono::DateTime::from_timestamp(secs, 0)?;
+    Some(ts.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+fn is_dump_continuation(group: &[TapeBlock]) -> bool {
+    matches!(
+        group.first().and_then(|b| parse_dump_header(&b.raw)),
+        Some(h) if h.kind == TS_TAPE && h.volume > 1
+    )
+}
+
+/// Which Unix archiver wrote a tape file, judged from its first record.
+pub fn detect_unix_archive(group: &[TapeBlock]) -> Option<UnixArchive> {
+    let first = group.first()?;
+    if is_tar_header(first.raw.get(..tar::TAR_BLOCK)?) {
+        Some(UnixArchive::Tar)
+    } else if matches!(parse_dump_header(&first.raw), Some(h) if h.kind == TS_TAPE) {
+        Some(UnixArchive::Dump)
+    } else if is_tp_archive(group) {
+        Some(UnixArchive::Tp)
+    } else {
+        None
+    }
+}
+
+/// Reclassify blocks of tape files written by tp, tar or dump.
+/// Returns the number of blocks claimed.
+pub fn classify_unix_tape(blocks: &mut [TapeBlock]) -> usize {
+    let mut claimed = 0usize;
+    let mut start = 0usize;
+    while start < blocks.len() {
+        let tf = blocks[start].tape_file;
+        let end = blocks[start..]
+            .iter()
+            .position(|b| b.tape_file != tf)
+            .map(|p| start + p)
+            .unwrap_or(blocks.len());
+        let group = &mut blocks[start..end];
+        if let Some(archive) = detect_unix_archive(group) {
+            let kind = match archive {
+                UnixArchive::Tp => UnixBlockKind::Tp,
+                UnixArchive::Tar => UnixBlockKind::Tar,
+                UnixArchive::Dump => UnixBlockKind::Dump,
+            };
+            for b in group.iter_mut() {
+                b.classification = BlockClassification::Unix(kind.clone());
+            }
+            claimed += group.len();
+        }
+        start = end;
+    }
+    claimed
+}
+
+/// Decode every Unix archive on the tape. A tape holding several archives
+/// gets one `fileNN` node per tape file so member paths cannot collide.
+pub fn reconstruct_unix_tape(blocks: &[TapeBlock]) -> Vec<TapeFile> {
+    let groups = tape_files(blocks);
+    let mut archives = Vec::new();
+    let mut start = 0usize;
+    let mut i = 0usize;
+    while i < groups.len() {
+        let group = groups[i];
+        let mut end = start + group.len();
+        i += 1;
+        let Some(archive) = detect_unix_archive(group) else {
+            start = end;
+            continue;
+        };
+        let files = match archive {
+            UnixArchive::Tar => decode_tar(group),
+            UnixArchive::Tp => decode_tp(group),
+            UnixArchive::Dump => {
+                // Later volumes of the same dump continue the stream.
+                while i < groups.len() && is_dump_continuation(groups[i]) {
+                    end += groups[i].len();
+                    i += 1;
+                }
+                decode_dump(&blocks[start..end])
+            }
+        };
+        if !files.is_empty() {
+            archives.push((group[0].tape_file, archive, files));
+        }
+        start = end;
+    }
+    if archives.len() == 1 {
+        return archives
+            .pop()
+            .map(|(_, _, files)| files)
+            .unwrap_or_default();
+    }
+    archives
+        .into_iter()
+        .map(|(tf, archive, files)| {
+            let label = format!("file{tf:02}");
+            let mut node = directory_node(vec![label.clone()], archive);
+            node.size_bytes = files.iter().map(|f| f.size_bytes).sum();
+            node.children = files.into_iter().map(|f| reparent(f, &label)).collect();
+            node
+        })
+        .collect()
+}
+
+fn reparent(mut file: TapeFile, prefix: &str) -> TapeFile {
+    file.path.elements.insert(0, prefix.to_string());
+    file.children = file
+        .children
+        .into_iter()
+        .map(|c| reparent(c, prefix))
+        .collect();
+    file
+}
+
+/// Split an archive member name into path elements, dropping `.`, `..`,
+/// empty components and any leading `/` so members stay inside the archive.
+pub fn split_member_path(name: &str) -> Vec<String> {
+    name.split('/')
+        .filter(|p| !p.is_empty() && *p != "." && *p != "..")
+        .map(str::to_string)
+        .collect()
+}
+
+/// Insert a member into a tree, creating directory nodes for missing parents.
+/// A later entry for an existing directory replaces its metadata but keeps
+/// the children already collected.
+pub fn insert_member(tree: &mut Vec<TapeFile>, file: TapeFile) {
+    let path = file.path.elements.clone();
+    if path.is_empty() {
+        return;
+    }
+    let archive = match &file.metadata {
+        FileMetadata::Unix(meta) => meta.archive,
+        _ => UnixArchive::Tar,
+    };
+    let mut level = tree;
+    for depth in 0..path.len() - 1 {
+        let prefix = &path[..=depth];
+        let idx = match level.iter().position(|n| n.path.elements == prefix) {
+            Some(i) => i,
+            None => {
+                level.push(directory_node(prefix.to_vec(), archive));
+                level.len() - 1
+            }
+        };
+        level[idx].size_bytes += file.size_bytes;
+        level = &mut level[idx].children;
+    }
+    match level.iter_mut().find(|n| n.path.elements == path) {
+        Some(existing) => {
+            existing.metadata = file.metadata;
+        }
+        None => level.push(file),
+    }
+}
+
+fn directory_node(path: Vec<String>, archive: UnixArchive) -> TapeFile {
+    TapeFile {
+        format: TapeFormat::Unix,
+        path: TapePath::new(path),
+        size_bytes: 0,
+        blocks: Vec::new(),
+        extents: Vec::new(),
+        metadata: FileMetadata::Unix(UnixFileMetadata {
+            archive,
+            mode: S_IFDIR | 0o755,
+            uid: 0,
+            gid: 0,
+            mtime: None,
+            inode: None,
+            link: None,
+        }),
+        children: Vec::new(),
+    }
+}
+
+/// Concatenate the records of one tape file into the archive byte stream.
+pub(crate) fn stream_bytes(group: &[TapeBlock]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(group.iter().map(|b| b.size).sum());
+    for b in group {
+        out.extend_from_slice(&b.raw);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_mode, format_unix_time, split_member_path};
+
+    #[test]
+    fn formats_modes_like_ls() {
+        assert_eq!(format_mode(0o040755), "drwxr-xr-x");
+        assert_eq!(format_mode(0o100644), "-rw-r--r--");
+        assert_eq!(format_mode(0o104755), "-rwsr-xr-x");
+        assert_eq!(format_mode(0o120777), "lrwxrwxrwx");
+        assert_eq!(format_mode(0o041777), "drwxrwxrwt");
+    }
+
+    #[test]
+    fn formats_times_and_paths() {
+        assert_eq!(
+            format_unix_time(315_532_800).as_deref(),
+            Some("1980-01-01 00:00:00")
+        );
+        assert_eq!(format_unix_time(0), None);
+        assert_eq!(split_member_path("./usr//bin/ls"), vec!["usr", "bin", "ls"]);
+    }
+}
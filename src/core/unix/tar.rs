@@ -0,0 +1,303 @@
+//! V7 / 4.xBSD `tar` archives, plus the POSIX ustar and GNU long-name
+//! extensions found on later tapes.
+//!
+//! The archive is a stream of 512-byte blocks packed into tape records
+//! (20 blocks per record by default): a header block per member, then the
+//! member data rounded up to a whole block. Two zero blocks end the archive.
+
+use std::collections::HashMap;
+
+use crate::core::block::{byte_extents, TapeBlock, TapeFormat};
+use crate::core::file::{
+    BlockExtent, FileMetadata, TapeFile, TapePath, UnixArchive, UnixFileMetadata, UnixLink,
+};
+
+use super::{
+    format_unix_time, insert_member, split_member_path, stream_bytes, S_IFBLK, S_IFCHR, S_IFDIR,
+    S_IFIFO, S_IFLNK, S_IFREG,
+};
+
+pub const TAR_BLOCK: usize = 512;
+
+/// Decoded tar header block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarHeader {
+    pub name: String,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub mtime: i64,
+    pub typeflag: u8,
+    pub linkname: String,
+}
+
+impl TarHeader {
+    /// Mode with `S_IFMT` bits derived from the type flag (V7 tar stores
+    /// permissions only and marks directories with a trailing `/`).
+    pub fn full_mode(&self) -> u32 {
+        let kind = match self.typeflag {
+            b'2' => S_IFLNK,
+            b'3' => S_IFCHR,
+            b'4' => S_IFBLK,
+            b'5' => S_IFDIR,
+            b'6' => S_IFIFO,
+            _ if self.name.ends_with('/') => S_IFDIR,
+            _ => S_IFREG,
+        };
+        kind | (self.mode & 0o7777)
+    }
+}
+
+/// True when `block` is a tar header with a valid checksum.
+pub fn is_tar_header(block: &[u8]) -> bool {
+    parse_tar_header(block).is_some()
+}
+
+/// Parse a 512-byte tar header, verifying its checksum.
+pub fn parse_tar_header(block: &[u8]) -> Option<TarHeader> {
+    if block.len() < TAR_BLOCK || block[0] == 0 {
+        return None;
+    }
+    let stored = octal(&block[148..156])?;
+    let unsigned: u64 = block[..TAR_BLOCK]
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if (148..156).contains(&i) {
+                0x20
+            } else {
+                u64::from(*b)
+            }
+        })
+        .sum();
+    let signed: i64 = block[..TAR_BLOCK]
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if (148..156).contains(&i) {
+                0x20
+            } else {
+                i64::from(*b as i8)
+            }
+        })
+        .sum();
+    if stored != unsigned && stored as i64 != signed {
+        return None;
+    }
+    let mut name = cstr(&block[0..100]);
+    if &block[257..262] == b"ustar" {
+        let prefix = cstr(&block[345..500]);
+        if !prefix.is_empty() {
+            name = format!("{prefix}/{name}");
+        }
+    }
+    Some(TarHeader {
+        name,
+        mode: octal(&block[100..108]).unwrap_or(0) as u32,
+        uid: octal(&block[108..116]).unwrap_or(0) as u32,
+        gid: octal(&block[116..124]).unwrap_or(0) as u32,
+        size: octal(&block[124..136]).unwrap_or(0),
+        mtime: octal(&block[136..148]).unwrap_or(0) as i64,
+        typeflag: block[156],
+        linkname: cstr(&block[157..257]),
+    })
+}
+
+/// Decode one tar tape file into a member tree.
+pub fn decode_tar(group: &[TapeBlock]) -> Vec<TapeFile> {
+    let stream = stream_bytes(group);
+    let mut tree = Vec::new();
+    let mut seen: HashMap<String, (Vec<BlockExtent>, u64)> = HashMap::new();
+    let mut long_name: Option<String> = None;
+    let mut long_link: Option<String> = None;
+    let mut pos = 0usize;
+
+    while pos + TAR_BLOCK <= stream.len() {
+        let Some(mut header) = parse_tar_header(&stream[pos..pos + TAR_BLOCK]) else {
+            break;
+        };
+        let data_start = pos + TAR_BLOCK;
+        let carries_data = matches!(header.typeflag, 0 | b'0' | b'7' | b'L' | b'K' | b'x' | b'g');
+        let data_len = if carries_data {
+            header.size as usize
+        } else {
+            0
+        };
+        pos = data_start + data_len.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+
+        match header.typeflag {
+            b'L' | b'K' => {
+                let end = (data_start + data_len).min(stream.len());
+                let text = cstr(&stream[data_start.min(end)..end]);
+                if header.typeflag == b'L' {
+                    long_name = Some(text);
+                } else {
+                    long_link = Some(text);
+                }
+                continue;
+            }
+            // POSIX extended headers: the following member still has a usable
+            // ustar header, so the attributes are not needed for a listing.
+            b'x' | b'g' => continue,
+            _ => {}
+        }
+        if let Some(name) = long_name.take() {
+            header.name = name;
+        }
+        if let Some(link) = long_link.take() {
+            header.linkname = link;
+        }
+
+        let path = split_member_path(&header.name);
+        if path.is_empty() {
+            continue;
+        }
+        let key = path.join("/");
+        let link = match header.typeflag {
+            b'1' => Some(UnixLink::Hard(
+                split_member_path(&header.linkname).join("/"),
+            )),
+            b'2' => Some(UnixLink::Symbolic(header.linkname.clone())),
+            _ => None,
+        };
+        let (extents, size) = match &link {
+            Some(UnixLink::Hard(target)) => seen.get(target).cloned().unwrap_or_default(),
+            _ => (byte_extents(group, data_start, data_len), data_len as u64),
+        };
+        seen.insert(key, (extents.clone(), size));
+
+        let mut blocks: Vec<u32> = extents.iter().map(|e| e.block).collect();
+        blocks.dedup();
+        insert_member(
+            &mut tree,
+            TapeFile {
+                format: TapeFormat::Unix,
+                path: TapePath::new(path),
+                size_bytes: size,
+                blocks,
+                extents,
+                metadata: FileMetadata::Unix(UnixFileMetadata {
+                    archive: UnixArchive::Tar,
+                    mode: header.full_mode(),
+                    uid: header.uid,
+                    gid: header.gid,
+                    mtime: format_unix_time(header.mtime),
+                    inode: None,
+                    link,
+                }),
+                children: Vec::new(),
+            },
+        );
+    }
+    tree
+}
+
+fn cstr(raw: &[u8]) -> String {
+    let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    String::from_utf8_lossy(&raw[..end]).to_string()
+}
+
+/// Parse a space/NUL padded octal field.
+fn octal(raw: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(raw).ok()?;
+    let digits = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(digits, 8).ok()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{decode_tar, parse_tar_header, TAR_BLOCK};
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::core::extract::file_bytes;
+    use crate::core::file::{FileMetadata, UnixLink};
+
+    /// Build a V7-style header block with a valid checksum.
+    pub(crate) fn header(name: &str, mode: u32, size: usize, typeflag: u8, link: &str) -> Vec<u8> {
+        let mut h = vec![0u8; TAR_BLOCK];
+        h[..name.len()].copy_from_slice(name.as_bytes());
+        let fields = [
+            (100, format!("{mode:06o} \0")),
+            (108, format!("{:06o} \0", 3)),
+            (116, format!("{:06o} \0", 4)),
+            (124, format!("{size:011o} ")),
+            (136, format!("{:011o} ", 315_532_800)),
+        ];
+        for (off, text) in fields {
+            h[off..off + text.len()].copy_from_slice(text.as_bytes());
+        }
+        h[156] = typeflag;
+        h[157..157 + link.len()].copy_from_slice(link.as_bytes());
+        h[148..156].fill(b' ');
+        let sum: u32 = h.iter().map(|b| u32::from(*b)).sum();
+        let text = format!("{sum:06o}\0 ");
+        h[148..156].copy_from_slice(text.as_bytes());
+        h
+    }
+
+    pub(crate) fn sample_archive() -> Vec<u8> {
+        let mut out = header("usr/", 0o755, 0, 0, "");
+        out.extend(header("usr/hello.c", 0o644, 600, b'0', ""));
+        out.extend(vec![b'h'; 600]);
+        out.resize(out.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+        out.extend(header("usr/hi.c", 0o644, 0, b'1', "usr/hello.c"));
+        out.extend(header("usr/link", 0o777, 0, b'2', "hello.c"));
+        out.resize(out.len() + 2 * TAR_BLOCK, 0);
+        out
+    }
+
+    fn records(stream: &[u8], size: usize) -> Vec<TapeBlock> {
+        stream
+            .chunks(size)
+            .enumerate()
+            .map(|(i, c)| TapeBlock {
+                index: i as u32,
+                tape_file: 0,
+                size: c.len(),
+                raw: c.to_vec().into(),
+                classification: BlockClassification::Unknown,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_header_fields() {
+        let h = parse_tar_header(&header("usr/hello.c", 0o644, 600, b'0', "")).unwrap();
+        assert_eq!(h.name, "usr/hello.c");
+        assert_eq!((h.uid, h.gid, h.size), (3, 4, 600));
+        assert_eq!(h.full_mode(), 0o100644);
+        let mut bad = header("x", 0o644, 0, b'0', "");
+        bad[0] = b'y';
+        assert!(parse_tar_header(&bad).is_none());
+    }
+
+    #[test]
+    fn decodes_members_across_records() {
+        let blocks = records(&sample_archive(), 1024);
+        let tree = decode_tar(&blocks);
+        assert_eq!(tree.len(), 1);
+        let usr = &tree[0];
+        assert_eq!(usr.path.to_string_path(), "usr");
+        assert_eq!(usr.children.len(), 3);
+        let hello = &usr.children[0];
+        assert_eq!(file_bytes(hello, &blocks), vec![b'h'; 600]);
+        let hard = &usr.children[1];
+        match &hard.metadata {
+            FileMetadata::Unix(meta) => {
+                assert_eq!(meta.link, Some(UnixLink::Hard("usr/hello.c".into())));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_eq!(file_bytes(hard, &blocks), vec![b'h'; 600]);
+        match &usr.children[2].metadata {
+            FileMetadata::Unix(meta) => {
+                assert_eq!(meta.mode, 0o120777);
+                assert_eq!(meta.link, Some(UnixLink::Symbolic("hello.c".into())));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! V6/V7 `tp` archives.
+//!
+//! Block 0 is a boot block, followed by the directory: 64-byte entries
+//! (eight per block) describing each file, then the file data. An entry is
+//!
+//! | offset | field                                            |
+//! |--------|--------------------------------------------------|
+//! | 0      | path name, NUL padded (32 bytes)                 |
+//! | 32     | u16 mode                                         |
+//! | 34     | u8 uid, u8 gid, spare byte, u8 high byte of size |
+//! | 38     | u16 low word of size                             |
+//! | 40     | PDP-11 long modification time                    |
+//! | 44     | u16 first tape block of the data                 |
+//! | 62     | u16 checksum: all 32 words sum to zero           |
+//!
+//! tp only stores plain files, so every entry is listed as a regular file.
+
+use crate::core::block::{byte_extents, TapeBlock, TapeFormat};
+use crate::core::file::{FileMetadata, TapeFile, TapePath, UnixArchive, UnixFileMetadata};
+
+use super::{format_unix_time, insert_member, split_member_path, stream_bytes, S_IFREG};
+
+pub const TP_BLOCK: usize = 512;
+const TP_ENTRY: usize = 64;
+/// Directory size written by `tp m` (magtape); DECtape directories are shorter
+/// and simply end where the first file's data begins.
+const TP_MT_DIR_BLOCKS: usize = 62;
+
+/// Decoded tp directory entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TpEntry {
+    pub name: String,
+    pub mode: u16,
+    pub uid: u8,
+    pub gid: u8,
+    pub size: u32,
+    pub mtime: i64,
+    pub tape_addr: u16,
+}
+
+/// Parse a 64-byte directory entry. Returns `None` for unused slots and for
+/// entries whose checksum or name does not check out.
+pub fn parse_tp_entry(raw: &[u8]) -> Option<TpEntry> {
+    if raw.len() < TP_ENTRY || raw[0] == 0 {
+        return None;
+    }
+    let sum = (0..TP_ENTRY / 2).fold(0u16, |acc, i| acc.wrapping_add(word(raw, i * 2)));
+    if sum != 0 {
+        return None;
+    }
+    let name_end = raw[..32].iter().position(|&b| b == 0).unwrap_or(32);
+    let name = &raw[..name_end];
+    if !name.iter().all(|b| (0x21..0x7F).contains(b)) {
+        return None;
+    }
+    let tape_addr = word(raw, 44);
+    if tape_addr < 2 {
+        return None;
+    }
+    Some(TpEntry {
+        name: String::from_utf8_lossy(name).to_string(),
+        mode: word(raw, 32),
+        uid: raw[34],
+        gid: raw[35],
+        size: (u32::from(raw[37]) << 16) | u32::from(word(raw, 38)),
+        mtime: i64::from((u32::from(word(raw, 40)) << 16) | u32::from(word(raw, 42))),
+        tape_addr,
+    })
+}
+
+/// Read the directory: entries from block 1 up to the first data block.
+pub fn read_tp_directory(stream: &[u8]) -> Vec<TpEntry> {
+    let mut entries: Vec<TpEntry> = Vec::new();
+    let mut dir_end = (1 + TP_MT_DIR_BLOCKS) * TP_BLOCK;
+    let mut pos = TP_BLOCK;
+    while pos + TP_ENTRY <= stream.len().min(dir_end) {
+        if let Some(entry) = parse_tp_entry(&stream[pos..pos + TP_ENTRY]) {
+            dir_end = dir_end.min(usize::from(entry.tape_addr) * TP_BLOCK);
+            entries.push(entry);
+        }
+        pos += TP_ENTRY;
+    }
+    entries
+}
+
+/// A tp archive has 512-byte records and at least one valid entry in the
+/// first directory block, with every used slot of that block valid.
+pub fn is_tp_archive(group: &[TapeBlock]) -> bool {
+    if group.len() < 3 || group.iter().any(|b| b.size != TP_BLOCK) {
+        return false;
+    }
+    let dir = &group[1].raw;
+    let mut valid = 0usize;
+    for slot in dir.chunks(TP_ENTRY) {
+        if slot.iter().all(|&b| b == 0) {
+            continue;
+        }
+        if parse_tp_entry(slot).is_none() {
+            return false;
+        }
+        valid += 1;
+    }
+    valid > 0
+}
+
+/// Decode one tp tape file into a member tree.
+pub fn decode_tp(group: &[TapeBlock]) -> Vec<TapeFile> {
+    let stream = stream_bytes(group);
+    let mut tree = Vec::new();
+    for entry in read_tp_directory(&stream) {
+        let path = split_member_path(&entry.name);
+        if path.is_empty() {
+            continue;
+        }
+        let start = usize::from(entry.tape_addr) * TP_BLOCK;
+        let extents = byte_extents(group, start, entry.size as usize);
+        let mut blocks: Vec<u32> = extents.iter().map(|e| e.block).collect();
+        blocks.dedup();
+        insert_member(
+            &mut tree,
+            TapeFile {
+                format: TapeFormat::Unix,
+                path: TapePath::new(path),
+                size_bytes: u64::from(entry.size),
+                blocks,
+                extents,
+                metadata: FileMetadata::Unix(UnixFileMetadata {
+                    archive: UnixArchive::Tp,
+                    mode: S_IFREG | (u32::from(entry.mode) & 0o7777),
+                    uid: u32::from(entry.uid),
+                    gid: u32::from(entry.gid),
+                    mtime: format_unix_time(entry.mtime),
+                    inode: None,
+                    link: None,
+                }),
+                children: Vec::new(),
+            },
+        );
+    }
+    tree
+}
+
+fn word(data: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([data[off], data[off + 1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_tp, is_tp_archive, TP_BLOCK, TP_ENTRY};
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::core::extract::file_bytes;
+    use crate::core::file::FileMetadata;
+
+    fn entry(name: &str, size: u32, addr: u16) -> Vec<u8> {
+        let mut e = vec![0u8; TP_ENTRY];
+        e[..name.len()].copy_from_slice(name.as_bytes());
+        e[32..34].copy_from_slice(&0o644u16.to_le_bytes());
+        e[34] = 3;
+        e[35] = 1;
+        e[37] = (size >> 16) as u8;
+        e[38..40].copy_from_slice(&(size as u16).to_le_bytes());
+        // 1980-01-01 as a PDP-11 long: high word first.
+        let t: u32 = 315_532_800;
+        e[40..42].copy_from_slice(&((t >> 16) as u16).to_le_bytes());
+        e[42..44].copy_from_slice(&(t as u16).to_le_bytes());
+        e[44..46].copy_from_slice(&addr.to_le_bytes());
+        let sum = (0..31).fold(0u16, |acc, i| {
+            acc.wrapping_add(u16::from_le_bytes([e[i * 2], e[i * 2 + 1]]))
+        });
+        e[62..64].copy_from_slice(&0u16.wrapping_sub(sum).to_le_bytes());
+        e
+    }
+
+    fn archive() -> Vec<TapeBlock> {
+        let mut dir = entry("usr/src/a.c", 700, 3);
+        dir.extend(entry("b.s", 10, 5));
+        dir.resize(TP_BLOCK, 0);
+        let mut raws = vec![vec![0u8; TP_BLOCK], dir, vec![0u8; TP_BLOCK]];
+        raws.push(vec![b'a'; TP_BLOCK]);
+        raws.push(vec![b'a'; TP_BLOCK]);
+        raws.push(vec![b'b'; TP_BLOCK]);
+        raws.into_iter()
+            .enumerate()
+            .map(|(i, raw)| TapeBlock {
+                index: i as u32,
+                tape_file: 0,
+                size: raw.len(),
+                raw: raw.into(),
+                classification: BlockClassification::Unknown,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_tp_directory() {
+        let blocks = archive();
+        assert!(is_tp_archive(&blocks));
+        let tree = decode_tp(&blocks);
+        assert_eq!(tree.len(), 2);
+        let a = &tree[0].children[0].children[0];
+        assert_eq!(a.path.to_string_path(), "usr/src/a.c");
+        assert_eq!(file_bytes(a, &blocks), vec![b'a'; 700]);
+        match &a.metadata {
+            FileMetadata::Unix(meta) => {
+                assert_eq!(meta.mode, 0o100644);
+                assert_eq!((meta.uid, meta.gid), (3, 1));
+                assert_eq!(meta.mtime.as_deref(), Some("1980-01-01 00:00:00"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_eq!(tree[1].path.to_string_path(), "b.s");
+    }
+
+    #[test]
+    fn rejects_non_tp_records() {
+        let mut blocks = archive();
+        blocks[1] = TapeBlock {
+            raw: vec![0x41u8; TP_BLOCK].into(),
+            ..blocks[1].clone()
+        };
+        assert!(!is_tp_archive(&blocks));
+    }
+}
@@ -1,5 +0,0 @@
-#[derive(Debug, Clone)]
-pub struct VmsDirectoryEntry {
-    pub name: String,
-    pub file_index: usize,
-}
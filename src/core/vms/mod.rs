@@ -1,83 +1,76 @@
-pub mod block;
-pub mod directory;
-pub mod extract;
-pub mod file;
-pub mod header;
+//! VMS BACKUP savesets.
+//! Every tape record of a saveset is one BACKUP block: a block header (BBH)
+//! and the records it carries, each behind a record header (BRH); see
+//! `backup::bbh`. A file record names a file and holds its attributes, and
+//! the VBN records after it hold its data. `reconstruct` follows those
+//! records and builds `TapeFile` nodes whose extents point at the data on
+//! tape. The XOR block closing each redundancy group carries no records.
 pub mod reconstruct;
 
-use std::collections::BTreeMap;
+use crate::backup::bbh::{has_bbh, is_xor_block};
 
-use crate::core::block::{BlockClassification, TapeBlock};
+pub use reconstruct::reconstruct_vms;
 
-pub use block::*;
-pub use directory::*;
-pub use extract::*;
-pub use file::*;
-pub use header::*;
-pub use reconstruct::*;
-
-/// A per-file temporary structure before final reconstruction.
-#[derive(Debug, Clone, Default)]
-pub struct VmsCollected {
-    pub fh2: Option<block::Fh2Record>,
-    pub xh2: Option<block::Xh2Record>,
-    pub xh3: Option<block::Xh3Record>,
-    pub vbn: Vec<block::VbnRecord>,
+/// True when a tape record is a BACKUP block, data or XOR.
+pub fn has_backup_block(record: &[u8]) -> bool {
+    has_bbh(record) || is_xor_block(record)
 }
 
-impl VmsCollected {
-    pub fn new() -> Self {
-        Self {
-            fh2: None,
-            xh2: None,
-            xh3: None,
-            vbn: Vec::new(),
-        }
+/// Convert a VMS 64-bit timestamp (100ns ticks since 1858-11-17)
+/// into ISO8601. If zero -> None.
+pub fn decode_vms_time(qw: u64) -> Option<String> {
+    if qw == 0 {
+        return None;
     }
+    // Convert 100ns ticks to seconds
+    let secs = qw / 10_000_000;
+    // VMS epoch -> Unix epoch offset
+    const VMS_TO_UNIX: i64 = -3_506_716_800; // seconds
+    let unix = secs as i64 + VMS_TO_UNIX;
+    chrono::DateTime::<chrono::Utc>::from_timestamp(unix, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
 }
 
-/// Scan a list of TapeBlocks and collect all VMS structures.
-pub fn collect_vms_blocks(blocks: &[TapeBlock]) -> BTreeMap<u32, VmsCollected> {
-    let mut out: BTreeMap<u32, VmsCollected> = BTreeMap::new();
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::has_backup_block;
+    use crate::backup::vms::{FAT_CR, FAT_STMLF};
+    use crate::backup::writer::{build_saveset, SavesetFile, SavesetOptions};
+    use crate::tap::reader::read_tap_blocks;
 
-    for blk in blocks {
-        if let BlockClassification::Vms(_) = &blk.classification {
-            if let Some(parsed) = block::classify_vms_block(blk.raw.as_ref(), blk.index) {
-                match parsed.kind {
-                    block::VmsBlockKind::FileHeader(fh2) => {
-                        let id = blk.index;
-                        out.entry(id).or_default().fh2 = Some(fh2);
-                    }
-                    block::VmsBlockKind::Extension(xh2) => {
-                        // Attach XH2 to the last FH2 block encountered.
-                        if let Some(k) = last_key(&out) {
-                            if let Some(entry) = out.get_mut(&k) {
-                                entry.xh2 = Some(xh2);
-                            }
-                        }
-                    }
-                    block::VmsBlockKind::Ident(xh3) => {
-                        if let Some(k) = last_key(&out) {
-                            if let Some(entry) = out.get_mut(&k) {
-                                entry.xh3 = Some(xh3);
-                            }
-                        }
-                    }
-                    block::VmsBlockKind::FileData(vbn) => {
-                        if let Some(k) = last_key(&out) {
-                            if let Some(entry) = out.get_mut(&k) {
-                                entry.vbn.push(vbn);
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
+    /// Saveset blocks written for `(VMS name, contents)` pairs as stream-LF
+    /// text.
+    pub(crate) fn saveset(files: &[(&str, &[u8])], opts: &SavesetOptions) -> Vec<Vec<u8>> {
+        let files: Vec<SavesetFile> = files
+            .iter()
+            .map(|(name, data)| SavesetFile {
+                vms_name: name.to_string(),
+                data: data.to_vec(),
+                record_format: FAT_STMLF,
+                record_attributes: FAT_CR,
+                max_record: 0,
+                modified: opts.date,
+            })
+            .collect();
+        build_saveset(&files, opts).unwrap()
     }
-    out
-}
 
-fn last_key(map: &BTreeMap<u32, VmsCollected>) -> Option<u32> {
-    map.keys().rev().next().cloned()
+    #[test]
+    fn recognises_data_and_xor_blocks() {
+        let opts = SavesetOptions {
+            block_size: 2048,
+            ..SavesetOptions::default()
+        };
+        let block = saveset(&[("[000000]A.TXT;1", b"a\n")], &opts).remove(0);
+        assert!(has_backup_block(&block));
+        assert!(!has_backup_block(&block[..200]));
+        assert!(!has_backup_block(&[0u8; 2048]));
+
+        // The XOR block of an even-sized group has a zero structure level.
+        let tape = read_tap_blocks("tests/data/TA0117.TAP").expect("missing VMS fixture");
+        let backup: Vec<_> = tape.iter().filter(|b| has_backup_block(&b.raw)).collect();
+        assert_eq!(backup.len(), 31);
+        assert_eq!(tape[13].raw[32..34], [0, 0]);
+        assert!(has_backup_block(&tape[13].raw));
+    }
 }
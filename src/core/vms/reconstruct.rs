@@ -1,105 +1,331 @@
 //! VMS file reconstruction layer.
-//! Turns FH2/XH2/XH3/VBN collections into TapeFile nodes.
+//! Turns the file and VBN records of BACKUP blocks into TapeFile nodes. File
+//! data is not copied: every VBN record becomes an extent over its data
+//! inside the tape record, placed at its starting VBN.
 
-use std::collections::BTreeMap;
+use std::ops::Range;
 
-use crate::core::block::TapeBlock;
-use crate::core::file::{FileMetadata, TapeFile, TapePath, VmsFileMetadata};
-use crate::core::vms::block::Fh2Record;
-use crate::core::vms::{collect_vms_blocks, VmsCollected};
+use crate::backup::bbh::{
+    block_records, has_bbh, is_xor_block, record_attributes, BRH_FILE, BRH_SUMMARY, BRH_VBN,
+    BSA_CREDATE, BSA_EXPDATE, BSA_FID, BSA_FILENAME, BSA_FPRO, BSA_RECATTR, BSA_REVDATE,
+    BSA_REVISION, BSA_UIC, VBN_SIZE,
+};
+use crate::core::block::{BlockClassification, TapeBlock, TapeFormat};
+use crate::core::file::{BlockExtent, FileMetadata, TapeFile, TapePath, VmsFileMetadata};
 
-/// Main entry point for VMS reconstruction.
-/// Collects parsed VMS structures from blocks, then builds file entries.
+use super::decode_vms_time;
+
+/// Main entry point for VMS reconstruction: walk the records of every VMS
+/// data block in tape order, start a file at each file record, add the VBN
+/// records after it and place the files under their directories.
 pub fn reconstruct_vms(blocks: &[TapeBlock]) -> Vec<TapeFile> {
-    let collected = collect_vms_blocks(blocks);
-    reconstruct_vms_from_collected(&collected)
+    let mut tree = Vec::new();
+    let mut current: Option<SavedFile> = None;
+    let data_blocks = blocks.iter().filter(|b| {
+        matches!(b.classification, BlockClassification::Vms(_))
+            && has_bbh(&b.raw)
+            && !is_xor_block(&b.raw)
+    });
+    for block in data_blocks {
+        for record in block_records(&block.raw) {
+            match record.rtype {
+                BRH_SUMMARY => {
+                    if let Some(file) = current.take() {
+                        insert_vms_file(&mut tree, file.into_tape_file());
+                    }
+                }
+                BRH_FILE => {
+                    if let Some(file) = current.take() {
+                        insert_vms_file(&mut tree, file.into_tape_file());
+                    }
+                    current = SavedFile::parse(&block.raw, &record.data);
+                }
+                BRH_VBN if record.address > 0 => {
+                    if let Some(file) = current.as_mut() {
+                        let start = (u64::from(record.address) - 1) * VBN_SIZE as u64;
+                        file.data.push((start, block.index, record.data));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if let Some(file) = current {
+        insert_vms_file(&mut tree, file.into_tape_file());
+    }
+    tree
 }
 
-/// Reconstruct files from a pre-collected VMS map.
-pub fn reconstruct_vms_from_collected(collected: &BTreeMap<u32, VmsCollected>) -> Vec<TapeFile> {
-    let mut out = Vec::new();
+/// A file record and the VBN data gathered for it so far.
+struct SavedFile {
+    path: Vec<String>,
+    /// End of file from the record attributes, when present.
+    size: Option<u64>,
+    meta: VmsFileMetadata,
+    /// (offset in the file, tape block, data range in the block).
+    data: Vec<(u64, u32, Range<usize>)>,
+}
 
-    for (_fh2_index, group) in collected.iter() {
-        if let Some(fh2) = &group.fh2 {
-            let file = build_file_from_group(fh2, group);
-            out.push(file);
+impl SavedFile {
+    /// Decode the BSA attributes of a file record; `None` without a name.
+    fn parse(raw: &[u8], data: &Range<usize>) -> Option<SavedFile> {
+        let mut name = None;
+        let mut size = None;
+        let mut meta = empty_metadata();
+        let word = |r: &Range<usize>, at: usize| {
+            raw.get(r.start + at..r.start + at + 2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .unwrap_or(0)
+        };
+        let quad = |r: &Range<usize>| {
+            raw.get(r.start..r.start + 8)
+                .map(|b| u64::from_le_bytes(b.try_into().unwrap_or_default()))
+                .unwrap_or(0)
+        };
+        for (kind, r) in record_attributes(raw, data) {
+            match kind {
+                BSA_FILENAME => name = Some(String::from_utf8_lossy(&raw[r]).into_owned()),
+                BSA_FID => meta.file_id = (word(&r, 0), word(&r, 2), word(&r, 4)),
+                BSA_UIC => meta.owner_uic = (word(&r, 2), word(&r, 0)),
+                BSA_FPRO => meta.protection = word(&r, 0),
+                BSA_REVISION => meta.rev = word(&r, 0),
+                BSA_RECATTR if r.len() >= 14 => {
+                    meta.record_format = raw[r.start] & 0x0F;
+                    meta.record_attributes = raw[r.start + 1];
+                    meta.record_length = word(&r, 2);
+                    // Block numbers are stored high word first.
+                    let efblk = u64::from(word(&r, 8)) << 16 | u64::from(word(&r, 10));
+                    let ffbyte = u64::from(word(&r, 12));
+                    size = Some(match efblk {
+                        0 => 0,
+                        n => (n - 1) * VBN_SIZE as u64 + ffbyte,
+                    });
+                }
+                BSA_CREDATE => meta.creation_time = decode_vms_time(quad(&r)),
+                BSA_REVDATE => meta.revision_time = decode_vms_time(quad(&r)),
+                BSA_EXPDATE => meta.expiration_time = decode_vms_time(quad(&r)),
+                _ => {}
+            }
         }
+        let name = name?;
+        let (directory, file_name) = split_vms_name(&name);
+        meta.seq = meta.file_id.1;
+        meta.file_type = file_type(file_name);
+        let mut path = directory_elements(directory);
+        path.push(file_name.to_string());
+        Some(SavedFile {
+            path,
+            size,
+            meta,
+            data: Vec::new(),
+        })
     }
 
-    out
+    /// Extents in file order, zero-filled where no VBN record was read and
+    /// cut at the end of file.
+    fn into_tape_file(mut self) -> TapeFile {
+        self.data.sort_by_key(|(start, _, _)| *start);
+        let end = self
+            .data
+            .iter()
+            .map(|(start, _, r)| start + r.len() as u64)
+            .max()
+            .unwrap_or(0);
+        let size = self.size.unwrap_or(end);
+        let mut extents = Vec::new();
+        let mut pos = 0u64;
+        for (start, block, range) in self.data {
+            if start >= size || start + (range.len() as u64) <= pos {
+                continue;
+            }
+            if start > pos {
+                extents.push(BlockExtent::hole((start - pos) as usize));
+                pos = start;
+            }
+            let skip = (pos - start) as usize;
+            let len = (range.len() - skip).min((size - pos) as usize);
+            extents.push(BlockExtent {
+                block,
+                offset: range.start + skip,
+                len,
+            });
+            pos += len as u64;
+        }
+        let mut indices: Vec<u32> = extents
+            .iter()
+            .filter(|e| !e.is_hole())
+            .map(|e| e.block)
+            .collect();
+        indices.dedup();
+        TapeFile {
+            format: TapeFormat::Vms,
+            path: TapePath::new(self.path),
+            size_bytes: pos,
+            blocks: indices,
+            extents,
+            metadata: FileMetadata::Vms(self.meta),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Split `[DIR.SUB]NAME.TYP;1` (or `<DIR.SUB>`) into directory and name.
+fn split_vms_name(name: &str) -> (&str, &str) {
+    match name.find([']', '>']) {
+        Some(end) => (&name[..=end], &name[end + 1..]),
+        None => ("", name),
+    }
+}
+
+/// Split a directory into path elements: `[A.B]` bracket notation or a
+/// slash-separated path. The master directory `[000000]` is the root.
+fn directory_elements(dir: &str) -> Vec<String> {
+    let dir = dir.trim();
+    let parts: Vec<&str> = match dir.strip_prefix(['[', '<']) {
+        Some(inner) => inner.trim_end_matches([']', '>']).split('.').collect(),
+        None => dir.split('/').collect(),
+    };
+    parts
+        .into_iter()
+        .filter(|p| !p.is_empty() && *p != "000000")
+        .map(str::to_string)
+        .collect()
+}
+
+/// `TYP` of `NAME.TYP;1`.
+fn file_type(name: &str) -> String {
+    let name = name.split(';').next().unwrap_or(name);
+    name.rsplit_once('.')
+        .map(|(_, ext)| ext.to_string())
+        .unwrap_or_default()
 }
 
-/// Turn a VMS FH2 + optional XH2/XH3 + VBN list into a concrete TapeFile.
-fn build_file_from_group(fh2: &Fh2Record, group: &VmsCollected) -> TapeFile {
-    // File path construction:
-    // VMS savesets do NOT store directory paths.
-    // So the filename is flat: NAME.TYPE;VERSION
-    let full = build_vms_fullname(fh2);
-
-    // Collect block identifiers from VBNs, sorted by VBN.
-    let mut indices: Vec<u32> = group.vbn.iter().map(|v| v.vbn).collect();
-    indices.sort_unstable();
-
-    // Estimate final size (approximate). VBN payload length is arbitrary, but normally 512-byte blocks.
-    let size = group
-        .vbn
-        .iter()
-        .map(|vb| vb.payload.len() as u64)
-        .sum::<u64>();
-
-    let mut metadata = VmsFileMetadata {
+fn empty_metadata() -> VmsFileMetadata {
+    VmsFileMetadata {
         file_id: (0, 0, 0),
-        rev: fh2.version,
+        rev: 0,
         seq: 0,
-        owner_uic: fh2.uic,
+        owner_uic: (0, 0),
         protection: 0,
-        record_format: fh2.record_format,
-        record_attributes: fh2.record_attributes,
+        record_format: 0,
+        record_attributes: 0,
         record_length: 0,
-        file_type: fh2.file_type.clone(),
+        file_type: String::new(),
         backup_flags: 0,
         creation_time: None,
         revision_time: None,
         expiration_time: None,
-    };
-
-    if let Some(xh3) = &group.xh3 {
-        metadata.file_id = (
-            (xh3.file_id & 0xFFFF) as u16,
-            ((xh3.file_id >> 16) & 0xFFFF) as u16,
-            0,
-        );
-        metadata.seq = xh3.sequence_number;
     }
+}
 
-    if let Some(xh2) = &group.xh2 {
-        metadata.backup_flags = xh2.backup_flags;
+/// Insert a file below its directories, creating directory nodes as needed.
+fn insert_vms_file(tree: &mut Vec<TapeFile>, file: TapeFile) {
+    let path = file.path.elements.clone();
+    let mut level = tree;
+    for depth in 0..path.len().saturating_sub(1) {
+        let prefix = &path[..=depth];
+        let idx = match level.iter().position(|n| n.path.elements == prefix) {
+            Some(i) => i,
+            None => {
+                level.push(directory_node(prefix.to_vec()));
+                level.len() - 1
+            }
+        };
+        level[idx].size_bytes += file.size_bytes;
+        level = &mut level[idx].children;
     }
+    level.push(file);
+}
 
+fn directory_node(path: Vec<String>) -> TapeFile {
     TapeFile {
-        format: crate::core::block::TapeFormat::Vms,
-        path: TapePath::new(vec![full]),
-        size_bytes: size,
-        blocks: indices,
-        metadata: FileMetadata::Vms(metadata),
+        format: TapeFormat::Vms,
+        path: TapePath::new(path),
+        size_bytes: 0,
+        blocks: Vec::new(),
+        extents: Vec::new(),
+        metadata: FileMetadata::Vms(VmsFileMetadata {
+            file_type: "DIR".to_string(),
+            ..empty_metadata()
+        }),
         children: Vec::new(),
     }
 }
 
-/// Build a canonical VMS filename: NAME.TYPE;VERSION
-fn build_vms_fullname(fh2: &Fh2Record) -> String {
-    let mut s = String::new();
-    s.push_str(fh2.file_name.trim());
+#[cfg(test)]
+mod tests {
+    use super::reconstruct_vms;
+    use crate::backup::writer::{saveset_tape_blocks, SavesetOptions};
+    use crate::core::block::TapeFormat;
+    use crate::core::extract::file_bytes;
+    use crate::core::file::{FileMetadata, TapeFile};
+    use crate::core::handler::registry;
+    use crate::core::vms::tests::saveset;
+    use crate::tap::reader::read_tap_blocks;
 
-    if !fh2.file_type.trim().is_empty() {
-        s.push('.');
-        s.push_str(fh2.file_type.trim());
+    fn find<'a>(tree: &'a [TapeFile], path: &str) -> Option<&'a TapeFile> {
+        tree.iter().find_map(|f| {
+            if f.path.to_string_path() == path {
+                Some(f)
+            } else {
+                find(&f.children, path)
+            }
+        })
     }
 
-    if fh2.version != 0 {
-        s.push(';');
-        s.push_str(&fh2.version.to_string());
+    #[test]
+    fn reads_back_savesets_from_the_writer() {
+        let opts = SavesetOptions {
+            block_size: 2048,
+            ..SavesetOptions::default()
+        };
+        let big: Vec<u8> = (0..5000u32).map(|i| b'a' + (i % 26) as u8).collect();
+        let blocks = saveset(
+            &[
+                ("[000000]README.TXT;1", b"hello\n"),
+                ("[SYS.MGR]BIG.DAT;2", &big),
+            ],
+            &opts,
+        );
+        let mut tape = saveset_tape_blocks(&blocks, &opts);
+        let detection = registry().classify(&mut tape);
+        assert_eq!(detection.winner, TapeFormat::Vms);
+
+        let tree = reconstruct_vms(&tape);
+        let readme = find(&tree, "README.TXT;1").expect("README.TXT");
+        assert_eq!(file_bytes(readme, &tape), b"hello\n");
+        let file = find(&tree, "SYS/MGR/BIG.DAT;2").expect("BIG.DAT");
+        assert_eq!(file.size_bytes, 5000);
+        assert!(file.blocks.len() > 1, "spans blocks");
+        assert_eq!(file_bytes(file, &tape), big);
+        match &file.metadata {
+            FileMetadata::Vms(meta) => {
+                assert_eq!(meta.owner_uic, (1, 4));
+                assert_eq!(meta.protection, 0xFA00);
+                assert_eq!(meta.record_format, 5);
+                assert_eq!(meta.file_type, "DAT");
+                assert_eq!(meta.rev, 1);
+            }
+            other => panic!("expected VMS metadata, got {other:?}"),
+        }
     }
 
-    s
+    #[test]
+    fn lists_files_of_a_real_saveset() {
+        let mut tape = read_tap_blocks("tests/data/TA0117.TAP").expect("missing VMS fixture");
+        assert_eq!(registry().classify(&mut tape).winner, TapeFormat::Vms);
+        let tree = reconstruct_vms(&tape);
+        let file = find(&tree, "MUP/SDC/AUTOGEN.VUS;1").expect("AUTOGEN.VUS");
+        // RECATTR: end of file in block 4 at byte 390.
+        assert_eq!(file.size_bytes, 3 * 512 + 390);
+        assert_eq!(file_bytes(file, &tape).len(), 1926);
+        match &file.metadata {
+            FileMetadata::Vms(meta) => {
+                assert_eq!(meta.owner_uic, (0o11, 0o132));
+                assert_eq!(meta.record_format, 2);
+                assert_eq!(meta.creation_time.as_deref().map(|t| &t[..4]), Some("1988"));
+            }
+            other => panic!("expected VMS metadata, got {other:?}"),
+        }
+    }
 }
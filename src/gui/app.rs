@@ -1,13 +1,15 @@
 //! App shell: wires together all tabs and routes shared state.
 use egui::{self, CentralPanel};
 
-use super::contents::contents_table;
+use super::contents::{contents_table, record_hex_window};
 use super::extraction::extraction_tab;
 use super::files::files_tab;
+use super::health::health_tab;
 use super::input::input_tab;
 use super::logview::draw_log;
 use super::state::{AppState, MainTab};
 use super::summary::summary_tab;
+use super::timeline::timeline_tab;
 
 /// Simple top-level tab rendering that places Summary after Files.
 pub fn render_app(ctx: &egui::Context, state: &mut AppState) {
@@ -15,24 +17,28 @@ pub fn render_app(ctx: &egui::Context, state: &mut AppState) {
         ui.horizontal(|ui| {
             tab_button(ui, "Input", MainTab::Input, state);
             tab_button(ui, "Contents", MainTab::Contents, state);
+            tab_button(ui, "Timeline", MainTab::Timeline, state);
             tab_button(ui, "Extraction", MainTab::Extraction, state);
             tab_button(ui, "Files", MainTab::Files, state);
             tab_button(ui, "Summary", MainTab::Summary, state);
             tab_button(ui, "Log", MainTab::Log, state);
+            tab_button(ui, "Health", MainTab::Health, state);
         });
         ui.separator();
         match state.current_tab {
             MainTab::Input => input_tab(ui, state),
             MainTab::Contents => contents_table(ui, state),
             MainTab::Extraction => {
-                let entries = state.tap_state.entries.clone();
-                extraction_tab(ui, &entries, &mut state.extraction)
+                extraction_tab(ui, &state.files, &state.blocks, &mut state.extraction)
             }
             MainTab::Files => files_tab(ui, state),
             MainTab::Summary => summary_tab(ui, state),
-            MainTab::Log => draw_log(ui, &state.log_state.data),
+            MainTab::Log => draw_log(ui, state),
+            MainTab::Health => health_tab(ui, state),
+            MainTab::Timeline => timeline_tab(ui, state),
         }
     });
+    record_hex_window(ctx, state);
 }
 
 fn tab_button(ui: &mut egui::Ui, label: &str, tab: MainTab, state: &mut AppState) {
@@ -1,16 +1,39 @@
-//! Contents tab: lists reconstructed tape files with quick hex viewing.
-use egui::{self, Align, Layout, ScrollArea, Vec2, Window};
+//! Contents tab: lists reconstructed tape files with quick hex viewing, or
+//! pages through the raw records of the loaded image.
+use std::ops::Range;
+
+use egui::{
+    self, Align, Color32, DragValue, Layout, RichText, ScrollArea, TextStyle, Vec2, Window,
+};
 use rfd::FileDialog;
 
 use crate::core::extract::extract_file;
-use crate::utils::hex::format_hex_with_ascii;
+use crate::core::fields::data_fields;
+use crate::log::parse::{line_of_record, LogLevel};
 use crate::utils::text::sanitize_display;
 
-use super::files::{collect_block_bytes, describe_metadata, flatten_files_tree};
-use super::state::AppState;
+use super::files::{collect_block_bytes, describe_metadata, shown_files};
+use super::hexview::{hex_viewer, HexView};
+use super::state::{AppState, MainTab};
+use super::timeline::span_filter_bar;
+
+/// Bytes of each record previewed in the records view.
+const RECORD_PREVIEW: usize = 16;
 
 /// Render a table of reconstructed files inside the Contents tab.
 pub fn contents_table(ui: &mut egui::Ui, app_state: &mut AppState) {
+    ui.horizontal(|ui| {
+        let show = &mut app_state.tap_state.show_records;
+        ui.selectable_value(show, false, "Files");
+        ui.selectable_value(show, true, "Records");
+    });
+    span_filter_bar(ui, app_state);
+    ui.separator();
+    if app_state.tap_state.show_records {
+        records_table(ui, app_state);
+        return;
+    }
+
     ui.horizontal(|ui| {
         ui.label("Idx");
         ui.add_space(6.0);
@@ -28,7 +51,7 @@ pub fn contents_table(ui: &mut egui::Ui, app_state: &mut AppState) {
     });
     ui.separator();
 
-    let flattened = flatten_files_tree(&app_state.files, 0);
+    let flattened = shown_files(app_state);
     if flattened.is_empty() {
         ui.label("No reconstructed files available.");
         return;
@@ -72,6 +95,7 @@ pub fn contents_table(ui: &mut egui::Ui, app_state: &mut AppState) {
                     }
                     if ui.button("View").clicked() {
                         app_state.tap_state.selected_entry = Some(idx);
+                        app_state.tap_state.entry_hex = HexView::default();
                     }
                 });
                 ui.separator();
@@ -107,11 +131,8 @@ pub fn contents_table(ui: &mut egui::Ui, app_state: &mut AppState) {
                         ui.label(line);
                     }
                     ui.separator();
-                    ScrollArea::vertical()
-                        .auto_shrink([false, false])
-                        .show(ui, |ui| {
-                            ui.monospace(format_hex_with_ascii(&bytes));
-                        });
+                    let fields = data_fields(&bytes);
+                    hex_viewer(ui, &bytes, &fields, &mut app_state.tap_state.entry_hex);
                 });
             if close || !open {
                 app_state.tap_state.selected_entry = None;
@@ -126,3 +147,159 @@ pub fn contents_table(ui: &mut egui::Ui, app_state: &mut AppState) {
         ui.label(&app_state.summary_status);
     }
 }
+
+/// Render every record of the loaded image. Only the visible rows are read
+/// from the index, so images with millions of records scroll smoothly.
+fn records_table(ui: &mut egui::Ui, app_state: &mut AppState) {
+    let Some(index) = app_state.tap_state.index.clone() else {
+        ui.label("No tape image loaded.");
+        return;
+    };
+    let shown = shown_records(app_state, index.len());
+    let tap_state = &mut app_state.tap_state;
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} records in {} tape files ({})",
+            index.len(),
+            index.tape_files(),
+            index.format().label()
+        ));
+        ui.add_space(12.0);
+        ui.label("Go to record");
+        ui.add(
+            DragValue::new(&mut tap_state.record_jump)
+                .clamp_range(shown.start..=shown.end.saturating_sub(1)),
+        );
+        if ui.button("Go").clicked() {
+            tap_state.scroll_to_record = Some(tap_state.record_jump);
+        }
+    });
+    ui.separator();
+    ui.monospace(format!(
+        "{:>9}  {:>5}  {:>12}  {:>7}  preview",
+        "record", "file", "offset", "bytes"
+    ));
+
+    let row_height = ui.text_style_height(&TextStyle::Monospace);
+    let mut area = ScrollArea::vertical().auto_shrink([false, false]);
+    if let Some(record) = tap_state.scroll_to_record.take() {
+        let row = record.saturating_sub(shown.start);
+        area = area.vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+    }
+    // Record clicked, and whether twice (opens the hex window).
+    let mut clicked: Option<(usize, bool)> = None;
+    let levels = &tap_state.record_levels;
+    let selected = tap_state.selected_record;
+    area.show_rows(ui, row_height, shown.len(), |ui, rows| {
+        for row in rows.map(|r| shown.start + r) {
+            let record = index.records()[row];
+            let preview = match index.record_bytes(row) {
+                Ok(bytes) => preview_bytes(&bytes[..bytes.len().min(RECORD_PREVIEW)]),
+                Err(err) => format!("read failed: {err}"),
+            };
+            let mut text = RichText::new(format!(
+                "{:>9}  {:>5}  {:>12}  {:>7}  {}",
+                row, record.tape_file, record.offset, record.len, preview
+            ))
+            .monospace();
+            match levels.get(row).and_then(Option::as_ref) {
+                Some(LogLevel::Error) => text = text.color(Color32::RED),
+                Some(LogLevel::Warning) => text = text.color(Color32::YELLOW),
+                _ => {}
+            }
+            let response = ui.selectable_label(selected == Some(row), text);
+            if response.double_clicked() {
+                clicked = Some((row, true));
+            } else if response.clicked() {
+                clicked = Some((row, false));
+            }
+        }
+    });
+    if let Some((record, hex)) = clicked {
+        select_record(record, app_state);
+        if hex {
+            app_state.tap_state.record_hex_viewer = Some(record);
+            app_state.tap_state.record_hex = HexView::default();
+        }
+    }
+}
+
+/// Records the records view lists: those of the Timeline span filter, or
+/// all `len`.
+pub fn shown_records(state: &AppState, len: usize) -> Range<usize> {
+    match &state.span_filter {
+        Some(span) => (span.records.start as usize).min(len)..(span.records.end as usize).min(len),
+        None => 0..len,
+    }
+}
+
+/// Select a tape record and point the Log tab at the line that reports on
+/// it, if any.
+pub fn select_record(record: usize, state: &mut AppState) {
+    state.tap_state.selected_record = Some(record);
+    let line = line_of_record(&state.log_state.links, record);
+    let view = &mut state.log_state.view;
+    if line.is_some() {
+        view.selected_line = line;
+    }
+    view.scroll_to_line = line;
+}
+
+/// Select a record and open it in the Contents records view.
+pub fn show_record(record: usize, state: &mut AppState) {
+    select_record(record, state);
+    if !shown_records(state, usize::MAX).contains(&record) {
+        state.span_filter = None;
+    }
+    let tap_state = &mut state.tap_state;
+    tap_state.show_records = true;
+    tap_state.record_jump = record;
+    tap_state.scroll_to_record = Some(record);
+    state.current_tab = MainTab::Contents;
+}
+
+/// Hex window for the record chosen in the records view or from a log line;
+/// shown over whichever tab is open.
+pub fn record_hex_window(ctx: &egui::Context, state: &mut AppState) {
+    let Some(record) = state.tap_state.record_hex_viewer else {
+        return;
+    };
+    let Some(block) = state.blocks.get(record) else {
+        state.tap_state.record_hex_viewer = None;
+        return;
+    };
+    let fields = data_fields(&block.raw);
+    let view = &mut state.tap_state.record_hex;
+    let mut open = true;
+    Window::new(format!("Record {record}"))
+        .id(egui::Id::new("record_hex_viewer"))
+        .resizable(true)
+        .default_size(Vec2::new(720.0, 480.0))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "{} bytes, tape file {}",
+                block.size, block.tape_file
+            ));
+            ui.separator();
+            hex_viewer(ui, &block.raw, &fields, view);
+        });
+    if !open {
+        state.tap_state.record_hex_viewer = None;
+    }
+}
+
+fn preview_bytes(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02X}")).collect();
+    let ascii: String = bytes
+        .iter()
+        .map(|&b| {
+            if (0x20..0x7F).contains(&b) {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    format!("{:<48} {}", hex.join(" "), ascii)
+}
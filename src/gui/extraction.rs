@@ -1,25 +1,34 @@
-//! Extraction tab: lets users choose an output folder and write assembled file payloads to disk.
+//! Extraction tab: lets users choose an output folder and write the reconstructed files to disk.
 use egui::{self, Align, Layout, ScrollArea, Vec2, Window};
 use rfd::FileDialog;
-use std::fs;
 use std::path::PathBuf;
 
-use crate::backup::extract::{assemble_files, assemble_vms_files, ExtractedFile, VmsFile};
-use crate::tap::legacy::TapEntry;
-use crate::utils::hex::format_hex;
+use crate::core::block::{block_at, TapeBlock};
+use crate::core::extract::{extract_tree, file_bytes};
+use crate::core::fields::data_fields;
+use crate::core::file::TapeFile;
 use crate::utils::text::{is_mostly_text, sanitize_display};
 
+use super::hexview::{hex_viewer, HexView};
+
 #[derive(Debug, Default, Clone)]
 pub struct ExtractionState {
     pub output_dir: Option<PathBuf>,
     pub status: String,
     pub hex_view: Option<(String, Vec<u8>)>,
+    pub hex: HexView,
 }
 
-/// Render the Extraction tab UI.
-pub fn extraction_tab(ui: &mut egui::Ui, entries: &[TapEntry], state: &mut ExtractionState) {
-    let vms_files = assemble_vms_files(entries);
-    let files = assemble_files(entries);
+/// Render the Extraction tab UI: every file of the reconstructed tree, with
+/// a hex view of its contents, extracted as a whole with "Extract Files".
+pub fn extraction_tab(
+    ui: &mut egui::Ui,
+    tree: &[TapeFile],
+    blocks: &[TapeBlock],
+    state: &mut ExtractionState,
+) {
+    let mut files = Vec::new();
+    collect_files(tree, &mut files);
 
     ui.heading("Extracted Files");
     ui.separator();
@@ -27,58 +36,24 @@ pub fn extraction_tab(ui: &mut egui::Ui, entries: &[TapEntry], state: &mut Extra
     ScrollArea::vertical()
         .auto_shrink([false, false])
         .show(ui, |ui| {
-            if !vms_files.is_empty() {
-                for file in &vms_files {
-                    let payload_len: usize = file.blocks.iter().map(|b| b.payload.len()).sum();
-                    let sanitized_name = sanitize_display(&format!(
-                        "{};{}",
-                        file.headers.full_name(),
-                        file.headers.version
-                    ));
-                    let text_hint = if is_mostly_text(&collect_vms_prefix(file, 512)) {
-                        "(text)"
-                    } else {
-                        "(binary)"
-                    };
-                    ui.horizontal_wrapped(|ui| {
-                        ui.label(sanitized_name);
-                        ui.label(format!("blocks: {}", file.blocks.len()));
-                        ui.label(format!("payload: {} bytes", payload_len));
-                        ui.label(format!(
-                            "type: {}",
-                            record_type_label(&file.blocks, !file.headers.file_type.is_empty())
-                        ));
-                        ui.label(format!("UIC {:X}", file.headers.owner_uic));
-                        ui.label(text_hint);
-                        if ui.button("View details").clicked() {
-                            let name_with_version =
-                                format!("{};{}", file.headers.full_name(), file.headers.version);
-                            state
-                                .hex_view
-                                .replace((name_with_version, collect_vms_all(file)));
-                        }
-                    });
-                }
-            } else {
-                for file in &files {
-                    let payload_len: usize = file.blocks.iter().map(|b| b.payload.len()).sum();
-                    let sanitized_name = sanitize_display(&file.name);
-                    let text_hint = if is_mostly_text(&collect_prefix(file, 512)) {
-                        "(text)"
-                    } else {
-                        "(binary)"
-                    };
-                    ui.horizontal_wrapped(|ui| {
-                        ui.label(sanitized_name);
-                        ui.label(format!("blocks: {}", file.blocks.len()));
-                        ui.label(format!("payload: {} bytes", payload_len));
-                        ui.label(format!("type: {}", record_type_label(&file.blocks, false)));
-                        ui.label(text_hint);
-                        if ui.button("View details").clicked() {
-                            state.hex_view = Some((file.name.clone(), collect_all(file)));
-                        }
-                    });
-                }
+            for file in &files {
+                let name = file.path.to_string_path();
+                let text_hint = if is_mostly_text(&file_prefix(file, blocks, 512)) {
+                    "(text)"
+                } else {
+                    "(binary)"
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(sanitize_display(&name));
+                    ui.label(format!("blocks: {}", file.blocks.len()));
+                    ui.label(format!("payload: {} bytes", file.size_bytes));
+                    ui.label(format!("type: {:?}", file.format));
+                    ui.label(text_hint);
+                    if ui.button("View details").clicked() {
+                        state.hex_view = Some((name.clone(), file_bytes(file, blocks)));
+                        state.hex = HexView::default();
+                    }
+                });
             }
         });
 
@@ -94,19 +69,10 @@ pub fn extraction_tab(ui: &mut egui::Ui, entries: &[TapEntry], state: &mut Extra
     if ui.button("Extract Files").clicked() {
         match &state.output_dir {
             Some(dir) => {
-                let mut errors = Vec::new();
-                for file in &files {
-                    let path = dir.join(&file.name);
-                    if let Err(err) = write_file(&path, file) {
-                        errors.push(format!("{}: {}", file.name, err));
-                    }
-                }
-
-                if errors.is_empty() {
-                    state.status = format!("Extracted {} file(s)", files.len());
-                } else {
-                    state.status = format!("Errors: {}", errors.join("; "));
-                }
+                state.status = match extract_tree(tree, blocks, dir) {
+                    Ok(count) => format!("Extracted {} file(s)", count),
+                    Err(err) => format!("Extraction failed: {}", err),
+                };
             }
             None => {
                 state.status = "Choose an output directory first".to_string();
@@ -135,14 +101,7 @@ pub fn extraction_tab(ui: &mut egui::Ui, entries: &[TapEntry], state: &mut Extra
                 });
                 ui.separator();
                 ui.heading(sanitize_display(&name));
-                ScrollArea::vertical()
-                    .auto_shrink([false, false])
-                    .show(ui, |ui| {
-                        ui.monospace(format_hex(&bytes));
-                    });
-                if ui.button("Close").clicked() {
-                    close = true;
-                }
+                hex_viewer(ui, &bytes, &data_fields(&bytes), &mut state.hex);
             });
         if close || !open {
             state.hex_view = None;
@@ -150,67 +109,42 @@ pub fn extraction_tab(ui: &mut egui::Ui, entries: &[TapEntry], state: &mut Extra
     }
 }
 
-fn write_file(path: &PathBuf, file: &ExtractedFile) -> Result<(), String> {
-    fs::write(path, file.payload())
-        .map_err(|err| format!("failed to write {}: {}", path.display(), err))
-}
-
-fn record_type_label(blocks: &[crate::backup::vms::BackupBlock], has_header: bool) -> &'static str {
-    if blocks.is_empty() {
-        "Unknown"
-    } else {
-        if has_header {
-            "VMS BACKUP file"
+/// Leaves of the reconstructed tree, in tree order.
+fn collect_files<'a>(nodes: &'a [TapeFile], out: &mut Vec<&'a TapeFile>) {
+    for node in nodes {
+        if node.children.is_empty() {
+            out.push(node);
         } else {
-            "VMS BACKUP block"
+            collect_files(&node.children, out);
         }
     }
 }
 
-fn collect_prefix(file: &ExtractedFile, limit: usize) -> Vec<u8> {
+/// Up to `limit` leading bytes of a file, reading only the records needed.
+fn file_prefix(file: &TapeFile, blocks: &[TapeBlock], limit: usize) -> Vec<u8> {
+    let find = |idx: u32| block_at(blocks, idx);
     let mut data = Vec::new();
-    for block in &file.blocks {
-        for byte in &block.payload {
+    if file.extents.is_empty() {
+        for b in file.blocks.iter().filter_map(|idx| find(*idx)) {
+            data.extend_from_slice(b.raw.as_ref());
             if data.len() >= limit {
-                return data;
+                break;
             }
-            data.push(*byte);
         }
-        if data.len() >= limit {
-            break;
-        }
-    }
-    data
-}
-
-fn collect_all(file: &ExtractedFile) -> Vec<u8> {
-    let mut data = Vec::new();
-    for block in &file.blocks {
-        data.extend_from_slice(&block.payload);
-    }
-    data
-}
-
-fn collect_vms_prefix(file: &VmsFile, limit: usize) -> Vec<u8> {
-    let mut data = Vec::new();
-    for block in &file.blocks {
-        for byte in &block.payload {
+    } else {
+        for ext in &file.extents {
+            if ext.is_hole() {
+                data.resize(data.len() + ext.len, 0);
+            } else if let Some(chunk) =
+                find(ext.block).and_then(|b| b.raw.get(ext.offset..ext.offset + ext.len))
+            {
+                data.extend_from_slice(chunk);
+            }
             if data.len() >= limit {
-                return data;
+                break;
             }
-            data.push(*byte);
-        }
-        if data.len() >= limit {
-            break;
         }
     }
-    data
-}
-
-fn collect_vms_all(file: &VmsFile) -> Vec<u8> {
-    let mut data = Vec::new();
-    for block in &file.blocks {
-        data.extend_from_slice(&block.payload);
-    }
+    data.truncate(limit);
     data
 }
@@ -1,30 +1,61 @@
 //! Files tab: displays reconstructed file tree with metadata and hex viewers.
-use egui::TextStyle;
-use egui::{self, Align, Layout, ScrollArea, Vec2, Window};
+use egui::{self, Align, Layout, ScrollArea, SidePanel, Vec2, Window};
 use rfd::FileDialog;
 
 use crate::core::block::TapeBlock;
-use crate::core::extract::extract_file;
-use crate::core::file::{FileMetadata, TapeFile};
+use crate::core::extract::{extract_file, host_bytes};
+use crate::core::fields::data_fields;
+use crate::core::file::TapeFile;
+use crate::core::handler::registry;
+use crate::core::timeline::files_in_records;
 use crate::utils::text::sanitize_display;
 
+use super::hexview::{hex_viewer, HexView};
+use super::preview::{preview_pane, PreviewState};
+use super::search::search_bar;
 use super::state::AppState;
+use super::timeline::span_filter_bar;
+
+/// Content matches linked under each search hit.
+const MATCH_LINKS: usize = 8;
 
 pub fn files_tab(ui: &mut egui::Ui, state: &mut AppState) {
     ui.heading("Files");
+    span_filter_bar(ui, state);
+    search_bar(ui, state);
     ui.separator();
 
-    let flattened = flatten_files_tree(&state.files, 0);
+    let flattened = shown_files(state);
 
     if flattened.is_empty() {
         ui.label("No reconstructed files available.");
         return;
     }
 
+    if let Some(idx) = state.preview.file {
+        match flattened.get(idx) {
+            Some((file, _)) => {
+                SidePanel::right("file_preview")
+                    .resizable(true)
+                    .default_width(ui.available_width() * 0.5)
+                    .show_inside(ui, |ui| {
+                        preview_pane(ui, idx, file, &state.blocks, &mut state.preview);
+                    });
+            }
+            None => state.preview = PreviewState::default(),
+        }
+    }
+
+    let hits = state.file_search.hits.clone();
+    let listed: Vec<usize> = match &hits {
+        Some(hits) => hits.iter().map(|hit| hit.index).collect(),
+        None => (0..flattened.len()).collect(),
+    };
     ScrollArea::vertical()
         .auto_shrink([false, false])
         .show(ui, |ui| {
-            for (idx, (file, depth)) in flattened.iter().enumerate() {
+            for (row, &idx) in listed.iter().enumerate() {
+                let (file, depth) = &flattened[idx];
                 ui.horizontal(|ui| {
                     ui.label(format!("{:>4}", idx));
                     ui.add_space(8.0);
@@ -61,11 +92,34 @@ pub fn files_tab(ui: &mut egui::Ui, state: &mut AppState) {
                         state.selected_file = Some(idx);
                         state.file_hex_viewer = None;
                     }
+                    if file.children.is_empty()
+                        && !file.blocks.is_empty()
+                        && ui.button("Preview").clicked()
+                    {
+                        state.preview.open(idx);
+                    }
                     if !file.blocks.is_empty() && ui.button("Hex").clicked() {
                         state.file_hex_viewer = Some(idx);
+                        state.file_hex = HexView::default();
                         state.selected_file = None;
                     }
                 });
+                let matches = hits.as_ref().map_or(&[][..], |h| &h[row].matches[..]);
+                if !matches.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.add_space(48.0);
+                        for m in matches.iter().take(MATCH_LINKS) {
+                            if ui.small_button(format!("@{}", m.offset)).clicked() {
+                                state.file_hex_viewer = Some(idx);
+                                state.file_hex = HexView::at(m.offset..m.offset + m.len);
+                                state.selected_file = None;
+                            }
+                        }
+                        if matches.len() > MATCH_LINKS {
+                            ui.label(format!("+{} more", matches.len() - MATCH_LINKS));
+                        }
+                    });
+                }
                 ui.separator();
             }
         });
@@ -104,6 +158,7 @@ pub fn files_tab(ui: &mut egui::Ui, state: &mut AppState) {
                 state.file_hex_viewer = None;
             } else if open_hex {
                 state.file_hex_viewer = Some(idx);
+                state.file_hex = HexView::default();
             }
         } else {
             state.selected_file = None;
@@ -131,39 +186,7 @@ pub fn files_tab(ui: &mut egui::Ui, state: &mut AppState) {
                     });
                     ui.separator();
                     ui.heading(sanitize_display(&file.path.to_string_path()));
-                    ScrollArea::vertical()
-                        .auto_shrink([false, false])
-                        .show(ui, |ui| {
-                            ui.style_mut().override_text_style = Some(TextStyle::Monospace);
-                            let mut line = 0usize;
-                            let mut pos = 0usize;
-                            while pos < bytes.len() {
-                                let chunk = &bytes[pos..usize::min(pos + 16, bytes.len())];
-                                let mut hex_part = String::new();
-                                let mut ascii_part = String::new();
-                                for b in chunk {
-                                    hex_part.push_str(&format!("{:02X} ", b));
-                                    let c = char::from(*b);
-                                    if c.is_ascii_graphic() || c == ' ' {
-                                        ascii_part.push(c);
-                                    } else {
-                                        ascii_part.push('.');
-                                    }
-                                }
-                                ui.label(format!(
-                                    "{:08X}:  {:<48}  |{}|",
-                                    line * 16,
-                                    hex_part,
-                                    ascii_part
-                                ));
-                                pos += 16;
-                                line += 1;
-                            }
-                            ui.style_mut().override_text_style = None;
-                        });
-                    if ui.button("Close").clicked() {
-                        close = true;
-                    }
+                    hex_viewer(ui, &bytes, &data_fields(&bytes), &mut state.file_hex);
                 });
             if close || !open {
                 state.file_hex_viewer = None;
@@ -190,88 +213,18 @@ pub fn flatten_files_tree(files: &[TapeFile], depth: usize) -> Vec<(TapeFile, us
     flat
 }
 
-pub fn describe_metadata(file: &TapeFile) -> Vec<String> {
-    match &file.metadata {
-        FileMetadata::Rsx(meta) => vec![
-            format!("Directory: {}", meta.is_directory),
-            format!("UIC {:03o},{:03o}", meta.uic.0, meta.uic.1),
-            format!("Protection: {:o}", meta.protection),
-        ],
-        FileMetadata::Rt11(meta) => vec![
-            format!(
-                "File: {}",
-                sanitize_display(&if meta.ext.is_empty() {
-                    file.path.to_string_path()
-                } else {
-                    format!("{}.{}", file.path.to_string_path(), meta.ext)
-                })
-            ),
-            format!("Start block: {}", meta.start_block),
-            format!("Length (blocks): {}", meta.length_blocks),
-        ],
-        FileMetadata::Rsts(meta) => vec![
-            format!(
-                "Owner UIC {:03o},{:03o}",
-                meta.owner_uic.0, meta.owner_uic.1
-            ),
-            format!("Status: 0x{:04X}", meta.status),
-            format!("Blocks: {}", meta.blocks),
-        ],
-        FileMetadata::Vms(meta) => {
-            let mut out = Vec::new();
-            out.push(format!(
-                "File ID: ({}, {}, {})",
-                meta.file_id.0, meta.file_id.1, meta.file_id.2
-            ));
-            out.push(format!("Revision: {}", meta.rev));
-            out.push(format!("Sequence: {}", meta.seq));
-            out.push(format!(
-                "Owner UIC: {:o},{:o}",
-                meta.owner_uic.0, meta.owner_uic.1
-            ));
-            out.push(format!("Protection: {:o}", meta.protection));
-            out.push(format!(
-                "Record Format: {}",
-                describe_record_format(meta.record_format)
-            ));
-            out.push(format!(
-                "Record Attributes: 0x{:02X}",
-                meta.record_attributes
-            ));
-            out.push(format!("Record Length: {}", meta.record_length));
-            out.push(format!("File Type: {}", meta.file_type));
-            out.push(format!("Backup Flags: 0x{:04X}", meta.backup_flags));
-            if let Some(t) = &meta.creation_time {
-                out.push(format!("Created: {}", t));
-            }
-            if let Some(t) = &meta.revision_time {
-                out.push(format!("Revised: {}", t));
-            }
-            if let Some(t) = &meta.expiration_time {
-                out.push(format!("Expires: {}", t));
-            }
-            out
-        }
-        FileMetadata::Raw => vec!["Raw data, no metadata".to_string()],
+/// Flattened file tree, limited to the Timeline span filter when one is set.
+pub fn shown_files(state: &AppState) -> Vec<(TapeFile, usize)> {
+    match &state.span_filter {
+        Some(span) => flatten_files_tree(&files_in_records(&state.files, &span.records), 0),
+        None => flatten_files_tree(&state.files, 0),
     }
 }
 
-fn describe_record_format(rfm: u8) -> &'static str {
-    match rfm {
-        0 => "Undefined",
-        1 => "Fixed",
-        2 => "Variable",
-        3 => "VFC",
-        _ => "Unknown",
-    }
+pub fn describe_metadata(file: &TapeFile) -> Vec<String> {
+    registry().describe(file)
 }
 
 pub fn collect_block_bytes(file: &TapeFile, blocks: &[TapeBlock]) -> Vec<u8> {
-    let mut bytes = Vec::new();
-    for idx in &file.blocks {
-        if let Some(block) = blocks.iter().find(|b| b.index == *idx) {
-            bytes.extend_from_slice(block.raw.as_ref());
-        }
-    }
-    bytes
+    host_bytes(file, blocks)
 }
@@ -0,0 +1,115 @@
+//! Health tab: one colored square per tape record, zoomable, with a click
+//! opening the record in Contents and PNG/SVG export for condition reports.
+use std::fs;
+
+use egui::{self, Color32, Rect, ScrollArea, Sense, Slider, Stroke, Vec2};
+use rfd::FileDialog;
+
+use crate::health::export::{health_png, health_svg};
+use crate::health::{HealthMap, RecordHealth};
+
+use super::contents::show_record;
+use super::state::AppState;
+
+/// Export grid width and cell size, in records and pixels.
+const EXPORT_COLUMNS: usize = 64;
+const EXPORT_CELL: usize = 8;
+
+fn color(health: RecordHealth) -> Color32 {
+    let [r, g, b] = health.rgb();
+    Color32::from_rgb(r, g, b)
+}
+
+/// Render the health map of the loaded tape.
+pub fn health_tab(ui: &mut egui::Ui, state: &mut AppState) {
+    let Some(map) = state.health.clone().filter(|m| !m.records.is_empty()) else {
+        ui.label("No tape records loaded.");
+        return;
+    };
+    ui.horizontal_wrapped(|ui| {
+        for (health, count) in map.counts() {
+            let (rect, _) = ui.allocate_exact_size(Vec2::splat(12.0), Sense::hover());
+            ui.painter().rect_filled(rect, 0.0, color(health));
+            ui.label(format!("{}: {count}", health.label()));
+            ui.add_space(8.0);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Zoom");
+        ui.add(Slider::new(&mut state.health_cell, 2.0..=32.0).suffix(" pt"));
+        ui.add_space(12.0);
+        if ui.button("Export PNG").clicked() {
+            export(state, &map, "png");
+        }
+        if ui.button("Export SVG").clicked() {
+            export(state, &map, "svg");
+        }
+    });
+    if !state.summary_status.is_empty() {
+        ui.label(&state.summary_status);
+    }
+    ui.separator();
+
+    let cell = state.health_cell;
+    let selected = state.tap_state.selected_record;
+    let mut clicked = None;
+    ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show_viewport(ui, |ui, viewport| {
+            let columns = ((ui.available_width() / cell) as usize).max(1);
+            let rows = map.records.len().div_ceil(columns);
+            let size = Vec2::new(columns as f32 * cell, rows as f32 * cell);
+            let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+            let painter = ui.painter_at(rect);
+            // Only the rows inside the viewport are painted.
+            let first = (viewport.min.y / cell).floor().max(0.0) as usize;
+            let last = ((viewport.max.y / cell).ceil() as usize).min(rows);
+            let gap = if cell >= 4.0 { 1.0 } else { 0.0 };
+            for row in first..last {
+                for col in 0..columns {
+                    let record = row * columns + col;
+                    let Some(&health) = map.records.get(record) else {
+                        break;
+                    };
+                    let min = rect.min + Vec2::new(col as f32 * cell, row as f32 * cell);
+                    let square = Rect::from_min_size(min, Vec2::splat(cell - gap));
+                    painter.rect_filled(square, 0.0, color(health));
+                    if selected == Some(record) {
+                        painter.rect_stroke(square, 0.0, Stroke::new(2.0, Color32::WHITE));
+                    }
+                }
+            }
+            let hovered = response.hover_pos().and_then(|pos| {
+                let offset = pos - rect.min;
+                let record = (offset.y / cell) as usize * columns + (offset.x / cell) as usize;
+                map.records.get(record).map(|h| (record, *h))
+            });
+            if let Some((record, health)) = hovered {
+                if response.clicked() {
+                    clicked = Some(record);
+                }
+                response.on_hover_text(format!("record {record}: {}", health.label()));
+            }
+        });
+    if let Some(record) = clicked {
+        show_record(record, state);
+    }
+}
+
+fn export(state: &mut AppState, map: &HealthMap, extension: &str) {
+    let Some(path) = FileDialog::new()
+        .add_filter(extension, &[extension])
+        .set_file_name(format!("health.{extension}"))
+        .save_file()
+    else {
+        return;
+    };
+    let written = match extension {
+        "png" => health_png(map, EXPORT_COLUMNS, EXPORT_CELL).and_then(|png| fs::write(&path, png)),
+        _ => fs::write(&path, health_svg(map, EXPORT_COLUMNS, EXPORT_CELL)),
+    };
+    state.summary_status = match written {
+        Ok(()) => format!("Health map written to {}", path.display()),
+        Err(err) => format!("Health map export failed: {err}"),
+    };
+}
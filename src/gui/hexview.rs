@@ -0,0 +1,275 @@
+//! Shared hex viewer: virtualized rows with decoder field overlays, hex,
+//! octal, word and RAD50 views, go-to-offset and byte or text search.
+use std::ops::Range;
+
+use egui::{self, Color32, Label, RichText, ScrollArea, Sense, TextEdit, TextStyle};
+
+use crate::core::fields::{Field, FieldKind};
+use crate::core::parse::rad50_chars;
+use crate::utils::hex::{find_bytes, parse_hex_bytes, parse_offset};
+
+const ROW_BYTES: usize = 16;
+
+/// How each row's bytes are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HexMode {
+    #[default]
+    Hex,
+    /// Bytes in octal.
+    Octal,
+    /// Little-endian 16-bit words in octal.
+    Words,
+    /// Little-endian 16-bit words decoded as RAD50.
+    Rad50,
+}
+
+impl HexMode {
+    pub const ALL: [HexMode; 4] = [HexMode::Hex, HexMode::Octal, HexMode::Words, HexMode::Rad50];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HexMode::Hex => "Hex",
+            HexMode::Octal => "Octal",
+            HexMode::Words => "Words",
+            HexMode::Rad50 => "RAD50",
+        }
+    }
+}
+
+/// Per-window viewer state.
+#[derive(Debug, Clone, Default)]
+pub struct HexView {
+    pub mode: HexMode,
+    pub goto: String,
+    pub search: String,
+    /// Search text is hex byte pairs rather than ASCII.
+    pub search_hex: bool,
+    /// Bytes matched by the last search or go-to.
+    pub found: Option<Range<usize>>,
+    pub scroll_to_row: Option<usize>,
+    pub status: String,
+}
+
+impl HexView {
+    /// View scrolled to `range`, highlighted as found.
+    pub fn at(range: Range<usize>) -> Self {
+        HexView {
+            goto: format!("0x{:X}", range.start),
+            scroll_to_row: Some(range.start / ROW_BYTES),
+            found: Some(range),
+            ..HexView::default()
+        }
+    }
+}
+
+/// Offset column: hex in hex mode, octal in the PDP-11 views.
+pub fn format_address(offset: usize, mode: HexMode) -> String {
+    match mode {
+        HexMode::Hex => format!("{offset:08X}"),
+        _ => format!("{offset:011o}"),
+    }
+}
+
+/// Cells of one row as (byte offset in the row, bytes covered, text). Word
+/// views show a trailing odd byte as an octal byte.
+pub fn row_cells(chunk: &[u8], mode: HexMode) -> Vec<(usize, usize, String)> {
+    match mode {
+        HexMode::Hex => chunk
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (i, 1, format!("{b:02X}")))
+            .collect(),
+        HexMode::Octal => chunk
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (i, 1, format!("{b:03o}")))
+            .collect(),
+        HexMode::Words | HexMode::Rad50 => chunk
+            .chunks(2)
+            .enumerate()
+            .map(|(i, pair)| match pair {
+                [lo, hi] => {
+                    let word = u16::from_le_bytes([*lo, *hi]);
+                    let text = match mode {
+                        HexMode::Rad50 => rad50_chars(word).unwrap_or_else(|| "???".into()),
+                        _ => format!("{word:06o}"),
+                    };
+                    (i * 2, 2, text)
+                }
+                _ => (i * 2, 1, format!("{:03o}", pair[0])),
+            })
+            .collect(),
+    }
+}
+
+fn field_color(kind: FieldKind, alternate: bool) -> Color32 {
+    let (r, g, b) = match kind {
+        FieldKind::Label => (0x4A, 0x7F, 0xC1),
+        FieldKind::BlockHeader => (0x3C, 0x9D, 0x6B),
+        FieldKind::RecordHeader => (0xC1, 0x7F, 0x2E),
+        FieldKind::Attribute => (0x9B, 0x59, 0xB6),
+        FieldKind::HomeBlock => (0x2E, 0x9D, 0xB0),
+        FieldKind::DirectoryEntry => (0xB0, 0x9D, 0x2E),
+    };
+    Color32::from_rgba_unmultiplied(r, g, b, if alternate { 120 } else { 70 })
+}
+
+/// Index and field covering `offset`, from fields sorted by offset.
+fn field_index(fields: &[Field], offset: usize) -> Option<(usize, &Field)> {
+    let idx = fields.partition_point(|f| f.range.start <= offset);
+    let i = idx.checked_sub(1)?;
+    fields[i].range.contains(&offset).then(|| (i, &fields[i]))
+}
+
+/// Run a go-to or search request from the controls.
+fn seek(bytes: &[u8], view: &mut HexView, search: bool) {
+    if search {
+        let needle = if view.search_hex {
+            match parse_hex_bytes(&view.search) {
+                Some(needle) => needle,
+                None => {
+                    view.status = "Enter hex byte pairs, e.g. 48 44 52 31".into();
+                    return;
+                }
+            }
+        } else {
+            view.search.as_bytes().to_vec()
+        };
+        let from = view.found.as_ref().map_or(0, |r| r.start + 1);
+        match find_bytes(bytes, &needle, from) {
+            Some(at) => {
+                view.found = Some(at..at + needle.len());
+                view.scroll_to_row = Some(at / ROW_BYTES);
+                view.status = format!("Found at {}", format_address(at, view.mode));
+            }
+            None => view.status = "Not found".into(),
+        }
+    } else {
+        match parse_offset(&view.goto) {
+            Some(at) if at < bytes.len() => {
+                view.found = Some(at..at + 1);
+                view.scroll_to_row = Some(at / ROW_BYTES);
+                view.status.clear();
+            }
+            Some(_) => view.status = format!("Offset past the end ({} bytes)", bytes.len()),
+            None => view.status = "Offsets are decimal, 0x hex or 0o octal".into(),
+        }
+    }
+}
+
+/// Render `bytes` with `fields` (sorted by offset) overlaid. Only the rows
+/// in view are laid out, so large files scroll smoothly.
+pub fn hex_viewer(ui: &mut egui::Ui, bytes: &[u8], fields: &[Field], view: &mut HexView) {
+    if view.found.as_ref().is_some_and(|r| r.end > bytes.len()) {
+        view.found = None;
+    }
+    ui.horizontal(|ui| {
+        for mode in HexMode::ALL {
+            ui.selectable_value(&mut view.mode, mode, mode.label());
+        }
+        ui.separator();
+        ui.label("Go to");
+        let goto = ui.add(TextEdit::singleline(&mut view.goto).desired_width(80.0));
+        let enter = goto.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.button("Go").clicked() || enter {
+            seek(bytes, view, false);
+        }
+        ui.separator();
+        ui.label("Find");
+        let find = ui.add(TextEdit::singleline(&mut view.search).desired_width(140.0));
+        ui.checkbox(&mut view.search_hex, "Hex bytes");
+        let enter = find.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.button("Find next").clicked() || enter {
+            seek(bytes, view, true);
+        }
+    });
+    if !view.status.is_empty() {
+        ui.label(&view.status);
+    }
+    ui.separator();
+
+    let row_height = ui.text_style_height(&TextStyle::Monospace);
+    let mut area = ScrollArea::vertical()
+        .id_source("hex_viewer_rows")
+        .auto_shrink([false, false]);
+    if let Some(row) = view.scroll_to_row.take() {
+        area = area.vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+    }
+    let rows = bytes.len().div_ceil(ROW_BYTES);
+    let mode = view.mode;
+    let found = view.found.clone();
+    area.show_rows(ui, row_height, rows, |ui, visible| {
+        for row in visible {
+            let start = row * ROW_BYTES;
+            let chunk = &bytes[start..(start + ROW_BYTES).min(bytes.len())];
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                ui.monospace(format!("{}:  ", format_address(start, mode)));
+                for (at, len, text) in row_cells(chunk, mode) {
+                    let offset = start + at;
+                    let gap = if mode == HexMode::Hex && at == 7 {
+                        "  "
+                    } else {
+                        " "
+                    };
+                    let mut cell = RichText::new(format!("{text}{gap}")).monospace();
+                    let field = field_index(fields, offset);
+                    if found.as_ref().is_some_and(|r| r.contains(&offset)) {
+                        cell = cell
+                            .background_color(Color32::from_rgb(0xFF, 0xD7, 0x00))
+                            .color(Color32::BLACK);
+                    } else if let Some((i, field)) = field {
+                        cell = cell.background_color(field_color(field.kind, i % 2 == 1));
+                    }
+                    let response = ui.add(Label::new(cell).sense(Sense::hover()));
+                    if let Some((_, field)) = field {
+                        response.on_hover_text(format!(
+                            "{}\nbytes {}..{} ({} bytes), cell at {}",
+                            field.name,
+                            format_address(field.range.start, mode),
+                            format_address(field.range.end, mode),
+                            field.range.len(),
+                            format_address(offset, mode)
+                        ));
+                    } else if len == 2 {
+                        response.on_hover_text(format_address(offset, mode));
+                    }
+                }
+                let ascii: String = chunk
+                    .iter()
+                    .map(|&b| {
+                        if (0x20..0x7F).contains(&b) {
+                            b as char
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect();
+                ui.monospace(format!(" |{ascii}|"));
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_address, row_cells, HexMode};
+
+    #[test]
+    fn renders_pdp11_views() {
+        // "ABC" in RAD50 is 1*1600 + 2*40 + 3 = 0o3223.
+        let chunk = [0x93, 0x06, 0xFF, 0xFF, 0x41];
+        let cells = |mode| -> Vec<String> {
+            row_cells(&chunk, mode)
+                .into_iter()
+                .map(|(_, _, text)| text)
+                .collect()
+        };
+        assert_eq!(cells(HexMode::Hex), ["93", "06", "FF", "FF", "41"]);
+        assert_eq!(cells(HexMode::Octal)[0], "223");
+        assert_eq!(cells(HexMode::Words), ["003223", "177777", "101"]);
+        assert_eq!(cells(HexMode::Rad50)[..2], ["ABC", "???"]);
+        assert_eq!(format_address(16, HexMode::Hex), "00000010");
+        assert_eq!(format_address(16, HexMode::Words), "00000000020");
+    }
+}
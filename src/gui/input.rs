@@ -1,65 +1,146 @@
 //! Input helpers and tab UI: load TAP/LOG inputs, correlate data, and refresh shared state.
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
 
 use egui;
 use rfd::FileDialog;
 
-use crate::backup::extract::{assemble_vms_files, build_directory_tree};
-use crate::core::block::TapeFormat;
-use crate::core::reconstruct::reconstruct_all;
-use crate::log::parse::{correlate_log, parse_log};
+use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::detect::detect_formats;
+use crate::core::handler::registry;
+use crate::core::rsts::{reconstruct_rsts_disk, rsts_disk_blocks};
+use crate::core::timeline::tape_timeline;
+use crate::health::compute_health_map;
+use crate::log::crosscheck::cross_check;
+use crate::log::parse::{correlate_log, parse_log, record_links};
 use crate::summary::compute_saveset_summary;
-use crate::tap::legacy::{read_tap_records, TapEntry};
-use crate::tap::reader::read_tap_blocks;
-use crate::TapeResult;
+use crate::tap::stream::TapeIndex;
+use crate::{TapeError, TapeResult};
 
+use super::preview::PreviewState;
+use super::search::clear_search;
 use super::state::AppState;
 
 /// Load a `.LOG` file selected in the Input tab and store parsed data.
 pub fn load_log_file(path: &Path, state: &mut AppState) -> TapeResult<()> {
-    let data = parse_log(path)?;
-    correlate_log(&mut state.tap_state.entries, &data);
-    state.log_state.data = Some(data);
-    state.log_state.correlated = true;
+    state.log_state.data = Some(parse_log(path)?);
+    state.log_state.view = Default::default();
+    correlate(state);
     state.tap_state.selected_entry = None;
     state.selected_file = None;
     state.summary = Some(compute_saveset_summary(state));
+    state.health = Some(compute_health_map(state));
     Ok(())
 }
 
-/// Parse a TAP file into entries using simple 512-byte chunks.
-pub fn parse_tap_file(path: &Path) -> TapeResult<Vec<TapEntry>> {
+/// Load an RSTS/E disk image: blocks become disk LBNs and files come from the UFD walk.
+pub fn load_rsts_disk(path: &Path, state: &mut AppState) -> TapeResult<()> {
     let data = fs::read(path)?;
-    if data.is_empty() {
-        return Err(crate::TapeError::Parse("empty TAP file".into()));
-    }
-    read_tap_records(&data)
+    let files = reconstruct_rsts_disk(&data)?;
+    state.blocks = rsts_disk_blocks(&data);
+    state.detected_format = TapeFormat::Rsts;
+    state.detection = None;
+    state.format_override = None;
+    state.files = files;
+    state.tap_state = Default::default();
+    state.timeline.clear();
+    state.span_filter = None;
+    state.log_state.layout = None;
+    state.log_state.links.clear();
+    state.selected_file = None;
+    state.file_hex_viewer = None;
+    state.preview = PreviewState::default();
+    clear_search(state);
+    state.summary = Some(compute_saveset_summary(state));
+    state.health = Some(compute_health_map(state));
+    Ok(())
 }
 
-/// Store TAP entries (e.g., after reading a TAP image) and correlate with any loaded log.
-pub fn set_tap_entries(entries: Vec<TapEntry>, state: &mut AppState) {
-    state.tap_state.entries = entries;
+/// Load tape records into the shared model every tab reads: classify them
+/// once, rebuild the file tree of the detected format and correlate with any
+/// loaded log.
+pub fn set_tape_blocks(mut blocks: Vec<TapeBlock>, state: &mut AppState) -> TapeResult<()> {
+    if blocks.is_empty() {
+        return Err(TapeError::Parse("empty TAP file".into()));
+    }
+    let detection = detect_formats(&mut blocks);
+    state.blocks = blocks;
+    state.detected_format = detection.winner.clone();
+    state.detection = Some(detection);
+    state.format_override = None;
+    state.tap_state.record_levels.clear();
     state.tap_state.selected_entry = None;
+    state.tap_state.selected_record = None;
+    state.tap_state.record_hex_viewer = None;
     state.selected_file = None;
     state.file_hex_viewer = None;
-    state.files = if state.detected_format == TapeFormat::Vms {
-        crate::core::vms::reconstruct::reconstruct_vms(&state.blocks)
-    } else {
-        reconstruct_all(&state.blocks)
+    state.preview = PreviewState::default();
+    clear_search(state);
+    state.files = registry().reconstruct_as(&state.blocks, &state.detected_format);
+    state.timeline = tape_timeline(&state.blocks);
+    state.span_filter = None;
+    correlate(state);
+    state.summary = Some(compute_saveset_summary(state));
+    state.health = Some(compute_health_map(state));
+    Ok(())
+}
+
+/// Take record log levels from the loaded log and, once tape records are
+/// loaded too, check the logged record layout against them.
+fn correlate(state: &mut AppState) {
+    let Some(log) = &state.log_state.data else {
+        return;
     };
-    // Build VMS file structures for Files tab.
-    state.vms_files = assemble_vms_files(&state.tap_state.entries);
-    state.vms_fs = if state.vms_files.is_empty() {
-        None
-    } else {
-        Some(build_directory_tree(&state.vms_files))
+    let sizes: Vec<usize> = state.blocks.iter().map(|b| b.size).collect();
+    state.tap_state.record_levels = correlate_log(&sizes, log);
+    state.log_state.links = record_links(log, &sizes);
+    state.log_state.layout = (!state.blocks.is_empty()).then(|| cross_check(log, &state.blocks));
+    state.log_state.correlated = true;
+}
+
+/// Decode the loaded tape as `format`, or as the detected winner for `None`,
+/// and rebuild the file tree and summary.
+pub fn set_format_override(format: Option<TapeFormat>, state: &mut AppState) {
+    state.detected_format = format
+        .clone()
+        .or_else(|| state.detection.as_ref().map(|d| d.winner.clone()))
+        .unwrap_or(TapeFormat::Unknown);
+    state.format_override = format;
+    state.selected_file = None;
+    state.file_hex_viewer = None;
+    state.preview = PreviewState::default();
+    clear_search(state);
+    state.files = registry().reconstruct_as(&state.blocks, &state.detected_format);
+    state.summary = Some(compute_saveset_summary(state));
+    state.health = Some(compute_health_map(state));
+}
+
+/// Load a tape image or `dd` directory and report the container it was read
+/// from. The image is indexed once; the decoders get its records as ranges of
+/// the image and the index is kept for the Contents tab.
+fn load_tape(path: &Path, state: &mut AppState) {
+    let loaded = TapeIndex::open(path).and_then(|index| {
+        let blocks = index.blocks()?;
+        Ok((index, blocks))
+    });
+    let (index, blocks) = match loaded {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            state.summary_status = format!("TAP load failed: {err}");
+            return;
+        }
     };
-    if let Some(log) = &state.log_state.data {
-        correlate_log(&mut state.tap_state.entries, log);
-        state.log_state.correlated = true;
+    match set_tape_blocks(blocks, state) {
+        Ok(()) => {
+            state.summary_status = format!("Loaded {} {}", index.format().label(), path.display());
+            state.tap_state.index = Some(Arc::new(index));
+            state.tap_state.scroll_to_record = None;
+            // SIMH error flags live in the index, stored after the records.
+            state.health = Some(compute_health_map(state));
+        }
+        Err(err) => state.summary_status = format!("TAP load failed: {err}"),
     }
-    state.summary = Some(compute_saveset_summary(state));
 }
 
 /// Render the Input tab with file pickers for TAP/LOG.
@@ -67,36 +148,27 @@ pub fn input_tab(ui: &mut egui::Ui, state: &mut AppState) {
     ui.heading("Load media");
     ui.horizontal(|ui| {
         if ui.button("Load TAP file").clicked() {
-            if let Some(path) = FileDialog::new().add_filter("TAP", &["tap"]).pick_file() {
-                let mut tap_status = None;
-                if let Some(path_str) = path.to_str() {
-                    match read_tap_blocks(path_str) {
-                        Ok(mut blocks) => {
-                            let detected = crate::core::detect::analyze_blocks(&mut blocks);
-                            state.blocks = blocks;
-                            state.detected_format = detected.clone();
-                            state.files = if detected == TapeFormat::Vms {
-                                crate::core::vms::reconstruct::reconstruct_vms(&state.blocks)
-                            } else {
-                                reconstruct_all(&state.blocks)
-                            };
-                        }
-                        Err(err) => tap_status = Some(format!("TAP load failed: {err}")),
-                    }
-                } else {
-                    tap_status = Some("TAP load failed: invalid path encoding".to_string());
-                }
-
-                match parse_tap_file(&path) {
-                    Ok(entries) => {
-                        set_tap_entries(entries, state);
-                        state.summary_status = format!("Loaded TAP {}", path.display());
-                    }
-                    Err(err) => tap_status = Some(format!("TAP load failed: {err}")),
-                }
-
-                if let Some(status) = tap_status {
-                    state.summary_status = status;
+            if let Some(path) = FileDialog::new()
+                .add_filter("Tape image", &["tap", "e11", "aws", "het", "tpc"])
+                .add_filter("All files", &["*"])
+                .pick_file()
+            {
+                load_tape(&path, state);
+            }
+        }
+        if ui.button("Load dd directory").clicked() {
+            if let Some(path) = FileDialog::new().pick_folder() {
+                load_tape(&path, state);
+            }
+        }
+        if ui.button("Load RSTS/E disk").clicked() {
+            if let Some(path) = FileDialog::new()
+                .add_filter("Disk image", &["dsk", "img"])
+                .pick_file()
+            {
+                match load_rsts_disk(&path, state) {
+                    Ok(_) => state.summary_status = format!("Loaded disk {}", path.display()),
+                    Err(err) => state.summary_status = format!("Disk load failed: {err}"),
                 }
             }
         }
@@ -112,4 +184,84 @@ pub fn input_tab(ui: &mut egui::Ui, state: &mut AppState) {
 
     ui.separator();
     ui.label(&state.summary_status);
+
+    ui.separator();
+    format_panel(ui, state);
+}
+
+/// Detection report: confidence per registered handler, the vote of each
+/// tape file, and a selector to decode the tape as another format.
+fn format_panel(ui: &mut egui::Ui, state: &mut AppState) {
+    ui.heading("Format");
+    let Some(detection) = state.detection.clone() else {
+        let names: Vec<&str> = registry().handlers().map(|h| h.name()).collect();
+        ui.label(format!("Registered handlers: {}", names.join(", ")));
+        return;
+    };
+
+    ui.label(format!(
+        "Detected: {} ({:.0}%)",
+        format_name(&detection.winner),
+        detection.confidence(&detection.winner) * 100.0
+    ));
+    egui::Grid::new("format_scores")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Handler");
+            ui.strong("Confidence");
+            ui.strong("Tape files won");
+            ui.end_row();
+            for handler in registry().handlers() {
+                let score = detection
+                    .candidates
+                    .iter()
+                    .find(|c| c.format == handler.format());
+                ui.label(handler.name());
+                match score {
+                    Some(c) => {
+                        ui.label(format!("{:.0}%", c.confidence * 100.0));
+                        ui.label(c.tape_files.to_string());
+                    }
+                    None => {
+                        ui.label("-");
+                        ui.label("-");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    ui.collapsing("Tape file votes", |ui| {
+        for vote in &detection.votes {
+            ui.label(format!(
+                "File {}: {} record(s), {} ({:.0}%)",
+                vote.tape_file,
+                vote.blocks,
+                format_name(&vote.format),
+                vote.confidence * 100.0
+            ));
+        }
+    });
+
+    let mut choice = state.format_override.clone();
+    egui::ComboBox::from_label("Decode as")
+        .selected_text(match &choice {
+            Some(format) => format_name(format),
+            None => "Auto".to_string(),
+        })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut choice, None, "Auto");
+            for handler in registry().handlers() {
+                ui.selectable_value(&mut choice, Some(handler.format()), handler.name());
+            }
+        });
+    if choice != state.format_override {
+        set_format_override(choice, state);
+    }
+}
+
+fn format_name(format: &TapeFormat) -> String {
+    registry()
+        .get(format)
+        .map(|h| h.name().to_string())
+        .unwrap_or_else(|| format!("{format:?}"))
 }
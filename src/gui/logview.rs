@@ -1,51 +1,257 @@
-//! Log tab: renders parsed log metadata and color-coded entries.
-use egui::{self, Color32, Grid, ScrollArea};
-
-use crate::log::parse::{LogData, LogLevel};
-
-/// Render parsed log data with metadata and per-line severity coloring.
-pub fn draw_log(ui: &mut egui::Ui, log: &Option<LogData>) {
-    match log {
-        None => {
-            ui.label("No log loaded");
-        }
-        Some(data) => {
-            ui.heading("Metadata");
-            if data.metadata.is_empty() {
-                ui.label("No metadata found");
-            } else {
-                Grid::new("log_metadata_grid")
-                    .num_columns(2)
-                    .show(ui, |ui| {
-                        for (key, value) in &data.metadata {
-                            ui.label(key);
-                            ui.label(value);
-                            ui.end_row();
-                        }
-                    });
-            }
+//! Log tab: renders parsed log metadata and color-coded entries, with
+//! severity filters, search, folded record runs and links to tape records.
+use std::collections::HashMap;
+use std::ops::Range;
 
-            ui.separator();
-            ui.heading("Entries");
-
-            ScrollArea::vertical()
-                .auto_shrink([false, false])
-                .show(ui, |ui| {
-                    for entry in &data.entries {
-                        let text = &entry.line;
-                        match entry.level {
-                            LogLevel::Info => {
-                                ui.label(text);
-                            }
-                            LogLevel::Warning => {
-                                ui.colored_label(Color32::YELLOW, text);
-                            }
-                            LogLevel::Error => {
-                                ui.colored_label(Color32::RED, text);
+use egui::text::LayoutJob;
+use egui::{
+    self, Align, CollapsingHeader, Color32, Grid, ScrollArea, TextEdit, TextFormat, TextStyle,
+};
+
+use crate::log::parse::{record_of_line, LogData, LogLevel, RecordLink};
+
+use super::contents::show_record;
+use super::hexview::HexView;
+use super::state::{AppState, LogView};
+
+/// One row of the entries list: a single line, or consecutive record-run
+/// lines folded together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogRow {
+    Line(usize),
+    Runs {
+        lines: Vec<usize>,
+        records: Range<usize>,
+    },
+}
+
+/// Rows for the lines passing the view's severity filters and search.
+/// Two or more record-run lines in a row fold into one `Runs` row.
+pub fn log_rows(data: &LogData, view: &LogView) -> Vec<LogRow> {
+    let runs: HashMap<usize, Range<usize>> = data
+        .record_layout()
+        .iter()
+        .map(|r| {
+            let first = r.first as usize;
+            let count = if r.size > 0 { r.count as usize } else { 0 };
+            (r.line, first..first + count)
+        })
+        .collect();
+    let search = view.search.to_ascii_lowercase();
+
+    let mut rows = Vec::new();
+    let mut group: Vec<usize> = Vec::new();
+    for (line, entry) in data.entries.iter().enumerate() {
+        let shown = match entry.level {
+            LogLevel::Info => view.show_info,
+            LogLevel::Warning => view.show_warnings,
+            LogLevel::Error => view.show_errors,
+        };
+        if !shown || !entry.line.to_ascii_lowercase().contains(&search) {
+            continue;
+        }
+        if runs.contains_key(&line) {
+            group.push(line);
+        } else {
+            flush_runs(&mut group, &runs, &mut rows);
+            rows.push(LogRow::Line(line));
+        }
+    }
+    flush_runs(&mut group, &runs, &mut rows);
+    rows
+}
+
+fn flush_runs(group: &mut Vec<usize>, runs: &HashMap<usize, Range<usize>>, rows: &mut Vec<LogRow>) {
+    match group.len() {
+        0 => {}
+        1 => rows.push(LogRow::Line(group[0])),
+        _ => {
+            let first = runs[&group[0]].start;
+            let end = runs[&group[group.len() - 1]].end;
+            rows.push(LogRow::Runs {
+                lines: group.clone(),
+                records: first..end,
+            });
+        }
+    }
+    group.clear();
+}
+
+/// Select the record a log line refers to: in the Contents records view,
+/// or in the record hex window when `hex` is set. Returns false when the
+/// line refers to no loaded record.
+pub fn open_line_record(line: usize, hex: bool, state: &mut AppState) -> bool {
+    let Some(record) = record_of_line(&state.log_state.links, line) else {
+        return false;
+    };
+    if record >= state.blocks.len() {
+        return false;
+    }
+    if hex {
+        state.tap_state.selected_record = Some(record);
+        state.tap_state.record_hex_viewer = Some(record);
+        state.tap_state.record_hex = HexView::default();
+    } else {
+        show_record(record, state);
+    }
+    state.log_state.view.selected_line = Some(line);
+    state.log_state.view.scroll_to_line = None;
+    true
+}
+
+/// Render parsed log data with metadata, the layout cross-check and the
+/// filtered, severity-colored entries.
+pub fn draw_log(ui: &mut egui::Ui, state: &mut AppState) {
+    let log_state = &mut state.log_state;
+    let Some(data) = &log_state.data else {
+        ui.label("No log loaded");
+        return;
+    };
+
+    ui.heading("Metadata");
+    if data.metadata.is_empty() {
+        ui.label("No metadata found");
+    } else {
+        Grid::new("log_metadata_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (key, value) in &data.metadata {
+                    ui.label(key);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+    }
+
+    if let Some(findings) = &log_state.layout {
+        ui.separator();
+        ui.heading("Cross-check with tape");
+        if findings.is_empty() {
+            ui.label("Logged record layout matches the tape image.");
+        }
+        for finding in findings {
+            ui.colored_label(Color32::YELLOW, finding.to_string());
+        }
+    }
+
+    ui.separator();
+    ui.heading("Entries");
+    let view = &mut log_state.view;
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut view.show_info, "Info");
+        ui.checkbox(&mut view.show_warnings, "Warnings");
+        ui.checkbox(&mut view.show_errors, "Errors");
+        ui.add_space(12.0);
+        ui.label("Search");
+        ui.add(TextEdit::singleline(&mut view.search).desired_width(200.0));
+        if ui.button("Clear").clicked() {
+            view.search.clear();
+        }
+    });
+
+    let rows = log_rows(data, view);
+    let links = &log_state.links;
+    // Line to open, and whether in the hex window.
+    let mut open: Option<(usize, bool)> = None;
+    ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            if rows.is_empty() {
+                ui.label("No lines match.");
+            }
+            for row in &rows {
+                match row {
+                    LogRow::Line(line) => draw_line(ui, data, *line, view, links, &mut open),
+                    LogRow::Runs { lines, records } => {
+                        let target = view.scroll_to_line.is_some_and(|l| lines.contains(&l));
+                        CollapsingHeader::new(format!(
+                            "{} record runs: records {}..{}",
+                            lines.len(),
+                            records.start,
+                            records.end
+                        ))
+                        .id_source(("log_runs", lines[0]))
+                        .open(target.then_some(true))
+                        .show(ui, |ui| {
+                            for line in lines {
+                                draw_line(ui, data, *line, view, links, &mut open);
                             }
-                        }
+                        });
                     }
-                });
+                }
+            }
+        });
+    view.scroll_to_line = None;
+
+    if let Some((line, hex)) = open {
+        open_line_record(line, hex, state);
+    }
+}
+
+fn draw_line(
+    ui: &mut egui::Ui,
+    data: &LogData,
+    line: usize,
+    view: &LogView,
+    links: &[RecordLink],
+    open: &mut Option<(usize, bool)>,
+) {
+    let entry = &data.entries[line];
+    let color = match entry.level {
+        LogLevel::Info => ui.visuals().text_color(),
+        LogLevel::Warning => Color32::YELLOW,
+        LogLevel::Error => Color32::RED,
+    };
+    let font = TextStyle::Monospace.resolve(ui.style());
+    let text = format!("{:>6}  {}", line + 1, entry.line);
+    let job = highlight(&text, &view.search, font, color);
+    ui.horizontal(|ui| {
+        let response = match record_of_line(links, line) {
+            Some(record) => {
+                let response = ui
+                    .selectable_label(view.selected_line == Some(line), job)
+                    .on_hover_text(format!("Show record {record} in Contents"));
+                if response.clicked() {
+                    *open = Some((line, false));
+                }
+                if ui.small_button("Hex").clicked() {
+                    *open = Some((line, true));
+                }
+                response
+            }
+            None => ui.label(job),
+        };
+        if view.scroll_to_line == Some(line) {
+            response.scroll_to_me(Some(Align::Center));
+        }
+    });
+}
+
+/// `text` in `color`, with case-insensitive matches of `search` on a
+/// highlighted background.
+fn highlight(text: &str, search: &str, font_id: egui::FontId, color: Color32) -> LayoutJob {
+    let plain = TextFormat {
+        font_id,
+        color,
+        ..Default::default()
+    };
+    let matched = TextFormat {
+        color: Color32::BLACK,
+        background: Color32::from_rgb(0xFF, 0xD7, 0x00),
+        ..plain.clone()
+    };
+    let mut job = LayoutJob::default();
+    let lower = text.to_ascii_lowercase();
+    let needle = search.to_ascii_lowercase();
+    let mut start = 0;
+    if !needle.is_empty() {
+        while let Some(pos) = lower[start..].find(&needle) {
+            let pos = start + pos;
+            job.append(&text[start..pos], 0.0, plain.clone());
+            job.append(&text[pos..pos + needle.len()], 0.0, matched.clone());
+            start = pos + needle.len();
         }
     }
+    job.append(&text[start..], 0.0, plain);
+    job
 }
@@ -2,9 +2,14 @@ pub mod app;
 pub mod contents;
 pub mod extraction;
 pub mod files;
+pub mod health;
+pub mod hexview;
 pub mod input;
 pub mod logview;
+pub mod preview;
+pub mod search;
 pub mod state;
 pub mod summary;
+pub mod timeline;
 
 pub use state::AppState;
@@ -0,0 +1,162 @@
+//! Preview pane of the Files tab: decoded text for text files, the hex
+//! viewer for binary ones.
+use egui::{self, ComboBox, RichText, ScrollArea, TextStyle};
+
+use crate::core::block::TapeBlock;
+use crate::core::extract::host_bytes;
+use crate::core::fields::data_fields;
+use crate::core::file::TapeFile;
+use crate::core::preview::{
+    text_preview, CarriageControl, Charset, PreviewOptions, RecordLayout, TextPreview,
+};
+use crate::utils::text::sanitize_display;
+
+use super::hexview::{hex_viewer, HexView};
+
+#[derive(Debug, Clone, Default)]
+pub struct PreviewState {
+    /// Index in the Files tab list of the file shown.
+    pub file: Option<usize>,
+    /// Options chosen in the pane; `None` follows the file's attributes.
+    pub options: Option<PreviewOptions>,
+    /// Show the hex viewer instead of the text.
+    pub show_hex: bool,
+    pub hex: HexView,
+    loaded: Option<LoadedPreview>,
+}
+
+/// File bytes and their decoding, kept while the file and options stay.
+#[derive(Debug, Clone)]
+struct LoadedPreview {
+    file: usize,
+    bytes: Vec<u8>,
+    options: PreviewOptions,
+    preview: TextPreview,
+    lines: Vec<String>,
+}
+
+impl PreviewState {
+    /// Show file `idx` of the Files tab list, as text when it reads as text.
+    pub fn open(&mut self, idx: usize) {
+        *self = PreviewState {
+            file: Some(idx),
+            ..PreviewState::default()
+        };
+    }
+
+    fn load(&mut self, idx: usize, file: &TapeFile, blocks: &[TapeBlock]) {
+        let fresh = self.loaded.as_ref().is_none_or(|l| l.file != idx);
+        let bytes = match self.loaded.take() {
+            Some(loaded) if !fresh => loaded.bytes,
+            _ => host_bytes(file, blocks),
+        };
+        let options = self
+            .options
+            .unwrap_or_else(|| PreviewOptions::for_file(file, &bytes));
+        let preview = text_preview(&bytes, &options);
+        if fresh {
+            self.show_hex = !preview.is_text;
+        }
+        let lines = preview.text.split('\n').map(str::to_string).collect();
+        self.loaded = Some(LoadedPreview {
+            file: idx,
+            bytes,
+            options,
+            preview,
+            lines,
+        });
+    }
+}
+
+/// Render the preview of `file`, entry `idx` of the Files tab list.
+pub fn preview_pane(
+    ui: &mut egui::Ui,
+    idx: usize,
+    file: &TapeFile,
+    blocks: &[TapeBlock],
+    state: &mut PreviewState,
+) {
+    let stale = match &state.loaded {
+        Some(loaded) => loaded.file != idx || state.options.is_some_and(|o| o != loaded.options),
+        None => true,
+    };
+    if stale {
+        state.load(idx, file, blocks);
+    }
+    let Some(current) = state.loaded.as_ref().map(|l| l.options) else {
+        return;
+    };
+
+    let mut from_file = false;
+    ui.horizontal(|ui| {
+        ui.heading(sanitize_display(&file.path.to_string_path()));
+        if ui.button("Close").clicked() {
+            state.file = None;
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut state.show_hex, false, "Text");
+        ui.selectable_value(&mut state.show_hex, true, "Hex");
+        if !state.show_hex {
+            let mut options = current;
+            ui.separator();
+            ComboBox::from_id_source("preview_layout")
+                .selected_text(format!("Records: {}", options.layout.label()))
+                .show_ui(ui, |ui| {
+                    for layout in RecordLayout::ALL {
+                        ui.selectable_value(&mut options.layout, layout, layout.label());
+                    }
+                });
+            ComboBox::from_id_source("preview_carriage")
+                .selected_text(format!("Carriage: {}", options.carriage.label()))
+                .show_ui(ui, |ui| {
+                    for carriage in CarriageControl::ALL {
+                        ui.selectable_value(&mut options.carriage, carriage, carriage.label());
+                    }
+                });
+            ComboBox::from_id_source("preview_charset")
+                .selected_text(options.charset.label())
+                .show_ui(ui, |ui| {
+                    for charset in Charset::ALL {
+                        ui.selectable_value(&mut options.charset, charset, charset.label());
+                    }
+                });
+            if options != current {
+                state.options = Some(options);
+            }
+            from_file = state.options.is_some() && ui.button("From file").clicked();
+        }
+    });
+    if from_file {
+        state.options = None;
+        state.load(idx, file, blocks);
+    }
+    let Some(loaded) = &state.loaded else {
+        return;
+    };
+    let mut note = format!(
+        "{} bytes, {} records",
+        loaded.bytes.len(),
+        loaded.preview.records
+    );
+    if !loaded.preview.is_text {
+        note.push_str("; does not read as text with these settings");
+    }
+    ui.label(RichText::new(note).weak());
+    ui.separator();
+
+    if state.show_hex {
+        let bytes = &loaded.bytes;
+        hex_viewer(ui, bytes, &data_fields(bytes), &mut state.hex);
+        return;
+    }
+    let row_height = ui.text_style_height(&TextStyle::Monospace);
+    ScrollArea::both()
+        .id_source("preview_text")
+        .auto_shrink([false, false])
+        .show_rows(ui, row_height, loaded.lines.len(), |ui, rows| {
+            for line in &loaded.lines[rows] {
+                ui.monospace(line);
+            }
+        });
+}
@@ -0,0 +1,163 @@
+//! Search bar of the Files tab: name, owner, date, record format, size and
+//! content filters run through `core::search` over the listed files.
+use egui::{self, ComboBox, TextEdit};
+
+use crate::core::search::{
+    search_tape_files, ContentPattern, FileQuery, NamePattern, SearchHit, UicFilter, RECORD_FORMATS,
+};
+
+use super::files::shown_files;
+use super::state::AppState;
+
+/// Query fields as typed, and the hits of the last search.
+#[derive(Debug, Clone, Default)]
+pub struct FileSearch {
+    pub name: String,
+    /// `name` is a regular expression rather than a glob.
+    pub name_regex: bool,
+    /// `[g,m]` in octal, `*` for any.
+    pub owner: String,
+    /// `YYYY-MM-DD`, inclusive.
+    pub since: String,
+    pub until: String,
+    pub record_format: Option<u8>,
+    pub min_size: String,
+    pub max_size: String,
+    pub content: String,
+    /// `content` is a regular expression rather than literal text.
+    pub content_regex: bool,
+    /// Hits of the last search, indexing the Files tab list; `None` lists
+    /// every file.
+    pub hits: Option<Vec<SearchHit>>,
+    pub status: String,
+}
+
+impl FileSearch {
+    /// Query from the typed fields; empty fields filter nothing.
+    pub fn query(&self) -> Result<FileQuery, String> {
+        let text = |s: &str| Some(s.trim()).filter(|s| !s.is_empty()).map(str::to_string);
+        let date = |s: &str| -> Result<Option<chrono::NaiveDate>, String> {
+            text(s)
+                .map(|d| {
+                    chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+                        .map_err(|_| format!("Dates are YYYY-MM-DD, not {d}"))
+                })
+                .transpose()
+        };
+        let size = |s: &str| -> Result<Option<u64>, String> {
+            text(s)
+                .map(|n| {
+                    n.parse()
+                        .map_err(|_| format!("Sizes are byte counts, not {n}"))
+                })
+                .transpose()
+        };
+        Ok(FileQuery {
+            name: match text(&self.name) {
+                Some(name) if self.name_regex => Some(NamePattern::regex(&name)?),
+                Some(name) => Some(NamePattern::Glob(name)),
+                None => None,
+            },
+            owner: text(&self.owner)
+                .map(|o| {
+                    UicFilter::parse(&o).ok_or_else(|| format!("UICs are [g,m] in octal, not {o}"))
+                })
+                .transpose()?,
+            since: date(&self.since)?,
+            until: date(&self.until)?,
+            record_format: self.record_format,
+            min_size: size(&self.min_size)?,
+            max_size: size(&self.max_size)?,
+            content: match text(&self.content) {
+                Some(content) if self.content_regex => Some(ContentPattern::regex(&content)?),
+                Some(content) => Some(ContentPattern::text(&content)?),
+                None => None,
+            },
+        })
+    }
+}
+
+/// Search the Files tab list with the typed query.
+pub fn run_search(state: &mut AppState) {
+    let query = match state.file_search.query() {
+        Ok(query) => query,
+        Err(msg) => {
+            state.file_search.status = msg;
+            return;
+        }
+    };
+    let shown = shown_files(state);
+    let hits = search_tape_files(shown.iter().map(|(f, _)| f), &state.blocks, &query);
+    let files = shown.iter().filter(|(f, _)| f.children.is_empty()).count();
+    state.file_search.status = format!("{} of {files} files match", hits.len());
+    state.file_search.hits = Some(hits);
+}
+
+/// Forget the hits, e.g. when the listed files change.
+pub fn clear_search(state: &mut AppState) {
+    state.file_search.hits = None;
+    state.file_search.status.clear();
+}
+
+/// Labelled text field; true when Enter was pressed in it.
+fn field(ui: &mut egui::Ui, label: &str, value: &mut String, width: f32) -> bool {
+    ui.label(label);
+    let response = ui.add(TextEdit::singleline(value).desired_width(width));
+    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))
+}
+
+/// Render the search fields.
+pub fn search_bar(ui: &mut egui::Ui, state: &mut AppState) {
+    let mut run = false;
+    let mut clear = false;
+    ui.collapsing("Search", |ui| {
+        let search = &mut state.file_search;
+        ui.horizontal(|ui| {
+            run |= field(ui, "Name", &mut search.name, 180.0);
+            ui.checkbox(&mut search.name_regex, "Regex");
+            ui.separator();
+            run |= field(ui, "Owner", &mut search.owner, 80.0);
+            ui.separator();
+            ui.label("Record format");
+            let selected = RECORD_FORMATS
+                .iter()
+                .find(|f| Some(f.code()) == search.record_format)
+                .map_or("any", |f| f.label());
+            ComboBox::from_id_source("search_record_format")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut search.record_format, None, "any");
+                    for format in RECORD_FORMATS {
+                        ui.selectable_value(
+                            &mut search.record_format,
+                            Some(format.code()),
+                            format.label(),
+                        );
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            run |= field(ui, "Dated from", &mut search.since, 90.0);
+            run |= field(ui, "to", &mut search.until, 90.0);
+            ui.separator();
+            run |= field(ui, "Size from", &mut search.min_size, 80.0);
+            run |= field(ui, "to", &mut search.max_size, 80.0);
+            ui.label("bytes");
+        });
+        ui.horizontal(|ui| {
+            run |= field(ui, "Contents", &mut search.content, 240.0);
+            ui.checkbox(&mut search.content_regex, "Regex");
+            ui.separator();
+            run |= ui.button("Search").clicked();
+            clear = search.hits.is_some() && ui.button("Show all").clicked();
+        });
+        if !search.status.is_empty() {
+            ui.label(&search.status);
+        }
+    });
+    if clear {
+        clear_search(state);
+    } else if run {
+        run_search(state);
+    }
+}
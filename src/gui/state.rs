@@ -1,11 +1,19 @@
 //! Shared GUI state structures and active tab tracking.
-use crate::backup::extract::{VmsFile, VmsFileSystem};
+use std::sync::Arc;
+
 use crate::core::block::{TapeBlock, TapeFormat};
+use crate::core::detect::Detection;
 use crate::core::file::TapeFile;
+use crate::core::timeline::{TimelineItem, TimelineSpan};
 use crate::gui::extraction::ExtractionState;
-use crate::log::parse::LogData;
+use crate::gui::hexview::HexView;
+use crate::gui::preview::PreviewState;
+use crate::gui::search::FileSearch;
+use crate::health::HealthMap;
+use crate::log::crosscheck::LayoutFinding;
+use crate::log::parse::{LogData, LogLevel, RecordLink};
 use crate::summary::SaveSetSummary;
-use crate::tap::legacy::TapEntry;
+use crate::tap::stream::TapeIndex;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MainTab {
@@ -15,44 +23,102 @@ pub enum MainTab {
     Files,
     Summary,
     Log,
+    Health,
+    Timeline,
 }
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub blocks: Vec<TapeBlock>,
+    /// Format the tape is decoded as: the detected winner or the override.
     pub detected_format: TapeFormat,
+    /// Per-format confidences of the loaded tape.
+    pub detection: Option<Detection>,
+    /// Format chosen in the Input tab instead of the detected winner.
+    pub format_override: Option<TapeFormat>,
     pub files: Vec<TapeFile>,
     pub tap_state: TapState,
     pub log_state: LogState,
-    pub vms_files: Vec<VmsFile>,
-    pub vms_fs: Option<VmsFileSystem>,
     pub selected_file: Option<usize>,
     pub file_hex_viewer: Option<usize>,
+    /// Hex viewer state of the Files tab window.
+    pub file_hex: HexView,
+    /// Text or hex preview pane of the Files tab.
+    pub preview: PreviewState,
+    /// Search fields and hits of the Files tab.
+    pub file_search: FileSearch,
     pub summary: Option<SaveSetSummary>,
     pub summary_status: String,
+    /// Read-quality status of every record, for the Health tab.
+    pub health: Option<HealthMap>,
+    /// Edge of one record square in the Health tab, in points.
+    pub health_cell: f32,
+    /// Label groups, savesets and tape marks of the loaded tape in order.
+    pub timeline: Vec<TimelineItem>,
+    /// Timeline width as a multiple of the window width.
+    pub timeline_zoom: f32,
+    /// Span chosen in the Timeline tab; Files and Contents only show what
+    /// lies inside it.
+    pub span_filter: Option<TimelineSpan>,
     pub current_tab: MainTab,
     pub extraction: ExtractionState,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TapState {
-    pub entries: Vec<TapEntry>,
+    /// Log level of each record from the correlated log, by record index.
+    pub record_levels: Vec<Option<LogLevel>>,
     pub selected_entry: Option<usize>,
-}
-
-impl Default for TapState {
-    fn default() -> Self {
-        Self {
-            entries: Vec::new(),
-            selected_entry: None,
-        }
-    }
+    /// Record index of the loaded image; payloads are read on demand.
+    pub index: Option<Arc<TapeIndex>>,
+    /// Contents tab shows raw tape records instead of reconstructed files.
+    pub show_records: bool,
+    pub record_jump: usize,
+    pub scroll_to_record: Option<usize>,
+    /// Record highlighted in the records view, e.g. from a Log tab line.
+    pub selected_record: Option<usize>,
+    /// Record shown in the record hex window.
+    pub record_hex_viewer: Option<usize>,
+    /// Hex viewer state of the file window.
+    pub entry_hex: HexView,
+    /// Hex viewer state of the record window.
+    pub record_hex: HexView,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct LogState {
     pub data: Option<LogData>,
     pub correlated: bool,
+    /// Log layout checked against the loaded tape; `None` until both are loaded.
+    pub layout: Option<Vec<LayoutFinding>>,
+    /// Log lines and the tape records they refer to, in line order.
+    pub links: Vec<RecordLink>,
+    pub view: LogView,
+}
+
+/// Filters, search and navigation of the Log tab.
+#[derive(Debug, Clone)]
+pub struct LogView {
+    pub show_info: bool,
+    pub show_warnings: bool,
+    pub show_errors: bool,
+    /// Case-insensitive text lines must contain; empty shows all.
+    pub search: String,
+    pub selected_line: Option<usize>,
+    pub scroll_to_line: Option<usize>,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        Self {
+            show_info: true,
+            show_warnings: true,
+            show_errors: true,
+            search: String::new(),
+            selected_line: None,
+            scroll_to_line: None,
+        }
+    }
 }
 
 impl Default for AppState {
@@ -60,15 +126,23 @@ impl Default for AppState {
         Self {
             blocks: Vec::new(),
             detected_format: TapeFormat::Unknown,
+            detection: None,
+            format_override: None,
             files: Vec::new(),
             tap_state: TapState::default(),
             log_state: LogState::default(),
-            vms_files: Vec::new(),
-            vms_fs: None,
             selected_file: None,
             file_hex_viewer: None,
+            file_hex: HexView::default(),
+            preview: PreviewState::default(),
+            file_search: FileSearch::default(),
             summary: None,
             summary_status: String::new(),
+            health: None,
+            health_cell: 8.0,
+            timeline: Vec::new(),
+            timeline_zoom: 1.0,
+            span_filter: None,
             current_tab: MainTab::Contents,
             extraction: ExtractionState::default(),
         }
@@ -13,6 +13,12 @@ pub fn summary_tab(ui: &mut egui::Ui, state: &mut AppState) {
     };
 
     Grid::new("summary_grid").num_columns(2).show(ui, |ui| {
+        for (name, value) in summary.tape.iter().flat_map(|t| t.fields()) {
+            ui.label(name);
+            ui.label(value);
+            ui.end_row();
+        }
+
         ui.label("Total files");
         ui.label(summary.total_files.to_string());
         ui.end_row();
@@ -89,6 +95,9 @@ pub fn summary_tab(ui: &mut egui::Ui, state: &mut AppState) {
 
 fn format_summary_txt(summary: &SaveSetSummary) -> String {
     let mut lines = Vec::new();
+    for (name, value) in summary.tape.iter().flat_map(|t| t.fields()) {
+        lines.push(format!("{}: {}", name, value));
+    }
     lines.push(format!("Total files: {}", summary.total_files));
     lines.push(format!("Total directories: {}", summary.total_directories));
     lines.push(format!("Total blocks: {}", summary.total_blocks));
@@ -0,0 +1,212 @@
+//! Timeline tab: the tape drawn left to right as label groups, savesets,
+//! DOS-11 files and data spans sized by bytes, split by tape marks. Clicking
+//! a span limits the Files and Contents tabs to it.
+use egui::{self, Align2, Color32, FontId, RichText, ScrollArea, Sense, Slider, Stroke, Vec2};
+
+use crate::core::timeline::{SpanKind, TimelineItem, TimelineSpan};
+use crate::utils::text::sanitize_display;
+
+use super::preview::PreviewState;
+use super::search::clear_search;
+use super::state::AppState;
+
+const STRIP_HEIGHT: f32 = 48.0;
+const MARK_WIDTH: f32 = 4.0;
+/// Narrowest span drawn, so label groups stay clickable on large tapes.
+const MIN_SPAN_WIDTH: f32 = 6.0;
+
+fn kind_color(kind: SpanKind) -> Color32 {
+    match kind {
+        SpanKind::Labels => Color32::from_rgb(0x4A, 0x7F, 0xC1),
+        SpanKind::Saveset => Color32::from_rgb(0x3C, 0x9D, 0x6B),
+        SpanKind::File => Color32::from_rgb(0xB0, 0x7A, 0x2E),
+        SpanKind::Data => Color32::from_rgb(0x6E, 0x6E, 0x6E),
+    }
+}
+
+fn kind_label(kind: SpanKind) -> &'static str {
+    match kind {
+        SpanKind::Labels => "ANSI labels",
+        SpanKind::Saveset => "saveset",
+        SpanKind::File => "DOS-11 file",
+        SpanKind::Data => "data records",
+    }
+}
+
+fn describe(span: &TimelineSpan) -> String {
+    format!(
+        "{}: {}\nrecords {}..{}, {} bytes, tape file {}",
+        kind_label(span.kind),
+        sanitize_display(&span.title()),
+        span.records.start,
+        span.records.end,
+        span.bytes,
+        span.tape_file
+    )
+}
+
+/// Limit the Files and Contents tabs to `span`, or lift the limit for
+/// `None`. Selections made in the unfiltered lists are dropped.
+pub fn set_span_filter(span: Option<TimelineSpan>, state: &mut AppState) {
+    state.span_filter = span;
+    state.selected_file = None;
+    state.file_hex_viewer = None;
+    state.preview = PreviewState::default();
+    clear_search(state);
+    state.tap_state.selected_entry = None;
+    if let Some(span) = &state.span_filter {
+        state.tap_state.record_jump = span.records.start as usize;
+        state.tap_state.scroll_to_record = Some(span.records.start as usize);
+    }
+}
+
+/// Note of the active span filter with a button to clear it; shown by the
+/// tabs it limits.
+pub fn span_filter_bar(ui: &mut egui::Ui, state: &mut AppState) {
+    let Some(span) = &state.span_filter else {
+        return;
+    };
+    let mut clear = false;
+    ui.horizontal(|ui| {
+        ui.label(
+            RichText::new(format!(
+                "Showing {} (records {}..{})",
+                sanitize_display(&span.title()),
+                span.records.start,
+                span.records.end
+            ))
+            .color(kind_color(span.kind)),
+        );
+        clear = ui.button("Show all").clicked();
+    });
+    if clear {
+        set_span_filter(None, state);
+    }
+}
+
+/// Render the tape layout timeline.
+pub fn timeline_tab(ui: &mut egui::Ui, state: &mut AppState) {
+    if state.timeline.is_empty() {
+        ui.label("No tape records loaded.");
+        return;
+    }
+    let items = state.timeline.clone();
+    let marks = items
+        .iter()
+        .filter(|i| matches!(i, TimelineItem::TapeMark))
+        .count();
+    let spans: Vec<&TimelineSpan> = items
+        .iter()
+        .filter_map(|i| match i {
+            TimelineItem::Span(span) => Some(span),
+            TimelineItem::TapeMark => None,
+        })
+        .collect();
+    let total: u64 = spans.iter().map(|s| s.bytes).sum();
+
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} spans, {marks} tape marks, {total} bytes",
+            spans.len()
+        ));
+        ui.add_space(12.0);
+        ui.label("Zoom");
+        ui.add(Slider::new(&mut state.timeline_zoom, 1.0..=64.0).logarithmic(true));
+        for kind in [
+            SpanKind::Labels,
+            SpanKind::Saveset,
+            SpanKind::File,
+            SpanKind::Data,
+        ] {
+            ui.add_space(8.0);
+            ui.colored_label(kind_color(kind), kind_label(kind));
+        }
+    });
+    span_filter_bar(ui, state);
+    ui.separator();
+
+    // Bytes share the width left after tape marks and minimum span widths.
+    let fixed = marks as f32 * MARK_WIDTH + spans.len() as f32 * MIN_SPAN_WIDTH;
+    let width = ui.available_width() * state.timeline_zoom;
+    let per_byte = (width - fixed).max(width * 0.25) / total.max(1) as f32;
+    let chosen = state.span_filter.clone();
+    let mut clicked: Option<TimelineSpan> = None;
+    ScrollArea::horizontal()
+        .id_source("timeline_strip")
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                for item in &items {
+                    let span = match item {
+                        TimelineItem::TapeMark => {
+                            let (rect, _) = ui.allocate_exact_size(
+                                Vec2::new(MARK_WIDTH, STRIP_HEIGHT),
+                                Sense::hover(),
+                            );
+                            ui.painter().rect_filled(rect, 0.0, Color32::BLACK);
+                            continue;
+                        }
+                        TimelineItem::Span(span) => span,
+                    };
+                    let size =
+                        Vec2::new(MIN_SPAN_WIDTH + span.bytes as f32 * per_byte, STRIP_HEIGHT);
+                    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+                    let painter = ui.painter_at(rect);
+                    let fill = kind_color(span.kind);
+                    painter.rect_filled(rect.shrink(0.5), 2.0, fill);
+                    if chosen.as_ref() == Some(span) {
+                        painter.rect_stroke(
+                            rect.shrink(1.0),
+                            2.0,
+                            Stroke::new(2.0, Color32::WHITE),
+                        );
+                    }
+                    if rect.width() > 40.0 {
+                        painter.text(
+                            rect.left_center() + Vec2::new(4.0, 0.0),
+                            Align2::LEFT_CENTER,
+                            sanitize_display(&span.title()),
+                            FontId::proportional(12.0),
+                            Color32::WHITE,
+                        );
+                    }
+                    if response.clicked() {
+                        clicked = Some(span.clone());
+                    }
+                    response.on_hover_text(describe(span));
+                }
+            });
+        });
+
+    ui.separator();
+    ScrollArea::vertical()
+        .id_source("timeline_list")
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            for span in &spans {
+                let text = RichText::new(format!(
+                    "{:>5}  {:>9}..{:<9} {:>12} bytes  {:<14} {}",
+                    span.tape_file,
+                    span.records.start,
+                    span.records.end,
+                    span.bytes,
+                    kind_label(span.kind),
+                    sanitize_display(&span.title())
+                ))
+                .monospace()
+                .color(kind_color(span.kind));
+                if ui
+                    .selectable_label(chosen.as_ref() == Some(*span), text)
+                    .clicked()
+                {
+                    clicked = Some((*span).clone());
+                }
+            }
+        });
+
+    if let Some(span) = clicked {
+        // A second click on the chosen span lifts the filter.
+        let span = (chosen.as_ref() != Some(&span)).then_some(span);
+        set_span_filter(span, state);
+    }
+}
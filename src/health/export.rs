@@ -0,0 +1,164 @@
+//! Health map images for condition reports: SVG with a legend, and a plain
+//! PNG grid of one square per record.
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use super::{HealthMap, RecordHealth};
+
+/// Legend height below the SVG grid.
+const LEGEND_ROW: usize = 18;
+
+/// Rows of a grid of `columns` records per row.
+fn grid_rows(map: &HealthMap, columns: usize) -> usize {
+    map.records.len().div_ceil(columns.max(1))
+}
+
+fn hex_color(health: RecordHealth) -> String {
+    let [r, g, b] = health.rgb();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// SVG of the map as `columns` squares of `cell` pixels per row, followed
+/// by a legend with the record count of each status. Equal neighbours in a
+/// row share one rectangle.
+pub fn health_svg(map: &HealthMap, columns: usize, cell: usize) -> String {
+    let columns = columns.max(1);
+    let rows = grid_rows(map, columns);
+    let width = (columns * cell).max(260);
+    let counts = map.counts();
+    let height = rows * cell + 8 + counts.len() * LEGEND_ROW;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="12">"#
+    );
+    for (row, chunk) in map.records.chunks(columns).enumerate() {
+        let mut start = 0;
+        while start < chunk.len() {
+            let health = chunk[start];
+            let len = chunk[start..].iter().take_while(|&&h| h == health).count();
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{cell}" fill="{}"><title>records {}-{}: {}</title></rect>"#,
+                start * cell,
+                row * cell,
+                len * cell,
+                hex_color(health),
+                row * columns + start,
+                row * columns + start + len - 1,
+                health.label()
+            );
+            start += len;
+        }
+    }
+    for (i, (health, count)) in counts.iter().enumerate() {
+        let y = rows * cell + 8 + i * LEGEND_ROW;
+        let _ = writeln!(
+            svg,
+            r#"<rect x="0" y="{y}" width="12" height="12" fill="{}"/><text x="18" y="{}">{}: {count}</text>"#,
+            hex_color(*health),
+            y + 11,
+            health.label()
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// PNG of the map as `columns` squares of `cell` pixels per row; cells past
+/// the last record are white.
+pub fn health_png(map: &HealthMap, columns: usize, cell: usize) -> io::Result<Vec<u8>> {
+    let columns = columns.max(1);
+    let cell = cell.max(1);
+    let (width, height) = (columns * cell, grid_rows(map, columns).max(1) * cell);
+    let mut pixels = Vec::with_capacity((width * 3 + 1) * height);
+    for y in 0..height {
+        // Filter type 0 (none) for every scanline.
+        pixels.push(0);
+        for x in 0..width {
+            let record = (y / cell) * columns + x / cell;
+            let rgb = map.records.get(record).map_or([0xFF; 3], |h| h.rgb());
+            pixels.extend_from_slice(&rgb);
+        }
+    }
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    zlib.write_all(&pixels)?;
+    let data = zlib.finish()?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8-bit RGB, deflate, adaptive filtering, no interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, body) in [(b"IHDR", &header), (b"IDAT", &data), (b"IEND", &Vec::new())] {
+        png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(body);
+        let crc = crc32fast::hash(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::ZlibDecoder;
+
+    use super::{health_png, health_svg};
+    use crate::health::{HealthMap, RecordHealth};
+
+    fn sample() -> HealthMap {
+        HealthMap {
+            records: vec![
+                RecordHealth::Good,
+                RecordHealth::Good,
+                RecordHealth::ReadError,
+                RecordHealth::Good,
+                RecordHealth::Flagged,
+            ],
+        }
+    }
+
+    #[test]
+    fn svg_merges_equal_neighbours() {
+        let svg = health_svg(&sample(), 3, 4);
+        assert!(svg.contains(r##"<rect x="0" y="0" width="8" height="4" fill="#3cb371">"##));
+        assert!(svg.contains("<title>records 2-2: read error</title>"));
+        assert!(svg.contains("<title>records 3-3: good</title>"));
+        assert!(svg.contains("good: 3</text>"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn png_has_one_square_per_record() {
+        let png = health_png(&sample(), 3, 2).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 6);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 4);
+        let crc = u32::from_be_bytes(png[29..33].try_into().unwrap());
+        assert_eq!(crc, crc32fast::hash(&png[12..29]));
+
+        let len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let mut pixels = Vec::new();
+        ZlibDecoder::new(&png[41..41 + len])
+            .read_to_end(&mut pixels)
+            .unwrap();
+        // 4 scanlines of a filter byte and 6 RGB pixels.
+        assert_eq!(pixels.len(), 4 * (1 + 6 * 3));
+        let pixel = |x: usize, y: usize| &pixels[y * 19 + 1 + x * 3..][..3];
+        assert_eq!(pixel(4, 1), RecordHealth::ReadError.rgb());
+        assert_eq!(pixel(2, 2), RecordHealth::Flagged.rgb());
+        // Past the last record.
+        assert_eq!(pixel(5, 3), [0xFF; 3]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Tape health map: one read-quality status per record, merged from the
+//! capture log, SIMH bad-record flags, BACKUP block CRCs and the format
+//! decoders, for the Health tab and condition-report exports.
+pub mod export;
+
+use crate::backup::bbh::{has_bbh, verify_block_crc};
+use crate::core::block::TapeFormat;
+use crate::gui::state::AppState;
+use crate::log::parse::LogLevel;
+
+/// Status of one record; a record with several problems takes the most
+/// severe, which is the latest variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RecordHealth {
+    Good,
+    /// Not claimed by the decoder of the tape's format.
+    Undecoded,
+    /// Retried or warned about in the log.
+    Retried,
+    /// VMS BACKUP block whose CRC does not verify.
+    BadCrc,
+    /// Read error reported in the log.
+    ReadError,
+    /// Marked bad in the image itself (SIMH error flag).
+    Flagged,
+}
+
+impl RecordHealth {
+    pub const ALL: [RecordHealth; 6] = [
+        RecordHealth::Good,
+        RecordHealth::Undecoded,
+        RecordHealth::Retried,
+        RecordHealth::BadCrc,
+        RecordHealth::ReadError,
+        RecordHealth::Flagged,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecordHealth::Good => "good",
+            RecordHealth::Undecoded => "not decoded",
+            RecordHealth::Retried => "retried / warning",
+            RecordHealth::BadCrc => "block CRC mismatch",
+            RecordHealth::ReadError => "read error",
+            RecordHealth::Flagged => "flagged bad in image",
+        }
+    }
+
+    /// Map color as RGB.
+    pub fn rgb(&self) -> [u8; 3] {
+        match self {
+            RecordHealth::Good => [0x3C, 0xB3, 0x71],
+            RecordHealth::Undecoded => [0x8A, 0x8A, 0x8A],
+            RecordHealth::Retried => [0xE6, 0xC2, 0x29],
+            RecordHealth::BadCrc => [0xF0, 0x8C, 0x28],
+            RecordHealth::ReadError => [0xD6, 0x33, 0x33],
+            RecordHealth::Flagged => [0x8B, 0x1E, 0x9E],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthMap {
+    /// Status of each record, by record index.
+    pub records: Vec<RecordHealth>,
+}
+
+impl HealthMap {
+    /// Records per status, in `RecordHealth::ALL` order, zero counts
+    /// included.
+    pub fn counts(&self) -> Vec<(RecordHealth, usize)> {
+        RecordHealth::ALL
+            .iter()
+            .map(|&h| (h, self.records.iter().filter(|&&r| r == h).count()))
+            .collect()
+    }
+
+    /// Records with any problem.
+    pub fn damaged(&self) -> usize {
+        self.records
+            .iter()
+            .filter(|&&r| r > RecordHealth::Undecoded)
+            .count()
+    }
+}
+
+/// Health of every loaded record. Log levels come from the correlated log
+/// and SIMH flags from the record index; blocks only count as
+/// undecoded when the tape decodes as a known format.
+pub fn compute_health_map(state: &AppState) -> HealthMap {
+    let decoded = !matches!(state.detected_format, TapeFormat::Unknown | TapeFormat::Raw);
+    let flags = state.tap_state.index.as_ref().map(|i| i.records());
+    let records = state
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(i, block)| {
+            let mut health = RecordHealth::Good;
+            let mut worst = |h: RecordHealth| health = health.max(h);
+            if decoded && block.classification.format() != state.detected_format {
+                worst(RecordHealth::Undecoded);
+            }
+            match state
+                .tap_state
+                .record_levels
+                .get(i)
+                .and_then(Option::as_ref)
+            {
+                Some(LogLevel::Warning) => worst(RecordHealth::Retried),
+                Some(LogLevel::Error) => worst(RecordHealth::ReadError),
+                _ => {}
+            }
+            if has_bbh(&block.raw) && !verify_block_crc(&block.raw) {
+                worst(RecordHealth::BadCrc);
+            }
+            if flags.and_then(|f| f.get(i)).is_some_and(|r| r.flagged) {
+                worst(RecordHealth::Flagged);
+            }
+            health
+        })
+        .collect();
+    HealthMap { records }
+}
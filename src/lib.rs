@@ -1,6 +1,8 @@
 pub mod backup;
+pub mod cli;
 pub mod core;
 pub mod gui;
+pub mod health;
 pub mod log;
 pub mod summary;
 pub mod tap;
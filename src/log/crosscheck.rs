@@ -0,0 +1,300 @@
+//! Cross-check of a capture log's record layout against the records of the
+//! tape image: logged runs are expanded record by record and paired with
+//! image records in order.
+use std::fmt;
+
+use crate::core::block::TapeBlock;
+
+use super::event::LogEventKind;
+use super::parse::{LogData, RecordRun};
+
+/// One disagreement between the log and the image, or an anomaly in the
+/// logged layout itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutFinding {
+    /// Image record index the finding applies to (for count findings, the
+    /// first record one side lacks).
+    pub record: u64,
+    /// Log line of the run, when the finding comes from one.
+    pub line: Option<usize>,
+    pub issue: LayoutIssue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutIssue {
+    /// Record size in the image differs from the logged run.
+    Size {
+        logged: u32,
+        actual: usize,
+    },
+    /// Tape marks fall elsewhere: from this record on, the image places
+    /// records in a different tape file than the log.
+    TapeMark {
+        logged_file: u32,
+        actual_file: u32,
+    },
+    /// A single record clearly shorter than the equal-sized blocks around it.
+    ShortRecord {
+        size: u32,
+        block_size: u32,
+    },
+    /// Zero-byte record in the log, which an image cannot hold.
+    ZeroLength,
+    /// The image holds fewer records than the log: a truncated capture.
+    MissingRecords {
+        logged: u64,
+        found: u64,
+    },
+    ExtraRecords {
+        logged: u64,
+        found: u64,
+    },
+    /// The log stops without reaching end of data.
+    NoEndOfData,
+}
+
+impl fmt::Display for LayoutFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let record = self.record;
+        match &self.issue {
+            LayoutIssue::Size { logged, actual } => write!(
+                f,
+                "record {record}: {actual} bytes in image, {logged} logged"
+            ),
+            LayoutIssue::TapeMark {
+                logged_file,
+                actual_file,
+            } => write!(
+                f,
+                "record {record}: in tape file {actual_file} of the image, {logged_file} logged"
+            ),
+            LayoutIssue::ShortRecord { size, block_size } => write!(
+                f,
+                "record {record}: short {size}-byte record between {block_size}-byte blocks"
+            ),
+            LayoutIssue::ZeroLength => {
+                write!(f, "zero-byte record logged before record {record}")
+            }
+            LayoutIssue::MissingRecords { logged, found } => {
+                write!(f, "image truncated: {found} records, {logged} logged")
+            }
+            LayoutIssue::ExtraRecords { logged, found } => {
+                write!(f, "image holds {found} records, {logged} logged")
+            }
+            LayoutIssue::NoEndOfData => write!(f, "capture stopped before end of data"),
+        }
+    }
+}
+
+/// Compare the logged layout with the image records. Logs without record
+/// runs (other tools) yield no findings.
+pub fn cross_check(log: &LogData, blocks: &[TapeBlock]) -> Vec<LayoutFinding> {
+    let runs = log.record_layout();
+    if runs.is_empty() {
+        return Vec::new();
+    }
+    let mut out = Vec::new();
+    let mut logged = 0u64;
+    // Image tape file minus logged tape file at the previous record.
+    let mut shift = 0i64;
+    for (i, run) in runs.iter().enumerate() {
+        let finding = |record, issue| LayoutFinding {
+            record,
+            line: Some(run.line),
+            issue,
+        };
+        if run.size == 0 {
+            out.push(finding(run.first, LayoutIssue::ZeroLength));
+            continue;
+        }
+        if let Some(block_size) = short_record(&runs, i) {
+            out.push(finding(
+                run.first,
+                LayoutIssue::ShortRecord {
+                    size: run.size,
+                    block_size,
+                },
+            ));
+        }
+        for record in run.first..run.first + run.count {
+            logged += 1;
+            let Some(block) = blocks.get(record as usize) else {
+                continue;
+            };
+            if block.size != run.size as usize {
+                out.push(finding(
+                    record,
+                    LayoutIssue::Size {
+                        logged: run.size,
+                        actual: block.size,
+                    },
+                ));
+            }
+            let offset = i64::from(block.tape_file) - i64::from(run.tape_file);
+            if offset != shift {
+                out.push(finding(
+                    record,
+                    LayoutIssue::TapeMark {
+                        logged_file: run.tape_file,
+                        actual_file: block.tape_file,
+                    },
+                ));
+                shift = offset;
+            }
+        }
+    }
+
+    let found = blocks.len() as u64;
+    if found != logged {
+        let issue = if found < logged {
+            LayoutIssue::MissingRecords { logged, found }
+        } else {
+            LayoutIssue::ExtraRecords { logged, found }
+        };
+        out.push(LayoutFinding {
+            record: found.min(logged),
+            line: None,
+            issue,
+        });
+    }
+    if !log
+        .events
+        .iter()
+        .any(|e| matches!(e.kind, LogEventKind::EndOfData { .. }))
+    {
+        out.push(LayoutFinding {
+            record: logged,
+            line: None,
+            issue: LayoutIssue::NoEndOfData,
+        });
+    }
+    out
+}
+
+/// Block size around run `i` when it is a single record shorter than, but
+/// more than half of, equal-sized runs on both sides in the same tape file
+/// (zero-byte runs aside). Label and header records are far smaller than
+/// the blocks they introduce.
+fn short_record(runs: &[RecordRun], i: usize) -> Option<u32> {
+    let run = &runs[i];
+    let prev = runs[..i].iter().rev().find(|r| r.size > 0)?;
+    let next = runs[i + 1..].iter().find(|r| r.size > 0)?;
+    let same_file = prev.tape_file == run.tape_file && next.tape_file == run.tape_file;
+    let block = prev.size;
+    (run.count == 1 && same_file && next.size == block && run.size < block && run.size * 2 > block)
+        .then_some(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cross_check, LayoutIssue};
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::log::parse::parse_log_text;
+
+    const LOG: &str = "X.TAP created by STP2TAP 0.02 on Wed Nov 05 10:58:29 2025
+Reading...
+2 x 80 bytes
+Tapemark hit at block 8071936
+2 x 8192 bytes
+1 x 7301 bytes
+1 x 0 bytes
+2 x 8192 bytes
+Tapemark hit at block 8071936
+End of data hit at 3542722
+";
+
+    fn tape(layout: &[(u32, usize)]) -> Vec<TapeBlock> {
+        layout
+            .iter()
+            .enumerate()
+            .map(|(i, &(tape_file, size))| TapeBlock {
+                index: i as u32,
+                tape_file,
+                size,
+                raw: vec![0u8; size].into(),
+                classification: BlockClassification::Unknown,
+            })
+            .collect()
+    }
+
+    fn issues(log: &str, blocks: &[TapeBlock]) -> Vec<(u64, LayoutIssue)> {
+        cross_check(&parse_log_text(log), blocks)
+            .into_iter()
+            .map(|f| (f.record, f.issue))
+            .collect()
+    }
+
+    #[test]
+    fn matching_image_reports_log_anomalies_only() {
+        let blocks = tape(&[
+            (0, 80),
+            (0, 80),
+            (1, 8192),
+            (1, 8192),
+            (1, 7301),
+            (1, 8192),
+            (1, 8192),
+        ]);
+        assert_eq!(
+            issues(LOG, &blocks),
+            vec![
+                (
+                    4,
+                    LayoutIssue::ShortRecord {
+                        size: 7301,
+                        block_size: 8192
+                    }
+                ),
+                (5, LayoutIssue::ZeroLength),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_sizes_tape_marks_and_truncation() {
+        // Tape mark lost after the labels, last blocks missing and a
+        // record shorter than logged.
+        let blocks = tape(&[(0, 80), (0, 80), (0, 8192), (0, 8000)]);
+        let log = LOG.replace("End of data hit at 3542722\n", "");
+        assert_eq!(
+            issues(&log, &blocks),
+            vec![
+                (
+                    2,
+                    LayoutIssue::TapeMark {
+                        logged_file: 1,
+                        actual_file: 0
+                    }
+                ),
+                (
+                    3,
+                    LayoutIssue::Size {
+                        logged: 8192,
+                        actual: 8000
+                    }
+                ),
+                (
+                    4,
+                    LayoutIssue::ShortRecord {
+                        size: 7301,
+                        block_size: 8192
+                    }
+                ),
+                (5, LayoutIssue::ZeroLength),
+                (
+                    4,
+                    LayoutIssue::MissingRecords {
+                        logged: 7,
+                        found: 4
+                    }
+                ),
+                (7, LayoutIssue::NoEndOfData),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_logs_without_record_runs() {
+        assert!(issues("WARNING record 3", &tape(&[(0, 512)])).is_empty());
+    }
+}
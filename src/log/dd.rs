@@ -0,0 +1,90 @@
+//! dd / tapecopy stderr grammar, for captures made with
+//! `dd if=/dev/nst0 bs=64k conv=noerror`:
+//!
+//! ```text
+//! dd: error reading '/dev/nst0': Input/output error
+//! 12+0 records in
+//! 12+0 records out
+//! 6553600 bytes (6.6 MB, 6.2 MiB) copied, 1.2 s, 5.4 MB/s
+//! ```
+//!
+//! With a block size above the largest tape record, every read returns
+//! one record, so `F+P records in` counts tape records. After a read error
+//! dd prints the counts so far, which places the error at the next record.
+use super::event::{free_text, LogEvent, LogEventKind};
+
+pub fn parse_events<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<LogEvent> {
+    let mut events: Vec<LogEvent> = Vec::new();
+    let mut record = 0u64;
+    // Errors waiting for the record count that follows them.
+    let mut pending: Vec<usize> = Vec::new();
+    for (line, text) in lines.into_iter().enumerate() {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        if let Some(count) = records_in(text) {
+            record = count;
+            for index in pending.drain(..) {
+                events[index].record = count;
+            }
+            continue;
+        }
+        if records_out(text) || (text.contains(" bytes") && text.contains(" copied")) {
+            continue;
+        }
+        let kind = match text.strip_prefix("dd: ") {
+            Some(message) if message.starts_with("error") => {
+                pending.push(events.len());
+                LogEventKind::ReadError(text.to_string())
+            }
+            _ => free_text(text),
+        };
+        events.push(LogEvent { line, record, kind });
+    }
+    events
+}
+
+/// Lines only dd writes, for dialect detection.
+pub(crate) fn is_signature(line: &str) -> bool {
+    line.starts_with("dd: ") || records_in(line).is_some() || records_out(line)
+}
+
+/// Full plus partial reads of `F+P records in`.
+fn records_in(line: &str) -> Option<u64> {
+    let (full, partial) = line.strip_suffix(" records in")?.split_once('+')?;
+    Some(full.parse::<u64>().ok()? + partial.parse::<u64>().ok()?)
+}
+
+fn records_out(line: &str) -> bool {
+    line.strip_suffix(" records out")
+        .and_then(|counts| counts.split_once('+'))
+        .is_some_and(|(full, partial)| {
+            full.parse::<u64>().is_ok() && partial.parse::<u64>().is_ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_events;
+    use crate::log::event::LogEventKind;
+
+    #[test]
+    fn places_errors_at_the_next_record() {
+        let log = "dd: error reading '/dev/nst0': Input/output error
+12+0 records in
+12+0 records out
+dd: error reading '/dev/nst0': Input/output error
+30+1 records in
+30+1 records out
+260000 bytes (260 kB) copied, 1.2 s, 216 kB/s";
+        let events = parse_events(log.lines());
+        let errors: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e.kind, LogEventKind::ReadError(_)))
+            .map(|e| (e.line, e.record))
+            .collect();
+        assert_eq!(errors, vec![(0, 12), (3, 31)]);
+        assert_eq!(events.len(), 2);
+    }
+}
@@ -0,0 +1,138 @@
+//! GNU ddrescue mapfile grammar:
+//!
+//! ```text
+//! # Mapfile. Created by GNU ddrescue version 1.27
+//! # Command line: ddrescue -b 8192 /dev/nst0 TA0114.bin TA0114.map
+//! # Start time:   2025-11-05 14:17:12
+//! # current_pos  current_status  current_pass
+//! 0x00008000     +               1
+//! #      pos        size  status
+//! 0x00000000  0x00008000  +
+//! 0x00008000  0x00000200  -
+//! 0x00008200  0x00010000  ?
+//! ```
+//!
+//! Blocks are byte ranges of the rescued data; `+` is finished, `-` a bad
+//! area, and `?`, `*`, `/` areas not yet tried, trimmed or scraped.
+use chrono::NaiveDateTime;
+
+use super::event::{LogEvent, LogEventKind};
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn parse_events<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<LogEvent> {
+    let mut events = Vec::new();
+    let mut version = String::new();
+    let mut image = String::new();
+    let mut created = None;
+    for (line, text) in lines.into_iter().enumerate() {
+        let text = text.trim();
+        if let Some(comment) = text.strip_prefix('#') {
+            let comment = comment.trim();
+            if let Some(v) = comment.split_once("ddrescue version ").map(|(_, v)| v) {
+                version = v.trim().to_string();
+            } else if let Some(args) = comment.strip_prefix("Command line:") {
+                image = output_file(args).unwrap_or_default();
+            } else if let Some(start) = comment.strip_prefix("Start time:") {
+                created = NaiveDateTime::parse_from_str(start.trim(), DATE_FORMAT).ok();
+            }
+            continue;
+        }
+        let Some((offset, length, status)) = parse_block(text) else {
+            continue;
+        };
+        if events.is_empty() {
+            events.push(LogEvent {
+                line: 0,
+                record: 0,
+                kind: LogEventKind::Header {
+                    image: image.clone(),
+                    tool: "ddrescue".into(),
+                    version: version.clone(),
+                    created,
+                },
+            });
+        }
+        let kind = match status {
+            '-' => LogEventKind::BadArea { offset, length },
+            '?' | '*' | '/' => LogEventKind::UnreadArea { offset, length },
+            _ => continue,
+        };
+        events.push(LogEvent {
+            line,
+            record: 0,
+            kind,
+        });
+    }
+    events
+}
+
+/// Lines only ddrescue writes, for dialect detection.
+pub(crate) fn is_signature(line: &str) -> bool {
+    line.starts_with("# Mapfile.")
+        || line.starts_with("# Rescue Logfile.")
+        || parse_block(line).is_some()
+}
+
+/// `POS SIZE STATUS`, both numbers in hex.
+fn parse_block(line: &str) -> Option<(u64, u64, char)> {
+    let mut fields = line.split_whitespace();
+    let offset = hex(fields.next()?)?;
+    let length = hex(fields.next()?)?;
+    let mut status = fields.next()?.chars();
+    let status = status.next().filter(|_| status.next().is_none())?;
+    (fields.next().is_none() && "?*/-+".contains(status)).then_some((offset, length, status))
+}
+
+fn hex(text: &str) -> Option<u64> {
+    u64::from_str_radix(text.strip_prefix("0x")?, 16).ok()
+}
+
+/// Output file of `ddrescue [options] infile outfile mapfile`: the
+/// second-last argument, as option values may also stand alone.
+fn output_file(args: &str) -> Option<String> {
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let file = args.len().checked_sub(2).map(|i| args[i])?;
+    (args.len() > 3 && !file.starts_with('-')).then(|| file.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_events;
+    use crate::log::event::LogEventKind;
+
+    #[test]
+    fn reports_bad_and_untried_areas() {
+        let map = "# Mapfile. Created by GNU ddrescue version 1.27
+# Command line: ddrescue -b 8192 /dev/nst0 TA0114.bin TA0114.map
+# Start time:   2025-11-05 14:17:12
+# current_pos  current_status  current_pass
+0x00008000     +               1
+#      pos        size  status
+0x00000000  0x00008000  +
+0x00008000  0x00000200  -
+0x00008200  0x00010000  ?";
+        let kinds: Vec<_> = parse_events(map.lines())
+            .into_iter()
+            .map(|e| e.kind)
+            .collect();
+        assert!(matches!(
+            &kinds[0],
+            LogEventKind::Header { image, version, created: Some(_), .. }
+                if image == "TA0114.bin" && version == "1.27"
+        ));
+        assert_eq!(
+            kinds[1..],
+            [
+                LogEventKind::BadArea {
+                    offset: 0x8000,
+                    length: 0x200
+                },
+                LogEventKind::UnreadArea {
+                    offset: 0x8200,
+                    length: 0x10000
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Capture-log dialects: which tool wrote a log, detected from the lines
+//! only that tool writes, and the grammar that decodes it.
+use super::event::LogEvent;
+use super::{dd, ddrescue, simh, stp2tap, tapedump};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogDialect {
+    Stp2tap,
+    /// SIMH `sim_tape` attach messages and debug trace.
+    Simh,
+    /// tapeutils `tapedump` listing.
+    Tapedump,
+    /// GNU ddrescue mapfile.
+    Ddrescue,
+    /// dd or tapecopy stderr.
+    Dd,
+    /// Anything else: lines are classified by keyword only.
+    #[default]
+    Generic,
+}
+
+impl LogDialect {
+    pub const ALL: [LogDialect; 5] = [
+        LogDialect::Stp2tap,
+        LogDialect::Simh,
+        LogDialect::Tapedump,
+        LogDialect::Ddrescue,
+        LogDialect::Dd,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogDialect::Stp2tap => "STP2TAP",
+            LogDialect::Simh => "SIMH sim_tape",
+            LogDialect::Tapedump => "tapeutils tapedump",
+            LogDialect::Ddrescue => "GNU ddrescue mapfile",
+            LogDialect::Dd => "dd",
+            LogDialect::Generic => "generic",
+        }
+    }
+
+    fn is_signature(&self, line: &str) -> bool {
+        match self {
+            LogDialect::Stp2tap => stp2tap::is_signature(line),
+            LogDialect::Simh => simh::is_signature(line),
+            LogDialect::Tapedump => tapedump::is_signature(line),
+            LogDialect::Ddrescue => ddrescue::is_signature(line),
+            LogDialect::Dd => dd::is_signature(line),
+            LogDialect::Generic => false,
+        }
+    }
+
+    /// Decode `content` with this dialect's grammar.
+    pub fn parse_events(&self, content: &str) -> Vec<LogEvent> {
+        let lines = content.lines();
+        match self {
+            LogDialect::Stp2tap => stp2tap::parse_events(lines),
+            LogDialect::Simh => simh::parse_events(lines),
+            LogDialect::Tapedump => tapedump::parse_events(lines),
+            LogDialect::Ddrescue => ddrescue::parse_events(lines),
+            LogDialect::Dd => dd::parse_events(lines),
+            LogDialect::Generic => Vec::new(),
+        }
+    }
+}
+
+/// The dialect with the most signature lines; `Generic` when none has any.
+/// Ties go to the earlier dialect in `LogDialect::ALL`.
+pub fn detect_dialect(content: &str) -> LogDialect {
+    let mut best = (0usize, LogDialect::Generic);
+    for dialect in LogDialect::ALL {
+        let score = content
+            .lines()
+            .filter(|l| dialect.is_signature(l.trim()))
+            .count();
+        if score > best.0 {
+            best = (score, dialect);
+        }
+    }
+    best.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_dialect, LogDialect};
+
+    #[test]
+    fn detects_each_dialect() {
+        let cases = [
+            (
+                "X.TAP created by STP2TAP 0.02 on Wed Nov 05 14:17:12 2025\nReading...\n3 x 80 bytes",
+                LogDialect::Stp2tap,
+            ),
+            (
+                "%SIM-INFO: MTA0: Tape Image 'X.TAP' scanned as SIMH format\n\
+                 DBG(1)> MTA0 DETAIL: rd_lnt: st: 0, lnt: 80, f: 0",
+                LogDialect::Simh,
+            ),
+            (
+                "tapedump: reading /dev/nst0\nrecord 1: 80 bytes\ntape mark",
+                LogDialect::Tapedump,
+            ),
+            (
+                "# Mapfile. Created by GNU ddrescue version 1.27\n0x00000000  0x00008000  +",
+                LogDialect::Ddrescue,
+            ),
+            (
+                "dd: error reading '/dev/nst0': Input/output error\n12+0 records in\n12+0 records out",
+                LogDialect::Dd,
+            ),
+            ("Tracks = 40\nWARNING slow\nRecord 2 ERROR", LogDialect::Generic),
+        ];
+        for (log, dialect) in cases {
+            assert_eq!(detect_dialect(log), dialect, "{log}");
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Normalised capture-log events. Every dialect grammar (STP2TAP, SIMH,
+//! tapedump, ddrescue, dd) turns its lines into these, so layout,
+//! correlation and labels work the same whichever tool made the capture.
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEvent {
+    /// Index of the line the event was read from.
+    pub line: usize,
+    /// Records logged before this event, i.e. the index of the next record
+    /// in the image. Zero-byte records cannot be stored in an image and are
+    /// not counted.
+    pub record: u64,
+    pub kind: LogEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogEventKind {
+    /// `IMAGE created by TOOL VERSION on DATE`, or what a tool reports of
+    /// the same.
+    Header {
+        image: String,
+        tool: String,
+        version: String,
+        created: Option<NaiveDateTime>,
+    },
+    /// One line of the options section.
+    Option(String),
+    /// Free-text label between the separator lines, blank edges trimmed.
+    Label(String),
+    /// `count` consecutive records of `size` bytes.
+    Records {
+        count: u64,
+        size: u32,
+    },
+    /// Position the tape mark was reported at: the drive block for
+    /// STP2TAP, the record count for tools that report none.
+    TapeMark {
+        block: u64,
+    },
+    EndOfData {
+        block: u64,
+    },
+    /// Read failure or drive fault reported while reading.
+    ReadError(String),
+    Retry(String),
+    /// Bytes `offset..offset + length` of the record data stream that
+    /// could not be read.
+    BadArea {
+        offset: u64,
+        length: u64,
+    },
+    /// Bytes of the record data stream not (fully) tried yet.
+    UnreadArea {
+        offset: u64,
+        length: u64,
+    },
+    Finished {
+        at: Option<NaiveDateTime>,
+    },
+    /// Any other non-blank line.
+    Note(String),
+}
+
+/// Append one record of `size` bytes at position `record`, extending the
+/// previous event when it is a run of the same size.
+pub(crate) fn push_record(events: &mut Vec<LogEvent>, line: usize, record: u64, size: u32) {
+    if let Some(LogEvent {
+        kind: LogEventKind::Records { count, size: run },
+        ..
+    }) = events.last_mut()
+    {
+        if *run == size {
+            *count += 1;
+            return;
+        }
+    }
+    events.push(LogEvent {
+        line,
+        record,
+        kind: LogEventKind::Records { count: 1, size },
+    });
+}
+
+/// Classify a line no grammar rule matched by its wording.
+pub(crate) fn free_text(line: &str) -> LogEventKind {
+    let lower = line.to_ascii_lowercase();
+    if lower.contains("retry") || lower.contains("retries") {
+        LogEventKind::Retry(line.to_string())
+    } else if ["error", "not-ready", "not ready", "bad ", "fail"]
+        .iter()
+        .any(|k| lower.contains(k))
+    {
+        LogEventKind::ReadError(line.to_string())
+    } else {
+        LogEventKind::Note(line.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{push_record, LogEventKind};
+
+    #[test]
+    fn merges_equal_sized_records() {
+        let mut events = Vec::new();
+        push_record(&mut events, 0, 0, 80);
+        push_record(&mut events, 1, 1, 80);
+        push_record(&mut events, 2, 2, 512);
+        let kinds: Vec<_> = events.iter().map(|e| (e.line, &e.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (0, &LogEventKind::Records { count: 2, size: 80 }),
+                (
+                    2,
+                    &LogEventKind::Records {
+                        count: 1,
+                        size: 512
+                    }
+                ),
+            ]
+        );
+    }
+}
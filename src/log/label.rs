@@ -0,0 +1,222 @@
+//! Tape metadata from the physical label transcribed into an STP2TAP log:
+//!
+//! ```text
+//! BB-KR74A-BE M48001TM              part number (and kit code)
+//! VAX/MICROVMS V4.4/4.5 MUP 16MT9   product title
+//! (C) COPYRIGHT 1987                copyright, with the owner line below
+//! Digital Equipment Corporation
+//! BAKED ON 4/11/25 55C BY AF        media notes
+//! ```
+use chrono::NaiveDateTime;
+
+use super::event::LogEventKind;
+use super::parse::LogData;
+use crate::utils::text::sanitize_display;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TapeMetadata {
+    /// Image name from the log header.
+    pub image: Option<String>,
+    /// DEC order number, e.g. `BB-KR74A-BE`.
+    pub part_number: Option<String>,
+    pub title: Option<String>,
+    pub copyright: Option<String>,
+    /// Remaining label lines: baking, condition and handling notes.
+    pub notes: Vec<String>,
+    /// When the capture was made (log header date).
+    pub captured: Option<NaiveDateTime>,
+}
+
+impl TapeMetadata {
+    /// Labelled, display-ready fields for the summary and catalog export;
+    /// absent fields are left out.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut out = Vec::new();
+        let mut push = |name, value: &Option<String>| {
+            if let Some(value) = value {
+                out.push((name, sanitize_display(value)));
+            }
+        };
+        push("Tape image", &self.image);
+        push("Part number", &self.part_number);
+        push("Product", &self.title);
+        push("Copyright", &self.copyright);
+        push(
+            "Captured",
+            &self
+                .captured
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+        );
+        for note in &self.notes {
+            out.push(("Media note", sanitize_display(note)));
+        }
+        out
+    }
+}
+
+/// Structured metadata of the tape a log was captured from; `None` when the
+/// log has neither an STP2TAP header nor a label.
+pub fn tape_metadata(log: &LogData) -> Option<TapeMetadata> {
+    let mut meta = TapeMetadata::default();
+    let mut label = None;
+    for event in &log.events {
+        match &event.kind {
+            LogEventKind::Header { image, created, .. } => {
+                meta.image = Some(image.clone());
+                meta.captured = *created;
+            }
+            LogEventKind::Label(text) if label.is_none() => label = Some(text.as_str()),
+            _ => {}
+        }
+    }
+    if meta.image.is_none() && label.is_none() {
+        return None;
+    }
+
+    let stem = meta
+        .image
+        .as_deref()
+        .and_then(|i| i.split('.').next())
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let mut lines = label
+        .into_iter()
+        .flat_map(str::lines)
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .peekable();
+    while let Some(line) = lines.next() {
+        if meta.part_number.is_none() {
+            if let Some(part) = line.split_whitespace().find(|w| is_part_number(w)) {
+                meta.part_number = Some(part.to_string());
+                continue;
+            }
+        }
+        if meta.copyright.is_none() && line.to_ascii_uppercase().contains("COPYRIGHT") {
+            let mut text = line.to_string();
+            if let Some(owner) = lines.next_if(|l| is_owner(l)) {
+                text = format!("{text} {owner}");
+            }
+            meta.copyright = Some(text);
+            continue;
+        }
+        // The tape's own catalogue id (the image name) is not a title.
+        let is_tape_id = !stem.is_empty() && line.eq_ignore_ascii_case(&stem);
+        if meta.title.is_none() && !is_tape_id && !is_note(line) {
+            meta.title = Some(line.to_string());
+            continue;
+        }
+        if !is_tape_id {
+            meta.notes.push(line.to_string());
+        }
+    }
+    Some(meta)
+}
+
+/// DEC order numbers: two letters, then two dash-separated groups, e.g.
+/// `BB-KR74A-BE`, `AQ-5394B-TE`.
+fn is_part_number(word: &str) -> bool {
+    let parts: Vec<&str> = word.split('-').collect();
+    let alnum = |s: &str| {
+        s.chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    };
+    matches!(parts.as_slice(), [prefix, middle, suffix]
+        if prefix.len() == 2
+            && prefix.chars().all(|c| c.is_ascii_uppercase())
+            && (4..=6).contains(&middle.len())
+            && alnum(middle)
+            && suffix.len() == 2
+            && alnum(suffix))
+}
+
+fn is_owner(line: &str) -> bool {
+    let upper = line.to_ascii_uppercase();
+    ["CORPORATION", "CORP.", "INC", "LTD", "COMPANY"]
+        .iter()
+        .any(|w| upper.contains(w))
+}
+
+/// Capture and conservation notes rather than printed label text.
+fn is_note(line: &str) -> bool {
+    let upper = line.to_ascii_uppercase();
+    [
+        "BAKED",
+        "LUBRICANT",
+        "READ BY",
+        "RECORDED AT",
+        "MUSEUM",
+        "CLEANED",
+    ]
+    .iter()
+    .any(|w| upper.contains(w))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_part_number, tape_metadata};
+    use crate::log::parse::parse_log_text;
+
+    #[test]
+    fn splits_a_dec_label() {
+        let log = parse_log_text(
+            "TA0114.TAP created by STP2TAP 0.02 on Wed Nov 05 14:17:12 2025
+---------------------------------------------
+
+BB-KR74A-BE M48001TM
+VAX/MICROVMS V4.4/4.5 MUP 16MT9
+(C) COPYRIGHT 1987
+Digital Equipment Corporation
+BAKED ON 4/11/25 55C BY AF
+SILICON LUBRICANT
+
+---------------------------------------------
+",
+        );
+        let meta = tape_metadata(&log).unwrap();
+        assert_eq!(meta.image.as_deref(), Some("TA0114.TAP"));
+        assert_eq!(meta.part_number.as_deref(), Some("BB-KR74A-BE"));
+        assert_eq!(
+            meta.title.as_deref(),
+            Some("VAX/MICROVMS V4.4/4.5 MUP 16MT9")
+        );
+        assert_eq!(
+            meta.copyright.as_deref(),
+            Some("(C) COPYRIGHT 1987 Digital Equipment Corporation")
+        );
+        assert_eq!(
+            meta.notes,
+            vec!["BAKED ON 4/11/25 55C BY AF", "SILICON LUBRICANT"]
+        );
+        assert_eq!(
+            meta.fields()[4],
+            ("Captured", "2025-11-05 14:17:12".to_string())
+        );
+        assert_eq!(meta.fields().len(), 7);
+    }
+
+    #[test]
+    fn skips_the_tape_id_line() {
+        let log = parse_log_text(
+            "TA0013.TAP created by STP2TAP 0.02 on Fri Jan 02 00:43:40 1998
+--------------------
+TA0013
+RSTS/E 9.7 Installer
+--------------------
+",
+        );
+        let meta = tape_metadata(&log).unwrap();
+        assert_eq!(meta.part_number, None);
+        assert_eq!(meta.title.as_deref(), Some("RSTS/E 9.7 Installer"));
+        assert!(meta.notes.is_empty());
+        assert!(tape_metadata(&parse_log_text("WARNING only")).is_none());
+    }
+
+    #[test]
+    fn recognises_part_numbers() {
+        assert!(is_part_number("BB-FY83D-BE"));
+        assert!(is_part_number("AQ-5394B-TE"));
+        assert!(!is_part_number("M48001TM"));
+        assert!(!is_part_number("25-11-92"));
+    }
+}
@@ -1 +1,10 @@
+pub mod crosscheck;
+pub mod dd;
+pub mod ddrescue;
+pub mod dialect;
+pub mod event;
+pub mod label;
 pub mod parse;
+pub mod simh;
+pub mod stp2tap;
+pub mod tapedump;
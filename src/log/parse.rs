@@ -1,11 +1,16 @@
-//! LOG parsing: classify lines by severity, collect drive metadata, and correlate with TAP records.
+//! LOG parsing: classify lines by severity, collect drive metadata, decode
+//! the events of the detected capture-tool dialect, and correlate with TAP
+//! records.
 use std::collections::HashMap;
 use std::fs;
+use std::ops::Range;
 use std::path::Path;
 
-use crate::tap::legacy::TapEntry;
 use crate::{TapeError, TapeResult};
 
+use super::dialect::{detect_dialect, LogDialect};
+use super::event::{LogEvent, LogEventKind};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LogLevel {
     Info,
@@ -23,15 +28,81 @@ pub struct LogEntry {
 pub struct LogData {
     pub entries: Vec<LogEntry>,
     pub metadata: HashMap<String, String>,
+    /// Events in log order; `LogEvent::line` indexes `entries`.
+    pub events: Vec<LogEvent>,
+    /// Tool the log was detected as coming from.
+    pub dialect: LogDialect,
+}
+
+/// Consecutive records of one size inside one tape file, as logged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordRun {
+    pub tape_file: u32,
+    /// Index of the first record of the run.
+    pub first: u64,
+    pub count: u64,
+    pub size: u32,
+    pub line: usize,
+}
+
+impl LogData {
+    /// Record runs with their tape files: the file number advances at every
+    /// logged tape mark.
+    pub fn record_layout(&self) -> Vec<RecordRun> {
+        let mut runs = Vec::new();
+        let mut tape_file = 0u32;
+        for event in &self.events {
+            match event.kind {
+                LogEventKind::TapeMark { .. } => tape_file += 1,
+                LogEventKind::Records { count, size } => runs.push(RecordRun {
+                    tape_file,
+                    first: event.record,
+                    count,
+                    size,
+                    line: event.line,
+                }),
+                _ => {}
+            }
+        }
+        runs
+    }
+
+    /// Free-text tape label written between the separator lines.
+    pub fn label(&self) -> Option<&str> {
+        self.events.iter().find_map(|e| match &e.kind {
+            LogEventKind::Label(text) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn options(&self) -> Vec<&str> {
+        self.events
+            .iter()
+            .filter_map(|e| match &e.kind {
+                LogEventKind::Option(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 /// Parse a TAP companion `.LOG` file, classifying lines by severity and
 /// extracting drive metadata hints when present.
 pub fn parse_log(path: &Path) -> TapeResult<LogData> {
-    let content = fs::read_to_string(path).map_err(|err| TapeError::Io(err))?;
+    let content = fs::read_to_string(path).map_err(TapeError::Io)?;
+    Ok(parse_log_text(&content))
+}
 
+/// Parse log text: the events of the detected dialect set the level of
+/// their lines, other lines are classified by keyword.
+pub fn parse_log_text(content: &str) -> LogData {
+    let dialect = detect_dialect(content);
+    let events = dialect.parse_events(content);
     let mut entries = Vec::new();
     let mut metadata = HashMap::new();
+    if dialect != LogDialect::Generic {
+        metadata.insert("Log format".into(), dialect.label().to_string());
+    }
 
     for line in content.lines() {
         // Capture metadata if present.
@@ -51,33 +122,190 @@ pub fn parse_log(path: &Path) -> TapeResult<LogData> {
         });
     }
 
-    Ok(LogData { entries, metadata })
+    for event in &events {
+        let entry = &mut entries[event.line];
+        match &event.kind {
+            LogEventKind::ReadError(_) | LogEventKind::BadArea { .. } => {
+                entry.level = LogLevel::Error
+            }
+            LogEventKind::Retry(_) | LogEventKind::UnreadArea { .. } => {
+                entry.level = LogLevel::Warning
+            }
+            LogEventKind::Header {
+                image,
+                tool,
+                version,
+                created,
+            } => {
+                if !image.is_empty() {
+                    metadata.insert("Image".into(), image.clone());
+                }
+                metadata.insert("Tool".into(), format!("{tool} {version}").trim_end().into());
+                if let Some(t) = created {
+                    metadata.insert("Created".into(), t.to_string());
+                }
+            }
+            LogEventKind::Finished { at: Some(t) } => {
+                metadata.insert("Finished".into(), t.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    LogData {
+        entries,
+        metadata,
+        events,
+        dialect,
+    }
 }
 
-/// Correlate log lines to TAP entries by record or block number, annotating entries with log levels.
-pub fn correlate_log(entries: &mut [TapEntry], log: &LogData) {
-    for log_entry in &log.entries {
-        let level = match log_entry.level {
-            LogLevel::Error => Some(LogLevel::Error),
-            LogLevel::Warning => Some(LogLevel::Warning),
-            LogLevel::Info => None,
-        };
+/// Correlate log lines to tape records by record number: the log level of
+/// each record of `record_sizes`, `None` where no line points at it.
+pub fn correlate_log(record_sizes: &[usize], log: &LogData) -> Vec<Option<LogLevel>> {
+    let mut levels = vec![None; record_sizes.len()];
+    for (record, level) in flagged_records(log, record_sizes) {
+        if let Some(slot) = levels.get_mut(record) {
+            // Preserve the highest severity when multiple log lines hit the same record.
+            match slot {
+                Some(LogLevel::Error) => {}
+                _ => *slot = Some(level),
+            }
+        }
+    }
+    levels
+}
 
-        let Some(level) = level else { continue };
+/// Records (0-based) that warning and error lines point at. Decoded errors
+/// and retries apply to the record being read at that position, and bad or
+/// unread byte areas to the records of `record_sizes` they overlap; other
+/// lines name a record number. Lines decoded as tape marks, record runs or
+/// end of data carry drive positions, not record numbers, and never match.
+pub fn flagged_records(log: &LogData, record_sizes: &[usize]) -> Vec<(usize, LogLevel)> {
+    flagged_lines(log, record_sizes)
+        .into_iter()
+        .flat_map(|(line, records)| {
+            let level = &log.entries[line].level;
+            records.map(move |r| (r, level.clone()))
+        })
+        .collect()
+}
+
+/// A log line and the image records it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordLink {
+    pub line: usize,
+    pub records: Range<usize>,
+}
+
+/// Links from log lines to records in line order: every logged record run
+/// and every warning or error line that points at records.
+pub fn record_links(log: &LogData, record_sizes: &[usize]) -> Vec<RecordLink> {
+    let mut links: Vec<RecordLink> = log
+        .record_layout()
+        .iter()
+        .filter(|r| r.size > 0)
+        .map(|r| RecordLink {
+            line: r.line,
+            records: r.first as usize..(r.first + r.count) as usize,
+        })
+        .collect();
+    links.extend(
+        flagged_lines(log, record_sizes)
+            .into_iter()
+            .map(|(line, records)| RecordLink { line, records }),
+    );
+    links.sort_by_key(|l| l.line);
+    links
+}
+
+/// First record a log line refers to.
+pub fn record_of_line(links: &[RecordLink], line: usize) -> Option<usize> {
+    links
+        .iter()
+        .find(|l| l.line == line)
+        .map(|l| l.records.start)
+}
 
-        let idx = find_record_index(&log_entry.line).and_then(|n| n.checked_sub(1));
-        if let Some(i) = idx {
-            if let Some(entry) = entries.get_mut(i) {
-                // Preserve the highest severity when multiple log lines hit the same record.
-                match entry.log_level {
-                    Some(LogLevel::Error) => {}
-                    _ => entry.log_level = Some(level.clone()),
+/// Line that refers most narrowly to `record`: a line reporting on the
+/// record itself wins over the run it was logged in.
+pub fn line_of_record(links: &[RecordLink], record: usize) -> Option<usize> {
+    links
+        .iter()
+        .filter(|l| l.records.contains(&record))
+        .min_by_key(|l| l.records.len())
+        .map(|l| l.line)
+}
+
+/// Warning and error lines with the records they point at; see
+/// `flagged_records`.
+fn flagged_lines(log: &LogData, record_sizes: &[usize]) -> Vec<(usize, Range<usize>)> {
+    let layout = log.record_layout();
+    let stored = layout
+        .iter()
+        .filter(|r| r.size > 0)
+        .map(|r| r.count)
+        .sum::<u64>();
+    // First event of each line: a line can report an error and then the
+    // record it was read into.
+    let structured: HashMap<usize, &LogEvent> = log
+        .events
+        .iter()
+        .rev()
+        .filter(|e| !matches!(e.kind, LogEventKind::Note(_)))
+        .map(|e| (e.line, e))
+        .collect();
+
+    let mut out = Vec::new();
+    for (line, entry) in log.entries.iter().enumerate() {
+        if entry.level == LogLevel::Info {
+            continue;
+        }
+        let records = match structured.get(&line) {
+            Some(event) => match event.kind {
+                // Without a logged layout (dd) any position is a record.
+                LogEventKind::ReadError(_) | LogEventKind::Retry(_)
+                    if layout.is_empty() || event.record < stored =>
+                {
+                    let record = event.record as usize;
+                    Some(record..record + 1)
                 }
-            }
+                LogEventKind::BadArea { offset, length }
+                | LogEventKind::UnreadArea { offset, length } => {
+                    Some(records_in_range(record_sizes, offset, length))
+                }
+                _ => None,
+            },
+            None => find_record_index(&entry.line)
+                .and_then(|n| n.checked_sub(1))
+                .map(|record| record..record + 1),
+        };
+        out.extend(records.map(|records| (line, records)));
+    }
+    out
+}
+
+/// Records whose bytes overlap `offset..offset + length` of the record data
+/// stream. A range running past the last record also takes in the index
+/// one past it.
+fn records_in_range(sizes: &[usize], offset: u64, length: u64) -> Range<usize> {
+    let end = offset + length.max(1);
+    let mut start = 0u64;
+    let mut first = None;
+    for (record, &size) in sizes.iter().enumerate() {
+        let stop = start + size as u64;
+        if stop > offset && first.is_none() {
+            first = Some(record);
         }
+        if stop >= end {
+            return first.unwrap_or(record)..record + 1;
+        }
+        start = stop;
     }
+    first.unwrap_or(sizes.len())..sizes.len() + 1
 }
 
+/// Record number (1-based) a log line refers to, if any.
 fn find_record_index(line: &str) -> Option<usize> {
     let lower = line.to_ascii_lowercase();
     for key in ["record", "block", "skipped"] {
@@ -116,9 +344,10 @@ fn parse_drive_metadata(line: &str, metadata: &mut HashMap<String, String>) {
 
 #[cfg(test)]
 mod tests {
-    use super::{correlate_log, parse_drive_metadata, parse_log, LogData, LogEntry, LogLevel};
-    use crate::tap::legacy::{TapDataKind, TapEntry};
-    use crate::tap::DetectedFormat;
+    use super::{
+        correlate_log, flagged_records, line_of_record, parse_drive_metadata, parse_log,
+        parse_log_text, record_links, record_of_line, LogData, LogEntry, LogLevel,
+    };
     use std::collections::HashMap;
     use std::fs;
     use std::io;
@@ -188,21 +417,6 @@ mod tests {
 
     #[test]
     fn correlates_record_numbers() {
-        let mut entries = vec![
-            TapEntry {
-                length: 1,
-                kind: TapDataKind::Raw(vec![]),
-                log_level: None,
-                detected_format: DetectedFormat::Raw,
-            },
-            TapEntry {
-                length: 1,
-                kind: TapDataKind::Raw(vec![]),
-                log_level: None,
-                detected_format: DetectedFormat::Raw,
-            },
-        ];
-
         let log = LogData {
             entries: vec![
                 LogEntry {
@@ -214,11 +428,36 @@ mod tests {
                     level: LogLevel::Error,
                 },
             ],
-            metadata: HashMap::new(),
+            ..Default::default()
         };
 
-        correlate_log(&mut entries, &log);
-        assert!(entries[0].log_level.is_none());
-        assert!(matches!(entries[1].log_level, Some(LogLevel::Error)));
+        let levels = correlate_log(&[1, 1], &log);
+        assert!(levels[0].is_none());
+        assert!(matches!(levels[1], Some(LogLevel::Error)));
+    }
+
+    #[test]
+    fn stp2tap_errors_flag_the_record_being_read() {
+        let log = parse_log_text(
+            "Reading...\n2 x 512 bytes\nTapemark hit at block 8071936\n1 x 512 bytes\n\
+             Read error at block 8071936\n4 x 512 bytes\nDrive went not-ready.  Exiting.\n",
+        );
+        // The drive fault comes after the last record and flags none.
+        assert_eq!(flagged_records(&log, &[]), vec![(3, LogLevel::Error)]);
+    }
+
+    #[test]
+    fn links_lines_and_records_both_ways() {
+        let log = parse_log_text(
+            "Reading...\n3 x 512 bytes\nRetrying read, retry 2\n2 x 512 bytes\nTapemark hit at block 9\n",
+        );
+        let links = record_links(&log, &[512; 5]);
+        assert_eq!(record_of_line(&links, 1), Some(0));
+        assert_eq!(record_of_line(&links, 2), Some(3));
+        assert_eq!(record_of_line(&links, 4), None);
+        // The retry names record 3 more narrowly than its run.
+        assert_eq!(line_of_record(&links, 3), Some(2));
+        assert_eq!(line_of_record(&links, 4), Some(3));
+        assert_eq!(line_of_record(&links, 5), None);
     }
 }
@@ -0,0 +1,123 @@
+//! SIMH `sim_tape` log grammar: the attach message and the `rd_lnt` debug
+//! trace (`SET <dev> DEBUG`) written while a tape image is read.
+//!
+//! ```text
+//! %SIM-INFO: MTA0: Tape Image 'TA0114.TAP' scanned as SIMH format
+//! DBG(1082)> MTA0 DETAIL: rd_lnt: st: 0, lnt: 80, f: 0
+//! DBG(1190)> MTA0 DETAIL: rd_lnt: st: 1, lnt: 0, f: 88
+//! DBG(2240)> MTA0 DETAIL: rd_lnt: st: 8, lnt: 8192, f: 92
+//! DBG(2301)> MTA0 DETAIL: rd_lnt: st: 7, lnt: 0, f: 8292
+//! ```
+//!
+//! `st` is the `MTSE_` status of the read and `lnt` the record length.
+use super::event::{free_text, push_record, LogEvent, LogEventKind};
+
+const MTSE_OK: u32 = 0;
+const MTSE_TMK: u32 = 1;
+const MTSE_UNATT: u32 = 2;
+const MTSE_BOT: u32 = 6;
+const MTSE_EOM: u32 = 7;
+/// Record read, but flagged as bad in the image.
+const MTSE_RECE: u32 = 8;
+const MTSE_WRP: u32 = 9;
+const MTSE_LEOT: u32 = 10;
+
+pub fn parse_events<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<LogEvent> {
+    let mut events = Vec::new();
+    let mut record = 0u64;
+    for (line, text) in lines.into_iter().enumerate() {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let mut push = |kind| events.push(LogEvent { line, record, kind });
+        if let Some(image) = attached_image(text) {
+            push(LogEventKind::Header {
+                image,
+                tool: "SIMH".into(),
+                version: String::new(),
+                created: None,
+            });
+            continue;
+        }
+        let Some((status, length)) = read_status(text) else {
+            push(free_text(text));
+            continue;
+        };
+        match status {
+            MTSE_OK | MTSE_RECE => {
+                if status == MTSE_RECE {
+                    push(LogEventKind::ReadError(text.to_string()));
+                }
+                if length > 0 {
+                    push_record(&mut events, line, record, length);
+                    record += 1;
+                }
+            }
+            MTSE_TMK => push(LogEventKind::TapeMark { block: record }),
+            MTSE_EOM | MTSE_LEOT => push(LogEventKind::EndOfData { block: record }),
+            MTSE_UNATT | MTSE_BOT | MTSE_WRP => push(LogEventKind::Note(text.to_string())),
+            _ => push(LogEventKind::ReadError(text.to_string())),
+        }
+    }
+    events
+}
+
+/// Lines only SIMH writes, for dialect detection.
+pub(crate) fn is_signature(line: &str) -> bool {
+    line.starts_with("%SIM-") || line.contains("sim_tape") || read_status(line).is_some()
+}
+
+/// `Tape Image 'NAME' scanned as ...`.
+fn attached_image(line: &str) -> Option<String> {
+    let rest = &line[line.find("Tape Image '")? + "Tape Image '".len()..];
+    Some(rest[..rest.find('\'')?].to_string())
+}
+
+/// `st` and `lnt` of an `rd_lnt` trace line.
+fn read_status(line: &str) -> Option<(u32, u32)> {
+    let rest = &line[line.find("rd_lnt:")? + "rd_lnt:".len()..];
+    Some((field(rest, "st:")?, field(rest, "lnt:")?))
+}
+
+fn field(text: &str, name: &str) -> Option<u32> {
+    let rest = text[text.find(name)? + name.len()..].trim_start();
+    let digits = rest.split(|c: char| !c.is_ascii_digit()).next()?;
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_events;
+    use crate::log::event::LogEventKind;
+
+    #[test]
+    fn decodes_read_statuses() {
+        let log = "%SIM-INFO: MTA0: Tape Image 'TA0114.TAP' scanned as SIMH format
+DBG(1)> MTA0 DETAIL: rd_lnt: st: 0, lnt: 80, f: 0
+DBG(2)> MTA0 DETAIL: rd_lnt: st: 0, lnt: 80, f: 88
+DBG(3)> MTA0 DETAIL: rd_lnt: st: 1, lnt: 0, f: 176
+DBG(4)> MTA0 DETAIL: rd_lnt: st: 8, lnt: 8192, f: 180
+DBG(5)> MTA0 DETAIL: rd_lnt: st: 7, lnt: 0, f: 8380";
+        let events = parse_events(log.lines());
+        let kinds: Vec<_> = events.iter().map(|e| (e.record, &e.kind)).collect();
+        assert!(matches!(
+            kinds[0].1,
+            LogEventKind::Header { image, tool, .. } if image == "TA0114.TAP" && tool == "SIMH"
+        ));
+        assert_eq!(kinds[1], (0, &LogEventKind::Records { count: 2, size: 80 }));
+        assert_eq!(kinds[2], (2, &LogEventKind::TapeMark { block: 2 }));
+        assert!(matches!(kinds[3], (2, LogEventKind::ReadError(_))));
+        assert_eq!(
+            kinds[4],
+            (
+                2,
+                &LogEventKind::Records {
+                    count: 1,
+                    size: 8192
+                }
+            )
+        );
+        assert_eq!(kinds[5], (3, &LogEventKind::EndOfData { block: 3 }));
+    }
+}
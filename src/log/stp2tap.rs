@@ -0,0 +1,273 @@
+//! STP2TAP log grammar. A log reads:
+//!
+//! ```text
+//! TA0114.TAP created by STP2TAP 0.02 on Wed Nov 05 14:17:12 2025
+//! Options are as follows:
+//!   Copy all blocks.
+//! ---------------------------------------------
+//! free-text tape label
+//! ---------------------------------------------
+//! Reading...
+//! 10 x 8192 bytes
+//! Tapemark hit at block 8071936
+//! End of data hit at 3542722
+//! Finished reading at Wed Nov 05 14:24:11 2025
+//! ```
+//!
+//! `parse_events` turns the lines into typed events; record runs are
+//! run-length: `N x S bytes` is N consecutive records of S bytes.
+use chrono::NaiveDateTime;
+
+use super::event::{free_text, LogEvent, LogEventKind};
+
+/// Date format STP2TAP prints (C `ctime` without the newline).
+const DATE_FORMAT: &str = "%a %b %d %H:%M:%S %Y";
+const SEPARATOR: char = '-';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Preamble,
+    Options,
+    Label,
+    Reading,
+}
+
+/// Parse the lines of a log into events. Lines that fit no rule become
+/// `Note`s, so logs from other tools yield notes only.
+pub fn parse_events<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<LogEvent> {
+    let mut events = Vec::new();
+    let mut section = Section::Preamble;
+    let mut record = 0u64;
+    let mut label: Vec<&str> = Vec::new();
+    let mut label_line = 0usize;
+
+    for (line, text) in lines.into_iter().enumerate() {
+        let trimmed = text.trim();
+        if is_separator(trimmed) {
+            if section == Section::Label {
+                if let Some(text) = label_text(&label) {
+                    events.push(LogEvent {
+                        line: label_line,
+                        record,
+                        kind: LogEventKind::Label(text),
+                    });
+                }
+                label.clear();
+                section = Section::Preamble;
+            } else if section != Section::Reading {
+                section = Section::Label;
+                label_line = line + 1;
+            }
+            continue;
+        }
+        if section == Section::Label {
+            label.push(trimmed);
+            continue;
+        }
+        if trimmed.is_empty() {
+            if section == Section::Options {
+                section = Section::Preamble;
+            }
+            continue;
+        }
+        if trimmed.eq_ignore_ascii_case("Options are as follows:") {
+            section = Section::Options;
+            continue;
+        }
+        if trimmed.eq_ignore_ascii_case("Reading...") {
+            section = Section::Reading;
+            continue;
+        }
+
+        let kind = if section == Section::Options {
+            LogEventKind::Option(trimmed.to_string())
+        } else {
+            parse_line(trimmed)
+        };
+        let count = match kind {
+            LogEventKind::Records { count, size } if size > 0 => count,
+            _ => 0,
+        };
+        events.push(LogEvent { line, record, kind });
+        record += count;
+    }
+    if section == Section::Label {
+        if let Some(text) = label_text(&label) {
+            events.push(LogEvent {
+                line: label_line,
+                record,
+                kind: LogEventKind::Label(text),
+            });
+        }
+    }
+    events
+}
+
+/// Lines only STP2TAP writes, for dialect detection.
+pub(crate) fn is_signature(line: &str) -> bool {
+    line.eq_ignore_ascii_case("Reading...")
+        || parse_header(line).is_some()
+        || parse_run(line).is_some()
+        || number_after(line, "Tapemark hit at block").is_some()
+}
+
+fn is_separator(line: &str) -> bool {
+    line.len() >= 8 && line.chars().all(|c| c == SEPARATOR)
+}
+
+fn label_text(lines: &[&str]) -> Option<String> {
+    let start = lines.iter().position(|l| !l.is_empty())?;
+    let end = lines.iter().rposition(|l| !l.is_empty())?;
+    Some(lines[start..=end].join("\n"))
+}
+
+/// Classify one line outside the options and label sections.
+fn parse_line(line: &str) -> LogEventKind {
+    if let Some(header) = parse_header(line) {
+        return header;
+    }
+    if let Some((count, size)) = parse_run(line) {
+        return LogEventKind::Records { count, size };
+    }
+    if let Some(block) = number_after(line, "Tapemark hit at block") {
+        return LogEventKind::TapeMark { block };
+    }
+    if let Some(block) = number_after(line, "End of data hit at") {
+        return LogEventKind::EndOfData { block };
+    }
+    if let Some(rest) = strip_prefix_ignore_case(line, "Finished reading at") {
+        return LogEventKind::Finished {
+            at: parse_date(rest),
+        };
+    }
+    free_text(line)
+}
+
+/// `IMAGE created by TOOL VERSION on DATE`.
+fn parse_header(line: &str) -> Option<LogEventKind> {
+    let (image, rest) = line.split_once(" created by ")?;
+    let (tool_version, date) = rest.split_once(" on ")?;
+    let (tool, version) = tool_version.trim().split_once(' ')?;
+    Some(LogEventKind::Header {
+        image: image.trim().to_string(),
+        tool: tool.to_string(),
+        version: version.trim().to_string(),
+        created: parse_date(date),
+    })
+}
+
+/// `N x S bytes`.
+fn parse_run(line: &str) -> Option<(u64, u32)> {
+    let mut words = line.split_whitespace();
+    let count = words.next()?.parse().ok()?;
+    if words.next()? != "x" {
+        return None;
+    }
+    let size = words.next()?.parse().ok()?;
+    if !words.next()?.starts_with("byte") || words.next().is_some() {
+        return None;
+    }
+    Some((count, size))
+}
+
+fn number_after(line: &str, prefix: &str) -> Option<u64> {
+    strip_prefix_ignore_case(line, prefix)?.trim().parse().ok()
+}
+
+fn strip_prefix_ignore_case<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = line.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &line[prefix.len()..])
+}
+
+fn parse_date(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text.trim(), DATE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::parse_events;
+    use crate::log::event::LogEventKind;
+
+    const LOG: &str = "
+TA0114.TAP created by STP2TAP 0.02 on Wed Nov 05 14:17:12 2025
+
+Options are as follows:
+  No rewind at beginning and end.
+  Copy all blocks.
+
+---------------------------------------------
+
+BB-KR74A-BE M48001TM
+VAX/MICROVMS V4.4/4.5 MUP 16MT9
+
+---------------------------------------------
+
+Reading...
+
+3 x 80 bytes
+Tapemark hit at block 8071936
+10 x 8192 bytes
+Error reading block, retrying
+1 x 0 bytes
+Drive went not-ready.  Exiting.
+
+End of data hit at 3542722
+
+Finished reading at Wed Nov 05 14:24:11 2025
+";
+
+    #[test]
+    fn parses_every_section() {
+        let events = parse_events(LOG.lines());
+        let kinds: Vec<_> = events.iter().map(|e| &e.kind).collect();
+        let at = |h, m, s| {
+            NaiveDate::from_ymd_opt(2025, 11, 5)
+                .and_then(|d| d.and_hms_opt(h, m, s))
+                .unwrap()
+        };
+        assert_eq!(
+            kinds,
+            vec![
+                &LogEventKind::Header {
+                    image: "TA0114.TAP".into(),
+                    tool: "STP2TAP".into(),
+                    version: "0.02".into(),
+                    created: Some(at(14, 17, 12)),
+                },
+                &LogEventKind::Option("No rewind at beginning and end.".into()),
+                &LogEventKind::Option("Copy all blocks.".into()),
+                &LogEventKind::Label(
+                    "BB-KR74A-BE M48001TM\nVAX/MICROVMS V4.4/4.5 MUP 16MT9".into()
+                ),
+                &LogEventKind::Records { count: 3, size: 80 },
+                &LogEventKind::TapeMark { block: 8071936 },
+                &LogEventKind::Records {
+                    count: 10,
+                    size: 8192
+                },
+                &LogEventKind::Retry("Error reading block, retrying".into()),
+                &LogEventKind::Records { count: 1, size: 0 },
+                &LogEventKind::ReadError("Drive went not-ready.  Exiting.".into()),
+                &LogEventKind::EndOfData { block: 3542722 },
+                &LogEventKind::Finished {
+                    at: Some(at(14, 24, 11))
+                },
+            ]
+        );
+        // Positions count the records logged before each event.
+        let positions: Vec<u64> = events.iter().skip(4).map(|e| e.record).collect();
+        assert_eq!(positions, vec![0, 3, 3, 13, 13, 13, 13, 13]);
+        assert_eq!(events[4].line, 16);
+    }
+
+    #[test]
+    fn other_logs_become_notes() {
+        let events = parse_events("Tracks = 40\nrecord 2 ok".lines());
+        assert!(events
+            .iter()
+            .all(|e| matches!(e.kind, LogEventKind::Note(_))));
+    }
+}
@@ -0,0 +1,99 @@
+//! tapeutils `tapedump` output grammar:
+//!
+//! ```text
+//! tapedump: reading /dev/nst0
+//! record 1: 80 bytes
+//! 0000: 564f4c31 20202020 ...   hex dump, skipped
+//! tape mark
+//! record 2: 8192 bytes
+//! read error on record 3: Input/output error
+//! end of tape
+//! ```
+//!
+//! Record numbers are 1-based; positions are counted from the records
+//! listed, so a dump that skips records still lines up with the image.
+use super::event::{free_text, push_record, LogEvent, LogEventKind};
+
+pub fn parse_events<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<LogEvent> {
+    let mut events = Vec::new();
+    let mut record = 0u64;
+    for (line, text) in lines.into_iter().enumerate() {
+        let text = text.trim();
+        if text.is_empty() || is_dump_line(text) {
+            continue;
+        }
+        if let Some(size) = record_size(text) {
+            if size > 0 {
+                push_record(&mut events, line, record, size);
+                record += 1;
+            }
+            continue;
+        }
+        let lower = text.to_ascii_lowercase();
+        let kind = if lower == "tape mark" || lower == "eof" {
+            LogEventKind::TapeMark { block: record }
+        } else if lower == "end of tape" || lower == "eot" {
+            LogEventKind::EndOfData { block: record }
+        } else if let Some(image) = lower.strip_prefix("tapedump: reading ") {
+            LogEventKind::Header {
+                image: text[text.len() - image.len()..].to_string(),
+                tool: "tapedump".into(),
+                version: String::new(),
+                created: None,
+            }
+        } else {
+            free_text(text)
+        };
+        events.push(LogEvent { line, record, kind });
+    }
+    events
+}
+
+/// Lines only tapedump writes, for dialect detection.
+pub(crate) fn is_signature(line: &str) -> bool {
+    line.starts_with("tapedump:") || record_size(line).is_some()
+}
+
+/// `record N: S bytes`.
+fn record_size(line: &str) -> Option<u32> {
+    let (number, size) = line.strip_prefix("record ")?.split_once(": ")?;
+    number.parse::<u64>().ok()?;
+    size.strip_suffix(" bytes")?.parse().ok()
+}
+
+/// `OFFSET: hex words...`.
+fn is_dump_line(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(offset, _)| {
+        offset.len() >= 4 && offset.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_events;
+    use crate::log::event::LogEventKind;
+
+    #[test]
+    fn counts_listed_records() {
+        let log = "tapedump: reading /dev/nst0
+record 1: 80 bytes
+0000: 564f4c31 20202020
+record 2: 80 bytes
+tape mark
+read error on record 3: Input/output error
+record 4: 512 bytes
+end of tape";
+        let kinds: Vec<_> = parse_events(log.lines())
+            .into_iter()
+            .map(|e| (e.record, e.kind))
+            .collect();
+        assert!(matches!(
+            &kinds[0].1,
+            LogEventKind::Header { image, .. } if image == "/dev/nst0"
+        ));
+        assert_eq!(kinds[1], (0, LogEventKind::Records { count: 2, size: 80 }));
+        assert_eq!(kinds[2], (2, LogEventKind::TapeMark { block: 2 }));
+        assert!(matches!(kinds[3], (2, LogEventKind::ReadError(_))));
+        assert_eq!(kinds[5], (3, LogEventKind::EndOfData { block: 3 }));
+    }
+}
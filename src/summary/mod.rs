@@ -1,8 +1,10 @@
 //! Save-set summary computation: aggregates counts, histograms, efficiency, and log metadata for the UI.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::backup::vms::{format_protection, RecordFormat};
+use crate::backup::vms::{format_protection, parse_record_format};
+use crate::core::file::{FileMetadata, TapeFile};
 use crate::gui::state::AppState;
+use crate::log::label::{tape_metadata, TapeMetadata};
 use crate::log::parse::{LogData, LogLevel};
 use crate::utils::text::sanitize_display;
 
@@ -22,17 +24,20 @@ pub struct SaveSetSummary {
     pub tracks: Option<String>,
     pub density: Option<String>,
     pub blocks_read: Option<String>,
+    /// Physical label and capture details from the log.
+    pub tape: Option<TapeMetadata>,
 }
 
+/// Summarise the reconstructed file tree of any format plus the loaded log.
+/// Blocks are the distinct tape records holding file data; efficiency is the
+/// share of those records' bytes that is file data.
 pub fn compute_saveset_summary(state: &AppState) -> SaveSetSummary {
-    let total_files = state.vms_files.len();
-    let total_directories = state
-        .vms_fs
-        .as_ref()
-        .map(|fs| count_dirs(&fs.root))
-        .unwrap_or(0);
+    let mut files = Vec::new();
+    let mut total_directories = 0usize;
+    collect_files(&state.files, &mut files, &mut total_directories);
 
-    let mut total_blocks = 0usize;
+    let block_sizes: HashMap<u32, usize> = state.blocks.iter().map(|b| (b.index, b.size)).collect();
+    let mut used_blocks: HashSet<u32> = HashSet::new();
     let mut total_bytes = 0usize;
     let mut rfm_hist: HashMap<String, usize> = HashMap::new();
     let mut protection_hist: HashMap<String, usize> = HashMap::new();
@@ -40,35 +45,41 @@ pub fn compute_saveset_summary(state: &AppState) -> SaveSetSummary {
     let mut largest: Option<(String, usize)> = None;
     let mut smallest: Option<(String, usize)> = None;
 
-    let mut block_size_sum = 0usize;
-
-    for file in &state.vms_files {
-        let payload_size: usize = file.blocks.iter().map(|b| b.payload.len()).sum();
-        let block_size: usize = file.blocks.iter().map(|b| b.block_size as usize).sum();
-        total_bytes += payload_size;
-        total_blocks += file.blocks.len();
-        block_size_sum += block_size;
-
-        let name = format!(
-            "{};{} [UIC {:X}]",
-            file.path, file.headers.version, file.headers.owner_uic
+    for file in &files {
+        let size = file.size_bytes as usize;
+        total_bytes += size;
+        used_blocks.extend(
+            file.blocks.iter().chain(
+                file.extents
+                    .iter()
+                    .filter(|e| !e.is_hole())
+                    .map(|e| &e.block),
+            ),
         );
+
+        let name = sanitize_display(&file.path.to_string_path());
         match largest {
-            Some((_, size)) if size >= payload_size => {}
-            _ => largest = Some((sanitize_display(&name), payload_size)),
+            Some((_, s)) if s >= size => {}
+            _ => largest = Some((name.clone(), size)),
         }
         match smallest {
-            Some((_, size)) if size <= payload_size => {}
-            _ => smallest = Some((sanitize_display(&name), payload_size)),
+            Some((_, s)) if s <= size => {}
+            _ => smallest = Some((name, size)),
         }
 
-        let rfm_key = record_format_text(&file.headers.record_format).to_string();
-        *rfm_hist.entry(rfm_key).or_insert(0) += 1;
-
-        let prot = format_protection(file.headers.protection_mask);
-        *protection_hist.entry(prot).or_insert(0) += 1;
+        if let FileMetadata::Vms(meta) = &file.metadata {
+            let rfm_key = parse_record_format(meta.record_format).label().to_string();
+            *rfm_hist.entry(rfm_key).or_insert(0) += 1;
+        }
+        if let Some(prot) = protection_text(&file.metadata) {
+            *protection_hist.entry(prot).or_insert(0) += 1;
+        }
     }
 
+    let block_size_sum: usize = used_blocks
+        .iter()
+        .filter_map(|idx| block_sizes.get(idx))
+        .sum();
     let block_efficiency = if block_size_sum == 0 {
         0.0
     } else {
@@ -83,9 +94,9 @@ pub fn compute_saveset_summary(state: &AppState) -> SaveSetSummary {
         .unwrap_or((0, 0, None, None, None));
 
     SaveSetSummary {
-        total_files,
+        total_files: files.len(),
         total_directories,
-        total_blocks,
+        total_blocks: used_blocks.len(),
         total_bytes,
         largest_file: largest.map(|(n, s)| format!("{} ({} bytes)", n, s)),
         smallest_file: smallest.map(|(n, s)| format!("{} ({} bytes)", n, s)),
@@ -97,25 +108,32 @@ pub fn compute_saveset_summary(state: &AppState) -> SaveSetSummary {
         tracks,
         density,
         blocks_read,
+        tape: state.log_state.data.as_ref().and_then(tape_metadata),
     }
 }
 
-fn record_format_text(rfm: &RecordFormat) -> &'static str {
-    match rfm {
-        RecordFormat::Udf => "UDF",
-        RecordFormat::Vfc => "VFC",
-        RecordFormat::Var => "VAR",
-        RecordFormat::Fix => "FIX",
-        RecordFormat::Unknown(_) => "UNKNOWN",
+/// Flatten a file tree into its leaves, counting the directory nodes.
+fn collect_files<'a>(nodes: &'a [TapeFile], files: &mut Vec<&'a TapeFile>, dirs: &mut usize) {
+    for node in nodes {
+        if node.children.is_empty() {
+            files.push(node);
+        } else {
+            *dirs += 1;
+            collect_files(&node.children, files, dirs);
+        }
     }
 }
 
-fn count_dirs(node: &crate::backup::extract::DirectoryNode) -> usize {
-    let mut count = node.children.len();
-    for child in &node.children {
-        count += count_dirs(child);
+/// Protection in each system's own notation, when the format records one.
+fn protection_text(meta: &FileMetadata) -> Option<String> {
+    match meta {
+        FileMetadata::Vms(m) => Some(format_protection(m.protection)),
+        FileMetadata::Rsx(m) => Some(format!("{:o}", m.protection)),
+        FileMetadata::Rsts(m) => Some(format!("<{}>", m.protection)),
+        FileMetadata::Unix(m) => Some(format!("{:04o}", m.mode & 0o7777)),
+        FileMetadata::Pdp10(m) => Some(format!("<{:o}>", m.protection)),
+        FileMetadata::Rt11(_) | FileMetadata::Raw => None,
     }
-    count
 }
 
 fn log_stats(log: &LogData) -> (usize, usize, Option<String>, Option<String>, Option<String>) {
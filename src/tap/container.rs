@@ -0,0 +1,434 @@
+//! Tape container formats: the on-disk framing that carries tape records.
+//!
+//! Every container is turned into the same `TapeBlock` stream that
+//! `reader::parse_tap_blocks` produces for SIMH images, so format decoders
+//! never see the framing.
+//!
+//! * SIMH `.tap`: 32-bit little-endian length before and after each record,
+//!   odd records padded to even.
+//! * Ersatz-11 `.tap`: SIMH framing without the odd-length pad byte.
+//! * Hercules AWSTAPE: six-byte chunk headers (this chunk length, previous
+//!   chunk length, two flag bytes); a record may span several chunks.
+//! * HET: AWSTAPE with optionally zlib or bzip2 compressed records.
+//! * TPC: 16-bit little-endian length before each record, padded to even,
+//!   no trailer; a zero length is a tape mark.
+//! * Raw `dd` directory: one file per record, sorted by name. An empty file
+//!   is a tape mark, and each subdirectory holds the records of one tape file.
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use flate2::read::ZlibDecoder;
+
+use crate::core::block::{BlockClassification, TapeBlock};
+
+use super::reader::parse_tap_blocks;
+use super::stream::{record_block, simh_records, TapeIndex};
+
+const SIMH_EOM: u32 = 0xFFFF_FFFF;
+const SIMH_ERROR_FLAG: u32 = 0x8000_0000;
+
+const AWS_HEADER: usize = 6;
+pub(super) const AWS_NEW_RECORD: u8 = 0x80;
+pub(super) const AWS_TAPE_MARK: u8 = 0x40;
+pub(super) const AWS_END_RECORD: u8 = 0x20;
+const HET_COMPRESS_MASK: u8 = 0x03;
+pub(super) const HET_ZLIB: u8 = 0x01;
+const HET_BZLIB: u8 = 0x02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerFormat {
+    Simh,
+    E11,
+    Aws,
+    Het,
+    Tpc,
+    RawDir,
+}
+
+impl ContainerFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ContainerFormat::Simh => "SIMH TAP",
+            ContainerFormat::E11 => "Ersatz-11 TAP",
+            ContainerFormat::Aws => "AWSTAPE",
+            ContainerFormat::Het => "HET",
+            ContainerFormat::Tpc => "TPC",
+            ContainerFormat::RawDir => "dd directory",
+        }
+    }
+}
+
+/// Guess the container of an in-memory image. SIMH is the fallback when no
+/// framing checks out end to end.
+pub fn detect_container(data: &[u8]) -> ContainerFormat {
+    if walk_simh(data, true) {
+        ContainerFormat::Simh
+    } else if walk_simh(data, false) {
+        ContainerFormat::E11
+    } else if let Some(compressed) = walk_aws(data) {
+        if compressed {
+            ContainerFormat::Het
+        } else {
+            ContainerFormat::Aws
+        }
+    } else if walk_tpc(data) {
+        ContainerFormat::Tpc
+    } else {
+        ContainerFormat::Simh
+    }
+}
+
+/// Read a tape image file or a `dd` directory, detecting its container.
+pub fn read_container_blocks(path: &Path) -> io::Result<(ContainerFormat, Vec<TapeBlock>)> {
+    if path.is_dir() {
+        return Ok((ContainerFormat::RawDir, read_raw_dir(path)?));
+    }
+    let index = TapeIndex::open(path)?;
+    Ok((index.format(), index.blocks()?))
+}
+
+/// Split an in-memory image into blocks using the given container framing.
+pub fn parse_container(data: &[u8], format: ContainerFormat) -> io::Result<Vec<TapeBlock>> {
+    match format {
+        ContainerFormat::Simh => Ok(parse_tap_blocks(data)),
+        ContainerFormat::E11 => Ok(parse_e11_blocks(data)),
+        ContainerFormat::Aws | ContainerFormat::Het => parse_aws_blocks(data),
+        ContainerFormat::Tpc => Ok(parse_tpc_blocks(data)),
+        ContainerFormat::RawDir => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "dd directories are read from a path",
+        )),
+    }
+}
+
+/// Collects records into `TapeBlock`s, numbering blocks and tape files.
+#[derive(Default)]
+struct BlockSink {
+    blocks: Vec<TapeBlock>,
+    tape_file: u32,
+}
+
+impl BlockSink {
+    fn record(&mut self, data: Vec<u8>) {
+        self.blocks.push(TapeBlock {
+            index: self.blocks.len() as u32,
+            tape_file: self.tape_file,
+            size: data.len(),
+            raw: data.into(),
+            classification: BlockClassification::Unknown,
+        });
+    }
+
+    fn tape_mark(&mut self) {
+        self.tape_file += 1;
+    }
+}
+
+fn le32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le16(data: &[u8], at: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as usize)
+}
+
+/// True when SIMH-style framing (with or without odd padding) accounts for
+/// every byte up to end of medium and trailers match their headers.
+fn walk_simh(data: &[u8], pad_odd: bool) -> bool {
+    let mut offset = 0usize;
+    let mut records = 0usize;
+    while offset < data.len() {
+        let Some(word) = le32(data, offset) else {
+            return false;
+        };
+        offset += 4;
+        if word == SIMH_EOM {
+            break;
+        }
+        if word == 0 || (word >> 16) == 0xFFFE {
+            continue;
+        }
+        let len = (word & !SIMH_ERROR_FLAG) as usize;
+        let end = offset + len + if pad_odd { len & 1 } else { 0 };
+        if le32(data, end) != Some(word) {
+            return false;
+        }
+        offset = end + 4;
+        records += 1;
+    }
+    records > 0
+}
+
+/// Ersatz-11 images frame records like SIMH but never pad odd lengths.
+pub fn parse_e11_blocks(data: &[u8]) -> Vec<TapeBlock> {
+    simh_records(data, false)
+        .enumerate()
+        .map(|(index, record)| record_block(index, &record, data))
+        .collect()
+}
+
+/// Walk AWSTAPE chunk headers; `Some(compressed)` when the chain of
+/// previous-length fields is consistent to the end of the image.
+fn walk_aws(data: &[u8]) -> Option<bool> {
+    let mut offset = 0usize;
+    let mut prev = 0usize;
+    let mut compressed = false;
+    let mut chunks = 0usize;
+    while offset < data.len() {
+        let header = data.get(offset..offset + AWS_HEADER)?;
+        let len = le16(header, 0)?;
+        let flags = header[4];
+        if le16(header, 2)? != prev || flags & 0x1C != 0 || header[5] & 0x7F != 0 {
+            return None;
+        }
+        if flags & AWS_TAPE_MARK != 0 && len != 0 {
+            return None;
+        }
+        if flags & (AWS_TAPE_MARK | AWS_NEW_RECORD | AWS_END_RECORD) == 0 && len == 0 {
+            return None;
+        }
+        compressed |= flags & HET_COMPRESS_MASK != 0;
+        offset += AWS_HEADER + len;
+        prev = len;
+        chunks += 1;
+    }
+    (offset == data.len() && chunks > 0).then_some(compressed)
+}
+
+/// Split an AWSTAPE or HET image into blocks, reassembling records that
+/// span several chunks and inflating compressed HET records.
+pub fn parse_aws_blocks(data: &[u8]) -> io::Result<Vec<TapeBlock>> {
+    let mut sink = BlockSink::default();
+    let mut offset = 0usize;
+    let mut record = Vec::new();
+    let mut compression = 0u8;
+    while offset + AWS_HEADER <= data.len() {
+        let header = &data[offset..offset + AWS_HEADER];
+        let len = le16(header, 0).unwrap_or(0);
+        let flags = header[4];
+        offset += AWS_HEADER;
+        if flags & AWS_TAPE_MARK != 0 {
+            sink.tape_mark();
+            continue;
+        }
+        let Some(chunk) = data.get(offset..offset + len) else {
+            break;
+        };
+        offset += len;
+        if flags & AWS_NEW_RECORD != 0 {
+            record.clear();
+            compression = flags & HET_COMPRESS_MASK;
+        }
+        record.extend_from_slice(chunk);
+        if flags & AWS_END_RECORD != 0 {
+            let data = std::mem::take(&mut record);
+            sink.record(inflate_het(data, compression)?);
+        }
+    }
+    Ok(sink.blocks)
+}
+
+fn inflate_het(data: Vec<u8>, compression: u8) -> io::Result<Vec<u8>> {
+    match compression {
+        0 => Ok(data),
+        HET_ZLIB => {
+            let mut out = Vec::new();
+            ZlibDecoder::new(data.as_slice()).read_to_end(&mut out)?;
+            Ok(out)
+        }
+        HET_BZLIB => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "bzip2-compressed HET records are not supported",
+        )),
+        other => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown HET compression {other}"),
+        )),
+    }
+}
+
+/// True when TPC length words account for the image exactly.
+fn walk_tpc(data: &[u8]) -> bool {
+    let mut offset = 0usize;
+    let mut records = 0usize;
+    while offset < data.len() {
+        let Some(len) = le16(data, offset) else {
+            return false;
+        };
+        offset += 2 + len + (len & 1);
+        records += usize::from(len != 0);
+    }
+    offset == data.len() && records > 0
+}
+
+/// Split a TPC image into blocks.
+pub fn parse_tpc_blocks(data: &[u8]) -> Vec<TapeBlock> {
+    let mut sink = BlockSink::default();
+    let mut offset = 0usize;
+    while let Some(len) = le16(data, offset) {
+        offset += 2;
+        if len == 0 {
+            sink.tape_mark();
+            continue;
+        }
+        let Some(record) = data.get(offset..offset + len) else {
+            break;
+        };
+        sink.record(record.to_vec());
+        offset += len + (len & 1);
+    }
+    sink.blocks
+}
+
+/// Read a directory of `dd` record files. Entries are taken in name order;
+/// an empty file is a tape mark and a subdirectory is one whole tape file.
+pub fn read_raw_dir(dir: &Path) -> io::Result<Vec<TapeBlock>> {
+    let mut sink = BlockSink::default();
+    let entries = sorted_entries(dir)?;
+    let nested = entries.iter().any(|p| p.is_dir());
+    for path in entries {
+        if path.is_dir() {
+            for file in sorted_entries(&path)?.into_iter().filter(|p| p.is_file()) {
+                let data = fs::read(file)?;
+                if !data.is_empty() {
+                    sink.record(data);
+                }
+            }
+            sink.tape_mark();
+        } else if !nested {
+            let data = fs::read(path)?;
+            if data.is_empty() {
+                sink.tape_mark();
+            } else {
+                sink.record(data);
+            }
+        }
+    }
+    Ok(sink.blocks)
+}
+
+fn sorted_entries(dir: &Path) -> io::Result<Vec<std::path::PathBuf>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use super::{
+        detect_container, parse_container, read_raw_dir, ContainerFormat, AWS_END_RECORD,
+        AWS_NEW_RECORD, AWS_TAPE_MARK, HET_ZLIB,
+    };
+
+    const RECORDS: [&[u8]; 2] = [b"abc", b"defg"];
+
+    fn frames(blocks: &[crate::core::block::TapeBlock]) -> Vec<(u32, Vec<u8>)> {
+        blocks
+            .iter()
+            .map(|b| (b.tape_file, b.raw.to_vec()))
+            .collect()
+    }
+
+    fn expected() -> Vec<(u32, Vec<u8>)> {
+        vec![(0, b"abc".to_vec()), (1, b"defg".to_vec())]
+    }
+
+    fn aws_chunk(out: &mut Vec<u8>, prev: &mut u16, flags: u8, data: &[u8]) {
+        out.extend((data.len() as u16).to_le_bytes());
+        out.extend(prev.to_le_bytes());
+        out.extend([flags, 0]);
+        out.extend(data);
+        *prev = data.len() as u16;
+    }
+
+    #[test]
+    fn reads_e11_without_odd_padding() {
+        let mut data = Vec::new();
+        for rec in RECORDS {
+            let len = (rec.len() as u32).to_le_bytes();
+            data.extend(len);
+            data.extend(rec);
+            data.extend(len);
+            data.extend(0u32.to_le_bytes());
+        }
+        assert_eq!(detect_container(&data), ContainerFormat::E11);
+        let blocks = parse_container(&data, ContainerFormat::E11).unwrap();
+        assert_eq!(frames(&blocks), expected());
+    }
+
+    #[test]
+    fn reassembles_aws_and_het_records() {
+        let mut aws = Vec::new();
+        let mut prev = 0u16;
+        aws_chunk(&mut aws, &mut prev, AWS_NEW_RECORD, b"ab");
+        aws_chunk(&mut aws, &mut prev, AWS_END_RECORD, b"c");
+        aws_chunk(&mut aws, &mut prev, AWS_TAPE_MARK, b"");
+        aws_chunk(
+            &mut aws,
+            &mut prev,
+            AWS_NEW_RECORD | AWS_END_RECORD,
+            b"defg",
+        );
+        assert_eq!(detect_container(&aws), ContainerFormat::Aws);
+        let blocks = parse_container(&aws, ContainerFormat::Aws).unwrap();
+        assert_eq!(frames(&blocks), expected());
+
+        let mut het = Vec::new();
+        let mut prev = 0u16;
+        aws_chunk(&mut het, &mut prev, AWS_NEW_RECORD | AWS_END_RECORD, b"abc");
+        aws_chunk(&mut het, &mut prev, AWS_TAPE_MARK, b"");
+        let mut z = ZlibEncoder::new(Vec::new(), Compression::default());
+        z.write_all(b"defg").unwrap();
+        let packed = z.finish().unwrap();
+        let flags = AWS_NEW_RECORD | AWS_END_RECORD | HET_ZLIB;
+        aws_chunk(&mut het, &mut prev, flags, &packed);
+        assert_eq!(detect_container(&het), ContainerFormat::Het);
+        let blocks = parse_container(&het, ContainerFormat::Het).unwrap();
+        assert_eq!(frames(&blocks), expected());
+    }
+
+    #[test]
+    fn reads_tpc_records() {
+        let mut data = Vec::new();
+        for rec in RECORDS {
+            data.extend((rec.len() as u16).to_le_bytes());
+            data.extend(rec);
+            if rec.len() % 2 == 1 {
+                data.push(0);
+            }
+            data.extend(0u16.to_le_bytes());
+        }
+        assert_eq!(detect_container(&data), ContainerFormat::Tpc);
+        let blocks = parse_container(&data, ContainerFormat::Tpc).unwrap();
+        assert_eq!(frames(&blocks), expected());
+    }
+
+    #[test]
+    fn reads_dd_directories() {
+        let dir = std::env::temp_dir().join(format!("rts_dd_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("000"), b"abc").unwrap();
+        std::fs::write(dir.join("001"), b"").unwrap();
+        std::fs::write(dir.join("002"), b"defg").unwrap();
+        let blocks = read_raw_dir(&dir).unwrap();
+        assert_eq!(frames(&blocks), expected());
+
+        let nested = dir.join("nested");
+        std::fs::create_dir_all(nested.join("file0")).unwrap();
+        std::fs::create_dir_all(nested.join("file1")).unwrap();
+        std::fs::write(nested.join("file0/rec0"), b"abc").unwrap();
+        std::fs::write(nested.join("file1/rec0"), b"defg").unwrap();
+        let blocks = read_raw_dir(&nested).unwrap();
+        assert_eq!(frames(&blocks), expected());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
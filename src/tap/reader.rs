@@ -1,54 +1,24 @@
-use std::fs::File;
-use std::io::{self, Read};
-use std::sync::Arc;
+use std::io;
+use std::path::Path;
 
-use crate::core::block::{BlockClassification, TapeBlock};
+use crate::core::block::TapeBlock;
 
-pub fn read_tap_blocks(path: &str) -> io::Result<Vec<TapeBlock>> {
-    let mut f = File::open(path)?;
-    let mut data = Vec::new();
-    f.read_to_end(&mut data)?;
-
-    let mut offset = 0usize;
-    let mut index = 0u32;
-    let mut blocks = Vec::new();
-
-    while offset + 4 <= data.len() {
-        // Read record length (little endian)
-        let len = u32::from_le_bytes([
-            data[offset],
-            data[offset + 1],
-            data[offset + 2],
-            data[offset + 3],
-        ]) as usize;
-
-        offset += 4;
-
-        // Safety: If len is too large or block incomplete -> break cleanly
-        if len == 0 || offset + len > data.len() {
-            break;
-        }
-
-        let block = Arc::<[u8]>::from(&data[offset..offset + len]);
+use super::container::read_container_blocks;
+use super::stream::{record_block, simh_records};
 
-        blocks.push(TapeBlock {
-            index,
-            size: len,
-            raw: block,
-            classification: BlockClassification::Unknown,
-        });
-
-        offset += len;
-
-        // Skip trailing length (TAP trailer)
-        if offset + 4 <= data.len() {
-            offset += 4;
-        } else {
-            break;
-        }
-
-        index += 1;
-    }
+/// Read a tape image in any supported container (see `container`).
+pub fn read_tap_blocks(path: &str) -> io::Result<Vec<TapeBlock>> {
+    read_container_blocks(Path::new(path)).map(|(_, blocks)| blocks)
+}
 
-    Ok(blocks)
+/// Split SIMH TAP bytes into data blocks.
+///
+/// Tape marks are not returned as blocks; they advance `TapeBlock::tape_file`
+/// so format decoders can tell which records belong to the same tape file.
+/// Odd-length records are padded to an even boundary before the trailer.
+pub fn parse_tap_blocks(data: &[u8]) -> Vec<TapeBlock> {
+    simh_records(data, true)
+        .enumerate()
+        .map(|(index, record)| record_block(index, &record, data))
+        .collect()
 }
@@ -0,0 +1,441 @@
+//! Streaming access to tape images too large to copy into memory.
+//!
+//! `TapeIndex::open` walks SIMH and Ersatz-11 framing through a buffered
+//! reader and keeps only the offset, length and tape file of each record
+//! (16 bytes per record). With the `mmap` feature (on by default) the image
+//! is memory-mapped and the blocks handed to the decoders are ranges of the
+//! mapping; without it payloads are read from the file on demand. Containers
+//! whose records must be decoded (AWSTAPE, HET, TPC, `dd` directories) are
+//! unframed in memory once and indexed the same way.
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::core::block::{BlockClassification, RecordBytes, SharedImage, TapeBlock};
+
+use super::container::{detect_container, parse_container, read_raw_dir, ContainerFormat};
+
+const SIMH_EOM: u32 = 0xFFFF_FFFF;
+const SIMH_ERROR_FLAG: u32 = 0x8000_0000;
+const SCAN_BUFFER: usize = 1 << 20;
+
+/// Location of one record payload inside the indexed image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordRef {
+    pub offset: u64,
+    pub len: u32,
+    /// Tape file number: incremented at every tape mark.
+    pub tape_file: u32,
+    /// SIMH error flag set in the length word: the drive reported the
+    /// record bad when the image was made.
+    pub flagged: bool,
+}
+
+impl RecordRef {
+    fn range(&self) -> std::ops::Range<usize> {
+        self.offset as usize..self.offset as usize + self.len as usize
+    }
+}
+
+/// Lenient walk over SIMH-style framing in memory, yielding record
+/// locations. Stops at end of medium or at the first record that runs past
+/// the data; trailers are skipped without being checked.
+pub struct SimhRecords<'a> {
+    data: &'a [u8],
+    offset: usize,
+    tape_file: u32,
+    pad_odd: bool,
+    done: bool,
+}
+
+/// Walk SIMH (`pad_odd`) or Ersatz-11 framing.
+pub fn simh_records(data: &[u8], pad_odd: bool) -> SimhRecords<'_> {
+    SimhRecords {
+        data,
+        offset: 0,
+        tape_file: 0,
+        pad_odd,
+        done: false,
+    }
+}
+
+impl Iterator for SimhRecords<'_> {
+    type Item = RecordRef;
+
+    fn next(&mut self) -> Option<RecordRef> {
+        while !self.done {
+            let header = self.data.get(self.offset..self.offset + 4)?;
+            let word = u32::from_le_bytes(header.try_into().ok()?);
+            self.offset += 4;
+            if word == SIMH_EOM {
+                self.done = true;
+                break;
+            }
+            if word == 0 {
+                self.tape_file += 1;
+                continue;
+            }
+            if (word >> 16) == 0xFFFE {
+                continue;
+            }
+            let len = (word & !SIMH_ERROR_FLAG) as usize;
+            if self.offset + len > self.data.len() {
+                self.done = true;
+                break;
+            }
+            let record = RecordRef {
+                offset: self.offset as u64,
+                len: len as u32,
+                tape_file: self.tape_file,
+                flagged: word & SIMH_ERROR_FLAG != 0,
+            };
+            self.offset += len + if self.pad_odd { len & 1 } else { 0 } + 4;
+            if self.offset > self.data.len() {
+                self.done = true;
+            }
+            return Some(record);
+        }
+        None
+    }
+}
+
+/// Make a `TapeBlock` for a record walked out of `data`.
+pub fn record_block(index: usize, record: &RecordRef, data: &[u8]) -> TapeBlock {
+    TapeBlock {
+        index: index as u32,
+        tape_file: record.tape_file,
+        size: record.len as usize,
+        raw: data[record.range()].into(),
+        classification: BlockClassification::Unknown,
+    }
+}
+
+enum Source {
+    #[cfg_attr(feature = "mmap", allow(dead_code))]
+    File(Mutex<File>),
+    /// Memory-mapped or in-memory image.
+    Image(SharedImage),
+}
+
+/// Record index over a tape image with lazily read payloads.
+pub struct TapeIndex {
+    source: Source,
+    format: ContainerFormat,
+    records: Vec<RecordRef>,
+}
+
+impl fmt::Debug for TapeIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TapeIndex")
+            .field("format", &self.format)
+            .field("records", &self.records.len())
+            .finish()
+    }
+}
+
+impl TapeIndex {
+    /// Index a tape image or `dd` directory. SIMH and Ersatz-11 images whose
+    /// framing checks out end to end are streamed; anything else is read
+    /// into memory and unframed by `container`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if path.is_dir() {
+            return Ok(Self::from_blocks(
+                ContainerFormat::RawDir,
+                &read_raw_dir(path)?,
+            ));
+        }
+        let file = File::open(path)?;
+        for (format, pad_odd) in [(ContainerFormat::Simh, true), (ContainerFormat::E11, false)] {
+            if let Some(records) = scan_framed(&file, pad_odd)? {
+                return Ok(Self {
+                    source: open_source(file)?,
+                    format,
+                    records,
+                });
+            }
+        }
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// Index an image already in memory.
+    pub fn from_bytes(data: Vec<u8>) -> io::Result<Self> {
+        let format = detect_container(&data);
+        match format {
+            ContainerFormat::Simh | ContainerFormat::E11 => {
+                let records = simh_records(&data, format == ContainerFormat::Simh).collect();
+                Ok(Self {
+                    source: Source::Image(Arc::new(data)),
+                    format,
+                    records,
+                })
+            }
+            _ => Ok(Self::from_blocks(format, &parse_container(&data, format)?)),
+        }
+    }
+
+    /// Index decoded records by packing their payloads back to back.
+    fn from_blocks(format: ContainerFormat, blocks: &[TapeBlock]) -> Self {
+        let mut data = Vec::with_capacity(blocks.iter().map(|b| b.raw.len()).sum());
+        let records = blocks
+            .iter()
+            .map(|b| {
+                let record = RecordRef {
+                    offset: data.len() as u64,
+                    len: b.raw.len() as u32,
+                    tape_file: b.tape_file,
+                    flagged: false,
+                };
+                data.extend_from_slice(&b.raw);
+                record
+            })
+            .collect();
+        Self {
+            source: Source::Image(Arc::new(data)),
+            format,
+            records,
+        }
+    }
+
+    pub fn format(&self) -> ContainerFormat {
+        self.format
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn records(&self) -> &[RecordRef] {
+        &self.records
+    }
+
+    /// Tape files up to the last record, empty ones included.
+    pub fn tape_files(&self) -> u32 {
+        self.records.last().map(|r| r.tape_file + 1).unwrap_or(0)
+    }
+
+    /// Payload of record `index`, borrowed when the image is in memory or
+    /// mapped and read from the file otherwise.
+    pub fn record_bytes(&self, index: usize) -> io::Result<Cow<'_, [u8]>> {
+        let record = self.records.get(index).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("record {index} out of range ({} records)", self.len()),
+            )
+        })?;
+        match &self.source {
+            Source::File(file) => {
+                let mut file = file
+                    .lock()
+                    .map_err(|_| io::Error::other("tape file lock poisoned"))?;
+                file.seek(SeekFrom::Start(record.offset))?;
+                let mut buf = vec![0u8; record.len as usize];
+                file.read_exact(&mut buf)?;
+                Ok(Cow::Owned(buf))
+            }
+            Source::Image(image) => Ok(Cow::Borrowed(&(**image).as_ref()[record.range()])),
+        }
+    }
+
+    /// Every record as a `TapeBlock`, for decoders that need the whole tape.
+    /// Payloads are ranges of the mapped or in-memory image, so no record is
+    /// copied; an image read through the file is loaded into memory once.
+    pub fn blocks(&self) -> io::Result<Vec<TapeBlock>> {
+        let image: SharedImage = match &self.source {
+            Source::Image(image) => image.clone(),
+            Source::File(file) => {
+                let mut file = file
+                    .lock()
+                    .map_err(|_| io::Error::other("tape file lock poisoned"))?;
+                file.rewind()?;
+                let mut data = Vec::new();
+                file.read_to_end(&mut data)?;
+                Arc::new(data)
+            }
+        };
+        Ok(self
+            .records
+            .iter()
+            .enumerate()
+            .map(|(index, record)| TapeBlock {
+                index: index as u32,
+                tape_file: record.tape_file,
+                size: record.len as usize,
+                raw: RecordBytes::shared(image.clone(), record.range()),
+                classification: BlockClassification::Unknown,
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "mmap")]
+fn open_source(file: File) -> io::Result<Source> {
+    if file.metadata()?.len() == 0 {
+        return Ok(Source::Image(Arc::new(Vec::new())));
+    }
+    // SAFETY: the mapping is read-only; an image truncated by another
+    // process while mapped is outside what this reader defends against.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    Ok(Source::Image(Arc::new(map)))
+}
+
+#[cfg(not(feature = "mmap"))]
+fn open_source(file: File) -> io::Result<Source> {
+    Ok(Source::File(Mutex::new(file)))
+}
+
+/// Strict streaming walk of SIMH-style framing: `None` unless every record
+/// trailer matches its header up to end of medium or end of file.
+fn scan_framed(file: &File, pad_odd: bool) -> io::Result<Option<Vec<RecordRef>>> {
+    let len = file.metadata()?.len();
+    let mut reader = BufReader::with_capacity(SCAN_BUFFER, file);
+    reader.rewind()?;
+    let mut offset = 0u64;
+    let mut tape_file = 0u32;
+    let mut records = Vec::new();
+    let mut word = [0u8; 4];
+    while offset < len {
+        if offset + 4 > len {
+            return Ok(None);
+        }
+        reader.read_exact(&mut word)?;
+        let header = u32::from_le_bytes(word);
+        offset += 4;
+        if header == SIMH_EOM {
+            break;
+        }
+        if header == 0 {
+            tape_file += 1;
+            continue;
+        }
+        if (header >> 16) == 0xFFFE {
+            continue;
+        }
+        let rec_len = u64::from(header & !SIMH_ERROR_FLAG);
+        let end = offset + rec_len + if pad_odd { rec_len & 1 } else { 0 };
+        if end + 4 > len {
+            return Ok(None);
+        }
+        reader.seek_relative((end - offset) as i64)?;
+        reader.read_exact(&mut word)?;
+        if u32::from_le_bytes(word) != header {
+            return Ok(None);
+        }
+        records.push(RecordRef {
+            offset,
+            len: rec_len as u32,
+            tape_file,
+            flagged: header & SIMH_ERROR_FLAG != 0,
+        });
+        offset = end + 4;
+    }
+    Ok((!records.is_empty()).then_some(records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{simh_records, TapeIndex};
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::tap::container::ContainerFormat;
+    use crate::tap::writer::write_container;
+
+    fn sample() -> Vec<TapeBlock> {
+        [(0, &b"HDR"[..]), (0, b"data"), (2, b"odd")]
+            .iter()
+            .enumerate()
+            .map(|(i, (tape_file, data))| TapeBlock {
+                index: i as u32,
+                tape_file: *tape_file,
+                size: data.len(),
+                raw: data.to_vec().into(),
+                classification: BlockClassification::Unknown,
+            })
+            .collect()
+    }
+
+    fn temp_image(name: &str, data: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("rts_stream_{name}_{}", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn walks_framing_lazily() {
+        let image = write_container(&sample(), ContainerFormat::Simh).unwrap();
+        let records: Vec<_> = simh_records(&image, true).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!((records[0].offset, records[0].len), (4, 3));
+        assert_eq!(records[2].tape_file, 2);
+        // A record running past the end stops the walk.
+        assert_eq!(simh_records(&image[..image.len() - 14], true).count(), 2);
+    }
+
+    #[test]
+    fn streams_records_from_file() {
+        for format in [
+            ContainerFormat::Simh,
+            ContainerFormat::E11,
+            ContainerFormat::Aws,
+        ] {
+            let image = write_container(&sample(), format).unwrap();
+            let path = temp_image(&format!("{format:?}"), &image);
+            let index = TapeIndex::open(&path).unwrap();
+            assert_eq!(index.format(), format);
+            assert_eq!(index.len(), 3);
+            assert_eq!(index.tape_files(), 3);
+            assert_eq!(index.record_bytes(1).unwrap().as_ref(), b"data");
+            let blocks = index.blocks().unwrap();
+            assert_eq!(blocks[2].raw.as_ref(), b"odd");
+            assert_eq!(blocks[2].tape_file, 2);
+            assert!(index.record_bytes(3).is_err());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn blocks_are_ranges_of_the_image() {
+        for format in [ContainerFormat::Simh, ContainerFormat::Aws] {
+            let image = write_container(&sample(), format).unwrap();
+            let index = TapeIndex::from_bytes(image).unwrap();
+            let blocks = index.blocks().unwrap();
+            assert_eq!(blocks.len(), 3);
+            for (i, block) in blocks.iter().enumerate() {
+                let record = index.record_bytes(i).unwrap();
+                assert_eq!(block.raw.as_ptr(), record.as_ptr(), "{format:?} record {i}");
+                assert_eq!(block.size, record.len());
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_simh_error_flags() {
+        let mut image = write_container(&sample(), ContainerFormat::Simh).unwrap();
+        // Flag record 1 ("data") in its header and trailer.
+        image[15] |= 0x80;
+        image[23] |= 0x80;
+        let flags: Vec<bool> = simh_records(&image, true).map(|r| r.flagged).collect();
+        assert_eq!(flags, vec![false, true, false]);
+        let path = temp_image("flagged", &image);
+        let index = TapeIndex::open(&path).unwrap();
+        assert!(index.records()[1].flagged);
+        assert_eq!(index.record_bytes(1).unwrap().as_ref(), b"data");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn damaged_images_fall_back_to_lenient_walk() {
+        let mut image = write_container(&sample(), ContainerFormat::Simh).unwrap();
+        image.truncate(image.len() - 14);
+        let path = temp_image("damaged", &image);
+        let index = TapeIndex::open(&path).unwrap();
+        assert_eq!(index.format(), ContainerFormat::Simh);
+        assert_eq!(index.len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
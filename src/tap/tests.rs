@@ -1,29 +1,23 @@
-use crate::tap::legacy::read_tap_entry;
-use crate::TapeError;
-
 #[test]
-fn short_read_errors() {
-    let err = read_tap_entry(&[]).unwrap_err();
-    assert!(matches!(err, TapeError::Parse(_)));
-}
+fn tape_marks_advance_tape_file() {
+    use crate::tap::reader::parse_tap_blocks;
 
-#[test]
-fn overly_large_record_rejected() {
-    let data = vec![0u8; 1_048_577];
-    let err = read_tap_entry(&data).unwrap_err();
-    assert!(matches!(err, TapeError::UnsupportedFormat(_)));
-}
+    let mut data = Vec::new();
+    for rec in [&b"abc"[..], &b"defg"[..]] {
+        let len = (rec.len() as u32).to_le_bytes();
+        data.extend_from_slice(&len);
+        data.extend_from_slice(rec);
+        if rec.len() % 2 == 1 {
+            data.push(0);
+        }
+        data.extend_from_slice(&len);
+        data.extend_from_slice(&0u32.to_le_bytes());
+    }
+    data.extend_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
 
-#[test]
-fn silent_eof_reports_error() {
-    // Declares a block size larger than the buffer, triggering a parse error.
-    let mut data = vec![0u8; 32];
-    data[0..2].copy_from_slice(&64u16.to_le_bytes());
-    data[2] = 1;
-    data[3] = 1;
-    let entry = read_tap_entry(&data).expect("should fall back to raw");
-    assert!(matches!(
-        entry.kind,
-        crate::tap::legacy::TapDataKind::Raw(_)
-    ));
+    let blocks = parse_tap_blocks(&data);
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0].raw.as_ref(), b"abc");
+    assert_eq!(blocks[1].raw.as_ref(), b"defg");
+    assert_eq!((blocks[0].tape_file, blocks[1].tape_file), (0, 1));
 }
@@ -0,0 +1,298 @@
+//! Tape image writer: the inverse of `container`.
+//!
+//! Records come from a `TapeBlock` list; a change of `tape_file` between
+//! consecutive blocks writes as many tape marks as the numbers differ, so a
+//! read-modify-write cycle keeps empty tape files. Every image ends with a
+//! tape mark after the last file plus one more for logical end of tape.
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::core::block::TapeBlock;
+
+use super::container::{ContainerFormat, AWS_END_RECORD, AWS_NEW_RECORD, AWS_TAPE_MARK, HET_ZLIB};
+
+const AWS_MAX_CHUNK: usize = u16::MAX as usize;
+
+/// One item of a tape image in write order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapeItem<'a> {
+    Record(&'a [u8]),
+    TapeMark,
+}
+
+/// Lay out blocks as records and tape marks, ending with a double mark.
+pub fn tape_items(blocks: &[TapeBlock]) -> Vec<TapeItem<'_>> {
+    let mut items = Vec::with_capacity(blocks.len() + 2);
+    let mut tape_file = 0u32;
+    for b in blocks {
+        while tape_file < b.tape_file {
+            items.push(TapeItem::TapeMark);
+            tape_file += 1;
+        }
+        items.push(TapeItem::Record(&b.raw));
+    }
+    if !blocks.is_empty() {
+        items.push(TapeItem::TapeMark);
+    }
+    items.push(TapeItem::TapeMark);
+    items
+}
+
+/// Encode blocks in the given container.
+pub fn write_container(blocks: &[TapeBlock], format: ContainerFormat) -> io::Result<Vec<u8>> {
+    let items = tape_items(blocks);
+    match format {
+        ContainerFormat::Simh => Ok(write_simh(&items, true)),
+        ContainerFormat::E11 => Ok(write_simh(&items, false)),
+        ContainerFormat::Aws => write_aws(&items, false),
+        ContainerFormat::Het => write_aws(&items, true),
+        ContainerFormat::Tpc => write_tpc(&items),
+        ContainerFormat::RawDir => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "dd directories are written with write_raw_dir",
+        )),
+    }
+}
+
+/// Write blocks to `path` as a SIMH, E11, AWS, HET or TPC image, or as a
+/// `dd` directory for `ContainerFormat::RawDir`.
+pub fn write_tape_image(
+    path: &Path,
+    blocks: &[TapeBlock],
+    format: ContainerFormat,
+) -> io::Result<()> {
+    if format == ContainerFormat::RawDir {
+        return write_raw_dir(path, blocks);
+    }
+    fs::write(path, write_container(blocks, format)?)
+}
+
+fn write_simh(items: &[TapeItem<'_>], pad_odd: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    for item in items {
+        match item {
+            TapeItem::TapeMark => out.extend(0u32.to_le_bytes()),
+            TapeItem::Record(data) => {
+                let len = (data.len() as u32).to_le_bytes();
+                out.extend(len);
+                out.extend_from_slice(data);
+                if pad_odd && data.len() % 2 == 1 {
+                    out.push(0);
+                }
+                out.extend(len);
+            }
+        }
+    }
+    out
+}
+
+fn write_aws(items: &[TapeItem<'_>], compress: bool) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut prev = 0usize;
+    let mut chunk = |out: &mut Vec<u8>, flags: u8, data: &[u8]| {
+        out.extend((data.len() as u16).to_le_bytes());
+        out.extend((prev as u16).to_le_bytes());
+        out.extend([flags, 0]);
+        out.extend_from_slice(data);
+        prev = data.len();
+    };
+    for item in items {
+        match item {
+            TapeItem::TapeMark => chunk(&mut out, AWS_TAPE_MARK, &[]),
+            TapeItem::Record(data) => {
+                let (payload, method) = if compress {
+                    let mut z = ZlibEncoder::new(Vec::new(), Compression::default());
+                    z.write_all(data)?;
+                    (z.finish()?, HET_ZLIB)
+                } else {
+                    (data.to_vec(), 0)
+                };
+                let pieces: Vec<&[u8]> = if payload.is_empty() {
+                    vec![&[]]
+                } else {
+                    payload.chunks(AWS_MAX_CHUNK).collect()
+                };
+                let last = pieces.len() - 1;
+                for (i, piece) in pieces.into_iter().enumerate() {
+                    let mut flags = 0u8;
+                    if i == 0 {
+                        flags |= AWS_NEW_RECORD | method;
+                    }
+                    if i == last {
+                        flags |= AWS_END_RECORD;
+                    }
+                    chunk(&mut out, flags, piece);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn write_tpc(items: &[TapeItem<'_>]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for item in items {
+        match item {
+            TapeItem::TapeMark => out.extend(0u16.to_le_bytes()),
+            TapeItem::Record(data) => {
+                let len = u16::try_from(data.len()).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{}-byte record does not fit a TPC length", data.len()),
+                    )
+                })?;
+                out.extend(len.to_le_bytes());
+                out.extend_from_slice(data);
+                if data.len() % 2 == 1 {
+                    out.push(0);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Write one subdirectory per tape file, one file per record.
+pub fn write_raw_dir(dir: &Path, blocks: &[TapeBlock]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let last = blocks.last().map(|b| b.tape_file).unwrap_or(0);
+    for tf in 0..=last {
+        fs::create_dir_all(dir.join(format!("file{tf:04}")))?;
+    }
+    for (n, b) in blocks.iter().enumerate() {
+        let path = dir
+            .join(format!("file{:04}", b.tape_file))
+            .join(format!("rec{n:06}"));
+        fs::write(path, &b.raw)?;
+    }
+    Ok(())
+}
+
+/// Renumber block indexes after records were dropped, inserted or moved.
+pub fn renumber(blocks: &mut [TapeBlock]) {
+    for (i, b) in blocks.iter_mut().enumerate() {
+        b.index = i as u32;
+    }
+}
+
+/// Insert a tape mark before block `at`: it and every later block move to
+/// the next tape file.
+pub fn insert_tape_mark(blocks: &mut [TapeBlock], at: usize) {
+    for b in blocks.iter_mut().skip(at) {
+        b.tape_file += 1;
+    }
+}
+
+/// Split a tape before tape file `tape_file`; the second half is renumbered
+/// to start at tape file 0.
+pub fn split_tape(blocks: &[TapeBlock], tape_file: u32) -> (Vec<TapeBlock>, Vec<TapeBlock>) {
+    let (head, mut tail): (Vec<_>, Vec<_>) = blocks
+        .iter()
+        .cloned()
+        .partition(|b| b.tape_file < tape_file);
+    for b in tail.iter_mut() {
+        b.tape_file -= tape_file;
+    }
+    renumber(&mut tail);
+    (head, tail)
+}
+
+/// Append `second` after `first`, starting it on a new tape file.
+pub fn merge_tapes(first: &[TapeBlock], second: &[TapeBlock]) -> Vec<TapeBlock> {
+    let base = first.last().map(|b| b.tape_file + 1).unwrap_or(0);
+    let mut out = first.to_vec();
+    out.extend(second.iter().cloned().map(|mut b| {
+        b.tape_file += base;
+        b
+    }));
+    renumber(&mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_tape_mark, merge_tapes, split_tape, write_container, write_raw_dir};
+    use crate::core::block::{BlockClassification, TapeBlock};
+    use crate::tap::container::{detect_container, parse_container, read_raw_dir, ContainerFormat};
+
+    fn block(index: u32, tape_file: u32, data: &[u8]) -> TapeBlock {
+        TapeBlock {
+            index,
+            tape_file,
+            size: data.len(),
+            raw: data.to_vec().into(),
+            classification: BlockClassification::Unknown,
+        }
+    }
+
+    fn sample() -> Vec<TapeBlock> {
+        vec![
+            block(0, 0, b"VOL1"),
+            block(1, 1, b"odd"),
+            block(2, 1, &vec![7u8; 70_000]),
+            block(3, 3, b"after an empty file"),
+        ]
+    }
+
+    fn layout(blocks: &[TapeBlock]) -> Vec<(u32, u32, Vec<u8>)> {
+        blocks
+            .iter()
+            .map(|b| (b.index, b.tape_file, b.raw.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_every_container() {
+        let blocks = sample();
+        for format in [
+            ContainerFormat::Simh,
+            ContainerFormat::E11,
+            ContainerFormat::Aws,
+            ContainerFormat::Het,
+        ] {
+            let image = write_container(&blocks, format).unwrap();
+            assert_eq!(detect_container(&image), format, "{format:?}");
+            let back = parse_container(&image, format).unwrap();
+            assert_eq!(layout(&back), layout(&blocks), "{format:?}");
+        }
+        // TPC lengths are 16 bits, so the 70000-byte record is refused.
+        assert!(write_container(&blocks, ContainerFormat::Tpc).is_err());
+        let small = &blocks[..2];
+        let image = write_container(small, ContainerFormat::Tpc).unwrap();
+        assert_eq!(detect_container(&image), ContainerFormat::Tpc);
+        let back = parse_container(&image, ContainerFormat::Tpc).unwrap();
+        assert_eq!(layout(&back), layout(small));
+    }
+
+    #[test]
+    fn writes_dd_directories() {
+        let dir = std::env::temp_dir().join(format!("rts_write_dd_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let blocks = sample();
+        write_raw_dir(&dir, &blocks).unwrap();
+        let back = read_raw_dir(&dir).unwrap();
+        assert_eq!(layout(&back), layout(&blocks));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn repairs_split_and_merge() {
+        let mut blocks = sample();
+        blocks.retain(|b| b.size != 70_000);
+        super::renumber(&mut blocks);
+        insert_tape_mark(&mut blocks, 1);
+        let files: Vec<u32> = blocks.iter().map(|b| b.tape_file).collect();
+        assert_eq!(files, vec![0, 2, 4]);
+
+        let (head, tail) = split_tape(&blocks, 2);
+        assert_eq!(head.len(), 1);
+        assert_eq!(layout(&tail)[0], (0, 0, b"odd".to_vec()));
+        let merged = merge_tapes(&head, &tail);
+        let files: Vec<u32> = merged.iter().map(|b| b.tape_file).collect();
+        assert_eq!(files, vec![0, 1, 3]);
+    }
+}
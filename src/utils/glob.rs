@@ -0,0 +1,58 @@
+//! Shell-style wildcards for file name selection (`rts extract --include`,
+//! file search).
+
+/// Match `text` against `pattern`, ignoring ASCII case: `*` matches any run
+/// of characters (including `/`) and `?` exactly one. A pattern without `/`
+/// is matched against the last path element only.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let text = if pattern.contains('/') {
+        text
+    } else {
+        text.rsplit('/').next().unwrap_or(text)
+    };
+    let p: Vec<char> = pattern.chars().map(|c| c.to_ascii_uppercase()).collect();
+    let t: Vec<char> = text.chars().map(|c| c.to_ascii_uppercase()).collect();
+
+    let (mut pi, mut ti) = (0usize, 0usize);
+    // Position after the last `*` and the text position it is matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        match p.get(pi) {
+            Some('*') => {
+                star = Some((pi + 1, ti));
+                pi += 1;
+            }
+            Some(&c) if c == '?' || c == t[ti] => {
+                pi += 1;
+                ti += 1;
+            }
+            _ => match star {
+                Some((after, matched)) => {
+                    pi = after;
+                    ti = matched + 1;
+                    star = Some((after, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(glob_match("*.TXT", "SYS/LOGIN.TXT"));
+        assert!(glob_match("*.txt", "LOGIN.TXT"));
+        assert!(!glob_match("*.TXT", "LOGIN.DAT"));
+        assert!(glob_match("SYS/*", "SYS/SUB/A.DAT"));
+        assert!(!glob_match("SYS/*", "USR/A.DAT"));
+        assert!(glob_match("A?C.*", "ABC.MAC"));
+        assert!(!glob_match("A?C", "AC"));
+        assert!(glob_match("*A*B*", "XAYYBZ"));
+        assert!(glob_match("*", ""));
+    }
+}
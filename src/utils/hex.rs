@@ -45,3 +45,73 @@ pub fn format_hex_with_ascii(data: &[u8]) -> String {
     }
     out
 }
+
+/// Parse a go-to offset: `0x` hex, `0o` or leading-zero octal, otherwise
+/// decimal.
+pub fn parse_offset(text: &str) -> Option<usize> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        usize::from_str_radix(hex, 16).ok()
+    } else if let Some(octal) = text.strip_prefix("0o") {
+        usize::from_str_radix(octal, 8).ok()
+    } else if text.len() > 1 && text.starts_with('0') {
+        usize::from_str_radix(&text[1..], 8).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+/// Parse hex byte pairs such as `DE AD be ef` or `deadbeef`.
+pub fn parse_hex_bytes(text: &str) -> Option<Vec<u8>> {
+    let digits: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// First occurrence of `needle` at or after `from`, wrapping around to the
+/// start of `data`.
+pub fn find_bytes(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if needle.is_empty() || needle.len() > data.len() {
+        return None;
+    }
+    let find = |range: std::ops::Range<usize>| {
+        data[range.clone()]
+            .windows(needle.len())
+            .position(|w| w == needle)
+            .map(|p| range.start + p)
+    };
+    let from = from.min(data.len());
+    find(from..data.len()).or_else(|| find(0..(from + needle.len() - 1).min(data.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_bytes, parse_hex_bytes, parse_offset};
+
+    #[test]
+    fn parses_offsets_in_three_radixes() {
+        assert_eq!(parse_offset("0x1F"), Some(31));
+        assert_eq!(parse_offset("0o17"), Some(15));
+        assert_eq!(parse_offset("017"), Some(15));
+        assert_eq!(parse_offset(" 42 "), Some(42));
+        assert_eq!(parse_offset("0"), Some(0));
+        assert_eq!(parse_offset("09"), None);
+    }
+
+    #[test]
+    fn searches_bytes_with_wraparound() {
+        assert_eq!(parse_hex_bytes("DE ad"), Some(vec![0xDE, 0xAD]));
+        assert_eq!(parse_hex_bytes("ABC"), None);
+        assert_eq!(parse_hex_bytes("zz"), None);
+        let data = b"abcabc";
+        assert_eq!(find_bytes(data, b"bc", 0), Some(1));
+        assert_eq!(find_bytes(data, b"bc", 2), Some(4));
+        assert_eq!(find_bytes(data, b"bc", 5), Some(1));
+        assert_eq!(find_bytes(data, b"x", 0), None);
+    }
+}
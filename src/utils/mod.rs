@@ -1,2 +1,3 @@
+pub mod glob;
 pub mod hex;
 pub mod text;
@@ -1,10 +1,12 @@
 //! Shared test utilities and fixture loading helpers.
 //! External fixtures should be placed under `tests/data/`; outputs belong in `tests/output/`.
+#![allow(dead_code)]
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use retro_tape_studio_v6_safe::core::block::BlockClassification;
+use retro_tape_studio_v6_safe::core::detect::classify_record;
 use retro_tape_studio_v6_safe::log::parse::parse_log;
-use retro_tape_studio_v6_safe::tap::legacy::{read_tap_entry, TapEntry};
 use retro_tape_studio_v6_safe::TapeResult;
 
 pub fn fixture_path(name: &str) -> PathBuf {
@@ -24,21 +26,14 @@ pub fn load_log_fixture(name: &str) -> String {
     fs::read_to_string(fixture_path(name)).expect("fixture LOG missing")
 }
 
-/// Chunk a TAP file into entries using 512-byte blocks.
-pub fn read_tap_file_with_chunks(bytes: &[u8]) -> TapeResult<Vec<TapEntry>> {
-    read_tap_with_chunks(bytes, 512)
+/// Classify a TAP file chunked into 512-byte blocks.
+pub fn classify_file_chunks(bytes: &[u8]) -> Vec<BlockClassification> {
+    classify_chunks(bytes, 512)
 }
 
-/// Chunk a TAP file into entries using a configurable block size.
-pub fn read_tap_with_chunks(bytes: &[u8], chunk_size: usize) -> TapeResult<Vec<TapEntry>> {
-    let mut entries = Vec::new();
-    for chunk in bytes.chunks(chunk_size) {
-        if chunk.is_empty() {
-            continue;
-        }
-        entries.push(read_tap_entry(chunk)?);
-    }
-    Ok(entries)
+/// Classify a TAP file chunked into blocks of a configurable size.
+pub fn classify_chunks(bytes: &[u8], chunk_size: usize) -> Vec<BlockClassification> {
+    bytes.chunks(chunk_size).map(classify_record).collect()
 }
 
 pub fn parse_log_path(path: &Path) -> TapeResult<retro_tape_studio_v6_safe::log::parse::LogData> {
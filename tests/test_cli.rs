@@ -0,0 +1,184 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use retro_tape_studio_v6_safe::backup::writer::{
+    build_saveset, saveset_tape_blocks, SavesetOptions,
+};
+use retro_tape_studio_v6_safe::cli::{run, EXIT_DAMAGED, EXIT_ERROR, EXIT_OK, EXIT_USAGE};
+use retro_tape_studio_v6_safe::core::rsts::writer::{build_dos11_tape, Dos11TapeOptions};
+use retro_tape_studio_v6_safe::core::rt11::writer::HostFile;
+use retro_tape_studio_v6_safe::tap::container::ContainerFormat;
+use retro_tape_studio_v6_safe::tap::writer::{write_container, write_tape_image};
+
+mod common;
+use common::write_output;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rts_cli_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn rts(args: &[&str]) -> (i32, String, String) {
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let code = run(&args, &mut out, &mut err);
+    (
+        code,
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
+/// A DOS-11 tape holding two small files.
+fn dos11_tape(dir: &Path) -> String {
+    let date = NaiveDate::from_ymd_opt(1984, 2, 29).unwrap();
+    let files = [
+        HostFile {
+            name: "HELLO.TXT".into(),
+            data: b"hello\r\n".to_vec(),
+            date,
+        },
+        HostFile {
+            name: "DATA.BIN".into(),
+            data: vec![7u8; 600],
+            date,
+        },
+    ];
+    let blocks = build_dos11_tape(&files, &Dos11TapeOptions::default()).unwrap();
+    let path = dir.join("dos11.tap");
+    write_tape_image(&path, &blocks, ContainerFormat::Simh).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn lists_and_extracts_with_globs() {
+    let dir = temp_dir("list");
+    let tape = dos11_tape(&dir);
+
+    let (code, out, _) = rts(&["list", &tape]);
+    assert_eq!(code, EXIT_OK);
+    assert!(out.contains("HELLO.TXT"));
+    assert!(out.contains("[1,1]"));
+    assert!(out.ends_with("2 file(s)\n"));
+
+    let (code, out, _) = rts(&["info", &tape]);
+    assert_eq!(code, EXIT_OK);
+    assert!(out.contains("Format:    RSTS/E"));
+    write_output("cli", "info.txt", &out);
+
+    let outdir = dir.join("out");
+    let (code, out, _) = rts(&[
+        "extract",
+        &tape,
+        outdir.to_str().unwrap(),
+        "--include",
+        "*.txt",
+    ]);
+    assert_eq!(code, EXIT_OK, "{out}");
+    assert!(out.contains("1 file(s) extracted"));
+    let extracted: Vec<_> = walk(&outdir);
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(fs::read(&extracted[0]).unwrap(), b"hello\r\n");
+
+    let (code, out, _) = rts(&["dump", &tape, "0"]);
+    assert_eq!(code, EXIT_OK);
+    assert!(out.starts_with("Record 0: 14 bytes, tape file 0"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn finds_files_by_name_metadata_and_content() {
+    let dir = temp_dir("find");
+    let tape = dos11_tape(&dir);
+
+    let (code, out, _) = rts(&["find", &tape, "--grep", "HELLO"]);
+    assert_eq!(code, EXIT_OK, "{out}");
+    write_output("cli", "find.txt", &out);
+    assert!(out.contains("HELLO.TXT"));
+    assert!(out.contains("@0  [hello].."));
+    assert!(!out.contains("DATA.BIN"));
+    assert!(out.ends_with("1 file(s) found\n"));
+
+    let (_, out, _) = rts(&["find", &tape, "--regex", r"\.bin$", "--uic", "[1,*]"]);
+    assert!(
+        out.contains("DATA.BIN") && !out.contains("HELLO.TXT"),
+        "{out}"
+    );
+    let (_, out, _) = rts(&["find", &tape, "--since", "1984-03-01"]);
+    assert!(out.ends_with("0 file(s) found\n"), "{out}");
+    let (_, out, _) = rts(&["find", &tape, "--min-size", "100", "--until", "1984-02-29"]);
+    assert!(
+        out.contains("DATA.BIN") && out.ends_with("1 file(s) found\n"),
+        "{out}"
+    );
+
+    let (code, _, err) = rts(&["find", &tape, "--grep-regex", "("]);
+    assert_eq!(code, EXIT_USAGE);
+    assert!(err.contains("regex parse error"), "{err}");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn walk(dir: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            out.extend(walk(&path));
+        } else {
+            out.push(path);
+        }
+    }
+    out
+}
+
+#[test]
+fn verify_reports_damage_with_exit_code() {
+    let dir = temp_dir("verify");
+    let opts = SavesetOptions {
+        block_size: 2048,
+        ..SavesetOptions::default()
+    };
+    let mut blocks = saveset_tape_blocks(&build_saveset(&[], &opts).unwrap(), &opts);
+    let good = dir.join("good.tap");
+    fs::write(
+        &good,
+        write_container(&blocks, ContainerFormat::Simh).unwrap(),
+    )
+    .unwrap();
+    let (code, out, _) = rts(&["verify", good.to_str().unwrap()]);
+    assert_eq!(code, EXIT_OK, "{out}");
+    assert!(out.starts_with("OK:"));
+
+    let saveset = blocks.iter().position(|b| b.size == 2048).unwrap();
+    let mut raw = blocks[saveset].raw.to_vec();
+    raw[400] ^= 0x55;
+    blocks[saveset].raw = raw.into();
+    let bad = dir.join("bad.tap");
+    fs::write(
+        &bad,
+        write_container(&blocks, ContainerFormat::Simh).unwrap(),
+    )
+    .unwrap();
+    let (code, out, _) = rts(&["verify", bad.to_str().unwrap()]);
+    assert_eq!(code, EXIT_DAMAGED);
+    assert!(out.contains(&format!("record {saveset}: BACKUP block CRC mismatch")));
+
+    // Other subcommands still run but flag the damage.
+    let (code, _, err) = rts(&["list", bad.to_str().unwrap()]);
+    assert_eq!(code, EXIT_DAMAGED);
+    assert!(err.contains("1 problem(s) found"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn rejects_bad_usage() {
+    assert_eq!(rts(&[]).0, EXIT_USAGE);
+    assert_eq!(rts(&["frobnicate", "x.tap"]).0, EXIT_USAGE);
+    assert_eq!(rts(&["list"]).0, EXIT_USAGE);
+    assert_eq!(rts(&["list", "x.tap", "--format", "nope"]).0, EXIT_USAGE);
+    assert_eq!(rts(&["dump", "x.tap", "first"]).0, EXIT_USAGE);
+    assert_eq!(rts(&["list", "/nonexistent/x.tap"]).0, EXIT_ERROR);
+}
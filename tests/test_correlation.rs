@@ -1,39 +1,109 @@
-use retro_tape_studio_v6_safe::log::parse::{correlate_log, LogData, LogEntry, LogLevel};
-use retro_tape_studio_v6_safe::tap::legacy::{TapDataKind, TapEntry};
-use retro_tape_studio_v6_safe::tap::DetectedFormat;
+use retro_tape_studio_v6_safe::gui::input::{load_log_file, set_tape_blocks};
+use retro_tape_studio_v6_safe::gui::state::AppState;
+use retro_tape_studio_v6_safe::log::crosscheck::LayoutIssue;
+use retro_tape_studio_v6_safe::log::parse::{
+    correlate_log, parse_log_text, LogData, LogEntry, LogLevel,
+};
+use retro_tape_studio_v6_safe::tap::stream::TapeIndex;
 mod common;
-use common::write_output;
+use common::{fixture_path, write_output};
 
 #[test]
 fn correlates_warning_to_entries() {
-    let mut entries = vec![
-        TapEntry {
-            length: 10,
-            kind: TapDataKind::Raw(vec![1, 2]),
-            log_level: None,
-            detected_format: DetectedFormat::Raw,
-        },
-        TapEntry {
-            length: 10,
-            kind: TapDataKind::Raw(vec![3, 4]),
-            log_level: None,
-            detected_format: DetectedFormat::Raw,
-        },
-    ];
-
     let log = LogData {
         entries: vec![LogEntry {
             line: "record 2 warning".into(),
             level: LogLevel::Warning,
         }],
-        metadata: Default::default(),
+        ..Default::default()
     };
 
-    correlate_log(&mut entries, &log);
-    assert!(entries[1].log_level.is_some());
+    let levels = correlate_log(&[10, 10], &log);
+    assert!(levels[1].is_some());
     write_output(
         "correlation",
         "correlation.txt",
-        &format!("{:?}", entries[1].log_level),
+        &format!("{:?}", levels[1]),
+    );
+}
+
+#[test]
+fn fixture_logs_match_their_tapes() {
+    for (tap, log, expected) in [
+        ("TA0114.TAP", "TA0114.LOG", vec![]),
+        (
+            "TA0117.TAP",
+            "TA0117.LOG",
+            vec![(
+                9,
+                LayoutIssue::ShortRecord {
+                    size: 7301,
+                    block_size: 8192,
+                },
+            )],
+        ),
+    ] {
+        let mut state = AppState::default();
+        let index = TapeIndex::open(&fixture_path(tap)).expect("index fixture");
+        set_tape_blocks(index.blocks().unwrap(), &mut state).unwrap();
+        load_log_file(&fixture_path(log), &mut state).unwrap();
+        let found: Vec<_> = state
+            .log_state
+            .layout
+            .clone()
+            .expect("layout checked")
+            .into_iter()
+            .map(|f| (f.record, f.issue))
+            .collect();
+        assert_eq!(found, expected, "{tap}");
+    }
+}
+
+#[test]
+fn tape_mark_positions_are_not_record_numbers() {
+    let log = parse_log_text(
+        "Reading...\n1 x 512 bytes\nTapemark hit at block 2\nError reading block 2, retrying\n2 x 512 bytes\n",
     );
+    let levels = correlate_log(&[512; 3], &log);
+    assert!(levels[0].is_none());
+    assert_eq!(levels[1], Some(LogLevel::Warning));
+    assert!(levels[2].is_none());
+}
+
+#[test]
+fn every_dialect_feeds_record_status() {
+    let flagged = |log: &str| correlate_log(&[512; 4], &parse_log_text(log));
+    let (warn, error) = (Some(LogLevel::Warning), Some(LogLevel::Error));
+
+    let simh = "%SIM-INFO: MTA0: Tape Image 'X.TAP' scanned as SIMH format
+DBG(1)> MTA0 DETAIL: rd_lnt: st: 0, lnt: 512, f: 0
+DBG(2)> MTA0 DETAIL: rd_lnt: st: 8, lnt: 512, f: 520
+DBG(3)> MTA0 DETAIL: rd_lnt: st: 0, lnt: 512, f: 1040
+DBG(4)> MTA0 DETAIL: rd_lnt: st: 7, lnt: 0, f: 1560";
+    assert_eq!(flagged(simh), vec![None, error.clone(), None, None]);
+
+    let tapedump = "tapedump: reading /dev/nst0
+record 1: 512 bytes
+record 2: 512 bytes
+read error on record 3: Input/output error
+record 4: 512 bytes
+end of tape";
+    assert_eq!(flagged(tapedump), vec![None, None, error.clone(), None]);
+
+    // Bytes 0x300..0x500 span records 1 and 2; the tail of record 3 is untried.
+    let ddrescue = "# Mapfile. Created by GNU ddrescue version 1.27
+0x00000000  0x00000300  +
+0x00000300  0x00000200  -
+0x00000500  0x00000200  +
+0x00000700  0x00000100  ?";
+    assert_eq!(
+        flagged(ddrescue),
+        vec![None, error.clone(), error.clone(), warn]
+    );
+
+    let dd = "1+0 records in
+dd: error reading '/dev/nst0': Input/output error
+1+0 records in
+1+0 records out";
+    assert_eq!(flagged(dd), vec![None, error, None, None]);
 }
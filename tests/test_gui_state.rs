@@ -1,12 +1,23 @@
-use retro_tape_studio_v6_safe::gui::input::{load_log_file, set_tap_entries};
-use retro_tape_studio_v6_safe::gui::state::AppState;
+use retro_tape_studio_v6_safe::core::block::TapeFormat;
+use retro_tape_studio_v6_safe::core::extract::file_bytes;
+use retro_tape_studio_v6_safe::core::preview::{text_preview, PreviewOptions};
+use retro_tape_studio_v6_safe::core::rsts::writer::{build_dos11_tape, Dos11TapeOptions};
+use retro_tape_studio_v6_safe::core::rt11::writer::HostFile;
+use retro_tape_studio_v6_safe::core::timeline::TimelineItem;
+use retro_tape_studio_v6_safe::gui::contents::{select_record, show_record, shown_records};
+use retro_tape_studio_v6_safe::gui::files::shown_files;
+use retro_tape_studio_v6_safe::gui::input::{load_log_file, set_format_override, set_tape_blocks};
+use retro_tape_studio_v6_safe::gui::logview::{log_rows, open_line_record, LogRow};
+use retro_tape_studio_v6_safe::gui::search::run_search;
+use retro_tape_studio_v6_safe::gui::state::{AppState, LogView, MainTab};
+use retro_tape_studio_v6_safe::gui::timeline::set_span_filter;
 use retro_tape_studio_v6_safe::log::parse::LogLevel;
-use retro_tape_studio_v6_safe::tap::legacy::{TapDataKind, TapEntry};
-use retro_tape_studio_v6_safe::tap::DetectedFormat;
+use retro_tape_studio_v6_safe::tap::reader::parse_tap_blocks;
+use retro_tape_studio_v6_safe::tap::stream::TapeIndex;
 use std::fs;
 use std::path::PathBuf;
 mod common;
-use common::{load_tap_fixture, read_tap_with_chunks, write_output};
+use common::{classify_chunks, fixture_path, load_tap_fixture, write_output};
 
 #[test]
 fn loading_tap_and_log_resets_selection() {
@@ -14,13 +25,7 @@ fn loading_tap_and_log_resets_selection() {
     state.tap_state.selected_entry = Some(0);
     state.selected_file = Some(1);
 
-    let entries = vec![TapEntry {
-        length: 4,
-        kind: TapDataKind::Raw(vec![1, 2, 3, 4]),
-        log_level: None,
-        detected_format: DetectedFormat::Raw,
-    }];
-    set_tap_entries(entries, &mut state);
+    set_tape_blocks(parse_tap_blocks(&one_record_tape()), &mut state).unwrap();
     assert!(state.tap_state.selected_entry.is_none());
     assert!(state.selected_file.is_none());
     write_output("tap", "state_reset.txt", "selection reset");
@@ -29,25 +34,19 @@ fn loading_tap_and_log_resets_selection() {
 #[test]
 fn loading_log_correlates() {
     let mut state = AppState::default();
-    let entries = vec![TapEntry {
-        length: 4,
-        kind: TapDataKind::Raw(vec![1, 2, 3, 4]),
-        log_level: None,
-        detected_format: DetectedFormat::Raw,
-    }];
-    set_tap_entries(entries, &mut state);
+    set_tape_blocks(parse_tap_blocks(&one_record_tape()), &mut state).unwrap();
 
     let path = temp_log("record 1 warning");
     load_log_file(&path, &mut state).unwrap();
     assert!(matches!(
-        state.tap_state.entries[0].log_level,
+        state.tap_state.record_levels[0],
         Some(LogLevel::Warning) | Some(LogLevel::Error)
     ));
     let _ = fs::remove_file(path);
     write_output(
         "correlation",
         "state_correlation.txt",
-        &format!("{:?}", state.tap_state.entries[0].log_level),
+        &format!("{:?}", state.tap_state.record_levels[0]),
     );
 }
 
@@ -56,21 +55,63 @@ fn loading_non_vms_taps_skips_block_tree() {
     for tap in ["TA0113.TAP", "TA0013.TAP"] {
         let mut state = AppState::default();
         let data = load_tap_fixture(tap);
-        let entries = read_tap_with_chunks(&data, 512).expect("should parse TAP without panic");
+        let classes = classify_chunks(&data, 512);
 
         assert!(
-            entries
-                .iter()
-                .all(|entry| entry.detected_format != DetectedFormat::VmsBackup),
+            classes.iter().all(|c| c.format() != TapeFormat::Vms),
             "expected {tap} to be detected as non-VMS"
         );
 
-        set_tap_entries(entries, &mut state);
-        assert!(state.vms_files.is_empty());
-        assert!(state.vms_fs.is_none());
+        set_tape_blocks(parse_tap_blocks(&data), &mut state).expect("blocks should load");
+        assert_ne!(state.detected_format, TapeFormat::Vms);
+        assert!(state
+            .files
+            .iter()
+            .all(|file| file.format != TapeFormat::Vms));
     }
 }
 
+#[test]
+fn format_override_replaces_detected_winner() {
+    // Bare 512-byte records only the loose RSX-11M heuristic matches.
+    let mut record = vec![0x55u8; 512];
+    record[0..2].copy_from_slice(&[0x31, 0x00]);
+    let mut tap = Vec::new();
+    for _ in 0..2 {
+        tap.extend_from_slice(&512u32.to_le_bytes());
+        tap.extend_from_slice(&record);
+        tap.extend_from_slice(&512u32.to_le_bytes());
+    }
+    tap.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut state = AppState::default();
+    set_tape_blocks(parse_tap_blocks(&tap), &mut state).expect("blocks should load");
+    let detection = state.detection.clone().expect("detection stored");
+    assert_eq!(detection.winner, TapeFormat::Unknown);
+    assert!(detection.confidence(&TapeFormat::Rsx) > 0.0);
+    assert_eq!(state.detected_format, TapeFormat::Unknown);
+
+    state.selected_file = Some(0);
+    set_format_override(Some(TapeFormat::Rsx), &mut state);
+    assert_eq!(state.detected_format, TapeFormat::Rsx);
+    assert_eq!(state.format_override, Some(TapeFormat::Rsx));
+    assert!(state.selected_file.is_none());
+    assert!(state.files.iter().all(|f| f.format == TapeFormat::Rsx));
+
+    set_format_override(None, &mut state);
+    assert_eq!(state.detected_format, TapeFormat::Unknown);
+    assert!(state.format_override.is_none());
+}
+
+/// SIMH image of one 4-byte record.
+fn one_record_tape() -> Vec<u8> {
+    let mut tap = Vec::new();
+    tap.extend_from_slice(&4u32.to_le_bytes());
+    tap.extend_from_slice(&[1, 2, 3, 4]);
+    tap.extend_from_slice(&4u32.to_le_bytes());
+    tap
+}
+
 fn temp_log(content: &str) -> PathBuf {
     let path = std::env::temp_dir().join(format!(
         "retro_tape_gui_state_{}.log",
@@ -82,3 +123,189 @@ fn temp_log(content: &str) -> PathBuf {
     fs::write(&path, content).unwrap();
     path
 }
+
+#[test]
+fn log_lines_and_records_link_both_ways() {
+    let mut state = AppState::default();
+    let index = TapeIndex::open(&fixture_path("TA0117.TAP")).expect("index fixture");
+    set_tape_blocks(index.blocks().unwrap(), &mut state).unwrap();
+    load_log_file(&fixture_path("TA0117.LOG"), &mut state).unwrap();
+
+    // Lines 25-27 log records 3-33 in three runs.
+    let data = state.log_state.data.as_ref().unwrap();
+    let rows = log_rows(data, &state.log_state.view);
+    assert!(rows.contains(&LogRow::Runs {
+        lines: vec![25, 26, 27],
+        records: 3..34,
+    }));
+    let mut view = LogView {
+        search: "TAPEMARK".into(),
+        ..Default::default()
+    };
+    assert_eq!(log_rows(data, &view).len(), 4);
+    view.search.clear();
+    view.show_info = false;
+    assert!(log_rows(data, &view).is_empty());
+
+    select_record(9, &mut state);
+    assert_eq!(state.log_state.view.scroll_to_line, Some(26));
+    assert_eq!(state.log_state.view.selected_line, Some(26));
+
+    state.current_tab = MainTab::Log;
+    assert!(open_line_record(27, false, &mut state));
+    assert_eq!(state.current_tab, MainTab::Contents);
+    assert!(state.tap_state.show_records);
+    assert_eq!(state.tap_state.selected_record, Some(10));
+    assert_eq!(state.tap_state.scroll_to_record, Some(10));
+
+    assert!(!open_line_record(24, true, &mut state), "tape mark line");
+    assert!(open_line_record(23, true, &mut state));
+    assert_eq!(state.tap_state.record_hex_viewer, Some(0));
+}
+
+#[test]
+fn timeline_spans_filter_files_and_records() {
+    let index = TapeIndex::open(&fixture_path("TA0117.TAP")).unwrap();
+    let mut state = AppState::default();
+    set_tape_blocks(index.blocks().unwrap(), &mut state).unwrap();
+
+    let layout: Vec<String> = state
+        .timeline
+        .iter()
+        .map(|item| match item {
+            TimelineItem::TapeMark => "|".to_string(),
+            TimelineItem::Span(span) => format!(
+                "{:?} {} {}..{}",
+                span.kind,
+                span.title(),
+                span.records.start,
+                span.records.end
+            ),
+        })
+        .collect();
+    write_output("timeline", "TA0117.txt", &layout.join("\n"));
+    assert_eq!(layout[0], "Labels VOL1 HDR1 HDR2 0..3");
+    assert_eq!(layout[1], "|");
+    assert_eq!(layout[2], "Saveset VMSMUP050.A 3..34");
+    assert_eq!(layout[3], "|");
+    assert!(layout[4].starts_with("Labels EOF1 EOF2"));
+
+    let all = shown_files(&state).len();
+    let TimelineItem::Span(saveset) = state.timeline[2].clone() else {
+        unreachable!()
+    };
+    set_span_filter(Some(saveset), &mut state);
+    assert_eq!(shown_records(&state, state.blocks.len()), 3..34);
+    assert_eq!(
+        shown_files(&state).len(),
+        all,
+        "every file is in the saveset"
+    );
+
+    let TimelineItem::Span(labels) = state.timeline[0].clone() else {
+        unreachable!()
+    };
+    set_span_filter(Some(labels), &mut state);
+    assert!(shown_files(&state).is_empty());
+    assert_eq!(shown_records(&state, state.blocks.len()), 0..3);
+
+    show_record(20, &mut state);
+    assert!(
+        state.span_filter.is_none(),
+        "record 20 lies outside the labels"
+    );
+}
+
+#[test]
+fn previews_text_files_and_falls_back_to_hex() {
+    let date = chrono::NaiveDate::from_ymd_opt(1985, 6, 1).unwrap();
+    let files = vec![
+        HostFile {
+            name: "README.TXT".into(),
+            data: b"RELEASE NOTES\n\nVersion 2.1\n".to_vec(),
+            date,
+        },
+        HostFile {
+            name: "DATA.BIN".into(),
+            data: (0..600u32).map(|i| (i * 7) as u8).collect(),
+            date,
+        },
+    ];
+    let blocks = build_dos11_tape(&files, &Dos11TapeOptions::default()).unwrap();
+    let mut state = AppState::default();
+    set_tape_blocks(blocks, &mut state).unwrap();
+    let shown = shown_files(&state);
+    let find = |name: &str| {
+        shown
+            .iter()
+            .position(|(f, _)| f.path.to_string_path().contains(name))
+            .unwrap()
+    };
+
+    let readme = find("README");
+    state.preview.open(readme);
+    assert_eq!(state.preview.file, Some(readme));
+    let file = &shown[readme].0;
+    let bytes = file_bytes(file, &state.blocks);
+    let preview = text_preview(&bytes, &PreviewOptions::for_file(file, &bytes));
+    write_output("preview", "README.TXT.txt", &preview.text);
+    assert!(preview.is_text);
+    assert_eq!(preview.text, "RELEASE NOTES\n\nVersion 2.1");
+
+    let file = &shown[find("DATA")].0;
+    let bytes = file_bytes(file, &state.blocks);
+    assert!(!text_preview(&bytes, &PreviewOptions::for_file(file, &bytes)).is_text);
+}
+
+#[test]
+fn searches_listed_files_and_links_content_offsets() {
+    let date = chrono::NaiveDate::from_ymd_opt(1985, 6, 1).unwrap();
+    let files = vec![
+        HostFile {
+            name: "NOTES.TXT".into(),
+            data: b"BUILD LOG\nERROR 12 IN PASS 2\n".to_vec(),
+            date,
+        },
+        HostFile {
+            name: "MAIN.MAC".into(),
+            data: b"\t.TITLE\tMAIN\n\tHALT\n".to_vec(),
+            date,
+        },
+    ];
+    let blocks = build_dos11_tape(&files, &Dos11TapeOptions::default()).unwrap();
+    let mut state = AppState::default();
+    set_tape_blocks(blocks, &mut state).unwrap();
+    let shown = shown_files(&state);
+
+    state.file_search.content = "error".into();
+    run_search(&mut state);
+    let hits = state.file_search.hits.clone().unwrap();
+    assert_eq!(hits.len(), 1);
+    assert!(shown[hits[0].index]
+        .0
+        .path
+        .to_string_path()
+        .contains("NOTES"));
+    // Text files are written with CR LF line ends.
+    assert_eq!(hits[0].matches[0].offset, 11);
+    assert_eq!(state.file_search.status, "1 of 2 files match");
+
+    state.file_search.content.clear();
+    state.file_search.name = "*.MAC".into();
+    run_search(&mut state);
+    let hits = state.file_search.hits.clone().unwrap();
+    assert_eq!(hits.len(), 1);
+    assert!(shown[hits[0].index]
+        .0
+        .path
+        .to_string_path()
+        .contains("MAIN"));
+
+    state.file_search.name = "(".into();
+    state.file_search.name_regex = true;
+    run_search(&mut state);
+    assert!(state.file_search.status.starts_with("regex parse error"));
+
+    set_span_filter(None, &mut state);
+    assert!(state.file_search.hits.is_none());
+}
@@ -0,0 +1,55 @@
+use retro_tape_studio_v6_safe::gui::input::{load_log_file, set_tape_blocks};
+use retro_tape_studio_v6_safe::gui::state::AppState;
+use retro_tape_studio_v6_safe::health::export::{health_png, health_svg};
+use retro_tape_studio_v6_safe::health::{compute_health_map, RecordHealth};
+use retro_tape_studio_v6_safe::log::parse::LogLevel;
+use retro_tape_studio_v6_safe::tap::stream::TapeIndex;
+use std::fs;
+use std::sync::Arc;
+mod common;
+use common::{ensure_output_dir, fixture_path, load_tap_fixture, write_output};
+
+#[test]
+fn health_map_merges_log_flags_and_crcs() {
+    // Flag record 12 in its SIMH length words and damage a byte of record 20.
+    let mut image = load_tap_fixture("TA0117.TAP");
+    let index = TapeIndex::open(&fixture_path("TA0117.TAP")).unwrap();
+    let flagged = index.records()[12];
+    let start = flagged.offset as usize;
+    let end = start + flagged.len as usize + flagged.len as usize % 2;
+    image[start - 1] |= 0x80;
+    image[end + 3] |= 0x80;
+    image[index.records()[20].offset as usize + 100] ^= 0xFF;
+    let path = std::env::temp_dir().join(format!("rts_health_{}.tap", std::process::id()));
+    fs::write(&path, &image).unwrap();
+
+    let index = TapeIndex::open(&path).unwrap();
+    let mut state = AppState::default();
+    set_tape_blocks(index.blocks().unwrap(), &mut state).unwrap();
+    state.tap_state.index = Some(Arc::new(index));
+    fs::remove_file(&path).unwrap();
+    load_log_file(&fixture_path("TA0117.LOG"), &mut state).unwrap();
+    state.tap_state.record_levels[5] = Some(LogLevel::Warning);
+    state.tap_state.record_levels[12] = Some(LogLevel::Error);
+
+    let map = compute_health_map(&state);
+    assert_eq!(map.records.len(), state.blocks.len());
+    assert_eq!(map.records[5], RecordHealth::Retried);
+    assert_eq!(
+        map.records[12],
+        RecordHealth::Flagged,
+        "flag beats the log error"
+    );
+    assert_eq!(map.records[20], RecordHealth::BadCrc);
+    assert_eq!(map.records[21], RecordHealth::Good);
+    // The 7301-byte short read the log reports is cut off mid-block.
+    assert_eq!(map.records[9], RecordHealth::BadCrc);
+    assert_eq!(map.damaged(), 4);
+
+    let svg = health_svg(&map, 32, 8);
+    write_output("health", "TA0117.svg", &svg);
+    assert!(svg.contains("flagged bad in image: 1</text>"));
+    let png = health_png(&map, 32, 8).unwrap();
+    fs::write(ensure_output_dir("health").join("TA0117.png"), &png).unwrap();
+    assert_eq!(&png[1..4], b"PNG");
+}
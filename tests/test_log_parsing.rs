@@ -1,7 +1,8 @@
 use std::fs;
 use std::path::PathBuf;
 
-use common::write_output;
+use common::{fixture_path, write_output};
+use retro_tape_studio_v6_safe::log::event::LogEventKind;
 use retro_tape_studio_v6_safe::log::parse::{parse_log, LogLevel};
 mod common;
 
@@ -31,6 +32,43 @@ fn parses_log_levels_and_metadata() {
     );
 }
 
+#[test]
+fn parses_stp2tap_fixture_logs() {
+    let data = parse_log(&fixture_path("TA0114.LOG")).expect("parse log");
+    assert!(matches!(
+        &data.events[0].kind,
+        LogEventKind::Header { tool, version, .. } if tool == "STP2TAP" && version == "0.02"
+    ));
+    assert_eq!(data.metadata.get("Tool").unwrap(), "STP2TAP 0.02");
+    assert_eq!(
+        data.metadata.get("Finished").unwrap(),
+        "2025-11-05 14:24:11"
+    );
+    assert_eq!(data.options().len(), 3);
+    assert!(data.label().unwrap().starts_with("BB-KR74A-BE M48001TM\n"));
+
+    let layout = data.record_layout();
+    let records: u64 = layout.iter().map(|r| r.count).sum();
+    assert_eq!(records, 27);
+    assert_eq!((layout[1].tape_file, layout[1].first), (1, 3));
+    assert_eq!((layout[1].count, layout[1].size), (10, 8192));
+    assert_eq!(data.log_errors(), 0);
+
+    let data = parse_log(&fixture_path("TA0113.LOG")).expect("parse log");
+    let fault = data
+        .events
+        .iter()
+        .find(|e| matches!(e.kind, LogEventKind::ReadError(_)))
+        .expect("drive fault");
+    assert_eq!(data.entries[fault.line].level, LogLevel::Error);
+    assert_eq!(data.log_errors(), 1);
+    write_output(
+        "log",
+        "stp2tap_layout.txt",
+        &format!("{:?}", data.record_layout()),
+    );
+}
+
 trait LogCounts {
     fn log_warnings(&self) -> usize;
     fn log_errors(&self) -> usize;
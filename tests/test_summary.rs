@@ -1,43 +1,92 @@
-use retro_tape_studio_v6_safe::backup::extract::assemble_files;
+use retro_tape_studio_v6_safe::backup::vms::{FAT_FIX, FAT_STMLF, FAT_VFC};
+use retro_tape_studio_v6_safe::backup::writer::{
+    build_saveset, saveset_tape_blocks, SavesetFile, SavesetOptions,
+};
+use retro_tape_studio_v6_safe::core::block::{BlockClassification, TapeBlock};
+use retro_tape_studio_v6_safe::core::handler::registry;
+use retro_tape_studio_v6_safe::gui::input::set_tape_blocks;
 use retro_tape_studio_v6_safe::gui::state::AppState;
-use retro_tape_studio_v6_safe::log::parse::{LogData, LogEntry, LogLevel};
+use retro_tape_studio_v6_safe::log::parse::{parse_log, LogData, LogEntry, LogLevel};
 use retro_tape_studio_v6_safe::summary::compute_saveset_summary;
-use retro_tape_studio_v6_safe::tap::legacy::{TapDataKind, TapEntry};
-use retro_tape_studio_v6_safe::tap::DetectedFormat;
 mod common;
-use common::write_output;
+use common::{fixture_path, write_output};
 
 #[test]
 fn computes_counts_and_histograms() {
-    let entries = vec![
-        TapEntry {
-            length: 12,
-            kind: TapDataKind::Raw(vec![1, 2, 3]),
-            log_level: None,
-            detected_format: DetectedFormat::Raw,
-        },
-        TapEntry {
-            length: 12,
-            kind: TapDataKind::Raw(vec![4, 5, 6]),
-            log_level: Some(LogLevel::Warning),
-            detected_format: DetectedFormat::Raw,
-        },
-    ];
+    let blocks = [[1u8, 2, 3], [4, 5, 6]]
+        .iter()
+        .enumerate()
+        .map(|(i, raw)| TapeBlock {
+            index: i as u32,
+            tape_file: 0,
+            size: raw.len(),
+            raw: raw[..].into(),
+            classification: BlockClassification::Unknown,
+        })
+        .collect();
     let mut state = AppState::default();
-    state.tap_state.entries = entries;
+    set_tape_blocks(blocks, &mut state).unwrap();
     state.log_state.data = Some(LogData {
         entries: vec![LogEntry {
             line: "Warning".into(),
             level: LogLevel::Warning,
         }],
-        metadata: Default::default(),
+        ..Default::default()
     });
 
     let summary = compute_saveset_summary(&state);
-    assert_eq!(
-        summary.total_files,
-        assemble_files(&state.tap_state.entries).len()
-    );
+    assert_eq!(summary.total_files, 0);
+    assert_eq!(summary.total_blocks, 0);
     assert_eq!(summary.log_warnings, 1);
     write_output("summary", "summary.txt", &format!("{summary:?}"));
 }
+
+#[test]
+fn counts_vms_record_formats_by_rms_code() {
+    let opts = SavesetOptions::default();
+    let file = |name: &str, record_format| SavesetFile {
+        vms_name: name.into(),
+        data: vec![b'x'; 100],
+        record_format,
+        record_attributes: 0,
+        max_record: 100,
+        modified: opts.date,
+    };
+    let files = [
+        file("[000000]A.DAT;1", FAT_FIX),
+        file("[000000]B.DAT;1", FAT_FIX),
+        file("[000000]C.LIS;1", FAT_VFC),
+        file("[000000]D.TXT;1", FAT_STMLF),
+    ];
+    let mut blocks = saveset_tape_blocks(&build_saveset(&files, &opts).unwrap(), &opts);
+    registry().classify(&mut blocks);
+    let state = AppState {
+        files: registry().reconstruct(&blocks),
+        blocks,
+        ..Default::default()
+    };
+
+    let hist = compute_saveset_summary(&state).rfm_hist;
+    assert_eq!(hist.get("FIX"), Some(&2));
+    assert_eq!(hist.get("VFC"), Some(&1));
+    assert_eq!(hist.get("STMLF"), Some(&1));
+    assert_eq!(hist.len(), 3);
+}
+
+#[test]
+fn summary_carries_the_physical_tape_label() {
+    let mut state = AppState::default();
+    state.log_state.data = Some(parse_log(&fixture_path("TA0117.LOG")).unwrap());
+
+    let tape = compute_saveset_summary(&state).tape.unwrap();
+    assert_eq!(tape.image.as_deref(), Some("TA0117.TAP"));
+    assert_eq!(tape.part_number.as_deref(), Some("BB-FY83D-BE"));
+    assert_eq!(tape.title.as_deref(), Some("VMS 5.0 BIN 16MT9"));
+    assert!(tape
+        .copyright
+        .as_deref()
+        .is_some_and(|c| c.ends_with("Digital Equipment Corporation")));
+    assert!(tape.notes.iter().any(|n| n.contains("1600BPI")));
+    assert!(tape.captured.is_some());
+    write_output("summary", "tape_label.txt", &format!("{tape:#?}"));
+}
@@ -1,30 +1,26 @@
-use retro_tape_studio_v6_safe::backup::vms::BackupBlock;
-use retro_tape_studio_v6_safe::tap::legacy::{read_tap_entry, TapDataKind};
-use retro_tape_studio_v6_safe::tap::DetectedFormat;
+use retro_tape_studio_v6_safe::core::block::{BlockClassification, TapeFormat};
+use retro_tape_studio_v6_safe::core::detect::classify_record;
+use retro_tape_studio_v6_safe::core::fields::{data_fields, field_at, FieldKind};
+use retro_tape_studio_v6_safe::tap::stream::TapeIndex;
 
 mod common;
-use common::{load_tap_fixture, read_tap_file_with_chunks, read_tap_with_chunks, write_output};
+use common::{classify_chunks, classify_file_chunks, fixture_path, load_tap_fixture, write_output};
 
 fn make_vms_block() -> Vec<u8> {
-    let mut raw = vec![0u8; 80];
-    raw[0..2].copy_from_slice(&80u16.to_le_bytes());
-    raw[2] = 2;
-    raw[3] = 1;
-    raw[4..8].copy_from_slice(&1u32.to_le_bytes());
-    raw[8..10].copy_from_slice(&0xAAAAu16.to_le_bytes());
-    raw[10] = 0x11;
+    let mut raw = vec![0u8; 512];
+    raw[0..2].copy_from_slice(&256u16.to_le_bytes());
+    raw[8..12].copy_from_slice(&1u32.to_le_bytes());
+    raw[32..34].copy_from_slice(&0x0101u16.to_le_bytes());
+    raw[40..44].copy_from_slice(&512u32.to_le_bytes());
+    raw[256] = 0x11;
     raw
 }
 
 #[test]
 fn detects_vms_backup() {
-    let entry = read_tap_entry(&make_vms_block()).expect("parse");
-    assert!(matches!(
-        entry.kind,
-        TapDataKind::VmsBlock(BackupBlock { .. })
-    ));
-    assert_eq!(entry.detected_format, DetectedFormat::VmsBackup);
-    write_output("tap", "vms_detect.txt", &format!("{entry:?}"));
+    let class = classify_record(&make_vms_block());
+    assert!(matches!(class, BlockClassification::Vms(_)));
+    write_output("tap", "vms_detect.txt", &format!("{class:?}"));
 }
 
 #[test]
@@ -33,13 +29,15 @@ fn detects_rsx_rt11_rsts() {
     let rt11 = load_tap_fixture("rt11.tap");
     let rsts = load_tap_fixture("rsts.tap");
 
-    let rsx_entry = read_tap_file_with_chunks(&rsx).unwrap()[0].detected_format;
-    let rt11_entry = read_tap_file_with_chunks(&rt11).unwrap()[0].detected_format;
-    let rsts_entry = read_tap_file_with_chunks(&rsts).unwrap()[0].detected_format;
+    let rsx_entry = classify_file_chunks(&rsx)[0].format();
+    let rt11_entry = classify_file_chunks(&rt11)[0].format();
+    let rsts_entry = classify_file_chunks(&rsts)[0].format();
 
-    assert_eq!(rsx_entry, DetectedFormat::Rsx11m);
-    assert_eq!(rt11_entry, DetectedFormat::Rt11);
-    assert_eq!(rsts_entry, DetectedFormat::RstsE);
+    assert_eq!(rsx_entry, TapeFormat::Rsx);
+    assert_eq!(rt11_entry, TapeFormat::Rt11);
+    // rsts.tap only carries the repeated prefix and "RSTS" tag the old
+    // heuristic keyed on; it holds no DOS-11 label or directory structure.
+    assert_ne!(rsts_entry, TapeFormat::Rsts);
     write_output(
         "tap",
         "format_detect.txt",
@@ -53,28 +51,52 @@ fn detects_rsx_rt11_rsts() {
 #[test]
 fn falls_back_to_raw() {
     let data = vec![1u8, 2, 3, 4];
-    let entry = read_tap_entry(&data).unwrap();
-    assert_eq!(entry.detected_format, DetectedFormat::Raw);
-    write_output("tap", "raw_detect.txt", &format!("{entry:?}"));
+    let class = classify_record(&data);
+    assert!(matches!(class, BlockClassification::Raw));
+    write_output("tap", "raw_detect.txt", &format!("{class:?}"));
 }
 
 #[test]
 fn non_vms_real_tapes_do_not_panic_or_parse_as_vms() {
     let rsx_data = load_tap_fixture("TA0113.TAP");
-    let rsx_entries = read_tap_with_chunks(&rsx_data, 512).unwrap();
+    let rsx_entries = classify_chunks(&rsx_data, 512);
     assert!(
-        rsx_entries
-            .iter()
-            .all(|e| e.detected_format != DetectedFormat::VmsBackup),
+        rsx_entries.iter().all(|c| c.format() != TapeFormat::Vms),
         "RSX tape should not be treated as VMS BACKUP"
     );
 
     let rsts_data = load_tap_fixture("TA0013.TAP");
-    let rsts_entries = read_tap_with_chunks(&rsts_data, 512).unwrap();
+    let rsts_entries = classify_chunks(&rsts_data, 512);
     assert!(
-        rsts_entries
-            .iter()
-            .all(|e| e.detected_format != DetectedFormat::VmsBackup),
+        rsts_entries.iter().all(|c| c.format() != TapeFormat::Vms),
         "RSTS/E tape should not be treated as VMS BACKUP"
     );
 }
+
+#[test]
+fn names_fields_of_real_label_and_backup_records() {
+    let index = TapeIndex::open(&fixture_path("TA0117.TAP")).unwrap();
+    let vol1 = index.record_bytes(0).unwrap();
+    let labels = data_fields(&vol1);
+    assert_eq!(labels[0].name, "VOL1 label identifier");
+    assert!(labels.iter().all(|f| f.kind == FieldKind::Label));
+
+    let block = index.record_bytes(3).unwrap();
+    let fields = data_fields(&block);
+    let ssname = field_at(&fields, 49).expect("saveset name field");
+    assert_eq!(ssname.name, "BBH t_ssname");
+    assert!(fields.iter().any(|f| f.kind == FieldKind::RecordHeader));
+    assert!(fields.iter().any(|f| f.name == "BSA SSNAME"));
+
+    let listing: String = labels
+        .iter()
+        .chain(&fields)
+        .map(|f| {
+            format!(
+                "{:>5}..{:<5} {:?} {}\n",
+                f.range.start, f.range.end, f.kind, f.name
+            )
+        })
+        .collect();
+    write_output("fields", "TA0117.txt", &listing);
+}
@@ -1,69 +1,42 @@
-use retro_tape_studio_v6_safe::backup::extract::{
-    assemble_vms_files, build_directory_tree, VmsFileSystem,
+use retro_tape_studio_v6_safe::backup::vms::FAT_STMLF;
+use retro_tape_studio_v6_safe::backup::writer::{
+    build_saveset, saveset_tape_blocks, SavesetFile, SavesetOptions,
 };
-use retro_tape_studio_v6_safe::backup::vms::BackupBlock;
-use retro_tape_studio_v6_safe::tap::legacy::{TapDataKind, TapEntry};
-use retro_tape_studio_v6_safe::tap::DetectedFormat;
+use retro_tape_studio_v6_safe::core::block::TapeFormat;
+use retro_tape_studio_v6_safe::core::extract::file_bytes;
+use retro_tape_studio_v6_safe::core::handler::registry;
 mod common;
 use common::write_output;
 
-fn make_block(seq: u32, payload: &[u8]) -> BackupBlock {
-    BackupBlock {
-        block_size: (10 + payload.len()) as u16,
-        format_version: 1,
-        phase: 1,
-        sequence_number: seq,
-        checksum: 0,
-        payload: payload.to_vec(),
-    }
-}
-
 #[test]
 fn assembles_files_and_fs() {
-    let fh2 = {
-        let mut data = Vec::new();
-        data.push(0x02);
-        let name = "DIR.FILE";
-        data.push(name.len() as u8);
-        data.extend_from_slice(name.as_bytes());
-        data.extend_from_slice(&1u16.to_le_bytes());
-        data.push(0);
-        data.extend_from_slice(&0u16.to_le_bytes());
-        data.extend_from_slice(&0u16.to_le_bytes());
-        data.extend_from_slice(&0u64.to_le_bytes());
-        data.extend_from_slice(&0u64.to_le_bytes());
-        data.extend_from_slice(&0u32.to_le_bytes());
-        data.extend_from_slice(&1u32.to_le_bytes());
-        data.extend_from_slice(&0u32.to_le_bytes());
-        data.extend_from_slice(&0u16.to_le_bytes());
-        data.extend_from_slice(&0u16.to_le_bytes());
-        data.extend_from_slice(&0u16.to_le_bytes());
-        data
+    let opts = SavesetOptions::default();
+    let file = SavesetFile {
+        vms_name: "[DIR]FILE.DAT;1".into(),
+        data: b"data".to_vec(),
+        record_format: FAT_STMLF,
+        record_attributes: 0,
+        max_record: 4,
+        modified: opts.date,
     };
+    let mut blocks = saveset_tape_blocks(&build_saveset(&[file], &opts).unwrap(), &opts);
+    assert_eq!(registry().classify(&mut blocks).winner, TapeFormat::Vms);
 
-    let entries = vec![
-        TapEntry {
-            length: 32,
-            kind: TapDataKind::VmsBlock(make_block(1, &fh2)),
-            log_level: None,
-            detected_format: DetectedFormat::VmsBackup,
-        },
-        TapEntry {
-            length: 16,
-            kind: TapDataKind::VmsBlock(make_block(2, b"data")),
-            log_level: None,
-            detected_format: DetectedFormat::VmsBackup,
-        },
-    ];
-
-    let files = assemble_vms_files(&entries);
-    assert_eq!(files.len(), 1);
-    assert!(files[0].path.contains("DIR"));
-    let fs = build_directory_tree(&files);
-    assert!(matches!(fs, VmsFileSystem { .. }));
+    let tree = registry().reconstruct(&blocks);
+    assert_eq!(tree.len(), 1);
+    let dir = &tree[0];
+    assert_eq!(dir.path.to_string_path(), "DIR");
+    assert_eq!(dir.children.len(), 1);
+    let file = &dir.children[0];
+    assert_eq!(file.path.to_string_path(), "DIR/FILE.DAT;1");
+    assert_eq!(file_bytes(file, &blocks), b"data");
     write_output(
         "extract",
         "vms_files.txt",
-        &format!("files={} path={}", files.len(), files[0].path),
+        &format!(
+            "files={} path={}",
+            dir.children.len(),
+            file.path.to_string_path()
+        ),
     );
 }